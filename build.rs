@@ -1,3 +1,7 @@
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
 enum OsTarget {
     Other(String),
     Windows,
@@ -39,4 +43,216 @@ fn main() {
     ) {
         println!("cargo:rustc-cfg=have_mod_supplement");
     }
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=Cargo.toml");
+    println!("cargo:rerun-if-changed=Cargo.lock");
+    let winit = winit_dir();
+    println!("cargo:rerun-if-changed={}", winit.join("src").display());
+    println!(
+        "cargo:rerun-if-changed={}",
+        winit.join("Cargo.toml").display()
+    );
+    let probes = [
+        ("winit_blur", "window.set_blur(true);"),
+        (
+            "winit_content_protected",
+            "window.set_content_protected(true);",
+        ),
+        (
+            "winit_is_minimized",
+            "let _: Option<bool> = window.is_minimized();",
+        ),
+        (
+            "winit_window_level",
+            "window.set_window_level(winit::window::WindowLevel::AlwaysOnTop);",
+        ),
+    ];
+    for cfg in probe_results(&winit, &probes) {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+}
+
+/// Returns the probes that compile against `winit`.
+///
+/// Running the probes requires a nested cargo invocation. The results are therefore cached
+/// in `OUT_DIR` and only recomputed if the winit revision or the probes change.
+fn probe_results(winit: &Path, probes: &[(&str, &str)]) -> Vec<String> {
+    let cache = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("winit-probes.json");
+    let key = format!("{:016x}", probe_key(winit, probes));
+    if let Ok(json) = std::fs::read(&cache) {
+        if let Ok(cached) = serde_json::from_slice::<Value>(&json) {
+            if cached["key"] == *key {
+                if let Some(cfgs) = cached["cfgs"].as_array() {
+                    return cfgs
+                        .iter()
+                        .filter_map(|c| c.as_str().map(|c| c.to_string()))
+                        .collect();
+                }
+            }
+        }
+    }
+    let dir = write_probe_crate(winit, probes);
+    let cfgs: Vec<_> = probes
+        .iter()
+        .filter(|(cfg, _)| probe_compiles(&dir, cfg))
+        .map(|(cfg, _)| cfg.to_string())
+        .collect();
+    let cached = serde_json::json!({ "key": key, "cfgs": cfgs });
+    std::fs::write(&cache, cached.to_string()).unwrap();
+    cfgs
+}
+
+/// Returns a hash of the probes, the lock file, and the revision of winit.
+///
+/// The revision is the git commit if `winit` is an unmodified git checkout. Otherwise the
+/// manifest and the sources of winit are hashed.
+fn probe_key(winit: &Path, probes: &[(&str, &str)]) -> u64 {
+    let mut hash = 0xcbf29ce484222325u64;
+    let mut feed = |bytes: &[u8]| {
+        for &b in bytes.iter().chain([0].iter()) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    };
+    for (cfg, code) in probes {
+        feed(cfg.as_bytes());
+        feed(code.as_bytes());
+    }
+    let lock = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    feed(&std::fs::read(lock).unwrap_or_default());
+    match git_revision(winit) {
+        Some(rev) => feed(rev.as_bytes()),
+        None => {
+            let mut files = vec![winit.join("Cargo.toml")];
+            collect_files(&winit.join("src"), &mut files);
+            files.sort();
+            for file in files {
+                feed(file.to_string_lossy().as_bytes());
+                feed(&std::fs::read(&file).unwrap());
+            }
+        }
+    }
+    hash
+}
+
+/// Returns the commit of the git checkout at `dir` or `None` if `dir` is not a git
+/// checkout or has uncommitted changes.
+fn git_revision(dir: &Path) -> Option<String> {
+    let git = |args: &[&str]| {
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(args)
+            .output()
+            .ok()?;
+        match output.status.success() {
+            true => Some(String::from_utf8_lossy(&output.stdout).trim().to_string()),
+            false => None,
+        }
+    };
+    let status = git(&["status", "--porcelain", "--", "."])?;
+    if !status.is_empty() {
+        return None;
+    }
+    git(&["rev-parse", "HEAD"])
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+/// Returns the directory of the winit package that this crate is built against.
+fn winit_dir() -> PathBuf {
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap());
+    let output = Command::new(std::env::var("CARGO").unwrap())
+        .args(["metadata", "--format-version", "1", "--manifest-path"])
+        .arg(manifest_dir.join("Cargo.toml"))
+        .output()
+        .unwrap();
+    if !output.status.success() {
+        panic!(
+            "cargo metadata failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+    let metadata: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let root = metadata["resolve"]["root"].as_str().unwrap();
+    let node = metadata["resolve"]["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|n| n["id"] == root)
+        .unwrap();
+    let winit = node["deps"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|d| d["name"] == "winit")
+        .unwrap();
+    let package = metadata["packages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|p| p["id"] == winit["pkg"])
+        .unwrap();
+    let manifest = Path::new(package["manifest_path"].as_str().unwrap());
+    manifest.parent().unwrap().to_path_buf()
+}
+
+/// Writes a crate with one binary per probe that calls the probed API on a window.
+fn write_probe_crate(winit: &Path, probes: &[(&str, &str)]) -> PathBuf {
+    let dir = PathBuf::from(std::env::var("OUT_DIR").unwrap()).join("winit-probe");
+    let bin = dir.join("src/bin");
+    std::fs::create_dir_all(&bin).unwrap();
+    let manifest = format!(
+        "[package]\n\
+         name = \"winit-probe\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"2021\"\n\
+         \n\
+         [dependencies]\n\
+         winit = {{ path = {:?} }}\n\
+         \n\
+         [workspace]\n",
+        winit.display().to_string(),
+    );
+    std::fs::write(dir.join("Cargo.toml"), manifest).unwrap();
+    // Use the versions of winit's dependencies that this crate is built with.
+    let lock = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    if lock.exists() {
+        std::fs::copy(&lock, dir.join("Cargo.lock")).unwrap();
+    }
+    for (cfg, code) in probes {
+        let source = format!(
+            "#[allow(dead_code)]\n\
+             fn probe(window: &winit::window::Window) {{\n    {}\n}}\n\
+             \n\
+             fn main() {{}}\n",
+            code
+        );
+        std::fs::write(bin.join(format!("{}.rs", cfg)), source).unwrap();
+    }
+    dir
+}
+
+/// Returns whether the probe `cfg` compiles, i.e. whether winit has the probed API.
+///
+/// Tests of winit APIs that only exist in some winit versions are gated on such a cfg.
+fn probe_compiles(dir: &Path, cfg: &str) -> bool {
+    let output = Command::new(std::env::var("CARGO").unwrap())
+        .args(["check", "--quiet", "--bin", cfg, "--manifest-path"])
+        .arg(dir.join("Cargo.toml"))
+        .env("CARGO_TARGET_DIR", dir.join("target"))
+        .env_remove("RUSTFLAGS")
+        .output()
+        .unwrap();
+    output.status.success()
 }
@@ -0,0 +1,323 @@
+use crate::backend::BackendFlags;
+use std::path::PathBuf;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage: winit-it [run] [OPTIONS] [PATTERN...]
+       winit-it trend [DIR...]
+       winit-it setup
+
+Runs the tests whose names match one of the PATTERNs, or all tests if no pattern is
+given. Patterns can contain the wildcards `*` and `?`.
+
+The trend subcommand merges the reports of the testrun directories DIR, or of all
+directories in testruns/records if no DIR is given, into testruns/trend.html and
+testruns/trend.json. The trend contains the pass rate and average duration of each
+test on each backend.
+
+The setup subcommand builds the x11 module for the installed Xorg server. This
+also happens automatically before the tests are run if the module has not been
+built for the installed server version.
+
+Options:
+    --backend <NAME>
+        Only run the tests of this backend. Can be repeated. The built-in
+        backends are x11 and mock. The mock backend only runs the self-tests of
+        the harness, does not require a display server, and only runs if it is
+        selected with this option. Unknown backends are an error.
+    --flag <FLAG>
+        Only run the tests that require the backend flag FLAG, e.g.
+        CONNECTION_PROXY. Can be repeated to select the tests that require all
+        of the flags.
+    --skip <PATTERN>
+        Do not run tests whose names match PATTERN. Can be repeated.
+    --timeout <DURATION>
+        Override the timeout of every test (e.g. 30s, 5m).
+    --fail-on-trailing-events
+        Fail tests if their windows receive events after the test has completed.
+        By default such events are only logged.
+    --retries <N>
+        Rerun failed tests up to N times. Tests marked as flaky are always
+        retried at least once.
+    --keep-failed-alive <DURATION>
+        Keep the display servers of failed tests alive for DURATION (e.g. 30s,
+        5m, 1h) before terminating them. The remaining tests continue to run.
+        The harness exits once all of these servers have been terminated.
+    --progress
+        Show a progress bar for each backend, the number of passed and failed
+        tests, and the names of the running tests instead of the log messages
+        of the harness. Warnings and errors are still printed. The log files
+        of the tests are not affected. Requires stderr to be a terminal.
+    --webhook <URL>
+        Post a JSON summary of the run, including the failed tests and the
+        record directory, to URL when all tests have completed. The `text`
+        field of the summary is understood by Slack incoming webhooks and
+        Matrix hookshot webhooks. Requires curl.
+    --backend-lib <PATH>
+        Load an additional backend from the shared library at PATH. Can be
+        repeated. Requires the plugins feature.
+    --bisect-winit <PATH>
+        Bisect the winit git checkout at PATH between --good and --bad. In each
+        step, the harness is rebuilt against the checkout and the selected tests
+        are run. The remaining options are passed on to these runs.
+    --good <REV>
+        A winit revision in which the tests pass. Required by --bisect-winit.
+    --bad <REV>
+        A winit revision in which the tests fail. Required by --bisect-winit.
+    -h, --help
+        Print this help.
+
+Environment variables:
+    WINIT_IT_JUNIT=<DIR>
+        Write a JUnit XML file for each backend into DIR.
+    WINIT_IT_TIMEOUT=<DURATION>
+        Same as --timeout. The command-line argument takes precedence.
+    WINIT_IT_WEBHOOK=<URL>
+        Same as --webhook. The command-line argument takes precedence.
+    WINIT_IT_X_SERVER=xorg|xvfb|xephyr
+        The X server used by the x11 backend. By default, Xorg is used if it and the x11
+        module are installed and Xvfb or Xephyr otherwise. Xvfb and Xephyr do not support
+        the tests that need the module.
+
+Exit status:
+    0 if no test failed, 1 otherwise.";
+
+#[derive(Default)]
+pub struct Args {
+    pub tests: Filter,
+    pub backends: Vec<String>,
+    /// The flags that selected tests must require. See `--flag`.
+    pub flags: BackendFlags,
+    pub timeout: Option<Duration>,
+    pub fail_on_trailing_events: bool,
+    pub retries: u32,
+    pub keep_failed_alive: Option<Duration>,
+    pub backend_libs: Vec<PathBuf>,
+    pub junit: Option<PathBuf>,
+    pub webhook: Option<String>,
+    pub progress: bool,
+    pub bisect: Option<Bisect>,
+    /// The testrun directories to merge if the trend subcommand was given.
+    pub trend: Option<Vec<PathBuf>>,
+    /// Whether the setup subcommand was given.
+    pub setup: bool,
+}
+
+pub struct Bisect {
+    pub winit: PathBuf,
+    pub good: String,
+    pub bad: String,
+    /// The arguments of the runs in each bisection step.
+    pub args: Vec<String>,
+}
+
+pub fn parse() -> Args {
+    let mut res = Args {
+        junit: std::env::var_os("WINIT_IT_JUNIT").map(PathBuf::from),
+        webhook: std::env::var("WINIT_IT_WEBHOOK").ok(),
+        timeout: std::env::var("WINIT_IT_TIMEOUT").ok().map(|val| {
+            parse_duration(&val).unwrap_or_else(|| {
+                eprintln!("Invalid value of WINIT_IT_TIMEOUT: {}", val);
+                std::process::exit(2);
+            })
+        }),
+        ..Default::default()
+    };
+    let mut args = std::env::args().skip(1).peekable();
+    if args.peek().map(|a| &**a) == Some("run") {
+        args.next();
+    } else if args.peek().map(|a| &**a) == Some("trend") {
+        args.next();
+        let mut dirs = vec![];
+        for arg in args {
+            match &*arg {
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ if arg.starts_with('-') => usage_error(&arg),
+                _ => dirs.push(PathBuf::from(arg)),
+            }
+        }
+        res.trend = Some(dirs);
+        return res;
+    } else if args.peek().map(|a| &**a) == Some("setup") {
+        args.next();
+        for arg in args {
+            match &*arg {
+                "-h" | "--help" => {
+                    println!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => usage_error(&arg),
+            }
+        }
+        res.setup = true;
+        return res;
+    }
+    let mut bisect_winit = None;
+    let mut good = None;
+    let mut bad = None;
+    while let Some(arg) = args.next() {
+        match &*arg {
+            "--backend" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                res.backends.push(val);
+            }
+            "--flag" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                match parse_flag(&val) {
+                    Some(f) => res.flags |= f,
+                    None => usage_error(&val),
+                }
+            }
+            "--skip" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                res.tests.skip.push(val);
+            }
+            "--timeout" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                match parse_duration(&val) {
+                    Some(d) => res.timeout = Some(d),
+                    None => usage_error(&val),
+                }
+            }
+            "--retries" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                res.retries = val.parse().unwrap_or_else(|_| usage_error(&val));
+            }
+            "--fail-on-trailing-events" => res.fail_on_trailing_events = true,
+            "--progress" => res.progress = true,
+            "--keep-failed-alive" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                match parse_duration(&val) {
+                    Some(d) => res.keep_failed_alive = Some(d),
+                    _ => usage_error(&val),
+                }
+            }
+            "--webhook" => {
+                res.webhook = Some(args.next().unwrap_or_else(|| usage_error(&arg)));
+            }
+            "--backend-lib" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                res.backend_libs.push(val.into());
+            }
+            "--bisect-winit" => {
+                let val = args.next().unwrap_or_else(|| usage_error(&arg));
+                bisect_winit = Some(PathBuf::from(val));
+            }
+            "--good" => good = Some(args.next().unwrap_or_else(|| usage_error(&arg))),
+            "--bad" => bad = Some(args.next().unwrap_or_else(|| usage_error(&arg))),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ if arg.starts_with('-') => usage_error(&arg),
+            _ => res.tests.include.push(arg),
+        }
+    }
+    if let Some(winit) = bisect_winit {
+        let good = good.unwrap_or_else(|| usage_error("--bisect-winit requires --good"));
+        let bad = bad.unwrap_or_else(|| usage_error("--bisect-winit requires --bad"));
+        if res.tests.include.is_empty() {
+            usage_error("--bisect-winit requires a PATTERN");
+        }
+        res.bisect = Some(Bisect {
+            winit,
+            good,
+            bad,
+            args: without_bisect_options(std::env::args().skip(1)),
+        });
+    } else if good.is_some() || bad.is_some() {
+        usage_error("--good and --bad require --bisect-winit");
+    }
+    res
+}
+
+/// Removes `--bisect-winit`, `--good`, `--bad`, and their values from `args`.
+fn without_bisect_options(args: impl Iterator<Item = String>) -> Vec<String> {
+    let mut res = vec![];
+    let mut skip_value = false;
+    for arg in args {
+        if skip_value {
+            skip_value = false;
+        } else if matches!(&*arg, "--bisect-winit" | "--good" | "--bad") {
+            skip_value = true;
+        } else {
+            res.push(arg);
+        }
+    }
+    res
+}
+
+/// Selects tests by name.
+#[derive(Default)]
+pub struct Filter {
+    pub include: Vec<String>,
+    pub skip: Vec<String>,
+}
+
+impl Filter {
+    pub fn matches(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| glob_match(p, name)))
+            && !self.skip.iter().any(|p| glob_match(p, name))
+    }
+}
+
+/// Matches `name` against `pattern`, where `*` matches any sequence of characters and `?`
+/// matches any single character.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((bp, bn)) => {
+                    p = bp + 1;
+                    n = bn + 1;
+                    backtrack = Some((bp, bn + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Returns the backend flag with the name `name`, e.g. `CONNECTION_PROXY`.
+fn parse_flag(name: &str) -> Option<BackendFlags> {
+    (0..64)
+        .filter_map(|bit| BackendFlags::from_bits(1 << bit))
+        .find(|flag| format!("{:?}", flag) == name)
+}
+
+fn usage_error(arg: &str) -> ! {
+    eprintln!("Invalid argument: {}\n\n{}", arg, USAGE);
+    std::process::exit(2);
+}
+
+/// Parses durations of the form `<n>[s|m|h]`. Without a unit, the value is in minutes.
+fn parse_duration(s: &str) -> Option<Duration> {
+    let (n, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(pos) => s.split_at(pos),
+        _ => (s, "m"),
+    };
+    let n: u64 = n.parse().ok()?;
+    let secs = match unit {
+        "s" => n,
+        "m" => n * 60,
+        "h" => n * 60 * 60,
+        _ => return None,
+    };
+    Some(Duration::from_secs(secs))
+}
@@ -1,21 +1,26 @@
-use crate::event::UserEvent;
+use crate::event::{Event, UserEvent, WindowEventExt};
 use crate::eventstream::EventStream;
-use crate::keyboard::{Key, Layout};
+use crate::keyboard::{compose_sequence, Key, Layout, Level};
+use crate::report::{EventQueueStats, ServerInfo};
+use crate::screenshot::Image;
+use crate::sleep::sleep_ms;
 use std::any::Any;
 use std::fmt::Display;
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 use winit::dpi::{Position, Size};
 use winit::event::DeviceId;
-use winit::event_loop::EventLoop as WEventLoop;
+use winit::event_loop::{ControlFlow, EventLoop as WEventLoop};
 use winit::monitor::MonitorHandle;
 use winit::window::{
     CursorIcon, Fullscreen, Icon, UserAttentionType, Window as WWindow, WindowBuilder, WindowId,
 };
 
 bitflags::bitflags! {
-    pub struct BackendFlags: u32 {
+    #[derive(Default)]
+    pub struct BackendFlags: u64 {
         const MT_SAFE = 1 << 0;
         const WINIT_SET_ALWAYS_ON_TOP = 1 << 1;
         const WINIT_SET_DECORATIONS = 1 << 2;
@@ -41,11 +46,59 @@ bitflags::bitflags! {
         const SINGLE_THREADED = 1 << 22;
         const WINIT_SET_CURSOR_POSITION = 1 << 23;
         const MANUAL_VERIFICATION = 1 << 24;
+        const CLIENT_WINDOWS = 1 << 25;
+        const CONNECTION_PROXY = 1 << 26;
+        const REMOVE_SEAT = 1 << 27;
+        const TEST_PATTERN = 1 << 28;
+        const WINIT_SET_CURSOR_ICON = 1 << 30;
+        const WINIT_SET_CURSOR_GRAB = 1 << 31;
+        const CONFIGURE_MONITORS = 1 << 32;
+        const SET_MONITOR_MODE = 1 << 33;
+        const WINIT_SET_FULLSCREEN = 1 << 34;
+        const SET_SCALE_FACTOR = 1 << 35;
+        const TITLE_BAR_MAXIMIZE = 1 << 36;
+        const CLOSE_BUTTON = 1 << 37;
+        const WINIT_SET_BLUR = 1 << 38;
+        const WINIT_SET_CONTENT_PROTECTED = 1 << 39;
+        const KEY_REPEAT = 1 << 40;
+        const LOCK_STATE = 1 << 41;
+        const ABSOLUTE_POINTER = 1 << 42;
+        const STACKING_ORDER = 1 << 43;
+        const EVENT_LOOP_ENV = 1 << 44;
+        const WM_FOCUS_POLICIES = 1 << 45;
+        const MAXIMIZE_BUTTON = 1 << 46;
+        const DRAG_RESIZE = 1 << 47;
+        const DRAG_MOVE = 1 << 48;
+        const MINIMIZE_BUTTON = 1 << 49;
+        /// The backend fabricates windows and events without a display server. Tests with
+        /// this flag run only on such backends and tests without it only on the others.
+        const MOCK = 1 << 50;
+        const WM_SHORTCUTS = 1 << 51;
+        const TOUCH = 1 << 52;
+        const PAGER = 1 << 53;
+        /// The WM can change the state of windows on its own initiative.
+        const WM_ACTIONS = 1 << 54;
+        const WM_REFUSE_RESIZE = 1 << 55;
+        /// The backend can make winit emit `Suspended` and `Resumed`. No backend supports
+        /// this yet. X11 has no application lifecycle, neither output changes nor signals
+        /// make winit suspend, so winit only emits the initial `Resumed` there.
+        const SUSPEND = 1 << 56;
+        const WINIT_SET_WINDOW_LEVEL = 1 << 57;
+        /// The backend implements `Instance::capture_window`.
+        const CAPTURE_WINDOW = 1 << 58;
+        /// Every device added to a seat is a distinct device. Without this flag, the
+        /// devices of a seat may be backed by a single device, e.g. XTest's.
+        const DISTINCT_DEVICES = 1 << 59;
+        /// The backend can tell which window holds the pointer grab. See
+        /// `WindowProperties::cursor_grabbed`.
+        const POINTER_GRAB_WINDOW = 1 << 60;
+        /// Monitors configured with `configure_monitors` can have different scales.
+        const PER_MONITOR_SCALE = 1 << 61;
     }
 }
 
 pub fn non_requirement_flags() -> BackendFlags {
-    BackendFlags::SINGLE_THREADED | BackendFlags::MANUAL_VERIFICATION
+    BackendFlags::SINGLE_THREADED | BackendFlags::MANUAL_VERIFICATION | BackendFlags::MOCK
 }
 
 pub trait Backend: Sync {
@@ -61,7 +114,7 @@ pub trait Instance {
     fn take_screenshot(&self);
     fn before_poll(&self);
     fn create_dnd_path(&self, file: &str) -> PathBuf;
-    fn start_dnd_process(&self, path: &Path) -> Box<dyn DndProcess>;
+    fn start_dnd_process(&self, paths: &[&Path]) -> Box<dyn DndProcess>;
     fn redraw_requested_scenarios(&self) -> usize;
     fn cursor_grabbed<'a>(&'a self, grab: bool) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
         let _ = grab;
@@ -70,10 +123,362 @@ pub trait Instance {
     fn create_seat(&self) -> Box<dyn Seat> {
         unimplemented!();
     }
+    /// Makes the system suspend the application. See `BackendFlags::SUSPEND`.
+    fn suspend(&self) {
+        unimplemented!();
+    }
+    /// Makes the system resume the application. See `BackendFlags::SUSPEND`.
+    fn resume(&self) {
+        unimplemented!();
+    }
     fn enable_second_monitor(&self, enabled: bool) {
         let _ = enabled;
         unimplemented!();
     }
+    /// Replaces the connected monitors. The first monitor becomes the primary monitor.
+    ///
+    /// Monitors with different scales require `PER_MONITOR_SCALE`. Backends without it
+    /// panic if the scales differ.
+    fn configure_monitors(&self, monitors: &[MonitorConfig]) {
+        let _ = monitors;
+        unimplemented!();
+    }
+    /// Switches the monitor at `monitor_index` to a mode with the given size and refresh
+    /// rate. The mode is created if it does not exist. The position of the monitor is
+    /// preserved.
+    fn set_monitor_mode(&self, monitor_index: usize, width: u32, height: u32, refresh_mhz: u32) {
+        let _ = monitor_index;
+        let _ = width;
+        let _ = height;
+        let _ = refresh_mhz;
+        unimplemented!();
+    }
+    /// Sets the scale factor of all connected monitors.
+    fn set_scale_factor(&self, scale_factor: f64) {
+        let _ = scale_factor;
+        unimplemented!();
+    }
+    /// Returns the backend ids of all windows that are currently owned by winit.
+    fn client_windows(&self) -> Vec<u64> {
+        unimplemented!();
+    }
+    /// Returns the backend ids of the mapped windows from bottom to top.
+    fn stacking_order(&self) -> Vec<u64> {
+        unimplemented!();
+    }
+    /// Changes the policies of the window manager. Windows that already exist are not
+    /// affected by the new focus policy.
+    fn configure_wm(&self, config: WmConfig) {
+        let _ = config;
+        unimplemented!();
+    }
+    /// Returns the number of keyboard shortcuts grabbed by the window manager that have
+    /// been pressed. See `WmConfig::super_shortcuts`.
+    fn wm_shortcuts(&self) -> u32 {
+        unimplemented!();
+    }
+    /// Creates an event loop with additional environment variables set.
+    ///
+    /// The variables stay set until the event loop is dropped. Since the environment is
+    /// shared by all threads, other threads that set environment variables block until then.
+    fn create_event_loop_with_env(&self, env: &[(&str, &str)]) -> Box<dyn EventLoop> {
+        let _ = env;
+        unimplemented!();
+    }
+    /// Creates an event loop whose connection to the display server goes through a proxy.
+    fn create_proxied_event_loop(&self) -> (Box<dyn EventLoop>, Box<dyn ConnectionProxy>) {
+        unimplemented!();
+    }
+    /// Creates an event loop that connects through an existing proxy, e.g. to reconnect
+    /// after the proxy has severed the connection of another event loop.
+    fn reconnect_proxied_event_loop(&self, proxy: &dyn ConnectionProxy) -> Box<dyn EventLoop> {
+        let _ = proxy;
+        unimplemented!();
+    }
+    /// Returns the rendered contents of the window without decorations.
+    fn capture_window(&self, window: &dyn Window) -> Image {
+        let _ = window;
+        unimplemented!();
+    }
+    /// Returns the CPU time used by the display server so far.
+    fn server_cpu_time(&self) -> Option<Duration> {
+        None
+    }
+    /// Returns the peak resident set size of the display server in KiB.
+    fn server_peak_rss_kb(&self) -> Option<u64> {
+        None
+    }
+    /// Returns the vendor, version, and extensions of the display server.
+    fn server_info(&self) -> Option<ServerInfo> {
+        None
+    }
+    /// Returns the name of the cursor that is currently displayed, if it has a name.
+    fn cursor_icon(&self) -> Option<String> {
+        unimplemented!();
+    }
+    /// Returns the position of the core pointer in screen coordinates.
+    fn cursor_position(&self) -> (i32, i32) {
+        unimplemented!();
+    }
+    /// Hands the display server over to the caller. The server is no longer terminated when
+    /// the instance is dropped but when the returned value is dropped, possibly on another
+    /// thread. `None` if the backend does not support this. See `--keep-failed-alive`.
+    fn detach_server(&self) -> Option<Box<dyn Send>> {
+        None
+    }
+    /// Returns an object that checks whether the instance leaks resources. It must not keep
+    /// the instance alive.
+    fn leak_check(&self) -> Box<dyn LeakCheck> {
+        Box::new(NoLeakCheck)
+    }
+    /// Writes the internal state of the backend, e.g. the window table of the window
+    /// manager, into `dir`. Called when a test times out.
+    fn dump_diagnostics(&self, dir: &Path) {
+        let _ = dir;
+    }
+}
+
+/// Checks that the resources of an instance are released after a test.
+pub trait LeakCheck {
+    /// Returns the resources that are still alive after all windows and event loops of the
+    /// test have been dropped. The instance is still alive at this point.
+    fn windows<'a>(&'a self) -> Pin<Box<dyn Future<Output = Vec<String>> + 'a>>;
+    /// Returns the resources that are still alive after the instance has been dropped and
+    /// all of its tasks have terminated.
+    fn instance(&self) -> Vec<String>;
+}
+
+struct NoLeakCheck;
+
+impl LeakCheck for NoLeakCheck {
+    fn windows<'a>(&'a self) -> Pin<Box<dyn Future<Output = Vec<String>> + 'a>> {
+        Box::pin(std::future::ready(vec![]))
+    }
+
+    fn instance(&self) -> Vec<String> {
+        vec![]
+    }
+}
+
+impl<'a> dyn Instance + 'a {
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn num_client_windows(&self, n: usize) {
+        loop {
+            let windows = self.client_windows();
+            if windows.len() == n {
+                return;
+            }
+            log::trace!("Client windows: {:?}", windows);
+            sleep_ms(10).await;
+        }
+    }
+
+    /// Asserts that `windows` appear in `stacking_order` in this order, from bottom to top.
+    pub fn assert_stacking_order(&self, windows: &[&dyn Window]) {
+        let order = self.stacking_order();
+        let mut prev = None;
+        for window in windows {
+            let id = u64::from(window.winit_id());
+            let position = match order.iter().position(|&w| w == id) {
+                Some(p) => p,
+                None => panic!(
+                    "Window {} is not in the stacking order {:?}",
+                    window.id(),
+                    order
+                ),
+            };
+            if let Some((prev, prev_window)) = prev {
+                if position < prev {
+                    panic!(
+                        "Window {} is stacked below window {}: {:?}",
+                        window.id(),
+                        prev_window,
+                        order
+                    );
+                }
+            }
+            prev = Some((position, window.id().to_string()));
+        }
+    }
+
+    /// Waits until the displayed cursor has one of the names in `names`.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn cursor_icon_in(&self, names: &[&str]) -> String {
+        loop {
+            if let Some(name) = self.cursor_icon() {
+                if names.contains(&&*name) {
+                    return name;
+                }
+                log::trace!("Cursor icon: {}", name);
+            }
+            sleep_ms(10).await;
+        }
+    }
+
+    /// Waits until the window manager has handled `n` shortcuts.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn wm_shortcuts_handled(&self, n: u32) {
+        loop {
+            let handled = self.wm_shortcuts();
+            if handled == n {
+                return;
+            }
+            log::trace!("Handled shortcuts: {}", handled);
+            sleep_ms(10).await;
+        }
+    }
+
+    /// Waits until the core pointer is at `(x, y)` in screen coordinates.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn cursor_at(&self, x: i32, y: i32) {
+        loop {
+            let pos = self.cursor_position();
+            if pos == (x, y) {
+                return;
+            }
+            log::trace!("Cursor position: {:?}", pos);
+            sleep_ms(10).await;
+        }
+    }
+
+    /// Types `text` into the focused window of the seat via Compose sequences using a
+    /// keyboard that is removed afterwards. See `Keyboard::compose`.
+    ///
+    /// The client must load `keyboard::compose_file` and use the local input method of
+    /// Xlib, i.e. `XCOMPOSEFILE` must point to the file and `XMODIFIERS` must be
+    /// `@im=none`.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn compose(&self, text: &str) {
+        let kb = self.add_keyboard();
+        kb.compose(text).await;
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MonitorConfig {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    /// In Hz.
+    pub refresh_rate: u16,
+    pub scale: f64,
+}
+
+/// How the window manager moves the focus of the default seat.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FocusPolicy {
+    /// The window manager never moves the focus in response to pointer input.
+    Manual,
+    /// Clicking a window focuses it.
+    ClickToFocus,
+    /// Moving the pointer into a window focuses it.
+    FocusFollowsMouse,
+}
+
+impl Default for FocusPolicy {
+    fn default() -> Self {
+        Self::Manual
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct WmConfig {
+    pub focus_policy: FocusPolicy,
+    /// Whether newly mapped windows and windows requesting activation via
+    /// `_NET_ACTIVE_WINDOW` are focused.
+    pub focus_stealing: bool,
+    /// The minimum border width of frames created afterwards. Dragging the border of a
+    /// frame resizes the window.
+    pub border_width: u16,
+    /// Whether the window manager grabs Super+D like desktop environments grab their
+    /// global shortcuts. The key events of the shortcut are not delivered to the focused
+    /// window. `Instance::configure_wm` wakes up the window manager, which then applies
+    /// the grab asynchronously.
+    pub super_shortcuts: bool,
+    /// Whether windows that are mapped or request activation via `_NET_ACTIVE_WINDOW` are
+    /// marked with `_NET_WM_STATE_DEMANDS_ATTENTION` instead of being focused. Only has an
+    /// effect if `focus_stealing` is not set. This is the focus stealing prevention of
+    /// desktop environments. The mark is removed when the window is focused.
+    pub demand_attention: bool,
+    /// Whether the window manager refuses the size changes that clients request for their
+    /// windows. As required by the ICCCM, the client is instead sent a synthetic
+    /// `ConfigureNotify` event with the current geometry of the window.
+    pub refuse_resize: bool,
+}
+
+/// A request that a pager, i.e. a client other than the owner of a window, sends to the
+/// WM on behalf of the window.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PagerRequest {
+    /// `WM_CHANGE_STATE` with `IconicState`.
+    Iconify,
+    /// `_NET_WM_STATE` adding or removing `_NET_WM_STATE_HIDDEN`.
+    SetHidden(bool),
+    /// `_NET_ACTIVE_WINDOW` with the source indication of a pager.
+    Activate,
+    /// `_NET_WM_STATE` adding or removing `_NET_WM_STATE_FULLSCREEN`.
+    SetFullscreen(bool),
+}
+
+/// An edge or corner of a window frame.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ResizeEdge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl ResizeEdge {
+    /// Returns -1 for edges on the left, 1 for edges on the right, and 0 otherwise.
+    pub fn horizontal(self) -> i32 {
+        match self {
+            Self::Left | Self::TopLeft | Self::BottomLeft => -1,
+            Self::Right | Self::TopRight | Self::BottomRight => 1,
+            Self::Top | Self::Bottom => 0,
+        }
+    }
+
+    /// Returns -1 for edges at the top, 1 for edges at the bottom, and 0 otherwise.
+    pub fn vertical(self) -> i32 {
+        match self {
+            Self::Top | Self::TopLeft | Self::TopRight => -1,
+            Self::Bottom | Self::BottomLeft | Self::BottomRight => 1,
+            Self::Left | Self::Right => 0,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct ProtocolStats {
+    pub requests: u64,
+    pub replies: u64,
+    pub events: u64,
+    pub errors: u64,
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct ProxyFaults {
+    /// Latency added to all data flowing through the proxy.
+    pub latency: Duration,
+    /// If set, data is held back and forwarded in batches at this interval.
+    pub batch_interval: Option<Duration>,
+}
+
+pub trait ConnectionProxy {
+    fn stats(&self) -> ProtocolStats;
+    fn reset_stats(&self);
+    fn set_faults(&self, faults: ProxyFaults);
+    /// Closes all connections going through the proxy.
+    ///
+    /// The proxy keeps accepting new connections afterwards.
+    fn sever(&self);
+    fn any(&self) -> &dyn Any;
 }
 
 pub trait DndProcess {
@@ -83,12 +488,50 @@ pub trait DndProcess {
 
 pub trait EventLoop {
     fn events(&self) -> Box<dyn EventStream>;
+    /// Returns a stream of the events of `window`, i.e. its window events and its
+    /// `RedrawRequested` events. Events of other windows and events that do not belong to a
+    /// window stay in the queue for the other streams of the event loop.
+    fn window_events(&self, window: WindowId) -> Box<dyn EventStream>;
     fn changed<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>>;
     fn create_window(&self, builder: WindowBuilder) -> Box<dyn Window>;
     fn with_winit<'a>(&self, f: Box<dyn FnOnce(&mut WEventLoop<UserEvent>) + 'a>);
     fn barrier<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>>;
+    /// Completes once the connection to the display server has been lost.
+    fn disconnected<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>>;
+    /// Returns the error with which the winit event loop aborted after the connection to
+    /// the display server has been lost. `None` if the connection is intact or winit did
+    /// not report the loss.
+    fn connection_error(&self) -> Option<String> {
+        None
+    }
+    /// Returns up to `RECENT_EVENTS` of the most recently received events, oldest first,
+    /// including events that have already been consumed.
+    fn recent_events(&self) -> Vec<(Instant, Event)> {
+        vec![]
+    }
+    /// Returns statistics of the queue of received events that have not been consumed.
+    fn queue_stats(&self) -> EventQueueStats {
+        Default::default()
+    }
+    /// Sets the control flow with which the winit event loop is run. The default is
+    /// `ControlFlow::Wait`. Wakeups other than `StartCause::Init` are reported as
+    /// `Event::NewEvents`.
+    fn set_control_flow(&self, control_flow: ControlFlow) {
+        let _ = control_flow;
+        unimplemented!();
+    }
+    /// Sets whether `Event::MainEventsCleared` and `Event::RedrawEventsCleared` are
+    /// reported. They are only reported for iterations of the event loop that deliver
+    /// other events. The default is `false`.
+    fn report_cleared_events(&self, report: bool) {
+        let _ = report;
+        unimplemented!();
+    }
 }
 
+/// The number of events that event loops retain for `EventLoop::recent_events`.
+pub const RECENT_EVENTS: usize = 100;
+
 impl dyn EventLoop {
     pub fn send_event(&self, event: UserEvent) {
         self.with_winit(Box::new(|el| el.create_proxy().send_event(event).unwrap()));
@@ -106,8 +549,13 @@ impl dyn EventLoop {
         res
     }
 
+    /// Returns the next window event of `window`. See `EventLoop::window_events`.
+    pub async fn window_event_for(&self, window: WindowId) -> WindowEventExt {
+        self.window_events(window).window_event().await
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn num_available_monitors(&self, n: usize) {
-        log::info!("Waiting for number of available monitors to become {}", n);
         loop {
             if self.available_monitors().len() == n {
                 return;
@@ -124,15 +572,41 @@ pub struct BackendIcon {
     pub height: u32,
 }
 
+/// The region behind a window that the compositor is asked to blur.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum BlurRegion {
+    /// The whole window.
+    Window,
+    /// The union of the rectangles `(x, y, width, height)` relative to the window.
+    Rects(Vec<(i32, i32, u32, u32)>),
+}
+
 impl Into<Icon> for BackendIcon {
     fn into(self) -> Icon {
         Icon::from_rgba(self.rgba, self.width, self.height).unwrap()
     }
 }
 
+impl BackendIcon {
+    /// Returns the frame that a window manager would display at `width`x`height`: the
+    /// smallest frame that is at least that large or, if there is none, the largest frame.
+    pub fn closest(frames: &[BackendIcon], width: u32, height: u32) -> Option<&BackendIcon> {
+        let area = |f: &&BackendIcon| f.width as u64 * f.height as u64;
+        frames
+            .iter()
+            .filter(|f| f.width >= width && f.height >= height)
+            .min_by_key(area)
+            .or_else(|| frames.iter().max_by_key(area))
+    }
+}
+
 pub trait WindowProperties {
     fn mapped(&self) -> bool;
     fn always_on_top(&self) -> bool;
+    /// Whether the window has `_NET_WM_STATE_BELOW` set.
+    fn always_on_bottom(&self) -> bool {
+        unimplemented!();
+    }
     fn decorations(&self) -> bool;
     fn x(&self) -> i32;
     fn y(&self) -> i32;
@@ -144,10 +618,20 @@ pub trait WindowProperties {
     fn maximized(&self) -> Option<bool>;
     fn minimized(&self) -> Option<bool>;
     fn resizable(&self) -> Option<bool>;
+    /// The first frame of the window icon.
     fn icon(&self) -> Option<BackendIcon>;
+    /// All frames of the window icon. See `BackendIcon::closest`.
+    fn icon_frames(&self) -> Vec<BackendIcon> {
+        self.icon().into_iter().collect()
+    }
+    /// Whether the window has the urgency hint or `_NET_WM_STATE_DEMANDS_ATTENTION` set.
     fn attention(&self) -> bool;
     fn supports_transparency(&self) -> bool;
     fn dragging(&self) -> bool;
+    /// Whether the window is being resized by dragging its frame.
+    fn resizing(&self) -> bool {
+        unimplemented!();
+    }
     fn fullscreen(&self) -> bool;
     fn class(&self) -> Option<String> {
         unimplemented!();
@@ -155,6 +639,83 @@ pub trait WindowProperties {
     fn instance(&self) -> Option<String> {
         unimplemented!();
     }
+    /// The number of times the client has asked the window manager to map the window.
+    fn map_requests(&self) -> u32 {
+        unimplemented!();
+    }
+    /// The region behind the window that should be blurred or `None` if no blur has been
+    /// requested.
+    fn blur(&self) -> Option<BlurRegion> {
+        unimplemented!();
+    }
+    /// Whether the contents of the window are protected from being captured.
+    fn content_protected(&self) -> bool {
+        unimplemented!();
+    }
+    /// The position of the window in the stacking order, 0 being the bottom-most window,
+    /// or `None` if the window is not mapped.
+    fn stacking_position(&self) -> Option<usize> {
+        unimplemented!();
+    }
+    /// Whether the pointer is currently grabbed by the window. This is not tracked by
+    /// `properties_changed`.
+    fn cursor_grabbed(&self) -> bool {
+        unimplemented!();
+    }
+    /// Whether the window requests decorations according to the `_MOTIF_WM_HINTS` stored
+    /// in the display server, or `None` if the window has not set the hints. This is not
+    /// tracked by `properties_changed`.
+    fn motif_decorations(&self) -> Option<bool> {
+        unimplemented!();
+    }
+}
+
+/// A copy of the properties of a window at a point in time.
+#[derive(Clone)]
+pub struct PropertySnapshot {
+    pub time: Instant,
+    pub mapped: bool,
+    pub always_on_top: bool,
+    pub decorations: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub title: Option<String>,
+    pub maximized: Option<bool>,
+    pub minimized: Option<bool>,
+    pub resizable: Option<bool>,
+    pub icon: Option<BackendIcon>,
+    pub attention: bool,
+    pub dragging: bool,
+    pub fullscreen: bool,
+}
+
+impl PropertySnapshot {
+    pub fn capture(p: &dyn WindowProperties) -> Self {
+        Self {
+            time: Instant::now(),
+            mapped: p.mapped(),
+            always_on_top: p.always_on_top(),
+            decorations: p.decorations(),
+            x: p.x(),
+            y: p.y(),
+            width: p.width(),
+            height: p.height(),
+            min_size: p.min_size(),
+            max_size: p.max_size(),
+            title: p.title(),
+            maximized: p.maximized(),
+            minimized: p.minimized(),
+            resizable: p.resizable(),
+            icon: p.icon(),
+            attention: p.attention(),
+            dragging: p.dragging(),
+            fullscreen: p.fullscreen(),
+        }
+    }
 }
 
 pub trait Window {
@@ -170,6 +731,62 @@ pub trait Window {
     /// left, right, top, bottom
     fn frame_extents(&self) -> (u32, u32, u32, u32);
     fn request_redraw(&self, scenario: usize);
+    /// Clears the region of the window to its background with exposures so that the
+    /// display server generates `Expose` events for it. A `width` or `height` of 0
+    /// extends the region to the edge of the window.
+    fn expose(&self, x: i32, y: i32, width: u32, height: u32) {
+        let _ = x;
+        let _ = y;
+        let _ = width;
+        let _ = height;
+        unimplemented!();
+    }
+    /// Returns the root coordinates of the center of the close button in the window
+    /// decorations.
+    fn close_button_position(&self) -> (i32, i32) {
+        unimplemented!();
+    }
+    /// Returns the root coordinates of the center of the maximize button in the window
+    /// decorations. Clicking the button toggles the maximized state.
+    fn maximize_button_position(&self) -> (i32, i32) {
+        unimplemented!();
+    }
+    /// Returns the root coordinates of the center of the minimize button in the window
+    /// decorations. Clicking the button iconifies the window.
+    fn minimize_button_position(&self) -> (i32, i32) {
+        unimplemented!();
+    }
+    /// Presses the left button of the default seat on the frame border at `edge`, moves the
+    /// pointer by `(dx, dy)`, and releases the button. The frame must have a border, see
+    /// `WmConfig::border_width`.
+    fn drag_resize_edge<'a>(
+        &'a self,
+        edge: ResizeEdge,
+        dx: i32,
+        dy: i32,
+    ) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        let _ = edge;
+        let _ = dx;
+        let _ = dy;
+        unimplemented!();
+    }
+    /// Makes the WM maximize or restore the window on its own initiative.
+    fn wm_set_maximized(&self, maximized: bool) {
+        let _ = maximized;
+        unimplemented!();
+    }
+    /// Sends `request` to the WM from a separate client.
+    fn pager_request(&self, request: PagerRequest) {
+        let _ = request;
+        unimplemented!();
+    }
+    /// Presses the left button of the default seat on the title bar, moves the pointer by
+    /// `(dx, dy)`, and releases the button.
+    fn drag_move<'a>(&'a self, dx: i32, dy: i32) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        let _ = dx;
+        let _ = dy;
+        unimplemented!();
+    }
     fn set_outer_position(&self, x: i32, y: i32) {
         let _ = x;
         let _ = y;
@@ -183,6 +800,45 @@ pub trait Window {
     fn ping<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
         unimplemented!();
     }
+    /// Draws the pattern described by `test_pattern` into the window, bypassing the
+    /// background color.
+    fn present_test_pattern(&self, seed: u32) -> Result<(), TestPatternError> {
+        let _ = seed;
+        unimplemented!();
+    }
+}
+
+/// The error returned by `Window::present_test_pattern`.
+#[derive(Debug)]
+pub enum TestPatternError {
+    /// The window is too large for the backend to draw the pattern into it.
+    TooLarge { width: u32, height: u32 },
+}
+
+impl Display for TestPatternError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TooLarge { width, height } => write!(
+                f,
+                "The window ({}x{}) is too large for the test pattern",
+                width, height
+            ),
+        }
+    }
+}
+
+/// Returns the RGBA color of the pixel at `(x, y)` of the test pattern identified by
+/// `seed`.
+///
+/// The pattern consists of 16x16 blocks whose colors depend on the seed and on the block
+/// position, so that shifted, scaled, or truncated contents can be told apart from the
+/// original.
+pub fn test_pattern(seed: u32, x: u32, y: u32) -> [u8; 4] {
+    let mut h = seed ^ (x / 16).wrapping_mul(0x9e37_79b9) ^ (y / 16).wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    [h as u8, (h >> 8) as u8, (h >> 16) as u8, 255]
 }
 
 pub const NONE_SIZE: Option<Size> = None;
@@ -236,6 +892,11 @@ impl dyn Window {
         self.winit().set_decorations(decorations);
     }
 
+    pub fn winit_request_redraw(&self) {
+        log::info!("Requesting a redraw of window {}", self.id());
+        self.winit().request_redraw();
+    }
+
     pub fn winit_set_visible(&self, visible: bool) {
         log::info!("Setting visibility of window {} to {}", self.id(), visible);
         self.winit().set_visible(visible);
@@ -250,6 +911,16 @@ impl dyn Window {
         self.winit().set_always_on_top(always_on_top);
     }
 
+    #[cfg(winit_window_level)]
+    pub fn winit_set_window_level(&self, level: winit::window::WindowLevel) {
+        log::info!(
+            "Setting window level of window {} to {:?}",
+            self.id(),
+            level
+        );
+        self.winit().set_window_level(level);
+    }
+
     pub fn winit_set_inner_size<S: Into<Size>>(&self, size: S) {
         let size = size.into();
         log::info!("Setting inner size of window {} to {:?}", self.id(), size);
@@ -275,6 +946,12 @@ impl dyn Window {
         self.winit().id()
     }
 
+    /// Returns the next window event of this window. Events of other windows stay in the
+    /// queue. See `EventLoop::window_events`.
+    pub async fn next_event(&self) -> WindowEventExt {
+        self.event_loop().window_event_for(self.winit_id()).await
+    }
+
     pub fn winit_set_minimized(&self, minimized: bool) {
         log::info!(
             "Setting minimized of window {} to {:?}",
@@ -328,59 +1005,60 @@ impl dyn Window {
         self.winit().set_window_icon(icon);
     }
 
-    pub async fn mapped(&self, mapped: bool) {
+    #[cfg(winit_blur)]
+    pub fn winit_set_blur(&self, blur: bool) {
+        log::info!("Setting blur of window {} to {}", self.id(), blur);
+        self.winit().set_blur(blur);
+    }
+
+    #[cfg(winit_content_protected)]
+    pub fn winit_set_content_protected(&self, protected: bool) {
         log::info!(
-            "Waiting for window {} to become mapped {}",
+            "Setting content protected of window {} to {}",
             self.id(),
-            mapped
+            protected
         );
+        self.winit().set_content_protected(protected);
+    }
+
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn mapped(&self, mapped: bool) -> PropertySnapshot {
         self.await_property(|p| p.mapped() == mapped).await
     }
 
-    pub async fn always_on_top(&self, always_on_top: bool) {
-        log::info!(
-            "Waiting for window {} to become always-on-top {}",
-            self.id(),
-            always_on_top
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn always_on_top(&self, always_on_top: bool) -> PropertySnapshot {
         self.await_property(|p| p.always_on_top() == always_on_top)
             .await
     }
 
-    pub async fn decorations(&self, decorations: bool) {
-        log::info!(
-            "Waiting for window {} to become decorations {}",
-            self.id(),
-            decorations,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn always_on_bottom(&self, always_on_bottom: bool) -> PropertySnapshot {
+        self.await_property(|p| p.always_on_bottom() == always_on_bottom)
+            .await
+    }
+
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn decorations(&self, decorations: bool) -> PropertySnapshot {
         self.await_property(|p| p.decorations() == decorations)
             .await
     }
 
-    pub async fn title(&self, title: &str) {
-        log::info!("Waiting for window {} to become title {}", self.id(), title,);
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn title(&self, title: &str) -> PropertySnapshot {
         self.await_property(|p| p.title().as_deref() == Some(title))
             .await
     }
 
-    pub async fn inner_size(&self, width: u32, height: u32) {
-        log::info!(
-            "Waiting for window {} to become inner size {}x{}",
-            self.id(),
-            width,
-            height,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn inner_size(&self, width: u32, height: u32) -> PropertySnapshot {
         self.await_property(|p| p.width() == width && p.height() == height)
             .await
     }
 
-    pub async fn icon(&self, icon: Option<&BackendIcon>) {
-        log::info!(
-            "Waiting for window {} to become icon {}",
-            self.id(),
-            icon.is_some()
-        );
-        self.await_property(|p| p.icon().as_ref() == icon).await;
+    #[tracing::instrument(level = "info", skip(self, icon), fields(window = %self.id(), icon = icon.is_some()))]
+    pub async fn icon(&self, icon: Option<&BackendIcon>) -> PropertySnapshot {
+        self.await_property(|p| p.icon().as_ref() == icon).await
     }
 
     pub fn inner_offset(&self) -> (i32, i32) {
@@ -388,109 +1066,84 @@ impl dyn Window {
         (left as i32, top as i32)
     }
 
-    pub async fn dragging(&self, dragging: bool) {
-        log::info!(
-            "Waiting for window {} to become dragging {}",
-            self.id(),
-            dragging,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn dragging(&self, dragging: bool) -> PropertySnapshot {
         self.await_property(|p| p.dragging() == dragging).await
     }
 
-    pub async fn outer_position(&self, x: i32, y: i32) {
-        log::info!(
-            "Waiting for window {} to become outer position {}x{}",
-            self.id(),
-            x,
-            y,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn resizing(&self, resizing: bool) -> PropertySnapshot {
+        self.await_property(|p| p.resizing() == resizing).await
+    }
+
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn outer_position(&self, x: i32, y: i32) -> PropertySnapshot {
         self.await_property(|p| p.x() == x && p.y() == y).await
     }
 
-    pub async fn maximized(&self, maximized: bool) {
-        log::info!(
-            "Waiting for window {} to become maximized {}",
-            self.id(),
-            maximized
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn maximized(&self, maximized: bool) -> PropertySnapshot {
         self.await_property(|p| p.maximized() == Some(maximized))
             .await
     }
 
-    pub async fn minimized(&self, minimized: bool) {
-        log::info!(
-            "Waiting for window {} to become minimized {}",
-            self.id(),
-            minimized
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn fullscreen(&self, fullscreen: bool) -> PropertySnapshot {
+        self.await_property(|p| p.fullscreen() == fullscreen).await
+    }
+
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn minimized(&self, minimized: bool) -> PropertySnapshot {
         self.await_property(|p| p.minimized() == Some(minimized))
             .await
     }
 
-    pub async fn min_size(&self, size: Option<(u32, u32)>) {
-        log::info!(
-            "Waiting for window {} to become min size {:?}",
-            self.id(),
-            size
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn min_size(&self, size: Option<(u32, u32)>) -> PropertySnapshot {
         self.await_property(|p| p.min_size() == size).await
     }
 
-    pub async fn max_size(&self, size: Option<(u32, u32)>) {
-        log::info!(
-            "Waiting for window {} to become max size {:?}",
-            self.id(),
-            size
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn max_size(&self, size: Option<(u32, u32)>) -> PropertySnapshot {
         self.await_property(|p| p.max_size() == size).await
     }
 
-    pub async fn attention(&self, attention: bool) {
-        log::info!(
-            "Waiting for window {} to become attention {:?}",
-            self.id(),
-            attention,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn attention(&self, attention: bool) -> PropertySnapshot {
         self.await_property(|p| p.attention() == attention).await
     }
 
-    pub async fn class(&self, class: &str) {
-        log::info!(
-            "Waiting for window {} to become class {:?}",
-            self.id(),
-            class,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn class(&self, class: &str) -> PropertySnapshot {
         self.await_property(|p| p.class().as_deref() == Some(class))
             .await
     }
 
-    pub async fn instance(&self, instance: &str) {
-        log::info!(
-            "Waiting for window {} to become instance {:?}",
-            self.id(),
-            instance,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn instance(&self, instance: &str) -> PropertySnapshot {
         self.await_property(|p| p.instance().as_deref() == Some(instance))
             .await
     }
 
-    pub async fn resizable(&self, resizable: bool) {
-        log::info!(
-            "Waiting for window {} to become resizable {:?}",
-            self.id(),
-            resizable,
-        );
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn resizable(&self, resizable: bool) -> PropertySnapshot {
         self.await_property(|p| p.resizable() == Some(resizable))
             .await
     }
 
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn blur(&self, blur: Option<&BlurRegion>) -> PropertySnapshot {
+        self.await_property(|p| p.blur().as_ref() == blur).await
+    }
+
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn content_protected(&self, protected: bool) -> PropertySnapshot {
+        self.await_property(|p| p.content_protected() == protected)
+            .await
+    }
+
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
     pub async fn winit_inner_size(&self, width: u32, height: u32) {
-        log::info!(
-            "Waiting for window {} to become winit inner size {}x{}",
-            self.id(),
-            width,
-            height,
-        );
         self.await_winit(|w| {
             let is = w.inner_size();
             let os = w.outer_size();
@@ -504,13 +1157,8 @@ impl dyn Window {
         .await
     }
 
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
     pub async fn winit_outer_position(&self, x: i32, y: i32) {
-        log::info!(
-            "Waiting for window {} to become winit outer position {}x{}",
-            self.id(),
-            x,
-            y,
-        );
         self.await_winit(|w| {
             let o_pos = w.outer_position().unwrap();
             let i_pos = w.inner_position().unwrap();
@@ -520,6 +1168,13 @@ impl dyn Window {
         .await
     }
 
+    #[tracing::instrument(level = "info", skip(self), fields(window = %self.id()))]
+    pub async fn winit_fullscreen(&self, fullscreen: bool) {
+        self.await_winit(|w| w.fullscreen().is_some() == fullscreen)
+            .await
+    }
+
+    #[tracing::instrument(level = "info", skip_all, fields(window = %self.id()))]
     pub async fn await_winit<F: FnMut(&WWindow) -> bool>(&self, mut f: F) {
         loop {
             if f(self.winit()) {
@@ -529,10 +1184,29 @@ impl dyn Window {
         }
     }
 
-    pub async fn await_property<F: FnMut(&dyn WindowProperties) -> bool>(&self, mut f: F) {
+    /// Waits until the window is stacked above `other`.
+    #[tracing::instrument(level = "info", skip(self, other), fields(window = %self.id(), other = %other.id()))]
+    pub async fn above(&self, other: &dyn Window) {
+        loop {
+            let a = self.properties().stacking_position();
+            let b = other.properties().stacking_position();
+            if let (Some(a), Some(b)) = (a, b) {
+                if a > b {
+                    return;
+                }
+            }
+            futures::future::select(self.properties_changed(), other.properties_changed()).await;
+        }
+    }
+
+    /// Waits until `f` returns true and returns the properties at that time.
+    pub async fn await_property<F: FnMut(&dyn WindowProperties) -> bool>(
+        &self,
+        mut f: F,
+    ) -> PropertySnapshot {
         loop {
             if f(self.properties()) {
-                return;
+                return PropertySnapshot::capture(self.properties());
             }
             self.properties_changed().await;
         }
@@ -543,14 +1217,75 @@ pub trait Seat {
     fn add_keyboard(&self) -> Box<dyn Keyboard>;
     fn add_mouse(&self) -> Box<dyn Mouse>;
     fn add_touchscreen(&self) -> Box<dyn Touchscreen>;
+    /// Adds a tablet-like pointer that positions the cursor in screen coordinates.
+    fn add_absolute_pointer(&self) -> Box<dyn AbsolutePointer> {
+        unimplemented!();
+    }
     fn focus(&self, window: &dyn Window);
     fn un_focus(&self);
+    /// Returns whether the display server considers `window` focused by this seat.
+    fn is_focused(&self, window: &dyn Window) -> bool {
+        let _ = window;
+        unimplemented!();
+    }
+    /// Moves the pointer of the seat by `(dx, dy)` via a mouse that is created on first use
+    /// and shared by all calls. Unlike `set_cursor_position`, the movement is subject to
+    /// pointer confinement.
+    fn move_pointer(&self, dx: i32, dy: i32) {
+        let _ = dx;
+        let _ = dy;
+        unimplemented!();
+    }
     fn set_layout(&self, layout: Layout);
+    /// Removes the seat from the display server. Its devices become floating.
+    fn remove(&self) {
+        unimplemented!();
+    }
     fn set_cursor_position(&self, x: i32, y: i32);
     fn cursor_position(&self) -> (i32, i32);
     fn is(&self, device_id: DeviceId) -> bool;
 }
 
+impl<'a> dyn Seat + 'a {
+    /// Focuses `window` and waits until winit has reported the focus and the display server
+    /// has confirmed it.
+    #[tracing::instrument(level = "info", skip_all, fields(window = %window.id()))]
+    pub async fn focus_and_wait(&self, window: &dyn Window, events: &mut dyn EventStream) {
+        self.focus(window);
+        self.focused(window, events).await;
+    }
+
+    /// Waits until winit has reported that `window` has been focused and the display server
+    /// confirms that the window has the focus of this seat. Focus events of other windows
+    /// are consumed.
+    #[tracing::instrument(level = "info", skip_all, fields(window = %window.id()))]
+    pub async fn focused(&self, window: &dyn Window, events: &mut dyn EventStream) {
+        log::info!("Waiting for window {} to be focused", window.id());
+        loop {
+            let (we, focused) = events.window_focus_event().await;
+            if we.window_id == window.winit().id() && focused {
+                break;
+            }
+        }
+        while !self.is_focused(window) {
+            sleep_ms(10).await;
+        }
+    }
+
+    /// Waits until the pointer of the seat is at `(x, y)` in screen coordinates.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn cursor_at(&self, x: i32, y: i32) {
+        loop {
+            let pos = self.cursor_position();
+            if pos == (x, y) {
+                return;
+            }
+            log::trace!("Cursor position: {:?}", pos);
+            sleep_ms(10).await;
+        }
+    }
+}
+
 pub trait BackendDeviceId {
     fn is(&self, device: DeviceId) -> bool;
 }
@@ -560,12 +1295,93 @@ pub trait Device {
 }
 
 pub trait Keyboard: Device {
+    /// Presses `key`. The key is released when the returned object is dropped. Dead keys
+    /// are pressed like any other key.
     fn press(&self, key: Key) -> Box<dyn PressedKey>;
+    /// The layout of the seat the keyboard is attached to.
+    fn layout(&self) -> Layout;
+    /// Presses `key` with key repeat enabled. After `delay`, the key repeats `rate` times
+    /// per second until it is released. Keys pressed with `press` do not repeat.
+    fn press_with_repeat(&self, key: Key, rate: u32, delay: Duration) -> Box<dyn PressedKey> {
+        let _ = key;
+        let _ = rate;
+        let _ = delay;
+        unimplemented!();
+    }
+    /// Sets the numlock and capslock state of the seat the keyboard is attached to. Returns
+    /// once the display server has applied the state.
+    fn set_lock_state(&self, numlock: bool, capslock: bool) {
+        let _ = numlock;
+        let _ = capslock;
+        unimplemented!();
+    }
+}
+
+/// The time between two key presses or releases in `type_text`.
+const TYPING_DELAY_MS: u64 = 15;
+
+impl<'a> dyn Keyboard + 'a {
+    /// Types `text` using the current layout.
+    ///
+    /// Panics if a character cannot be produced by the layout.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn type_text(&self, text: &str) {
+        let layout = self.layout();
+        log::info!("Typing {:?} with layout {:?}", text, layout);
+        for c in text.chars() {
+            let (key, level) = match layout.key_for_char(c) {
+                Some(k) => k,
+                None => panic!("{:?} cannot be typed with layout {:?}", c, layout),
+            };
+            let modifier = match level {
+                Level::Base => None,
+                Level::Shift => Some(self.press(Key::KeyLeftshift)),
+                Level::AltGr => Some(self.press(Key::KeyRightalt)),
+            };
+            if modifier.is_some() {
+                sleep_ms(TYPING_DELAY_MS).await;
+            }
+            let pressed = self.press(key);
+            sleep_ms(TYPING_DELAY_MS).await;
+            drop(pressed);
+            if let Some(modifier) = modifier {
+                sleep_ms(TYPING_DELAY_MS).await;
+                drop(modifier);
+            }
+            sleep_ms(TYPING_DELAY_MS).await;
+        }
+    }
+
+    /// Types `text` via Compose sequences. Each character is produced by the Compose key
+    /// followed by the two characters of its sequence in `keyboard::compose_file`.
+    ///
+    /// Panics if a character has no Compose sequence.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn compose(&self, text: &str) {
+        for c in text.chars() {
+            let [c1, c2] = match compose_sequence(c) {
+                Some(s) => s,
+                None => panic!("{:?} has no Compose sequence", c),
+            };
+            log::info!("Composing {:?} from {:?} and {:?}", c, c1, c2);
+            self.press(Key::KeyCompose);
+            sleep_ms(TYPING_DELAY_MS).await;
+            self.type_text(&format!("{}{}", c1, c2)).await;
+        }
+    }
 }
 
 pub trait Mouse: Device {
     fn press(&self, button: Button) -> Box<dyn PressedButton>;
+    /// Moves the device by `dx`, `dy`. Backends must disable pointer acceleration for the
+    /// device so that the cursor moves by exactly this amount.
     fn move_(&self, dx: i32, dy: i32);
+    /// Moves the cursor to the absolute position `x`, `y` by moving this device.
+    fn move_to(&self, x: i32, y: i32) {
+        let _ = x;
+        let _ = y;
+        unimplemented!();
+    }
     fn scroll(&self, dx: i32, dy: i32);
 }
 
@@ -582,6 +1398,13 @@ pub enum Button {
     Back,
 }
 
+pub trait AbsolutePointer: Device {
+    /// Moves the cursor to `(x, y)` in screen coordinates. Unlike
+    /// `Seat::set_cursor_position`, this generates regular motion events instead of
+    /// warping the pointer.
+    fn move_to(&self, x: i32, y: i32);
+}
+
 pub trait Touchscreen: Device {
     fn down(&self, x: i32, y: i32) -> Box<dyn Finger>;
 }
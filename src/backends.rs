@@ -1,7 +1,47 @@
 use crate::backend::Backend;
+use std::path::PathBuf;
 
+pub mod mock;
+#[cfg(feature = "plugins")]
+pub mod plugin;
 mod x11;
 
-pub fn backends() -> Vec<Box<dyn Backend>> {
-    vec![x11::backend()]
+/// Prepares the built-in backends without running tests, i.e. builds the x11 module.
+pub fn setup() {
+    x11::setup();
+}
+
+/// Whether the backend runs if no backend has been selected explicitly. The mock backend
+/// only runs the self-tests of the harness and must be selected with `--backend mock`.
+pub fn runs_by_default(name: &str) -> bool {
+    name != "mock"
+}
+
+/// Returns the built-in backends whose names are accepted by `selected` followed by the
+/// backends loaded from `libs`.
+///
+/// Built-in backends that are not selected are not initialized. This allows the mock
+/// backend to run on systems without a display server.
+pub fn backends(selected: &dyn Fn(&str) -> bool, libs: &[PathBuf]) -> Vec<Box<dyn Backend>> {
+    let builtin: [(&str, fn() -> Box<dyn Backend>); 2] =
+        [("x11", x11::backend), ("mock", mock::backend)];
+    let builtin: Vec<_> = builtin
+        .iter()
+        .filter(|(name, _)| selected(name))
+        .map(|(_, backend)| backend())
+        .collect();
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "plugins")] {
+            builtin
+                .into_iter()
+                .chain(libs.iter().map(|l| plugin::load(l)))
+                .collect()
+        } else {
+            assert!(
+                libs.is_empty(),
+                "Loading backends from libraries requires the plugins feature"
+            );
+            builtin
+        }
+    }
 }
@@ -0,0 +1,532 @@
+//! A backend that fabricates windows and events in-process without a display server.
+//!
+//! The backend does not create winit event loops or windows. Tests that call into winit
+//! panic. It only exists to test the helpers of the harness, e.g. the `EventStream`
+//! combinators, the awaiters, and the runner. Such tests have the `MOCK` flag and use
+//! `mock_window` to drive the fabricated windows. Tests without this flag are not run on
+//! this backend.
+
+use crate::backend::{
+    Backend, BackendFlags, BackendIcon, DndProcess, EventLoop, Instance, Keyboard, Mouse, Seat,
+    Touchscreen, Window, WindowProperties,
+};
+use crate::event::{Event, UserEvent, WindowEvent, WindowEventExt};
+use crate::eventstream::EventStream;
+use crate::keyboard::Layout;
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt::Display;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll, Waker};
+use std::time::Instant;
+use winit::dpi::Size;
+use winit::event::DeviceId;
+use winit::event_loop::EventLoop as WEventLoop;
+use winit::window::{Window as WWindow, WindowBuilder, WindowId};
+
+static BACKEND: MockBackend = MockBackend;
+
+pub fn backend() -> Box<dyn Backend> {
+    Box::new(MockBackend)
+}
+
+/// Returns the mock window underlying `window`.
+///
+/// Panics if the window was not created by the mock backend.
+pub fn mock_window(window: &dyn Window) -> &MockWindow {
+    match window.any().downcast_ref() {
+        Some(w) => w,
+        None => panic!("Window {} was not created by the mock backend", window.id()),
+    }
+}
+
+struct MockBackend;
+
+impl Backend for MockBackend {
+    fn instantiate(&self) -> Box<dyn Instance> {
+        Box::new(Rc::new(MockInstance {
+            next_window_id: Cell::new(1),
+            cursor_position: Cell::new((0, 0)),
+        }))
+    }
+
+    fn flags(&self) -> BackendFlags {
+        BackendFlags::MT_SAFE | BackendFlags::MOCK
+    }
+
+    fn name(&self) -> &str {
+        "mock"
+    }
+}
+
+struct MockInstance {
+    next_window_id: Cell<u32>,
+    cursor_position: Cell<(i32, i32)>,
+}
+
+impl Instance for Rc<MockInstance> {
+    fn backend(&self) -> &dyn Backend {
+        &BACKEND
+    }
+
+    fn default_seat(&self) -> Box<dyn Seat> {
+        Box::new(MockSeat {
+            instance: self.clone(),
+        })
+    }
+
+    fn create_event_loop(&self) -> Box<dyn EventLoop> {
+        let el = Rc::new(MockEventLoop {
+            instance: self.clone(),
+            events: Default::default(),
+            recent_events: Default::default(),
+            waiters: Default::default(),
+            version: Cell::new(0),
+        });
+        crate::test::with_test_data(|td| td.event_loops.borrow_mut().push(Box::new(el.clone())));
+        // Like winit, start the event loop in the resumed state.
+        el.push(Event::Resumed);
+        Box::new(el)
+    }
+
+    fn take_screenshot(&self) {
+        log::warn!("The mock backend cannot take screenshots");
+    }
+
+    fn before_poll(&self) {
+        // nothing
+    }
+
+    fn create_dnd_path(&self, file: &str) -> PathBuf {
+        let _ = file;
+        unimplemented!();
+    }
+
+    fn start_dnd_process(&self, paths: &[&Path]) -> Box<dyn DndProcess> {
+        let _ = paths;
+        unimplemented!();
+    }
+
+    fn redraw_requested_scenarios(&self) -> usize {
+        0
+    }
+
+    fn cursor_position(&self) -> (i32, i32) {
+        self.cursor_position.get()
+    }
+}
+
+struct MockEventLoop {
+    instance: Rc<MockInstance>,
+    events: RefCell<VecDeque<(Instant, Event)>>,
+    /// The last `RECENT_EVENTS` events, including those that have been consumed.
+    recent_events: RefCell<VecDeque<(Instant, Event)>>,
+    waiters: RefCell<Vec<Waker>>,
+    version: Cell<u32>,
+}
+
+impl MockEventLoop {
+    fn push(&self, event: Event) {
+        log::debug!("mock event: {:?}", event);
+        let now = crate::clock::now();
+        let mut recent_events = self.recent_events.borrow_mut();
+        if recent_events.len() == crate::backend::RECENT_EVENTS {
+            recent_events.pop_front();
+        }
+        recent_events.push_back((now, event.clone()));
+        self.events.borrow_mut().push_back((now, event));
+        self.wake();
+    }
+
+    fn wake(&self) {
+        self.version.set(self.version.get() + 1);
+        for waiter in self.waiters.borrow_mut().drain(..) {
+            waiter.wake();
+        }
+    }
+
+    /// Removes the first event of `window` from the queue, or the first event if `window`
+    /// is `None`, and waits for it if there is none.
+    fn event_for(
+        &self,
+        window: Option<WindowId>,
+    ) -> Pin<Box<dyn Future<Output = (Instant, Event)> + '_>> {
+        struct Next<'b>(&'b MockEventLoop, Option<WindowId>);
+        impl<'b> Future for Next<'b> {
+            type Output = (Instant, Event);
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let mut events = self.0.events.borrow_mut();
+                let pos = match self.1 {
+                    Some(window) => events
+                        .iter()
+                        .position(|(_, e)| e.window_id() == Some(window)),
+                    None => Some(0),
+                };
+                if let Some(e) = pos.and_then(|pos| events.remove(pos)) {
+                    Poll::Ready(e)
+                } else {
+                    self.0.waiters.borrow_mut().push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+        Box::pin(Next(self, window))
+    }
+}
+
+impl EventStream for Rc<MockEventLoop> {
+    fn event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = Event> + 'a>> {
+        Box::pin(async move { self.timed_event().await.1 })
+    }
+
+    fn timed_event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
+        self.event_for(None)
+    }
+}
+
+/// The events of a single window. See `EventLoop::window_events`.
+struct MockWindowEventStream {
+    el: Rc<MockEventLoop>,
+    window: WindowId,
+}
+
+impl EventStream for MockWindowEventStream {
+    fn event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = Event> + 'a>> {
+        Box::pin(async move { self.timed_event().await.1 })
+    }
+
+    fn timed_event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
+        self.el.event_for(Some(self.window))
+    }
+}
+
+impl EventLoop for Rc<MockEventLoop> {
+    fn events(&self) -> Box<dyn EventStream> {
+        Box::new(self.clone())
+    }
+
+    fn window_events(&self, window: WindowId) -> Box<dyn EventStream> {
+        Box::new(MockWindowEventStream {
+            el: self.clone(),
+            window,
+        })
+    }
+
+    fn changed<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        struct Changed<'b>(&'b MockEventLoop, u32);
+        impl<'b> Future for Changed<'b> {
+            type Output = ();
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                if self.1 != self.0.version.get() {
+                    Poll::Ready(())
+                } else {
+                    self.0.waiters.borrow_mut().push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+        Box::pin(Changed(self, self.version.get()))
+    }
+
+    fn create_window(&self, builder: WindowBuilder) -> Box<dyn Window> {
+        let id = self.instance.next_window_id.get();
+        self.instance.next_window_id.set(id + 1);
+        log::info!("Created mock window {}", id);
+        let attrs = &builder.window;
+        let size = |s: Option<Size>| {
+            s.map(|s| {
+                let s = s.to_physical::<u32>(1.0);
+                (s.width, s.height)
+            })
+        };
+        let (width, height) = size(attrs.inner_size).unwrap_or((800, 600));
+        let props = MockProperties {
+            mapped: attrs.visible,
+            always_on_top: attrs.always_on_top,
+            decorations: attrs.decorations,
+            x: 0,
+            y: 0,
+            width,
+            height,
+            min_size: size(attrs.min_inner_size),
+            max_size: size(attrs.max_inner_size),
+            title: Some(attrs.title.clone()),
+            maximized: Some(attrs.maximized),
+            minimized: Some(false),
+            resizable: Some(attrs.resizable),
+            icon: None,
+            attention: false,
+            dragging: false,
+            fullscreen: attrs.fullscreen.is_some(),
+        };
+        Box::new(MockWindow {
+            el: self.clone(),
+            id,
+            properties: RefCell::new(props),
+            property_generation: Cell::new(0),
+        })
+    }
+
+    fn with_winit<'a>(&self, f: Box<dyn FnOnce(&mut WEventLoop<UserEvent>) + 'a>) {
+        let _ = f;
+        panic!("The mock backend does not create winit event loops");
+    }
+
+    fn barrier<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        // Events are delivered synchronously.
+        Box::pin(async {})
+    }
+
+    fn disconnected<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(futures::future::pending())
+    }
+
+    fn recent_events(&self) -> Vec<(Instant, Event)> {
+        self.recent_events.borrow().iter().cloned().collect()
+    }
+}
+
+/// The properties of a mock window. See `WindowProperties`.
+#[derive(Clone)]
+pub struct MockProperties {
+    pub mapped: bool,
+    pub always_on_top: bool,
+    pub decorations: bool,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+    pub title: Option<String>,
+    pub maximized: Option<bool>,
+    pub minimized: Option<bool>,
+    pub resizable: Option<bool>,
+    pub icon: Option<BackendIcon>,
+    pub attention: bool,
+    pub dragging: bool,
+    pub fullscreen: bool,
+}
+
+pub struct MockWindow {
+    el: Rc<MockEventLoop>,
+    id: u32,
+    properties: RefCell<MockProperties>,
+    property_generation: Cell<u32>,
+}
+
+impl MockWindow {
+    /// The id of all window events of mock windows.
+    pub fn winit_id(&self) -> WindowId {
+        unsafe { WindowId::dummy() }
+    }
+
+    /// Modifies the properties of the window and wakes the tasks waiting for them to
+    /// change.
+    pub fn set_properties(&self, f: impl FnOnce(&mut MockProperties)) {
+        f(&mut self.properties.borrow_mut());
+        log::info!("Properties of mock window {} changed", self.id);
+        self.property_generation
+            .set(self.property_generation.get() + 1);
+        self.el.wake();
+    }
+
+    /// Delivers `event` to the event loop of the window.
+    pub fn send_event(&self, event: Event) {
+        self.el.push(event);
+    }
+
+    /// Delivers a window event of this window to its event loop.
+    pub fn send_window_event(&self, event: WindowEvent) {
+        self.send_event(Event::WindowEvent(WindowEventExt {
+            window_id: self.winit_id(),
+            event,
+        }));
+    }
+}
+
+impl Window for MockWindow {
+    fn id(&self) -> &dyn Display {
+        &self.id
+    }
+
+    fn backend(&self) -> &dyn Backend {
+        &BACKEND
+    }
+
+    fn event_loop(&self) -> &dyn EventLoop {
+        &self.el
+    }
+
+    fn winit(&self) -> &WWindow {
+        panic!("The mock backend does not create winit windows");
+    }
+
+    fn properties_changed<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        struct Changed<'b>(&'b MockWindow, u32);
+        impl<'b> Future for Changed<'b> {
+            type Output = ();
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                if self.1 != self.0.property_generation.get() {
+                    Poll::Ready(())
+                } else {
+                    self.0.el.waiters.borrow_mut().push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+        Box::pin(Changed(self, self.property_generation.get()))
+    }
+
+    fn properties(&self) -> &dyn WindowProperties {
+        self
+    }
+
+    fn set_background_color(&self, r: u8, g: u8, b: u8) {
+        log::info!(
+            "Setting background color of mock window {} to {}/{}/{}",
+            self.id,
+            r,
+            g,
+            b
+        );
+    }
+
+    fn any(&self) -> &dyn Any {
+        self
+    }
+
+    fn delete(&self) {
+        self.send_window_event(WindowEvent::CloseRequested);
+    }
+
+    fn frame_extents(&self) -> (u32, u32, u32, u32) {
+        (0, 0, 0, 0)
+    }
+
+    fn request_redraw(&self, scenario: usize) {
+        let _ = scenario;
+        self.send_event(Event::RedrawRequested(self.winit_id()));
+    }
+}
+
+impl WindowProperties for MockWindow {
+    fn mapped(&self) -> bool {
+        self.properties.borrow().mapped
+    }
+
+    fn always_on_top(&self) -> bool {
+        self.properties.borrow().always_on_top
+    }
+
+    fn decorations(&self) -> bool {
+        self.properties.borrow().decorations
+    }
+
+    fn x(&self) -> i32 {
+        self.properties.borrow().x
+    }
+
+    fn y(&self) -> i32 {
+        self.properties.borrow().y
+    }
+
+    fn width(&self) -> u32 {
+        self.properties.borrow().width
+    }
+
+    fn height(&self) -> u32 {
+        self.properties.borrow().height
+    }
+
+    fn min_size(&self) -> Option<(u32, u32)> {
+        self.properties.borrow().min_size
+    }
+
+    fn max_size(&self) -> Option<(u32, u32)> {
+        self.properties.borrow().max_size
+    }
+
+    fn title(&self) -> Option<String> {
+        self.properties.borrow().title.clone()
+    }
+
+    fn maximized(&self) -> Option<bool> {
+        self.properties.borrow().maximized
+    }
+
+    fn minimized(&self) -> Option<bool> {
+        self.properties.borrow().minimized
+    }
+
+    fn resizable(&self) -> Option<bool> {
+        self.properties.borrow().resizable
+    }
+
+    fn icon(&self) -> Option<BackendIcon> {
+        self.properties.borrow().icon.clone()
+    }
+
+    fn attention(&self) -> bool {
+        self.properties.borrow().attention
+    }
+
+    fn supports_transparency(&self) -> bool {
+        false
+    }
+
+    fn dragging(&self) -> bool {
+        self.properties.borrow().dragging
+    }
+
+    fn fullscreen(&self) -> bool {
+        self.properties.borrow().fullscreen
+    }
+}
+
+struct MockSeat {
+    instance: Rc<MockInstance>,
+}
+
+impl Seat for MockSeat {
+    fn add_keyboard(&self) -> Box<dyn Keyboard> {
+        unimplemented!();
+    }
+
+    fn add_mouse(&self) -> Box<dyn Mouse> {
+        unimplemented!();
+    }
+
+    fn add_touchscreen(&self) -> Box<dyn Touchscreen> {
+        unimplemented!();
+    }
+
+    fn focus(&self, window: &dyn Window) {
+        mock_window(window).send_window_event(WindowEvent::Focused(true));
+    }
+
+    fn un_focus(&self) {
+        // nothing
+    }
+
+    fn set_layout(&self, layout: Layout) {
+        let _ = layout;
+    }
+
+    fn set_cursor_position(&self, x: i32, y: i32) {
+        self.instance.cursor_position.set((x, y));
+    }
+
+    fn cursor_position(&self) -> (i32, i32) {
+        self.instance.cursor_position.get()
+    }
+
+    fn is(&self, device_id: DeviceId) -> bool {
+        let _ = device_id;
+        false
+    }
+}
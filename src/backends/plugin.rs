@@ -0,0 +1,33 @@
+use crate::backend::Backend;
+use libloading::{Library, Symbol};
+use std::path::Path;
+
+/// The signature of the function exported by backend libraries.
+///
+/// The library must export it unmangled under the name `winit_it_backend` and must be built
+/// against the same version of this crate with the same compiler.
+pub type BackendConstructor = fn() -> Box<dyn Backend>;
+
+const CONSTRUCTOR_SYMBOL: &[u8] = b"winit_it_backend\0";
+
+/// Loads a backend from the shared library at `path`.
+///
+/// The library stays loaded for the remainder of the process.
+pub fn load(path: &Path) -> Box<dyn Backend> {
+    log::info!("Loading backend from {}", path.display());
+    unsafe {
+        let lib = match Library::new(path) {
+            Ok(l) => Box::leak(Box::new(l)),
+            Err(e) => panic!("Could not load {}: {}", path.display(), e),
+        };
+        let ctor: Symbol<BackendConstructor> = match lib.get(CONSTRUCTOR_SYMBOL) {
+            Ok(c) => c,
+            Err(e) => panic!(
+                "{} does not export a backend constructor: {}",
+                path.display(),
+                e
+            ),
+        };
+        ctor()
+    }
+}
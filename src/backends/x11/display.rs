@@ -0,0 +1,121 @@
+//! Bookkeeping of the displays used by the X servers of this process.
+//!
+//! The X servers choose their display numbers themselves via `-displayfd`. Since they are
+//! killed with SIGKILL, they leave their lock files and sockets behind. This module removes
+//! them. Every display used by the harness is recorded in `RECORDS` together with the pid
+//! of its server. Only the lock files and sockets of recorded displays are ever removed so
+//! that displays of other users of `/tmp` are left alone.
+
+use parking_lot::Mutex;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+static DISPLAYS: Mutex<Vec<u32>> = parking_lot::const_mutex(Vec::new());
+
+/// The directory containing one file per display of a running or crashed X server started
+/// by the harness. The file is named after the display and contains the pid of the server.
+const RECORDS: &str = "/tmp/.winit-it-displays";
+
+fn lock_file(display: u32) -> PathBuf {
+    PathBuf::from(format!("/tmp/.X{}-lock", display))
+}
+
+fn socket(display: u32) -> PathBuf {
+    PathBuf::from(format!("/tmp/.X11-unix/X{}", display))
+}
+
+fn record(display: u32) -> PathBuf {
+    Path::new(RECORDS).join(display.to_string())
+}
+
+fn read_pid(path: &Path) -> Option<libc::pid_t> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn is_running(pid: libc::pid_t) -> bool {
+    Path::new("/proc").join(pid.to_string()).exists()
+}
+
+/// Acquires an exclusive lock on the records of all invocations of the harness.
+fn lock_records() -> File {
+    if let Err(e) = std::fs::create_dir_all(RECORDS) {
+        panic!("Could not create {}: {}", RECORDS, e);
+    }
+    let path = Path::new(RECORDS).join("lock");
+    let file = match OpenOptions::new().create(true).write(true).open(&path) {
+        Ok(f) => f,
+        Err(e) => panic!("Could not open {}: {}", path.display(), e),
+    };
+    unsafe {
+        uapi::map_err!(libc::flock(file.as_raw_fd(), libc::LOCK_EX)).unwrap();
+    }
+    file
+}
+
+/// Removes the lock file and socket of `display` if they belong to the server `pid` and
+/// forgets the display.
+fn remove(display: u32, pid: libc::pid_t) {
+    let mut paths = vec![record(display)];
+    // The lock file might already belong to a server of someone else that has reused the
+    // display.
+    if read_pid(&lock_file(display)) == Some(pid) {
+        paths.push(lock_file(display));
+        paths.push(socket(display));
+    }
+    for path in paths {
+        if let Err(e) = std::fs::remove_file(&path) {
+            if e.kind() != ErrorKind::NotFound {
+                log::warn!("Could not remove {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// Records that `display` is used by the server `pid` of an instance of this process.
+pub(super) fn register(display: u32, pid: libc::pid_t) {
+    let _lock = lock_records();
+    let mut displays = DISPLAYS.lock();
+    if displays.contains(&display) {
+        log::error!("Display :{} is already used by another instance", display);
+    }
+    displays.push(display);
+    if let Err(e) = std::fs::write(record(display), pid.to_string()) {
+        log::warn!("Could not record display :{}: {}", display, e);
+    }
+}
+
+/// Releases a display after its server `pid` has terminated.
+pub(super) fn release(display: u32, pid: libc::pid_t) {
+    let _lock = lock_records();
+    DISPLAYS.lock().retain(|d| *d != display);
+    remove(display, pid);
+}
+
+/// Removes the lock files and sockets of displays of X servers started by the harness that
+/// are no longer running, e.g. because the harness crashed.
+pub(super) fn remove_stale_displays() {
+    let _lock = lock_records();
+    for entry in std::fs::read_dir(RECORDS).into_iter().flatten().flatten() {
+        let display = match entry.file_name().to_str().and_then(|n| n.parse().ok()) {
+            Some(d) => d,
+            _ => continue,
+        };
+        match read_pid(&entry.path()) {
+            Some(pid) if is_running(pid) => {}
+            Some(pid) => {
+                log::warn!(
+                    "Removing stale lock file and socket of display :{} (pid {} is not running)",
+                    display,
+                    pid
+                );
+                remove(display, pid);
+            }
+            None => {
+                log::warn!("Removing invalid record of display :{}", display);
+                let _ = std::fs::remove_file(entry.path());
+            }
+        }
+    }
+}
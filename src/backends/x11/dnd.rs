@@ -1,5 +1,7 @@
 use super::XInstanceData;
-use crate::backends::x11::XConnection;
+use crate::backends::x11::{XConnection, XRequestError};
+use crate::tlog;
+use crate::tlog::Sequence;
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::ptr;
@@ -8,7 +10,7 @@ use tokio::io::unix::AsyncFd;
 use tokio::io::Interest;
 use tokio::sync::mpsc::UnboundedReceiver;
 use xcb_dl::ffi;
-use xcb_dl_util::error::{XcbError, XcbErrorType};
+use xcb_dl_util::error::XcbErrorType;
 
 #[derive(Debug, PartialEq)]
 pub(super) enum DndMsg {
@@ -21,7 +23,7 @@ pub(super) enum DndMsg {
 pub(super) fn run(
     instance: Arc<XInstanceData>,
     rx: UnboundedReceiver<DndMsg>,
-    path: &Path,
+    paths: &[&Path],
 ) -> impl Future<Output = ()> {
     unsafe {
         let c = XConnection::new(&instance.backend, instance.display);
@@ -42,12 +44,12 @@ pub(super) fn run(
             0,
             ptr::null(),
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could not create dummy window: {}", e);
         }
         let cookie =
             xcb.xcb_set_selection_owner_checked(c.c, window_id, instance.atoms.x_dnd_selection, 0);
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could not take ownership of XdndSelection: {}", e);
         }
         let cookie = xcb.xcb_change_property_checked(
@@ -60,7 +62,7 @@ pub(super) fn run(
             1,
             &instance.atoms.uri_list as *const _ as _,
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could set XdndTypeList on {}: {}", window_id, e);
         }
 
@@ -73,7 +75,7 @@ pub(super) fn run(
             drop: false,
             dropped: false,
             accept: None,
-            path: path.to_owned(),
+            paths: paths.iter().map(|p| p.to_path_buf()).collect(),
             stop: false,
         };
 
@@ -90,7 +92,7 @@ struct Dnd {
     drop: bool,
     dropped: bool,
     accept: Option<bool>,
-    path: PathBuf,
+    paths: Vec<PathBuf>,
     stop: bool,
 }
 
@@ -177,7 +179,7 @@ impl Dnd {
         window: ffi::xcb_window_t,
         type_: ffi::xcb_atom_t,
         data32: [u32; 5],
-    ) -> Result<(), XcbError> {
+    ) -> Result<(), XRequestError> {
         let xcb = &self.instance.backend.xcb;
         let msg = ffi::xcb_client_message_event_t {
             response_type: ffi::XCB_CLIENT_MESSAGE,
@@ -189,7 +191,7 @@ impl Dnd {
         };
         unsafe {
             let cookie = xcb.xcb_send_event_checked(self.c.c, 0, window, 0, &msg as *const _ as _);
-            self.c.errors.check_cookie(xcb, cookie)
+            self.c.check_cookie(cookie)
         }
     }
 
@@ -317,7 +319,8 @@ impl Dnd {
                         panic!("The connection is in error: {}", e);
                     }
                 };
-                self.handle_event(&event);
+                let sequence = Sequence::Event(event.full_sequence);
+                tlog::with_sequence(sequence, || self.handle_event(&event));
             }
             self.instance.backend.xcb.xcb_flush(self.c.c);
         }
@@ -353,7 +356,12 @@ impl Dnd {
             event.property
         };
         unsafe {
-            let path = format!("file://{}", self.path.display());
+            let uris: Vec<_> = self
+                .paths
+                .iter()
+                .map(|p| format!("file://{}", p.display()))
+                .collect();
+            let uris = uris.join("\r\n");
             let xcb = &self.instance.backend.xcb;
             let cookie = xcb.xcb_change_property_checked(
                 self.c.c,
@@ -362,10 +370,10 @@ impl Dnd {
                 property,
                 self.instance.atoms.uri_list,
                 8,
-                path.len() as _,
-                path.as_ptr() as _,
+                uris.len() as _,
+                uris.as_ptr() as _,
             );
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 log::warn!("Could not set property on {}: {}", event.requestor, e);
                 return;
             }
@@ -380,7 +388,7 @@ impl Dnd {
             };
             let cookie =
                 xcb.xcb_send_event_checked(self.c.c, 0, event.requestor, 0, &msg as *const _ as _);
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 log::warn!(
                     "Could not send selection notify to {}: {}",
                     event.requestor,
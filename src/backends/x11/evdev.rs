@@ -826,6 +826,7 @@ pub fn map_key(key: Key) -> u32 {
         Key::KeyC => KEY_C,
         Key::KeyCapslock => KEY_CAPSLOCK,
         Key::KeyComma => KEY_COMMA,
+        Key::KeyCompose => KEY_COMPOSE,
         Key::KeyD => KEY_D,
         Key::KeyDelete => KEY_DELETE,
         Key::KeyDot => KEY_DOT,
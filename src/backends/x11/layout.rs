@@ -21,7 +21,11 @@ pub struct Msg {
 
 pub fn layouts() -> Layouts {
     Layouts {
-        msg1: create_msg(&[keymap(Layout::Qwerty), keymap(Layout::Azerty)]),
+        msg1: create_msg(&[
+            keymap(Layout::Qwerty),
+            keymap(Layout::Azerty),
+            keymap(Layout::UsIntl),
+        ]),
         msg2: create_msg(&[keymap(Layout::QwertySwapped)]),
     }
 }
@@ -386,7 +390,7 @@ fn create_msg(layouts: &[HashMap<u32, Vec<u32>>]) -> Msg {
 fn keymap(layout: Layout) -> HashMap<u32, Vec<u32>> {
     let mut res = HashMap::new();
     match layout {
-        Qwerty | Azerty => {
+        Qwerty | Azerty | UsIntl => {
             res.insert(KEY_ESC, vec![XK_Escape]);
             res.insert(KEY_CAPSLOCK, vec![XK_Caps_Lock]);
             res.insert(KEY_LEFTSHIFT, vec![XK_Shift_L]);
@@ -400,7 +404,7 @@ fn keymap(layout: Layout) -> HashMap<u32, Vec<u32>> {
         }
     }
     match layout {
-        Qwerty | QwertySwapped => {
+        Qwerty | QwertySwapped | UsIntl => {
             res.insert(KEY_1, vec![XK_1, XK_exclam]);
             res.insert(KEY_2, vec![XK_2, XK_at]);
             res.insert(KEY_3, vec![XK_3, XK_numbersign]);
@@ -464,6 +468,19 @@ fn keymap(layout: Layout) -> HashMap<u32, Vec<u32>> {
             res.insert(KEY_RIGHTALT, vec![XK_ISO_Level3_Shift]);
         }
     }
+    if layout == UsIntl {
+        res.insert(KEY_6, vec![XK_6, XK_dead_circumflex]);
+        res.insert(KEY_E, vec![XK_e, XK_E, XK_eacute, XK_Eacute]);
+        res.insert(
+            KEY_APOSTROPHE,
+            vec![XK_dead_acute, XK_dead_diaeresis, XK_apostrophe, XK_quotedbl],
+        );
+        res.insert(
+            KEY_GRAVE,
+            vec![XK_dead_grave, XK_dead_tilde, XK_grave, XK_asciitilde],
+        );
+        res.insert(KEY_RIGHTALT, vec![XK_ISO_Level3_Shift]);
+    }
     res.insert(KEY_BACKSPACE, vec![XK_BackSpace]);
     res.insert(KEY_TAB, vec![XK_Tab, XK_ISO_Left_Tab]);
     res.insert(KEY_R, vec![XK_r, XK_R]);
@@ -536,6 +553,7 @@ fn keymap(layout: Layout) -> HashMap<u32, Vec<u32>> {
     res.insert(KEY_LEFTMETA, vec![XK_Super_L]);
     res.insert(KEY_RIGHTMETA, vec![XK_Super_R]);
     res.insert(KEY_MENU, vec![XK_Menu]);
+    res.insert(KEY_COMPOSE, vec![XK_Multi_key]);
     res
 }
 
@@ -1,36 +1,48 @@
 use crate::backend::{
-    Backend, BackendDeviceId, BackendFlags, BackendIcon, Button, Device, DndProcess, EventLoop,
-    Finger, Instance, Keyboard, Mouse, PressedButton, PressedKey, Seat, Touchscreen, Window,
-    WindowProperties,
+    AbsolutePointer, Backend, BackendDeviceId, BackendFlags, BackendIcon, BlurRegion, Button,
+    ConnectionProxy, Device, DndProcess, EventLoop, Finger, Instance, Keyboard, LeakCheck,
+    MonitorConfig, Mouse, PagerRequest, PressedButton, PressedKey, ResizeEdge, Seat,
+    TestPatternError, Touchscreen, Window, WindowProperties, WmConfig, RECENT_EVENTS,
 };
 use crate::backends::x11::dnd::DndMsg;
 use crate::backends::x11::layout::{layouts, set_names, Layouts};
-use crate::backends::x11::wm::TITLE_HEIGHT;
+use crate::backends::x11::proxy::XProxy;
+use crate::backends::x11::wm::{
+    WmAction, CLOSE_BUTTON_X, MAXIMIZE_BUTTON_X, MINIMIZE_BUTTON_X, TITLE_HEIGHT,
+};
 use crate::backends::x11::MessageType::{
-    MT_BUTTON_PRESS, MT_BUTTON_RELEASE, MT_CREATE_MOUSE, MT_CREATE_MOUSE_REPLY, MT_CREATE_TOUCH,
-    MT_CREATE_TOUCH_REPLY, MT_ENABLE_SECOND_MONITOR, MT_ENABLE_SECOND_MONITOR_REPLY,
-    MT_GET_VIDEO_INFO, MT_GET_VIDEO_INFO_REPLY, MT_MOUSE_MOVE, MT_MOUSE_SCROLL, MT_REMOVE_DEVICE,
-    MT_TOUCH_DOWN, MT_TOUCH_DOWN_REPLY, MT_TOUCH_MOVE, MT_TOUCH_UP,
+    MT_BUTTON_PRESS, MT_BUTTON_RELEASE, MT_CREATE_MOUSE, MT_CREATE_MOUSE_REPLY, MT_CREATE_TABLET,
+    MT_CREATE_TABLET_REPLY, MT_CREATE_TOUCH, MT_CREATE_TOUCH_REPLY, MT_ENABLE_SECOND_MONITOR,
+    MT_ENABLE_SECOND_MONITOR_REPLY, MT_GET_VIDEO_INFO, MT_GET_VIDEO_INFO_REPLY, MT_MOUSE_MOVE,
+    MT_MOUSE_SCROLL, MT_REMOVE_DEVICE, MT_SET_PHYSICAL_SIZE, MT_SET_PHYSICAL_SIZE_REPLY,
+    MT_TABLET_MOVE, MT_TOUCH_DOWN, MT_TOUCH_DOWN_REPLY, MT_TOUCH_MOVE, MT_TOUCH_UP,
 };
-use crate::env::set_env;
+use crate::env::{set_env, EnvGuard};
 use crate::event::{map_event, DeviceEvent, DeviceEventExt, Event, UserEvent};
 use crate::eventstream::EventStream;
+use crate::eventtrace::event_kind;
 use crate::keyboard::{Key, Layout};
-use crate::test::with_test_data;
+use crate::report::{EventQueueStats, Extension, ServerInfo};
+use crate::screenshot::Image;
+use crate::task::spawn_local;
+use crate::test::{panic_message, with_test_data, ConnectionLost};
+use crate::tlog::Sequence;
 use parking_lot::Mutex;
 use std::any::Any;
 use std::cell::{Cell, RefCell};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::fmt::Display;
+use std::fmt::{Display, Write as _};
 use std::fs::File;
 use std::future::Future;
 use std::io::Write;
+use std::panic::AssertUnwindSafe;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
 use std::sync::{Arc, Weak};
 use std::task::{Context, Poll, Waker};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{mem, ptr};
 use tokio::io::unix::AsyncFd;
 use tokio::io::Interest;
@@ -38,50 +50,141 @@ use tokio::sync::mpsc::UnboundedSender;
 use tokio::task::JoinHandle;
 use uapi::c::{AF_UNIX, O_CLOEXEC, SOCK_CLOEXEC, SOCK_SEQPACKET};
 use uapi::{pipe2, socketpair, IntoUstr, OwnedFd, Pod, UapiReadExt, UstrPtr};
-use winit::event::{DeviceId, ElementState, RawKeyEvent};
+use winit::event::{DeviceId, ElementState, Event as WEvent, RawKeyEvent, StartCause};
 use winit::event_loop::{ControlFlow, EventLoop as WEventLoop};
 use winit::keyboard::KeyCode;
 use winit::platform::run_return::EventLoopExtRunReturn;
 use winit::platform::unix::{
     DeviceIdExtUnix, EventLoopExtUnix, EventLoopWindowTargetExtUnix, WindowExtUnix,
 };
-use winit::window::{Window as WWindow, WindowBuilder};
-use xcb_dl::{ffi, Xcb, XcbRandr, XcbRender, XcbXfixes, XcbXinput, XcbXkb};
-use xcb_dl_util::error::XcbErrorParser;
+use winit::window::{Window as WWindow, WindowBuilder, WindowId};
+use xcb_dl::{
+    ffi, Xcb, XcbPresent, XcbRandr, XcbRender, XcbShm, XcbXfixes, XcbXinput, XcbXkb, XcbXtest,
+};
+use xcb_dl_util::error::{XcbError, XcbErrorParser};
 use MessageType::{MT_CREATE_KEYBOARD, MT_CREATE_KEYBOARD_REPLY, MT_KEY_PRESS, MT_KEY_RELEASE};
 
+mod display;
 mod dnd;
 mod evdev;
 mod keysyms;
 mod layout;
+mod module;
+mod proxy;
 mod wm;
 
 const DEFAULT_X_PATH: &str = "/usr/lib/Xorg";
 // const DEFAULT_X_PATH: &str = "/home/julian/c/xserver/install/bin/X";
 
-pub fn backend() -> Box<dyn Backend> {
-    let x_path = match std::env::var("X_PATH") {
+/// The maximum screen size supported by the x11 module.
+const MAX_SCREEN_WIDTH: i32 = 2048;
+const MAX_SCREEN_HEIGHT: i32 = 768;
+
+/// The X server started by the backend.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum XServer {
+    /// Xorg with the driver and input module from `x11-module`.
+    Xorg,
+    /// Xvfb. Input is injected via XTest.
+    Xvfb,
+    /// Xephyr nested in the display named by `DISPLAY`. Input is injected via XTest.
+    Xephyr,
+}
+
+impl XServer {
+    /// Selects the server via `WINIT_IT_X_SERVER`. If the variable is not set, Xorg is used
+    /// if it is installed and the x11 module is cached or can be built. Otherwise Xvfb or
+    /// Xephyr is used if it can be found in `PATH`.
+    fn select(x_path: &str) -> Self {
+        match std::env::var("WINIT_IT_X_SERVER").as_deref() {
+            Ok("xorg") => return XServer::Xorg,
+            Ok("xvfb") => return XServer::Xvfb,
+            Ok("xephyr") => return XServer::Xephyr,
+            Ok(val) => panic!("Invalid value of WINIT_IT_X_SERVER: {}", val),
+            Err(_) => {}
+        }
+        if Path::new(x_path).exists() && module::available(x_path) {
+            return XServer::Xorg;
+        }
+        let server = if in_path("Xvfb") {
+            XServer::Xvfb
+        } else if in_path("Xephyr") && std::env::var_os("DISPLAY").is_some() {
+            XServer::Xephyr
+        } else {
+            return XServer::Xorg;
+        };
+        log::warn!(
+            "Cannot use Xorg with the x11 module. Using {:?} with reduced functionality",
+            server
+        );
+        server
+    }
+
+    fn binary<'a>(self, x_path: &'a str) -> &'a str {
+        match self {
+            XServer::Xorg => x_path,
+            XServer::Xvfb => "Xvfb",
+            XServer::Xephyr => "Xephyr",
+        }
+    }
+}
+
+fn in_path(binary: &str) -> bool {
+    match std::env::var_os("PATH") {
+        Some(path) => std::env::split_paths(&path).any(|dir| dir.join(binary).exists()),
+        None => false,
+    }
+}
+
+fn x_path() -> String {
+    match std::env::var("X_PATH") {
         Ok(p) => p,
         _ => DEFAULT_X_PATH.to_string(),
+    }
+}
+
+/// Builds the x11 module for the installed Xorg server unless it has already been built.
+pub fn setup() {
+    let dir = module::ensure_built(&x_path());
+    log::info!("The x11 module is available in {}", dir.display());
+}
+
+pub fn backend() -> Box<dyn Backend> {
+    let x_path = x_path();
+    let server = XServer::select(&x_path);
+    let (default_module_path, module_dir) = match server {
+        XServer::Xorg => {
+            let output = Command::new(&x_path)
+                .arg("-showDefaultModulePath")
+                .output()
+                .unwrap()
+                .stderr;
+            (
+                String::from_utf8(output).unwrap().trim().to_string(),
+                module::ensure_built(&x_path),
+            )
+        }
+        _ => (String::new(), PathBuf::new()),
     };
-    let default_module_path = Command::new(&x_path)
-        .arg("-showDefaultModulePath")
-        .output()
-        .unwrap()
-        .stderr;
+    display::remove_stale_displays();
     unsafe {
         Box::new(Arc::new(XBackend {
             x_path,
-            default_module_path: String::from_utf8(default_module_path)
-                .unwrap()
-                .trim()
-                .to_string(),
+            server,
+            default_module_path,
+            module_dir,
             xcb: Xcb::load_loose().unwrap(),
             xinput: XcbXinput::load_loose().unwrap(),
             xrandr: XcbRandr::load_loose().unwrap(),
             xfixes: XcbXfixes::load_loose().unwrap(),
             render: XcbRender::load_loose().unwrap(),
+            shm: XcbShm::load_loose().unwrap(),
             xkb: XcbXkb::load_loose().unwrap(),
+            present: XcbPresent::load_loose().unwrap(),
+            xtest: match server {
+                XServer::Xorg => None,
+                _ => Some(XcbXtest::load_loose().unwrap()),
+            },
             layouts: layouts(),
         }))
     }
@@ -89,19 +192,195 @@ pub fn backend() -> Box<dyn Backend> {
 
 struct XBackend {
     x_path: String,
+    server: XServer,
     default_module_path: String,
+    /// The directory containing the x11 module. Only set if the server is Xorg.
+    module_dir: PathBuf,
     xcb: Xcb,
     xinput: XcbXinput,
     xrandr: XcbRandr,
     xfixes: XcbXfixes,
     render: XcbRender,
+    shm: XcbShm,
     xkb: XcbXkb,
+    present: XcbPresent,
+    /// Only loaded if the server is not Xorg.
+    xtest: Option<XcbXtest>,
     layouts: Layouts,
 }
 
+impl XBackend {
+    /// Queries the vendor, release, and extensions of the X server.
+    ///
+    /// This uses a separate connection because the XInput version can only be queried
+    /// once per client and the version negotiated by the instance is not the version
+    /// supported by the server.
+    fn query_server_info(self: &Arc<Self>, display: u32) -> ServerInfo {
+        let c = XConnection::new(self, display);
+        unsafe {
+            let setup = &*self.xcb.xcb_get_setup(c.c);
+            let vendor = std::slice::from_raw_parts(
+                self.xcb.xcb_setup_vendor(setup) as *const u8,
+                self.xcb.xcb_setup_vendor_length(setup) as usize,
+            );
+            let mut err = ptr::null_mut();
+            let reply = self.xcb.xcb_list_extensions_reply(
+                c.c,
+                self.xcb.xcb_list_extensions(c.c),
+                &mut err,
+            );
+            let reply = match c.errors.check(&self.xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not list the extensions: {}", e),
+            };
+            let mut names = vec![];
+            let mut iter = self.xcb.xcb_list_extensions_names_iterator(&*reply);
+            while iter.rem > 0 {
+                let name = std::slice::from_raw_parts(
+                    self.xcb.xcb_str_name(iter.data) as *const u8,
+                    self.xcb.xcb_str_name_length(iter.data) as usize,
+                );
+                names.push(String::from_utf8_lossy(name).into_owned());
+                self.xcb.xcb_str_next(&mut iter);
+            }
+            names.sort();
+            let version = |name: &str, res: Result<(u32, u32), _>| match res {
+                Ok((major, minor)) => Some(format!("{}.{}", major, minor)),
+                Err(e) => {
+                    log::warn!("Could not query the version of {}: {}", name, e);
+                    None
+                }
+            };
+            let xinput = version("XInputExtension", {
+                let reply = self.xinput.xcb_input_xi_query_version_reply(
+                    c.c,
+                    self.xinput.xcb_input_xi_query_version(c.c, 2, 4),
+                    &mut err,
+                );
+                c.errors
+                    .check(&self.xcb, reply, err)
+                    .map(|r| (r.major_version as u32, r.minor_version as u32))
+            });
+            let xkb = version("XKEYBOARD", {
+                let reply = self.xkb.xcb_xkb_use_extension_reply(
+                    c.c,
+                    self.xkb.xcb_xkb_use_extension(c.c, 1, 0),
+                    &mut err,
+                );
+                c.errors
+                    .check(&self.xcb, reply, err)
+                    .map(|r| (r.server_major as u32, r.server_minor as u32))
+            });
+            let randr = version("RANDR", {
+                let reply = self.xrandr.xcb_randr_query_version_reply(
+                    c.c,
+                    self.xrandr.xcb_randr_query_version(c.c, 1, 6),
+                    &mut err,
+                );
+                c.errors
+                    .check(&self.xcb, reply, err)
+                    .map(|r| (r.major_version, r.minor_version))
+            });
+            let present = match names.iter().any(|n| n == "Present") {
+                true => version("Present", {
+                    let reply = self.present.xcb_present_query_version_reply(
+                        c.c,
+                        self.present.xcb_present_query_version(c.c, 1, 2),
+                        &mut err,
+                    );
+                    c.errors
+                        .check(&self.xcb, reply, err)
+                        .map(|r| (r.major_version, r.minor_version))
+                }),
+                false => None,
+            };
+            let extensions = names
+                .into_iter()
+                .map(|name| Extension {
+                    version: match &*name {
+                        "XInputExtension" => xinput.clone(),
+                        "XKEYBOARD" => xkb.clone(),
+                        "RANDR" => randr.clone(),
+                        "Present" => present.clone(),
+                        _ => None,
+                    },
+                    name,
+                })
+                .collect();
+            ServerInfo {
+                vendor: String::from_utf8_lossy(vendor).into_owned(),
+                version: setup.release_number.to_string(),
+                extensions,
+            }
+        }
+    }
+
+    /// Returns the first output of the server or 0 if the server has no outputs.
+    fn query_first_output(self: &Arc<Self>, display: u32) -> u32 {
+        let c = XConnection::new(self, display);
+        unsafe {
+            let mut err = ptr::null_mut();
+            let reply = self.xrandr.xcb_randr_query_version_reply(
+                c.c,
+                self.xrandr.xcb_randr_query_version(c.c, 1, 3),
+                &mut err,
+            );
+            if let Err(e) = c.errors.check(&self.xcb, reply, err) {
+                panic!("Could not query the RANDR version: {}", e);
+            }
+            let reply = self.xrandr.xcb_randr_get_screen_resources_current_reply(
+                c.c,
+                self.xrandr
+                    .xcb_randr_get_screen_resources_current(c.c, c.screen.root),
+                &mut err,
+            );
+            let resources = match c.errors.check(&self.xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not get screen resources: {}", e),
+            };
+            let outputs = std::slice::from_raw_parts(
+                self.xrandr
+                    .xcb_randr_get_screen_resources_current_outputs(&*resources),
+                resources.num_outputs as _,
+            );
+            outputs.first().copied().unwrap_or(0)
+        }
+    }
+
+    /// Opens the connection used to inject input via XTest.
+    fn connect_xtest(self: &Arc<Self>, display: u32) -> XConnection {
+        let c = XConnection::new(self, display);
+        let xtest = self.xtest.as_ref().unwrap();
+        unsafe {
+            let mut err = ptr::null_mut();
+            let reply = xtest.xcb_test_get_version_reply(
+                c.c,
+                xtest.xcb_test_get_version(c.c, 2, 2),
+                &mut err,
+            );
+            if let Err(e) = c.errors.check(&self.xcb, reply, err) {
+                panic!("Could not query the XTest version: {}", e);
+            }
+            // XISetClientPointer requires XInput 2.
+            let reply = self.xinput.xcb_input_xi_query_version_reply(
+                c.c,
+                self.xinput.xcb_input_xi_query_version(c.c, 2, 0),
+                &mut err,
+            );
+            if let Err(e) = c.errors.check(&self.xcb, reply, err) {
+                panic!("Could not query the XInput version: {}", e);
+            }
+        }
+        c
+    }
+}
+
 impl Backend for Arc<XBackend> {
     fn instantiate(&self) -> Box<dyn Instance> {
-        let (psock, chsock) = socketpair(AF_UNIX, SOCK_SEQPACKET | SOCK_CLOEXEC, 0).unwrap();
+        let module_sock = match self.server {
+            XServer::Xorg => Some(socketpair(AF_UNIX, SOCK_SEQPACKET | SOCK_CLOEXEC, 0).unwrap()),
+            _ => None,
+        };
         let (mut ppipe, chpipe) = pipe2(O_CLOEXEC).unwrap();
         let tmpdir = crate::test::with_test_data(|td| td.test_dir.join("x11_data"));
         std::fs::create_dir_all(&tmpdir).unwrap();
@@ -109,33 +388,53 @@ impl Backend for Arc<XBackend> {
         let log_file = tmpdir.join("log");
         let stderr_file = tmpdir.join("stderr").into_ustr();
         let config_dir = tmpdir.join("conf");
-        let module_path = format!(
-            "{},{}/x11-module/install",
-            self.default_module_path,
-            env!("CARGO_MANIFEST_DIR")
-        );
+        let module_path = format!("{},{}", self.default_module_path, self.module_dir.display());
         std::fs::write(&config_file, CONFIG).unwrap();
         let env = {
             let mut env = UstrPtr::new();
             for name in ["HOME", "PATH"] {
                 env.push(format!("{}={}", name, std::env::var(name).unwrap()));
             }
-            env.push(format!("WINIT_IT_SOCKET={}", chsock.raw()));
+            if self.server == XServer::Xephyr {
+                for name in ["DISPLAY", "XAUTHORITY"] {
+                    if let Ok(val) = std::env::var(name) {
+                        env.push(format!("{}={}", name, val));
+                    }
+                }
+            }
+            if let Some((_, chsock)) = &module_sock {
+                env.push(format!("WINIT_IT_SOCKET={}", chsock.raw()));
+            }
             env
         };
+        let binary = self.server.binary(&self.x_path);
+        let screen_size = format!("{}x{}", MAX_SCREEN_WIDTH, MAX_SCREEN_HEIGHT);
         let args = {
             let mut args = UstrPtr::new();
-            args.push(&*self.x_path);
-            args.push("-config");
-            args.push(&*config_file);
-            args.push("-configdir");
-            args.push(&*config_dir);
-            args.push("-modulepath");
-            args.push(&*module_path);
-            args.push("-seat");
-            args.push("winit-seat");
-            args.push("-logfile");
-            args.push(&*log_file);
+            args.push(binary);
+            match self.server {
+                XServer::Xorg => {
+                    args.push("-config");
+                    args.push(&*config_file);
+                    args.push("-configdir");
+                    args.push(&*config_dir);
+                    args.push("-modulepath");
+                    args.push(&*module_path);
+                    args.push("-seat");
+                    args.push("winit-seat");
+                    args.push("-logfile");
+                    args.push(&*log_file);
+                }
+                XServer::Xvfb => {
+                    args.push("-screen");
+                    args.push("0");
+                    args.push(format!("{}x24", screen_size));
+                }
+                XServer::Xephyr => {
+                    args.push("-screen");
+                    args.push(&*screen_size);
+                }
+            }
             args.push("-noreset");
             args.push("-displayfd");
             args.push(chpipe.to_string().into_ustr().to_owned());
@@ -150,16 +449,19 @@ impl Backend for Arc<XBackend> {
             uapi::dup2(null.raw(), 0).unwrap();
             uapi::dup2(null.raw(), 1).unwrap();
             uapi::dup2(stderr.raw(), 2).unwrap();
-            uapi::fcntl_setfd(chsock.raw(), 0).unwrap();
+            if let Some((_, chsock)) = &module_sock {
+                uapi::fcntl_setfd(chsock.raw(), 0).unwrap();
+            }
             uapi::fcntl_setfd(chpipe.raw(), 0).unwrap();
             drop(null);
             drop(stderr);
             unsafe {
                 uapi::map_err!(libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL)).unwrap();
             }
-            uapi::execvpe(&*self.x_path, &args, &env).unwrap();
+            uapi::execvpe(binary, &args, &env).unwrap();
         }
         drop(chpipe);
+        let sock = module_sock.map(|(psock, _)| psock);
         let display = ppipe
             .read_to_new_ustring()
             .unwrap()
@@ -169,26 +471,45 @@ impl Backend for Arc<XBackend> {
             .parse()
             .unwrap();
         log::trace!("display: {}", display);
+        display::register(display, chpid);
+        let server_info = self.query_server_info(display);
 
         let (second_crtc, second_output, first_output, large_mode_id, small_mode_id);
-        unsafe {
-            let mut msg = Message {
-                ty: MT_GET_VIDEO_INFO as _,
-            };
-            uapi::write(psock.raw(), &msg).unwrap();
-            uapi::read(psock.raw(), &mut msg).unwrap();
-            assert_eq!(msg.ty, MT_GET_VIDEO_INFO_REPLY as _);
-            second_crtc = msg.get_video_info_reply.second_crtc;
-            second_output = msg.get_video_info_reply.second_output;
-            first_output = msg.get_video_info_reply.first_output;
-            large_mode_id = msg.get_video_info_reply.large_mode_id;
-            small_mode_id = msg.get_video_info_reply.small_mode_id;
+        match &sock {
+            Some(sock) => unsafe {
+                let mut msg = Message {
+                    ty: MT_GET_VIDEO_INFO as _,
+                };
+                uapi::write(sock.raw(), &msg).unwrap();
+                uapi::read(sock.raw(), &mut msg).unwrap();
+                assert_eq!(msg.ty, MT_GET_VIDEO_INFO_REPLY as _);
+                second_crtc = msg.get_video_info_reply.second_crtc;
+                second_output = msg.get_video_info_reply.second_output;
+                first_output = msg.get_video_info_reply.first_output;
+                large_mode_id = msg.get_video_info_reply.large_mode_id;
+                small_mode_id = msg.get_video_info_reply.small_mode_id;
+            },
+            None => {
+                // Without the module there is only the output created by the server.
+                second_crtc = 0;
+                second_output = 0;
+                first_output = self.query_first_output(display);
+                large_mode_id = 0;
+                small_mode_id = 0;
+            }
         }
 
+        let server_exited = Arc::new(AtomicBool::new(false));
         let mut instance = XInstanceData {
+            server: Mutex::new(Some(XServerProcess {
+                pid: chpid,
+                display,
+                exited: server_exited.clone(),
+            })),
             backend: self.clone(),
             xserver_pid: chpid,
-            sock: psock,
+            server_exited,
+            sock,
             display,
             wm_data: Mutex::new(WmData {
                 wakers: vec![],
@@ -196,13 +517,18 @@ impl Backend for Arc<XBackend> {
                 parents: Default::default(),
                 window_to_parent: Default::default(),
                 pongs: Default::default(),
+                config: Default::default(),
+                shortcuts: 0,
+                actions: vec![],
             }),
+            wm_wakeup: Default::default(),
             atoms: Default::default(),
             second_crtc,
             second_output,
             first_output,
             _large_mode_id: large_mode_id,
             small_mode_id,
+            server_info,
         };
 
         let c = XConnection::new(self, display);
@@ -211,7 +537,7 @@ impl Backend for Arc<XBackend> {
             let cookie =
                 self.xrandr
                     .xcb_randr_set_output_primary_checked(c.c, c.screen.root, first_output);
-            c.errors.check_cookie(&self.xcb, cookie).unwrap();
+            c.check_cookie(cookie).unwrap();
         }
 
         instance.atoms.net_wm_state = c.atom("_NET_WM_STATE");
@@ -223,7 +549,10 @@ impl Backend for Arc<XBackend> {
         instance.atoms.net_wm_ping = c.atom("_NET_WM_PING");
         instance.atoms.utf8_string = c.atom("UTF8_STRING");
         instance.atoms.net_wm_state_above = c.atom("_NET_WM_STATE_ABOVE");
+        instance.atoms.net_wm_state_below = c.atom("_NET_WM_STATE_BELOW");
         instance.atoms.net_wm_state_fullscreen = c.atom("_NET_WM_STATE_FULLSCREEN");
+        instance.atoms.net_wm_state_hidden = c.atom("_NET_WM_STATE_HIDDEN");
+        instance.atoms.net_wm_state_demands_attention = c.atom("_NET_WM_STATE_DEMANDS_ATTENTION");
         instance.atoms.net_frame_extents = c.atom("_NET_FRAME_EXTENTS");
         instance.atoms.net_wm_state_maximized_horz = c.atom("_NET_WM_STATE_MAXIMIZED_HORZ");
         instance.atoms.net_wm_state_maximized_vert = c.atom("_NET_WM_STATE_MAXIMIZED_VERT");
@@ -251,10 +580,11 @@ impl Backend for Arc<XBackend> {
         instance.atoms.x_dnd_leave = c.atom("XdndLeave");
         instance.atoms.x_dnd_drop = c.atom("XdndDrop");
         instance.atoms.uri_list = c.atom("text/uri-list");
+        instance.atoms.kde_net_wm_blur_behind_region = c.atom("_KDE_NET_WM_BLUR_BEHIND_REGION");
 
         let instance = Arc::new(instance);
 
-        let wm = Some(tokio::task::spawn_local(wm::run(instance.clone())));
+        let wm = Some(spawn_local("wm", wm::run(instance.clone())));
 
         let (core_p, core_kb) = unsafe {
             let mut err = ptr::null_mut();
@@ -303,8 +633,14 @@ impl Backend for Arc<XBackend> {
             core.unwrap()
         };
 
+        let xtest_c = match instance.sock {
+            Some(_) => None,
+            None => Some(self.connect_xtest(display)),
+        };
+
         Box::new(Arc::new(XInstance {
             c,
+            xtest_c,
             data: instance.clone(),
             event_loops: Default::default(),
             wm,
@@ -312,6 +648,7 @@ impl Backend for Arc<XBackend> {
             core_kb,
             core_layout: Arc::new(Cell::new(Layout::Qwerty)),
             next_seat_id: Cell::new(1),
+            move_pointer_mice: Default::default(),
         }))
     }
 
@@ -320,7 +657,7 @@ impl Backend for Arc<XBackend> {
     }
 
     fn flags(&self) -> BackendFlags {
-        BackendFlags::MT_SAFE
+        let flags = BackendFlags::MT_SAFE
             | BackendFlags::WINIT_SET_ALWAYS_ON_TOP
             | BackendFlags::WINIT_SET_DECORATIONS
             | BackendFlags::WINIT_SET_INNER_SIZE
@@ -343,6 +680,57 @@ impl Backend for Arc<XBackend> {
             | BackendFlags::SECOND_MONITOR
             | BackendFlags::MONITOR_NAMES
             | BackendFlags::WINIT_SET_CURSOR_POSITION
+            | BackendFlags::CLIENT_WINDOWS
+            | BackendFlags::CONNECTION_PROXY
+            | BackendFlags::REMOVE_SEAT
+            | BackendFlags::TEST_PATTERN
+            | BackendFlags::CAPTURE_WINDOW
+            | BackendFlags::DISTINCT_DEVICES
+            | BackendFlags::POINTER_GRAB_WINDOW
+            | BackendFlags::WINIT_SET_CURSOR_ICON
+            | BackendFlags::WINIT_SET_CURSOR_GRAB
+            | BackendFlags::CONFIGURE_MONITORS
+            | BackendFlags::SET_MONITOR_MODE
+            | BackendFlags::WINIT_SET_FULLSCREEN
+            | BackendFlags::SET_SCALE_FACTOR
+            | BackendFlags::TITLE_BAR_MAXIMIZE
+            | BackendFlags::CLOSE_BUTTON
+            | BackendFlags::WINIT_SET_BLUR
+            | BackendFlags::KEY_REPEAT
+            | BackendFlags::LOCK_STATE
+            | BackendFlags::ABSOLUTE_POINTER
+            | BackendFlags::STACKING_ORDER
+            | BackendFlags::EVENT_LOOP_ENV
+            | BackendFlags::WM_FOCUS_POLICIES
+            | BackendFlags::MAXIMIZE_BUTTON
+            | BackendFlags::DRAG_RESIZE
+            | BackendFlags::DRAG_MOVE
+            | BackendFlags::MINIMIZE_BUTTON
+            | BackendFlags::WM_SHORTCUTS
+            | BackendFlags::TOUCH
+            | BackendFlags::PAGER
+            | BackendFlags::WM_ACTIONS
+            | BackendFlags::WM_REFUSE_RESIZE
+            | BackendFlags::WINIT_SET_WINDOW_LEVEL;
+        match self.server {
+            XServer::Xorg => flags,
+            // Without the module, devices cannot be created and the outputs are fixed.
+            XServer::Xvfb | XServer::Xephyr => {
+                flags
+                    - BackendFlags::DISTINCT_DEVICES
+                    - BackendFlags::POINTER_GRAB_WINDOW
+                    - BackendFlags::DEVICE_ADDED
+                    - BackendFlags::DEVICE_REMOVED
+                    - BackendFlags::TOUCH
+                    - BackendFlags::ABSOLUTE_POINTER
+                    - BackendFlags::KEY_REPEAT
+                    - BackendFlags::SECOND_MONITOR
+                    - BackendFlags::MONITOR_NAMES
+                    - BackendFlags::CONFIGURE_MONITORS
+                    - BackendFlags::SET_MONITOR_MODE
+                    - BackendFlags::SET_SCALE_FACTOR
+            }
+        }
     }
 }
 
@@ -377,6 +765,17 @@ impl XConnection {
         }
     }
 
+    /// Checks the result of a request. The error contains the sequence number of the
+    /// request.
+    fn check_cookie(&self, cookie: ffi::xcb_void_cookie_t) -> Result<(), XRequestError> {
+        self.errors
+            .check_cookie(&self.backend.xcb, cookie)
+            .map_err(|error| XRequestError {
+                sequence: cookie.sequence,
+                error,
+            })
+    }
+
     fn atom(&self, name: &str) -> ffi::xcb_atom_t {
         unsafe {
             let mut err = ptr::null_mut();
@@ -395,6 +794,22 @@ impl XConnection {
     }
 }
 
+/// An error produced by a request of the harness.
+///
+/// The error is displayed together with the sequence number of the request so that it can
+/// be correlated with xtrace captures.
+#[derive(Debug)]
+pub(super) struct XRequestError {
+    sequence: u32,
+    error: XcbError,
+}
+
+impl Display for XRequestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} [{}]", self.error, Sequence::Request(self.sequence))
+    }
+}
+
 impl Drop for XConnection {
     fn drop(&mut self) {
         unsafe {
@@ -404,21 +819,46 @@ impl Drop for XConnection {
 }
 
 struct XInstanceData {
+    /// The X server process. This is the first field so that the server is terminated
+    /// before the other resources are released. `None` if the server has been detached.
+    server: Mutex<Option<XServerProcess>>,
     backend: Arc<XBackend>,
     xserver_pid: libc::pid_t,
-    sock: OwnedFd,
+    /// Set once the X server has been reaped.
+    server_exited: Arc<AtomicBool>,
+    /// The socket connected to the x11 module. Only set if the server is Xorg.
+    sock: Option<OwnedFd>,
     display: u32,
     wm_data: Mutex<WmData>,
+    /// Wakes up the window manager, e.g. after an action has been added to
+    /// `WmData::actions`.
+    wm_wakeup: Arc<tokio::sync::Notify>,
     atoms: Atoms,
     second_crtc: u32,
     second_output: u32,
     first_output: u32,
     _large_mode_id: u32,
     small_mode_id: u32,
+    server_info: ServerInfo,
+}
+
+impl XInstanceData {
+    /// Returns the socket connected to the x11 module.
+    ///
+    /// Operations that require the module are gated behind backend flags that are not
+    /// set if the server is not Xorg.
+    fn sock(&self) -> libc::c_int {
+        match &self.sock {
+            Some(sock) => sock.raw(),
+            None => panic!("This operation requires the x11 module"),
+        }
+    }
 }
 
 struct XInstance {
     c: XConnection,
+    /// The connection used to inject input if the server is not Xorg.
+    xtest_c: Option<XConnection>,
     data: Arc<XInstanceData>,
     event_loops: Mutex<Vec<Weak<XEventLoopData>>>,
     wm: Option<JoinHandle<()>>,
@@ -426,12 +866,121 @@ struct XInstance {
     core_kb: ffi::xcb_input_device_id_t,
     core_layout: Arc<Cell<Layout>>,
     next_seat_id: Cell<usize>,
+    /// The mice used by `Seat::move_pointer`, keyed by the master pointer of their seat.
+    move_pointer_mice: RefCell<HashMap<ffi::xcb_input_device_id_t, ffi::xcb_input_device_id_t>>,
 }
 
 unsafe impl Send for XInstance {}
 unsafe impl Sync for XInstance {}
 
 impl XInstance {
+    fn uses_xtest(&self) -> bool {
+        self.xtest_c.is_some()
+    }
+
+    /// Injects a core input event into the seat whose master pointer is `pointer`.
+    fn fake_input(&self, pointer: ffi::xcb_input_device_id_t, ty: u32, detail: u8, x: i16, y: i16) {
+        let c = self.xtest_c.as_ref().unwrap();
+        unsafe {
+            let xinput = &self.data.backend.xinput;
+            let xtest = self.data.backend.xtest.as_ref().unwrap();
+            // XTest injects core events via the XTEST devices of the seat of the client
+            // pointer of the requesting client.
+            let cookie = xinput.xcb_input_xi_set_client_pointer_checked(c.c, 0, pointer);
+            if let Err(e) = c.check_cookie(cookie) {
+                panic!("Could not set the client pointer: {}", e);
+            }
+            let cookie = xtest.xcb_test_fake_input_checked(c.c, ty as _, detail, 0, 0, x, y, 0);
+            if let Err(e) = c.check_cookie(cookie) {
+                panic!("Could not inject input: {}", e);
+            }
+        }
+    }
+
+    /// Returns the XTEST slave device of the master `master`.
+    fn xtest_device(&self, master: ffi::xcb_input_device_id_t) -> ffi::xcb_input_device_id_t {
+        unsafe {
+            let xinput = &self.data.backend.xinput;
+            let xcb = &self.data.backend.xcb;
+            let mut err = ptr::null_mut();
+            let reply = xinput.xcb_input_xi_query_device_reply(
+                self.c.c,
+                xinput.xcb_input_xi_query_device(self.c.c, ffi::XCB_INPUT_DEVICE_ALL as _),
+                &mut err,
+            );
+            let reply = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not query input devices: {}", e),
+            };
+            let mut infos = xinput.xcb_input_xi_query_device_infos_iterator(&*reply);
+            while infos.rem > 0 {
+                let info = &*infos.data;
+                let name = std::slice::from_raw_parts(
+                    xinput.xcb_input_xi_device_info_name(infos.data) as *const u8,
+                    info.name_len as _,
+                );
+                let xtest = name.ends_with(b" XTEST keyboard") || name.ends_with(b" XTEST pointer");
+                if xtest && info.attachment == master {
+                    return info.deviceid;
+                }
+                xinput.xcb_input_xi_device_info_next(&mut infos);
+            }
+            panic!("Master {} has no XTEST device", master);
+        }
+    }
+
+    fn connect_second_output(&self, connected: bool) {
+        unsafe {
+            let mut msg = Message {
+                enable_second_monitor: EnableSecondMonitor {
+                    ty: MT_ENABLE_SECOND_MONITOR as _,
+                    enable: connected as _,
+                },
+            };
+            uapi::write(self.data.sock(), &msg).unwrap();
+            uapi::read(self.data.sock(), &mut msg).unwrap();
+            assert_eq!(msg.ty, MT_ENABLE_SECOND_MONITOR_REPLY as _);
+        }
+    }
+
+    /// Creates a mode with the given size and refresh rate and returns its id.
+    fn create_mode(&self, width: u32, height: u32, refresh_mhz: u32) -> ffi::xcb_randr_mode_t {
+        let name = format!("{}x{}@{}", width, height, refresh_mhz);
+        let info = ffi::xcb_randr_mode_info_t {
+            width: width as _,
+            height: height as _,
+            dot_clock: (width as u64 * height as u64 * refresh_mhz as u64 / 1000) as _,
+            hsync_start: width as _,
+            hsync_end: width as _,
+            htotal: width as _,
+            vsync_start: height as _,
+            vsync_end: height as _,
+            vtotal: height as _,
+            name_len: name.len() as _,
+            ..Default::default()
+        };
+        unsafe {
+            let xrandr = &self.data.backend.xrandr;
+            let xcb = &self.data.backend.xcb;
+            let mut err = ptr::null_mut();
+            let reply = xrandr.xcb_randr_create_mode_reply(
+                self.c.c,
+                xrandr.xcb_randr_create_mode(
+                    self.c.c,
+                    self.c.screen.root,
+                    info,
+                    name.len() as _,
+                    name.as_ptr() as _,
+                ),
+                &mut err,
+            );
+            match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r.mode,
+                Err(e) => panic!("Could not create mode {}: {}", name, e),
+            }
+        }
+    }
+
     fn cursor_grab_status(&self) -> bool {
         let grabbed;
         unsafe {
@@ -453,7 +1002,7 @@ impl XInstance {
             };
             if !grabbed {
                 let cookie = xcb.xcb_ungrab_pointer_checked(self.c.c, 0);
-                if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+                if let Err(e) = self.c.check_cookie(cookie) {
                     panic!("Could not ungrab pointer: {}", e);
                 }
             }
@@ -461,10 +1010,30 @@ impl XInstance {
         grabbed
     }
 
+    /// Returns the window that holds the active grab of the core pointer. Implicit grabs
+    /// of button presses are ignored.
+    fn pointer_grab_window(&self) -> Option<ffi::xcb_window_t> {
+        let mut msg = Message {
+            get_pointer_grab: GetPointerGrab {
+                ty: MT_GET_POINTER_GRAB as _,
+                pointer: self.core_p as _,
+            },
+        };
+        uapi::write(self.data.sock(), &msg).unwrap();
+        uapi::read(self.data.sock(), &mut msg).unwrap();
+        unsafe {
+            assert_eq!(msg.ty, MT_GET_POINTER_GRAB_REPLY as _);
+            match msg.get_pointer_grab_reply.window {
+                0 => None,
+                w => Some(w),
+            }
+        }
+    }
+
     fn add_dev(&self, req: MessageType, rep: MessageType) -> ffi::xcb_input_device_id_t {
         let mut msg = Message { ty: req as _ };
-        uapi::write(self.data.sock.raw(), &msg).unwrap();
-        uapi::read(self.data.sock.raw(), &mut msg).unwrap();
+        uapi::write(self.data.sock(), &msg).unwrap();
+        uapi::read(self.data.sock(), &mut msg).unwrap();
         unsafe {
             assert_eq!(msg.ty, rep as _);
             msg.create_keyboard_reply.id as _
@@ -483,9 +1052,12 @@ impl XInstance {
         self.add_dev(MT_CREATE_TOUCH, MT_CREATE_TOUCH_REPLY)
     }
 
+    fn add_absolute_pointer(&self) -> ffi::xcb_input_device_id_t {
+        self.add_dev(MT_CREATE_TABLET, MT_CREATE_TABLET_REPLY)
+    }
+
     fn assign_slave(&self, slave: ffi::xcb_input_device_id_t, master: ffi::xcb_input_device_id_t) {
         unsafe {
-            let xcb = &self.data.backend.xcb;
             let xinput = &self.data.backend.xinput;
             #[repr(C)]
             struct Change {
@@ -503,7 +1075,7 @@ impl XInstance {
                 },
             };
             let cookie = xinput.xcb_input_xi_change_hierarchy_checked(self.c.c, 1, &change.hc);
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 panic!("Could not assign slave to master: {}", e);
             }
         }
@@ -529,6 +1101,7 @@ impl XInstance {
             Layout::Qwerty => (0, &backend.layouts.msg1),
             Layout::Azerty => (1, &backend.layouts.msg1),
             Layout::QwertySwapped => (0, &backend.layouts.msg2),
+            Layout::UsIntl => (2, &backend.layouts.msg1),
         };
         unsafe {
             let xcb = &self.data.backend.xcb;
@@ -567,22 +1140,122 @@ impl XInstance {
                     &request,
                 );
                 let cookie = ffi::xcb_void_cookie_t { sequence };
-                if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+                if let Err(e) = self.c.check_cookie(cookie) {
                     panic!("Could not set keymap: {}", e);
                 }
                 let cookie = set_names(xkb, &self.c, slave);
-                if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+                if let Err(e) = self.c.check_cookie(cookie) {
                     panic!("Could not set level names: {}", e);
                 }
             }
             let cookie =
                 xkb.xcb_xkb_latch_lock_state_checked(self.c.c, slave, 0, 0, 1, group, 0, 0, 0);
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 panic!("Could not set keymap group: {}", e);
             }
         }
     }
 
+    /// Enables autorepeat of the keyboard with the given delay and interval in
+    /// milliseconds or disables it.
+    fn set_key_repeat(&self, slave: ffi::xcb_input_device_id_t, repeat: Option<(u16, u16)>) {
+        let (enabled, (delay, interval)) = match repeat {
+            Some(r) => (ffi::XCB_XKB_BOOL_CTRL_REPEAT_KEYS, r),
+            None => (0, (0, 0)),
+        };
+        let mut change = ffi::XCB_XKB_CONTROL_CONTROLS_ENABLED;
+        if repeat.is_some() {
+            // Changes the delay and interval.
+            change |= ffi::XCB_XKB_BOOL_CTRL_REPEAT_KEYS;
+        }
+        let per_key_repeat = [0xff; 32];
+        unsafe {
+            let xkb = &self.data.backend.xkb;
+            let cookie = xkb.xcb_xkb_set_controls_checked(
+                self.c.c,
+                slave,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                ffi::XCB_XKB_BOOL_CTRL_REPEAT_KEYS,
+                enabled,
+                change,
+                delay,
+                interval,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                0,
+                per_key_repeat.as_ptr(),
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                panic!("Could not set key repeat: {}", e);
+            }
+        }
+    }
+
+    /// Locks or unlocks the numlock (mod3) and capslock modifiers of the master keyboard
+    /// and its slaves and checks that the server has applied the new state.
+    fn set_lock_state(&self, master: ffi::xcb_input_device_id_t, numlock: bool, capslock: bool) {
+        let affect = ffi::XCB_MOD_MASK_3 | ffi::XCB_MOD_MASK_LOCK;
+        let mut locks = 0;
+        if numlock {
+            locks |= ffi::XCB_MOD_MASK_3;
+        }
+        if capslock {
+            locks |= ffi::XCB_MOD_MASK_LOCK;
+        }
+        unsafe {
+            let xcb = &self.data.backend.xcb;
+            let xkb = &self.data.backend.xkb;
+            let cookie = xkb.xcb_xkb_latch_lock_state_checked(
+                self.c.c,
+                master,
+                affect as _,
+                locks as _,
+                0,
+                0,
+                0,
+                0,
+                0,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                panic!("Could not set the lock state: {}", e);
+            }
+            let mut err = ptr::null_mut();
+            let reply = xkb.xcb_xkb_get_state_reply(
+                self.c.c,
+                xkb.xcb_xkb_get_state(self.c.c, master),
+                &mut err,
+            );
+            let reply = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not query the keyboard state: {}", e),
+            };
+            assert_eq!(
+                reply.locked_mods as u32 & affect,
+                locks,
+                "The server did not apply the lock state"
+            );
+        }
+    }
+
     fn create_seat2(&self) -> (ffi::xcb_input_device_id_t, ffi::xcb_input_device_id_t) {
         unsafe {
             let xinput = &self.data.backend.xinput;
@@ -609,7 +1282,7 @@ impl XInstance {
             write!(&mut change.name[..], "{}", name).unwrap();
             let cookie =
                 xinput.xcb_input_xi_change_hierarchy_checked(self.c.c, 1, &change as *const _ as _);
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 panic!("Could not add master: {}", e);
             }
             let mut err = ptr::null_mut();
@@ -680,20 +1353,103 @@ fn create_seat(instance: &Arc<XInstance>) -> Arc<XSeat> {
         pointer: pointer_id,
         keyboard: kb_id,
         layout: Arc::new(Cell::new(Layout::Qwerty)),
+        removed: Cell::new(false),
     })
 }
 
-impl Instance for Arc<XInstance> {
-    fn backend(&self) -> &dyn Backend {
-        &self.data.backend
-    }
-
-    fn default_seat(&self) -> Box<dyn Seat> {
+fn create_event_loop(
+    instance: &Arc<XInstance>,
+    display: &str,
+    env: &[(&str, &str)],
+) -> Arc<XEventLoop> {
+    let barrier_seat = create_seat(instance);
+    barrier_seat.un_focus();
+    let barrier_kb = add_keyboard(&barrier_seat);
+    // winit reads some variables, e.g. WINIT_X11_SCALE_FACTOR, after the event loop has
+    // been created. They must therefore stay set for the lifetime of the event loop.
+    let env: Vec<_> = env.iter().map(|(var, val)| set_env(var, val)).collect();
+    let el = {
+        let _var = set_env("DISPLAY", display);
+        WEventLoop::new_x11_any_thread().unwrap()
+    };
+    let el_c = el.xcb_connection().unwrap();
+    let el_fd = unsafe { instance.data.backend.xcb.xcb_get_file_descriptor(el_c as _) };
+    let (resource_id_base, resource_id_mask) = unsafe {
+        let setup = &*instance.data.backend.xcb.xcb_get_setup(el_c as _);
+        (setup.resource_id_base, setup.resource_id_mask)
+    };
+    let el = Arc::new(XEventLoopData {
+        instance: instance.clone(),
+        el: Mutex::new(el),
+        waiters: Default::default(),
+        events: Default::default(),
+        recent_events: Default::default(),
+        queue_stats: Default::default(),
+        version: Cell::new(1),
+        cached_num_monitors: Cell::new(usize::MAX),
+        started: Cell::new(false),
+        barrier_kb,
+        resource_id_base,
+        resource_id_mask,
+        el_c: el_c as _,
+        connection_lost: Cell::new(false),
+        connection_error: Default::default(),
+        control_flow: Cell::new(ControlFlow::Wait),
+        control_flow_changed: Default::default(),
+        resume_time_reached: Cell::new(None),
+        report_cleared_events: Cell::new(false),
+    });
+    let el2 = el.clone();
+    let jh = spawn_local("event loop", async move {
+        let afd = AsyncFd::with_interest(el_fd, Interest::READABLE).unwrap();
+        while el2.run() {
+            // The deadline of `WaitUntil` is emulated here so that winit never blocks the
+            // thread. Once it has been reached, `dispatch` lets winit observe it. Afterwards
+            // the loop waits like in `Wait` mode until the control flow is changed.
+            let deadline = match el2.control_flow.get() {
+                ControlFlow::Poll => Some(Instant::now() + POLL_INTERVAL),
+                ControlFlow::WaitUntil(deadline)
+                    if el2.resume_time_reached.get() != Some(deadline) =>
+                {
+                    Some(deadline)
+                }
+                _ => None,
+            };
+            let timeout = async {
+                match deadline {
+                    Some(d) => tokio::time::sleep_until(d.into()).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                res = afd.readable() => res.unwrap().clear_ready(),
+                _ = el2.control_flow_changed.notified() => {},
+                _ = timeout => {},
+            }
+        }
+    });
+    instance.event_loops.lock().push(Arc::downgrade(&el));
+    let el = Arc::new(XEventLoop {
+        data: el,
+        jh: Some(jh),
+        _env: env,
+    });
+    with_test_data(|td| td.event_loops.borrow_mut().push(Box::new(el.clone())));
+    el
+}
+
+impl Instance for Arc<XInstance> {
+    fn backend(&self) -> &dyn Backend {
+        &self.data.backend
+    }
+
+    fn default_seat(&self) -> Box<dyn Seat> {
         Box::new(Arc::new(XSeat {
             instance: self.clone(),
             pointer: self.core_p,
             keyboard: self.core_kb,
             layout: self.core_layout.clone(),
+            removed: Cell::new(false),
         }))
     }
 
@@ -702,37 +1458,30 @@ impl Instance for Arc<XInstance> {
     }
 
     fn create_event_loop(&self) -> Box<dyn EventLoop> {
-        let barrier_seat = create_seat(self);
-        barrier_seat.un_focus();
-        let barrier_kb = add_keyboard(&barrier_seat);
-        let el = {
-            let _var = set_env("DISPLAY", &format!(":{}", self.data.display));
-            WEventLoop::new_x11_any_thread().unwrap()
-        };
-        let el_c = el.xcb_connection().unwrap();
-        let el_fd = unsafe { self.data.backend.xcb.xcb_get_file_descriptor(el_c as _) };
-        let el = Arc::new(XEventLoopData {
-            instance: self.clone(),
-            el: Mutex::new(el),
-            waiters: Default::default(),
-            events: Default::default(),
-            version: Cell::new(1),
-            cached_num_monitors: Cell::new(usize::MAX),
-            barrier_kb,
-        });
-        let el2 = el.clone();
-        let jh = tokio::task::spawn_local(async move {
-            let afd = AsyncFd::with_interest(el_fd, Interest::READABLE).unwrap();
-            loop {
-                el2.run();
-                afd.readable().await.unwrap().clear_ready();
-            }
-        });
-        self.event_loops.lock().push(Arc::downgrade(&el));
-        Box::new(Arc::new(XEventLoop {
-            data: el,
-            jh: Some(jh),
-        }))
+        Box::new(create_event_loop(
+            self,
+            &format!(":{}", self.data.display),
+            &[],
+        ))
+    }
+
+    fn create_event_loop_with_env(&self, env: &[(&str, &str)]) -> Box<dyn EventLoop> {
+        Box::new(create_event_loop(
+            self,
+            &format!(":{}", self.data.display),
+            env,
+        ))
+    }
+
+    fn create_proxied_event_loop(&self) -> (Box<dyn EventLoop>, Box<dyn ConnectionProxy>) {
+        let proxy = XProxy::new(self.data.display);
+        let el = create_event_loop(self, &proxy.display(), &[]);
+        (Box::new(el), Box::new(proxy))
+    }
+
+    fn reconnect_proxied_event_loop(&self, proxy: &dyn ConnectionProxy) -> Box<dyn EventLoop> {
+        let proxy: &XProxy = proxy.any().downcast_ref().unwrap();
+        Box::new(create_event_loop(self, &proxy.display(), &[]))
     }
 
     fn take_screenshot(&self) {
@@ -809,6 +1558,200 @@ impl Instance for Arc<XInstance> {
         }
     }
 
+    fn server_info(&self) -> Option<ServerInfo> {
+        Some(self.data.server_info.clone())
+    }
+
+    fn server_cpu_time(&self) -> Option<Duration> {
+        let stat = match std::fs::read_to_string(format!("/proc/{}/stat", self.data.xserver_pid)) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Could not read the stat file of the X server: {}", e);
+                return None;
+            }
+        };
+        // The fields following the command name start at field 3. utime and stime are
+        // fields 14 and 15.
+        let fields: Vec<_> = stat[stat.rfind(')')? + 1..].split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64;
+        Some(Duration::from_millis((utime + stime) * 1000 / ticks))
+    }
+
+    fn server_peak_rss_kb(&self) -> Option<u64> {
+        let status =
+            match std::fs::read_to_string(format!("/proc/{}/status", self.data.xserver_pid)) {
+                Ok(s) => s,
+                Err(e) => {
+                    log::warn!("Could not read the status file of the X server: {}", e);
+                    return None;
+                }
+            };
+        let line = status.lines().find_map(|l| l.strip_prefix("VmHWM:"))?;
+        line.trim().strip_suffix("kB")?.trim().parse().ok()
+    }
+
+    fn cursor_icon(&self) -> Option<String> {
+        unsafe {
+            let xcb = &self.data.backend.xcb;
+            let xfixes = &self.data.backend.xfixes;
+            let mut err = ptr::null_mut();
+            let reply = xfixes.xcb_xfixes_get_cursor_image_and_name_reply(
+                self.c.c,
+                xfixes.xcb_xfixes_get_cursor_image_and_name(self.c.c),
+                &mut err,
+            );
+            let reply = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not get cursor name: {}", e),
+            };
+            if reply.cursor_atom == 0 {
+                return None;
+            }
+            let name = std::slice::from_raw_parts(
+                xfixes.xcb_xfixes_get_cursor_image_and_name_name(&*reply) as *const u8,
+                reply.nbytes as usize,
+            );
+            Some(String::from_utf8_lossy(name).into_owned())
+        }
+    }
+
+    fn cursor_position(&self) -> (i32, i32) {
+        unsafe {
+            let xcb = &self.data.backend.xcb;
+            let mut err = ptr::null_mut();
+            let reply = xcb.xcb_query_pointer_reply(
+                self.c.c,
+                xcb.xcb_query_pointer(self.c.c, self.c.screen.root),
+                &mut err,
+            );
+            let reply = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not query pointer: {}", e),
+            };
+            (reply.root_x as i32, reply.root_y as i32)
+        }
+    }
+
+    fn detach_server(&self) -> Option<Box<dyn Send>> {
+        log::info!("Detaching the X server on display :{}", self.data.display);
+        let server = self.data.server.lock().take()?;
+        // The module aborts the server once its socket is closed. Keep a copy of the
+        // socket with the server.
+        let sock = self
+            .data
+            .sock
+            .as_ref()
+            .map(|s| uapi::fcntl_dupfd_cloexec(s.raw(), 0).unwrap());
+        Some(Box::new(DetachedServer {
+            _server: server,
+            _sock: sock,
+        }))
+    }
+
+    fn leak_check(&self) -> Box<dyn LeakCheck> {
+        Box::new(XLeakCheck {
+            data: Arc::downgrade(&self.data),
+            xserver_pid: self.data.xserver_pid,
+            server_exited: self.data.server_exited.clone(),
+        })
+    }
+
+    fn dump_diagnostics(&self, dir: &Path) {
+        let mut table = String::new();
+        {
+            let wm_data = self.data.wm_data.lock();
+            let _ = writeln!(table, "Pending wakers: {}", wm_data.wakers.len());
+            let _ = writeln!(table, "Pending WM actions: {}", wm_data.actions.len());
+            let mut windows: Vec<_> = wm_data.windows.values().flat_map(|w| w.upgrade()).collect();
+            windows.sort_by_key(|w| w.id);
+            for win in windows {
+                let _ = writeln!(
+                    table,
+                    "{}: parent={} title={:?} mapped={} state={:?}/{:?} geometry={}x{}+{}+{} \
+                     border={} maximized={}/{} fullscreen={} always_on_top={} always_on_bottom={} \
+                     destroyed={}",
+                    win.id,
+                    win.parent_id.get(),
+                    win.utf8_title.borrow(),
+                    win.mapped.get(),
+                    win.current_state.get(),
+                    win.desired_state.get(),
+                    win.width.get(),
+                    win.height.get(),
+                    win.x.get(),
+                    win.y.get(),
+                    win.border.get(),
+                    win.maximized_horz.get(),
+                    win.maximized_vert.get(),
+                    win.fullscreen.get(),
+                    win.always_on_top.get(),
+                    win.always_on_bottom.get(),
+                    win.destroyed.get(),
+                );
+            }
+        }
+        if let Err(e) = std::fs::write(dir.join("wm_windows"), table) {
+            log::warn!("Could not write the window table of the WM: {}", e);
+        }
+        let x11_data = crate::test::with_test_data(|td| td.test_dir.join("x11_data"));
+        for file in ["log", "stderr"] {
+            let contents = match std::fs::read_to_string(x11_data.join(file)) {
+                Ok(c) => c,
+                Err(_) => continue,
+            };
+            let lines: Vec<_> = contents.lines().collect();
+            let tail = lines[lines.len().saturating_sub(SERVER_LOG_TAIL)..].join("\n");
+            if let Err(e) = std::fs::write(dir.join(format!("server_{}_tail", file)), tail) {
+                log::warn!("Could not write the tail of the X server {}: {}", file, e);
+            }
+        }
+    }
+
+    fn capture_window(&self, window: &dyn Window) -> Image {
+        let window: &Arc<XWindow> = window.any().downcast_ref().unwrap();
+        let xcb = &self.data.backend.xcb;
+        let width = window.width.get();
+        let height = window.height.get();
+        unsafe {
+            let mut err = ptr::null_mut();
+            let reply = xcb.xcb_get_image_reply(
+                self.c.c,
+                xcb.xcb_get_image(
+                    self.c.c,
+                    ffi::XCB_IMAGE_FORMAT_Z_PIXMAP as u8,
+                    window.id,
+                    0,
+                    0,
+                    width as u16,
+                    height as u16,
+                    !0,
+                ),
+                &mut err,
+            );
+            let mut image = match self.c.errors.check(xcb, reply, err) {
+                Ok(i) => i,
+                Err(e) => panic!("Could not capture window: {}", e),
+            };
+            let mut data = std::slice::from_raw_parts(
+                xcb.xcb_get_image_data(&mut *image),
+                image.length as usize * 4,
+            )
+            .to_vec();
+            if image.depth != 32 {
+                for pixel in data.chunks_mut(4) {
+                    pixel[3] = 255;
+                }
+            }
+            Image {
+                width,
+                height,
+                data,
+            }
+        }
+    }
+
     fn before_poll(&self) {
         let els = self.event_loops.lock();
         for el in &*els {
@@ -819,16 +1762,8 @@ impl Instance for Arc<XInstance> {
     }
 
     fn enable_second_monitor(&self, enabled: bool) {
+        self.connect_second_output(enabled);
         unsafe {
-            let mut msg = Message {
-                enable_second_monitor: EnableSecondMonitor {
-                    ty: MT_ENABLE_SECOND_MONITOR as _,
-                    enable: enabled as _,
-                },
-            };
-            uapi::write(self.data.sock.raw(), &msg).unwrap();
-            uapi::read(self.data.sock.raw(), &mut msg).unwrap();
-            assert_eq!(msg.ty, MT_ENABLE_SECOND_MONITOR_REPLY as _);
             let xrandr = &self.data.backend.xrandr;
             let xcb = &self.data.backend.xcb;
             let mut err = ptr::null_mut();
@@ -842,7 +1777,7 @@ impl Instance for Arc<XInstance> {
                         1,
                         1,
                     );
-                    self.c.errors.check_cookie(xcb, cookie).unwrap();
+                    self.c.check_cookie(cookie).unwrap();
                 } else {
                     let cookie = xrandr.xcb_randr_set_crtc_config(
                         self.c.c,
@@ -869,13 +1804,13 @@ impl Instance for Arc<XInstance> {
                     self.data.first_output
                 },
             );
-            self.c.errors.check_cookie(xcb, cookie).unwrap();
+            self.c.check_cookie(cookie).unwrap();
         }
     }
 
-    fn start_dnd_process(&self, path: &Path) -> Box<dyn DndProcess> {
+    fn start_dnd_process(&self, paths: &[&Path]) -> Box<dyn DndProcess> {
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
-        tokio::task::spawn_local(dnd::run(self.data.clone(), rx, path));
+        spawn_local("dnd", dnd::run(self.data.clone(), rx, paths));
         Box::new(XDndProcess {
             tx,
             dropped: Cell::new(false),
@@ -905,6 +1840,363 @@ impl Instance for Arc<XInstance> {
     fn redraw_requested_scenarios(&self) -> usize {
         1
     }
+
+    fn configure_monitors(&self, monitors: &[MonitorConfig]) {
+        log::info!("Configuring monitors: {:?}", monitors);
+        assert!(
+            (1..=2).contains(&monitors.len()),
+            "The X backend supports one or two monitors"
+        );
+        let scale = monitors[0].scale;
+        assert!(
+            monitors.iter().all(|m| m.scale == scale),
+            "X11 has no per-monitor scale. All monitors must have the same scale"
+        );
+        assert!(
+            monitors.iter().all(|m| m.x >= 0 && m.y >= 0),
+            "Monitors must not have negative positions"
+        );
+        let width = monitors.iter().map(|m| m.x + m.width as i32).max().unwrap();
+        let height = monitors
+            .iter()
+            .map(|m| m.y + m.height as i32)
+            .max()
+            .unwrap();
+        assert!(
+            width <= MAX_SCREEN_WIDTH && height <= MAX_SCREEN_HEIGHT,
+            "The monitors must fit into a {}x{} screen",
+            MAX_SCREEN_WIDTH,
+            MAX_SCREEN_HEIGHT
+        );
+        self.connect_second_output(monitors.len() == 2);
+        let outputs = [self.data.first_output, self.data.second_output];
+        unsafe {
+            let xrandr = &self.data.backend.xrandr;
+            let xcb = &self.data.backend.xcb;
+            let mut err = ptr::null_mut();
+            let reply = xrandr.xcb_randr_get_screen_resources_current_reply(
+                self.c.c,
+                xrandr.xcb_randr_get_screen_resources_current(self.c.c, self.c.screen.root),
+                &mut err,
+            );
+            let resources = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => panic!("Could not get screen resources: {}", e),
+            };
+            let crtcs = std::slice::from_raw_parts(
+                xrandr.xcb_randr_get_screen_resources_current_crtcs(&*resources),
+                resources.num_crtcs as _,
+            );
+            let mut set_crtc = |crtc, x, y, mode, output: Option<&u32>| {
+                let reply = xrandr.xcb_randr_set_crtc_config_reply(
+                    self.c.c,
+                    xrandr.xcb_randr_set_crtc_config(
+                        self.c.c,
+                        crtc,
+                        0,
+                        0,
+                        x,
+                        y,
+                        mode,
+                        ffi::XCB_RANDR_ROTATION_ROTATE_0 as _,
+                        output.is_some() as _,
+                        output.map(|o| o as *const _).unwrap_or(ptr::null()),
+                    ),
+                    &mut err,
+                );
+                if let Err(e) = self.c.errors.check(xcb, reply, err) {
+                    panic!("Could not configure crtc {}: {}", crtc, e);
+                }
+            };
+            // Disable all crtcs first so that the screen can be shrunk.
+            for &crtc in crtcs.iter().take(2) {
+                set_crtc(crtc, 0, 0, 0, None);
+            }
+            let cookie = xrandr.xcb_randr_set_screen_size_checked(
+                self.c.c,
+                self.c.screen.root,
+                width as _,
+                height as _,
+                1,
+                1,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                panic!("Could not set the screen size: {}", e);
+            }
+            for (i, monitor) in monitors.iter().enumerate() {
+                let mode = self.create_mode(
+                    monitor.width,
+                    monitor.height,
+                    monitor.refresh_rate as u32 * 1000,
+                );
+                let cookie = xrandr.xcb_randr_add_output_mode_checked(self.c.c, outputs[i], mode);
+                if let Err(e) = self.c.check_cookie(cookie) {
+                    panic!("Could not add mode to output: {}", e);
+                }
+                set_crtc(
+                    crtcs[i],
+                    monitor.x as _,
+                    monitor.y as _,
+                    mode,
+                    Some(&outputs[i]),
+                );
+            }
+            let cookie = xrandr.xcb_randr_set_output_primary_checked(
+                self.c.c,
+                self.c.screen.root,
+                outputs[0],
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                panic!("Could not set the primary output: {}", e);
+            }
+        }
+        // The scale is derived from the physical size of the outputs and Xft.dpi, both of
+        // which set_scale_factor updates for the new modes.
+        self.set_scale_factor(scale);
+    }
+
+    fn set_monitor_mode(&self, monitor_index: usize, width: u32, height: u32, refresh_mhz: u32) {
+        log::info!(
+            "Setting the mode of monitor {} to {}x{}@{}mHz",
+            monitor_index,
+            width,
+            height,
+            refresh_mhz
+        );
+        let output = match monitor_index {
+            0 => self.data.first_output,
+            1 => self.data.second_output,
+            _ => panic!("The X backend has only two monitors"),
+        };
+        unsafe {
+            let xrandr = &self.data.backend.xrandr;
+            let xcb = &self.data.backend.xcb;
+            let mut err = ptr::null_mut();
+            let reply = xrandr.xcb_randr_get_output_info_reply(
+                self.c.c,
+                xrandr.xcb_randr_get_output_info(self.c.c, output, 0),
+                &mut err,
+            );
+            let crtc = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r.crtc,
+                Err(e) => panic!("Could not get output info: {}", e),
+            };
+            assert_ne!(crtc, 0, "Monitor {} is not enabled", monitor_index);
+            let reply = xrandr.xcb_randr_get_crtc_info_reply(
+                self.c.c,
+                xrandr.xcb_randr_get_crtc_info(self.c.c, crtc, 0),
+                &mut err,
+            );
+            let (x, y) = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => (r.x, r.y),
+                Err(e) => panic!("Could not get crtc info: {}", e),
+            };
+            let reply = xcb.xcb_get_geometry_reply(
+                self.c.c,
+                xcb.xcb_get_geometry(self.c.c, self.c.screen.root),
+                &mut err,
+            );
+            let (screen_width, screen_height) = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => (r.width as u32, r.height as u32),
+                Err(e) => panic!("Could not get the screen size: {}", e),
+            };
+            let right = x as u32 + width;
+            let bottom = y as u32 + height;
+            assert!(
+                right <= MAX_SCREEN_WIDTH as u32 && bottom <= MAX_SCREEN_HEIGHT as u32,
+                "The monitor must fit into a {}x{} screen",
+                MAX_SCREEN_WIDTH,
+                MAX_SCREEN_HEIGHT
+            );
+            if right > screen_width || bottom > screen_height {
+                let cookie = xrandr.xcb_randr_set_screen_size_checked(
+                    self.c.c,
+                    self.c.screen.root,
+                    right.max(screen_width) as _,
+                    bottom.max(screen_height) as _,
+                    1,
+                    1,
+                );
+                if let Err(e) = self.c.check_cookie(cookie) {
+                    panic!("Could not set the screen size: {}", e);
+                }
+            }
+            let mode = self.create_mode(width, height, refresh_mhz);
+            let cookie = xrandr.xcb_randr_add_output_mode_checked(self.c.c, output, mode);
+            if let Err(e) = self.c.check_cookie(cookie) {
+                panic!("Could not add mode to output: {}", e);
+            }
+            let reply = xrandr.xcb_randr_set_crtc_config_reply(
+                self.c.c,
+                xrandr.xcb_randr_set_crtc_config(
+                    self.c.c,
+                    crtc,
+                    0,
+                    0,
+                    x,
+                    y,
+                    mode,
+                    ffi::XCB_RANDR_ROTATION_ROTATE_0 as _,
+                    1,
+                    &output,
+                ),
+                &mut err,
+            );
+            if let Err(e) = self.c.errors.check(xcb, reply, err) {
+                panic!("Could not set the mode of crtc {}: {}", crtc, e);
+            }
+        }
+    }
+
+    fn set_scale_factor(&self, scale_factor: f64) {
+        log::info!("Setting the scale factor to {}", scale_factor);
+        // Pixels per millimeter at the requested scale. 96 DPI corresponds to a scale of 1.
+        let ppmm = scale_factor * 96.0 / 25.4;
+        let outputs = [self.data.first_output, self.data.second_output];
+        unsafe {
+            let xrandr = &self.data.backend.xrandr;
+            let xcb = &self.data.backend.xcb;
+            let mut err = ptr::null_mut();
+            for (idx, &output) in outputs.iter().enumerate() {
+                let reply = xrandr.xcb_randr_get_output_info_reply(
+                    self.c.c,
+                    xrandr.xcb_randr_get_output_info(self.c.c, output, 0),
+                    &mut err,
+                );
+                let crtc = match self.c.errors.check(xcb, reply, err) {
+                    Ok(r) => r.crtc,
+                    Err(e) => panic!("Could not get output info: {}", e),
+                };
+                if crtc == 0 {
+                    continue;
+                }
+                let reply = xrandr.xcb_randr_get_crtc_info_reply(
+                    self.c.c,
+                    xrandr.xcb_randr_get_crtc_info(self.c.c, crtc, 0),
+                    &mut err,
+                );
+                let (width, height) = match self.c.errors.check(xcb, reply, err) {
+                    Ok(r) => (r.width, r.height),
+                    Err(e) => panic!("Could not get crtc info: {}", e),
+                };
+                let mut msg = Message {
+                    set_physical_size: SetPhysicalSize {
+                        ty: MT_SET_PHYSICAL_SIZE as _,
+                        output: idx as _,
+                        mm_width: (width as f64 / ppmm).round() as _,
+                        mm_height: (height as f64 / ppmm).round() as _,
+                    },
+                };
+                uapi::write(self.data.sock(), &msg).unwrap();
+                uapi::read(self.data.sock(), &mut msg).unwrap();
+                assert_eq!(msg.ty, MT_SET_PHYSICAL_SIZE_REPLY as _);
+            }
+            // Xlib reads the resource database when the display is opened. This only
+            // affects event loops created afterwards.
+            let resources = format!("Xft.dpi:\t{}\n", (scale_factor * 96.0).round());
+            let cookie = xcb.xcb_change_property_checked(
+                self.c.c,
+                ffi::XCB_PROP_MODE_REPLACE as _,
+                self.c.screen.root,
+                ffi::XCB_ATOM_RESOURCE_MANAGER,
+                ffi::XCB_ATOM_STRING,
+                8,
+                resources.len() as _,
+                resources.as_ptr() as _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                panic!("Could not set the resource database: {}", e);
+            }
+        }
+    }
+
+    fn client_windows(&self) -> Vec<u64> {
+        let owners: Vec<_> = self
+            .event_loops
+            .lock()
+            .iter()
+            .flat_map(|el| el.upgrade())
+            .map(|el| (el.resource_id_base, el.resource_id_mask))
+            .collect();
+        let is_owned = |win: ffi::xcb_window_t| {
+            owners
+                .iter()
+                .any(|&(base, mask)| win & !mask == base & !mask)
+        };
+        let mut res = vec![];
+        unsafe {
+            let xcb = &self.data.backend.xcb;
+            let client_list = xcb_dl_util::property::get_property::<u32>(
+                xcb,
+                &self.c.errors,
+                self.c.screen.root,
+                self.data.atoms.net_client_list,
+                ffi::XCB_ATOM_WINDOW,
+                false,
+                10000,
+            );
+            match client_list {
+                Ok(list) => res.extend(list.into_iter().filter(|&w| is_owned(w))),
+                Err(e) => log::warn!("Could not retrieve _NET_CLIENT_LIST: {}", e),
+            }
+            let mut todo = vec![self.c.screen.root];
+            while let Some(win) = todo.pop() {
+                let mut err = ptr::null_mut();
+                let reply =
+                    xcb.xcb_query_tree_reply(self.c.c, xcb.xcb_query_tree(self.c.c, win), &mut err);
+                let reply = match self.c.errors.check(xcb, reply, err) {
+                    Ok(r) => r,
+                    Err(e) => {
+                        // The window might have been destroyed in the meantime.
+                        log::debug!("Could not query tree of {}: {}", win, e);
+                        continue;
+                    }
+                };
+                let children = std::slice::from_raw_parts(
+                    xcb.xcb_query_tree_children(&*reply),
+                    reply.children_len as _,
+                );
+                for &child in children {
+                    if is_owned(child) {
+                        res.push(child);
+                    }
+                    todo.push(child);
+                }
+            }
+        }
+        res.sort_unstable();
+        res.dedup();
+        res.into_iter().map(|w| w as u64).collect()
+    }
+
+    fn stacking_order(&self) -> Vec<u64> {
+        let res = unsafe {
+            xcb_dl_util::property::get_property::<u32>(
+                &self.data.backend.xcb,
+                &self.c.errors,
+                self.c.screen.root,
+                self.data.atoms.net_client_list_stacking,
+                ffi::XCB_ATOM_WINDOW,
+                false,
+                10000,
+            )
+        };
+        match res {
+            Ok(list) => list.into_iter().map(|w| w as u64).collect(),
+            Err(e) => panic!("Could not retrieve _NET_CLIENT_LIST_STACKING: {}", e),
+        }
+    }
+
+    fn configure_wm(&self, config: WmConfig) {
+        log::info!("Configuring the window manager: {:?}", config);
+        self.data.wm_data.lock().config = config;
+        // The WM applies the shortcut grab the next time it handles events.
+        self.data.wm_wakeup.notify_one();
+    }
+
+    fn wm_shortcuts(&self) -> u32 {
+        self.data.wm_data.lock().shortcuts
+    }
 }
 
 struct XDndProcess {
@@ -945,6 +2237,11 @@ struct WmData {
     parents: HashMap<ffi::xcb_window_t, Weak<XWindow>>,
     window_to_parent: HashMap<ffi::xcb_window_t, ffi::xcb_window_t>,
     pongs: HashSet<ffi::xcb_window_t>,
+    config: WmConfig,
+    /// The number of shortcuts handled by the window manager.
+    shortcuts: u32,
+    /// The actions the window manager has been asked to perform on its own initiative.
+    actions: Vec<(ffi::xcb_window_t, WmAction)>,
 }
 
 impl WmData {
@@ -969,12 +2266,29 @@ impl WmData {
     }
 }
 
-impl Drop for XInstanceData {
+/// The X server of an instance whose teardown has been delayed. See
+/// `Instance::detach_server`.
+struct DetachedServer {
+    _server: XServerProcess,
+    _sock: Option<OwnedFd>,
+}
+
+/// A running X server. The server is killed and reaped when this is dropped.
+struct XServerProcess {
+    pid: libc::pid_t,
+    display: u32,
+    /// Set once the server has been reaped.
+    exited: Arc<AtomicBool>,
+}
+
+impl Drop for XServerProcess {
     fn drop(&mut self) {
         log::info!("Killing the X server");
-        uapi::kill(self.xserver_pid, libc::SIGKILL).unwrap();
+        uapi::kill(self.pid, libc::SIGKILL).unwrap();
         log::info!("Waiting for the X server to terminate");
-        uapi::waitpid(self.xserver_pid, 0).unwrap();
+        uapi::waitpid(self.pid, 0).unwrap();
+        self.exited.store(true, Relaxed);
+        display::release(self.display, self.pid);
     }
 }
 
@@ -984,27 +2298,215 @@ impl Drop for XInstance {
     }
 }
 
+/// The number of lines of the X server log included in the diagnostics of a timed out test.
+const SERVER_LOG_TAIL: usize = 50;
+
+/// How long the WM has to process the destruction of the windows of a test.
+const WINDOW_CLEANUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// The interval between two iterations of the event loop in `ControlFlow::Poll` mode.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+struct XLeakCheck {
+    data: Weak<XInstanceData>,
+    xserver_pid: libc::pid_t,
+    server_exited: Arc<AtomicBool>,
+}
+
+impl LeakCheck for XLeakCheck {
+    fn windows<'a>(&'a self) -> Pin<Box<dyn Future<Output = Vec<String>> + 'a>> {
+        struct Cleaned<'b>(&'b XInstanceData);
+        impl<'b> Future for Cleaned<'b> {
+            type Output = ();
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                let mut data = self.0.wm_data.lock();
+                if data.windows.is_empty() && data.parents.is_empty() {
+                    Poll::Ready(())
+                } else {
+                    data.wakers.push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+        Box::pin(async move {
+            let data = match self.data.upgrade() {
+                Some(d) => d,
+                _ => return vec![],
+            };
+            let _ = tokio::time::timeout(WINDOW_CLEANUP_TIMEOUT, Cleaned(&data)).await;
+            let wm_data = data.wm_data.lock();
+            let mut leaks = vec![];
+            for &win in wm_data.windows.keys() {
+                leaks.push(format!("Window {} is still registered with the WM", win));
+            }
+            for (&win, &parent) in &wm_data.window_to_parent {
+                leaks.push(format!(
+                    "Frame {} of window {} has not been destroyed",
+                    parent, win
+                ));
+            }
+            leaks
+        })
+    }
+
+    fn instance(&self) -> Vec<String> {
+        let mut leaks = vec![];
+        if self.data.strong_count() > 0 {
+            leaks.push("The instance data is still referenced".to_string());
+        }
+        if !self.server_exited.load(Relaxed) {
+            leaks.push(format!(
+                "The X server (pid {}) has not exited",
+                self.xserver_pid
+            ));
+        }
+        leaks
+    }
+}
+
 struct XEventLoopData {
     instance: Arc<XInstance>,
     el: Mutex<WEventLoop<UserEvent>>,
     waiters: Mutex<Vec<Waker>>,
-    events: Mutex<VecDeque<Event>>,
+    events: Mutex<VecDeque<(Instant, Event)>>,
+    /// The last `RECENT_EVENTS` events, including those that have been consumed.
+    recent_events: Mutex<VecDeque<(Instant, Event)>>,
+    queue_stats: Mutex<EventQueueStats>,
     version: Cell<u32>,
     cached_num_monitors: Cell<usize>,
+    /// Whether winit has run the first iteration of the event loop. See `Event::Resumed`.
+    started: Cell<bool>,
     barrier_kb: Arc<XKeyboard>,
+    resource_id_base: u32,
+    resource_id_mask: u32,
+    el_c: *mut ffi::xcb_connection_t,
+    connection_lost: Cell<bool>,
+    /// See `EventLoop::connection_error`.
+    connection_error: RefCell<Option<String>>,
+    /// The control flow requested by the test. See `EventLoop::set_control_flow`.
+    control_flow: Cell<ControlFlow>,
+    /// Wakes up the task driving the event loop after `control_flow` has changed.
+    control_flow_changed: tokio::sync::Notify,
+    /// The `WaitUntil` deadline for which `ResumeTimeReached` has already been reported.
+    resume_time_reached: Cell<Option<Instant>>,
+    /// See `EventLoop::report_cleared_events`.
+    report_cleared_events: Cell<bool>,
 }
 
 impl XEventLoopData {
-    fn run(&self) {
+    /// Dispatches pending winit events. Returns `false` if the connection has been lost.
+    fn run(&self) -> bool {
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| self.dispatch()));
+        let xcb = &self.instance.data.backend.xcb;
+        if unsafe { xcb.xcb_connection_has_error(self.el_c) } != 0 {
+            log::warn!("The connection of the event loop to the X server has been lost");
+            if let Err(e) = &res {
+                let msg = panic_message(&**e);
+                log::info!("winit aborted the event loop: {}", msg);
+                *self.connection_error.borrow_mut() = Some(msg);
+            }
+            self.connection_lost.set(true);
+            self.wake();
+            return false;
+        }
+        if let Err(e) = res {
+            std::panic::resume_unwind(e);
+        }
+        true
+    }
+
+    fn check_connection(&self) {
+        if self.connection_lost.get() {
+            std::panic::panic_any(ConnectionLost);
+        }
+    }
+
+    fn wake(&self) {
+        self.version.set(self.version.get() + 1);
+        let mut waiters = self.waiters.lock();
+        for waiter in waiters.drain(..) {
+            waiter.wake();
+        }
+    }
+
+    fn dispatch(&self) {
         let mut el = self.el.lock();
         let mut events = self.events.lock();
+        let mut recent_events = self.recent_events.lock();
+        let mut queue_stats = self.queue_stats.lock();
         let mut wake = false;
+        // Each call runs a single iteration of the winit event loop, which starts with
+        // `StartCause::Init`. If the test has requested `Poll` or `WaitUntil` with a
+        // deadline that has been reached, a second iteration is run so that winit reports
+        // the corresponding start cause.
+        let control_flow = self.control_flow.get();
+        let mut exit = match control_flow {
+            ControlFlow::Poll => false,
+            ControlFlow::WaitUntil(deadline) => {
+                Instant::now() < deadline || self.resume_time_reached.get() == Some(deadline)
+            }
+            _ => true,
+        };
+        if let (ControlFlow::WaitUntil(deadline), false) = (control_flow, exit) {
+            self.resume_time_reached.set(Some(deadline));
+        }
+        // `MainEventsCleared` and `RedrawEventsCleared` are only reported for iterations
+        // that deliver other events. Otherwise every dispatch, e.g. in `before_poll`,
+        // would produce events. `MainEventsCleared` is held back until it is known whether
+        // the iteration delivers redraw events.
+        let report_cleared = self.report_cleared_events.get();
+        let mut delivered = false;
+        let mut main_cleared = None;
+        // Whether the last event was `NewEvents(Init)` of an iteration other than the
+        // first. See `Event::Resumed`.
+        let mut after_restart = false;
+        let mut push = |now: Instant, ev: Event| {
+            log::debug!("winit event: {:?}", ev);
+            if recent_events.len() == RECENT_EVENTS {
+                recent_events.pop_front();
+            }
+            recent_events.push_back((now, ev.clone()));
+            *queue_stats.counts.entry(event_kind(&ev)).or_default() += 1;
+            events.push_back((now, ev));
+            queue_stats.max_depth = queue_stats.max_depth.max(events.len());
+        };
         el.run_return(|ev, _, cf| {
-            *cf = ControlFlow::Exit;
+            if let WEvent::NewEvents(cause) = &ev {
+                if *cause != StartCause::Init {
+                    exit = true;
+                }
+                delivered = false;
+                main_cleared = None;
+            }
+            *cf = match exit {
+                true => ControlFlow::Exit,
+                false => control_flow,
+            };
+            let skip = after_restart && matches!(ev, WEvent::Resumed);
+            after_restart =
+                matches!(ev, WEvent::NewEvents(StartCause::Init)) && self.started.replace(true);
+            if skip {
+                return;
+            }
             if let Some(ev) = map_event(ev) {
-                log::debug!("winit event: {:?}", ev);
-                events.push_back(ev);
-                wake = true;
+                let now = crate::clock::now();
+                match ev {
+                    Event::MainEventsCleared | Event::RedrawEventsCleared if !report_cleared => {}
+                    Event::MainEventsCleared if !delivered => main_cleared = Some(now),
+                    Event::MainEventsCleared | Event::RedrawEventsCleared => {
+                        if delivered {
+                            push(now, ev);
+                        }
+                    }
+                    _ => {
+                        if let Some(time) = main_cleared.take() {
+                            push(time, Event::MainEventsCleared);
+                        }
+                        push(now, ev);
+                        delivered = true;
+                        wake = true;
+                    }
+                }
             }
         });
         if !wake {
@@ -1015,11 +2517,7 @@ impl XEventLoopData {
             }
         }
         if wake {
-            self.version.set(self.version.get() + 1);
-            let mut waiters = self.waiters.lock();
-            for waiter in waiters.drain(..) {
-                waiter.wake();
-            }
+            self.wake();
         }
     }
 }
@@ -1027,6 +2525,7 @@ impl XEventLoopData {
 struct XEventLoop {
     data: Arc<XEventLoopData>,
     jh: Option<JoinHandle<()>>,
+    _env: Vec<EnvGuard>,
 }
 
 impl Drop for XEventLoop {
@@ -1036,20 +2535,35 @@ impl Drop for XEventLoop {
 }
 
 impl XEventLoop {
-    fn event2<'a>(&'a self) -> Pin<Box<dyn Future<Output = Event> + 'a>> {
-        struct Changed<'b>(&'b XEventLoopData);
+    fn event2<'a>(&'a self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
+        self.event_for(None)
+    }
+
+    /// Returns the next event. If `window` is set, only events of that window are returned
+    /// and the events before it stay in the queue.
+    fn event_for<'a>(
+        &'a self,
+        window: Option<WindowId>,
+    ) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
+        struct Changed<'b>(&'b XEventLoopData, Option<WindowId>);
         impl<'b> Future for Changed<'b> {
-            type Output = Event;
+            type Output = (Instant, Event);
             fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-                if let Some(e) = self.0.events.lock().pop_front() {
+                let mut events = self.0.events.lock();
+                let pos = match self.1 {
+                    None => Some(0),
+                    Some(id) => events.iter().position(|(_, e)| e.window_id() == Some(id)),
+                };
+                if let Some(e) = pos.and_then(|pos| events.remove(pos)) {
                     Poll::Ready(e)
                 } else {
+                    self.0.check_connection();
                     self.0.waiters.lock().push(cx.waker().clone());
                     Poll::Pending
                 }
             }
         }
-        Box::pin(Changed(&self.data))
+        Box::pin(Changed(&self.data, window))
     }
 
     fn get_window_format(&self, id: ffi::xcb_window_t) -> ffi::xcb_render_directformat_t {
@@ -1104,15 +2618,42 @@ impl XEventLoop {
 
 impl EventStream for Arc<XEventLoop> {
     fn event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = Event> + 'a>> {
+        Box::pin(async move { self.event2().await.1 })
+    }
+
+    fn timed_event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
         self.event2()
     }
 }
 
+/// The events of a single window. See `EventLoop::window_events`.
+struct XWindowEventStream {
+    el: Arc<XEventLoop>,
+    window: WindowId,
+}
+
+impl EventStream for XWindowEventStream {
+    fn event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = Event> + 'a>> {
+        Box::pin(async move { self.el.event_for(Some(self.window)).await.1 })
+    }
+
+    fn timed_event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
+        self.el.event_for(Some(self.window))
+    }
+}
+
 impl EventLoop for Arc<XEventLoop> {
     fn events(&self) -> Box<dyn EventStream> {
         Box::new(self.clone())
     }
 
+    fn window_events(&self, window: WindowId) -> Box<dyn EventStream> {
+        Box::new(XWindowEventStream {
+            el: self.clone(),
+            window,
+        })
+    }
+
     fn changed<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
         struct Changed<'b>(&'b XEventLoopData, u32);
         impl<'b> Future for Changed<'b> {
@@ -1121,6 +2662,7 @@ impl EventLoop for Arc<XEventLoop> {
                 if self.1 != self.0.version.get() {
                     Poll::Ready(())
                 } else {
+                    self.0.check_connection();
                     self.0.waiters.lock().push(cx.waker().clone());
                     Poll::Pending
                 }
@@ -1129,6 +2671,45 @@ impl EventLoop for Arc<XEventLoop> {
         Box::pin(Changed(&self.data, self.data.version.get()))
     }
 
+    fn disconnected<'a>(&'a self) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        struct Disconnected<'b>(&'b XEventLoopData);
+        impl<'b> Future for Disconnected<'b> {
+            type Output = ();
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+                if self.0.connection_lost.get() {
+                    Poll::Ready(())
+                } else {
+                    self.0.waiters.lock().push(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+        Box::pin(Disconnected(&self.data))
+    }
+
+    fn connection_error(&self) -> Option<String> {
+        self.data.connection_error.borrow().clone()
+    }
+
+    fn recent_events(&self) -> Vec<(Instant, Event)> {
+        self.data.recent_events.lock().iter().cloned().collect()
+    }
+
+    fn queue_stats(&self) -> EventQueueStats {
+        self.data.queue_stats.lock().clone()
+    }
+
+    fn set_control_flow(&self, control_flow: ControlFlow) {
+        log::info!("Setting the control flow to {:?}", control_flow);
+        self.data.control_flow.set(control_flow);
+        self.data.resume_time_reached.set(None);
+        self.data.control_flow_changed.notify_one();
+    }
+
+    fn report_cleared_events(&self, report: bool) {
+        self.data.report_cleared_events.set(report);
+    }
+
     fn create_window(&self, builder: WindowBuilder) -> Box<dyn Window> {
         let winit = builder.build(&*self.data.el.lock()).unwrap();
         let id = winit.x11_window().unwrap();
@@ -1146,6 +2727,7 @@ impl EventLoop for Arc<XEventLoop> {
             destroyed: Cell::new(false),
             mapped: Cell::new(false),
             always_on_top: Cell::new(false),
+            always_on_bottom: Cell::new(false),
             maximized_vert: Cell::new(false),
             maximized_horz: Cell::new(false),
             fullscreen: Cell::new(false),
@@ -1154,6 +2736,11 @@ impl EventLoop for Arc<XEventLoop> {
             pre_fs_width: Cell::new(0),
             pre_fs_height: Cell::new(0),
             pre_fs_border: Cell::new(0),
+            pre_max_x: Cell::new(0),
+            pre_max_y: Cell::new(0),
+            pre_max_width: Cell::new(0),
+            pre_max_height: Cell::new(0),
+            pre_max_border: Cell::new(0),
             decorations: Cell::new(true),
             border: Cell::new(0),
             x: Cell::new(0),
@@ -1170,14 +2757,19 @@ impl EventLoop for Arc<XEventLoop> {
             wm_name: RefCell::new("".to_string()),
             utf8_title: RefCell::new("".to_string()),
             urgency: Cell::new(false),
+            demands_attention: Cell::new(false),
             class: RefCell::new(None),
             instance: RefCell::new(None),
             protocols: Cell::new(Protocols::empty()),
             desired_state: Cell::new(WindowState::Withdrawn),
             current_state: Cell::new(WindowState::Withdrawn),
             maximizable: Cell::new(true),
-            icon: RefCell::new(None),
+            icon: RefCell::new(vec![]),
             dragging: Cell::new(false),
+            resizing: Cell::new(false),
+            blur: RefCell::new(None),
+            stacking_position: Cell::new(None),
+            map_requests: Cell::new(0),
         });
         self.data
             .instance
@@ -1198,7 +2790,7 @@ impl EventLoop for Arc<XEventLoop> {
         Box::pin(async {
             self.data.barrier_kb.press(Key::KeyEsc);
             loop {
-                let ev = self.event2().await;
+                let (_, ev) = self.event2().await;
                 if let Event::DeviceEvent(DeviceEventExt { device_id, event }) = ev {
                     if device_id.xinput_id() == Some(self.data.barrier_kb.dev.id as u32) {
                         if let DeviceEvent::Key(RawKeyEvent {
@@ -1240,6 +2832,7 @@ struct XWindow {
     destroyed: Cell<bool>,
     mapped: Cell<bool>,
     always_on_top: Cell<bool>,
+    always_on_bottom: Cell<bool>,
     maximized_vert: Cell<bool>,
     maximized_horz: Cell<bool>,
     fullscreen: Cell<bool>,
@@ -1248,6 +2841,11 @@ struct XWindow {
     pre_fs_width: Cell<u32>,
     pre_fs_height: Cell<u32>,
     pre_fs_border: Cell<u32>,
+    pre_max_x: Cell<i32>,
+    pre_max_y: Cell<i32>,
+    pre_max_width: Cell<u32>,
+    pre_max_height: Cell<u32>,
+    pre_max_border: Cell<u32>,
     decorations: Cell<bool>,
     border: Cell<u32>,
     x: Cell<i32>,
@@ -1264,14 +2862,21 @@ struct XWindow {
     wm_name: RefCell<String>,
     utf8_title: RefCell<String>,
     urgency: Cell<bool>,
+    /// Whether `_NET_WM_STATE_DEMANDS_ATTENTION` is set.
+    demands_attention: Cell<bool>,
     class: RefCell<Option<String>>,
     instance: RefCell<Option<String>>,
     protocols: Cell<Protocols>,
     desired_state: Cell<WindowState>,
+    map_requests: Cell<u32>,
     current_state: Cell<WindowState>,
     maximizable: Cell<bool>,
-    icon: RefCell<Option<BackendIcon>>,
+    /// The frames of `_NET_WM_ICON` in the order in which they appear in the property.
+    icon: RefCell<Vec<BackendIcon>>,
     dragging: Cell<bool>,
+    resizing: Cell<bool>,
+    blur: RefCell<Option<BlurRegion>>,
+    stacking_position: Cell<Option<usize>>,
 }
 
 impl XWindow {
@@ -1279,6 +2884,17 @@ impl XWindow {
         self.property_generation
             .set(self.property_generation.get() + 1);
     }
+
+    /// Returns the root coordinates of the center of the title bar button at `x` relative
+    /// to the right edge of the frame.
+    fn title_button_position(&self, x: i32) -> (i32, i32) {
+        let border = self.border.get() as i32;
+        let half = TITLE_HEIGHT as i32 / 2;
+        (
+            self.x.get() + border + self.width.get() as i32 + x + half,
+            self.y.get() + border + half,
+        )
+    }
 }
 
 impl Window for Arc<XWindow> {
@@ -1329,7 +2945,7 @@ impl Window for Arc<XWindow> {
                 (ffi::XCB_CONFIG_WINDOW_WIDTH | ffi::XCB_CONFIG_WINDOW_HEIGHT) as _,
                 [width, height].as_ptr() as _,
             );
-            if let Err(e) = instance.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = instance.c.check_cookie(cookie) {
                 log::warn!("Could not resize window: {}", e);
             }
         }
@@ -1346,18 +2962,105 @@ impl Window for Arc<XWindow> {
                 ffi::XCB_CW_BACK_PIXEL,
                 &color as *const u32 as *const _,
             );
-            if let Err(e) = instance.c.errors.check_cookie(&backend.xcb, cookie) {
+            if let Err(e) = instance.c.check_cookie(cookie) {
                 panic!("Could not change back pixel: {}", e);
             }
             let cookie = backend
                 .xcb
                 .xcb_clear_area(instance.c.c, 0, self.id, 0, 0, 0, 0);
-            if let Err(e) = instance.c.errors.check_cookie(&backend.xcb, cookie) {
+            if let Err(e) = instance.c.check_cookie(cookie) {
                 panic!("Could not clear window: {}", e);
             }
         }
     }
 
+    fn present_test_pattern(&self, seed: u32) -> Result<(), TestPatternError> {
+        log::info!("Presenting test pattern {} in window {}", seed, self.id);
+        let instance = &self.el.data.instance;
+        let backend = &instance.data.backend;
+        let xcb = &backend.xcb;
+        let width = self.width.get();
+        let height = self.height.get();
+        if width == 0 || height == 0 {
+            return Ok(());
+        }
+        // PutImage describes the image with 16-bit dimensions.
+        let (width16, height16) = match (u16::try_from(width), u16::try_from(height)) {
+            (Ok(w), Ok(h)) => (w, h),
+            _ => return Err(TestPatternError::TooLarge { width, height }),
+        };
+        let f = &self.format;
+        let channel = |v: u8, shift: u16, mask: u16| (v as u32 * mask as u32 / 255) << shift;
+        let depth = if f.alpha_mask != 0 { 32 } else { 24 };
+        let size = width as usize * height as usize * 4;
+        unsafe {
+            let shmid = libc::shmget(libc::IPC_PRIVATE, size, libc::IPC_CREAT | 0o600);
+            if shmid == -1 {
+                panic!(
+                    "Could not create shared memory segment: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            let addr = libc::shmat(shmid, ptr::null(), 0);
+            libc::shmctl(shmid, libc::IPC_RMID, ptr::null_mut());
+            if addr as isize == -1 {
+                panic!(
+                    "Could not map shared memory segment: {}",
+                    std::io::Error::last_os_error()
+                );
+            }
+            let pixels = std::slice::from_raw_parts_mut(addr as *mut u32, size / 4);
+            for y in 0..height {
+                for x in 0..width {
+                    let [r, g, b, a] = crate::backend::test_pattern(seed, x, y);
+                    pixels[(y * width + x) as usize] = channel(r, f.red_shift, f.red_mask)
+                        | channel(g, f.green_shift, f.green_mask)
+                        | channel(b, f.blue_shift, f.blue_mask)
+                        | channel(a, f.alpha_shift, f.alpha_mask);
+                }
+            }
+            let seg = xcb.xcb_generate_id(instance.c.c);
+            let cookie = backend
+                .shm
+                .xcb_shm_attach_checked(instance.c.c, seg, shmid as u32, 1);
+            if let Err(e) = instance.c.check_cookie(cookie) {
+                panic!("Could not attach shared memory segment: {}", e);
+            }
+            let gc = xcb.xcb_generate_id(instance.c.c);
+            let cookie = xcb.xcb_create_gc_checked(instance.c.c, gc, self.id, 0, ptr::null());
+            if let Err(e) = instance.c.check_cookie(cookie) {
+                panic!("Could not create graphics context: {}", e);
+            }
+            let cookie = backend.shm.xcb_shm_put_image_checked(
+                instance.c.c,
+                self.id,
+                gc,
+                width16,
+                height16,
+                0,
+                0,
+                width16,
+                height16,
+                0,
+                0,
+                depth,
+                ffi::XCB_IMAGE_FORMAT_Z_PIXMAP as u8,
+                0,
+                seg,
+                0,
+            );
+            let res = instance.c.check_cookie(cookie);
+            xcb.xcb_free_gc(instance.c.c, gc);
+            backend.shm.xcb_shm_detach(instance.c.c, seg);
+            xcb.xcb_flush(instance.c.c);
+            libc::shmdt(addr);
+            if let Err(e) = res {
+                panic!("Could not put image: {}", e);
+            }
+            Ok(())
+        }
+    }
+
     fn any(&self) -> &dyn Any {
         self
     }
@@ -1383,7 +3086,7 @@ impl Window for Arc<XWindow> {
             } else {
                 xcb.xcb_destroy_window_checked(instance.c.c, self.id)
             };
-            if let Err(e) = instance.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = instance.c.check_cookie(cookie) {
                 log::warn!("Could not destroy window: {}", e);
             }
         }
@@ -1398,6 +3101,81 @@ impl Window for Arc<XWindow> {
         )
     }
 
+    fn close_button_position(&self) -> (i32, i32) {
+        self.title_button_position(CLOSE_BUTTON_X)
+    }
+
+    fn maximize_button_position(&self) -> (i32, i32) {
+        self.title_button_position(MAXIMIZE_BUTTON_X)
+    }
+
+    fn minimize_button_position(&self) -> (i32, i32) {
+        self.title_button_position(MINIMIZE_BUTTON_X)
+    }
+
+    fn drag_resize_edge<'a>(
+        &'a self,
+        edge: ResizeEdge,
+        dx: i32,
+        dy: i32,
+    ) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            let border = self.border.get() as i32;
+            assert!(border > 0, "The frame of window {} has no border", self.id);
+            // The center of the border segment at the edge.
+            let center = |pos: i32, size: i32, dir: i32| match dir {
+                -1 => pos + border / 2,
+                1 => pos + border + size + border / 2,
+                _ => pos + border + size / 2,
+            };
+            let x = center(self.x.get(), self.width.get() as i32, edge.horizontal());
+            let y = center(
+                self.y.get(),
+                self.height.get() as i32 + TITLE_HEIGHT as i32,
+                edge.vertical(),
+            );
+            log::info!(
+                "Dragging the {:?} edge of {} by {}x{}",
+                edge,
+                self.id,
+                dx,
+                dy
+            );
+            let seat = self.el.data.instance.default_seat();
+            seat.set_cursor_position(x, y);
+            seat.cursor_at(x, y).await;
+            let mouse = seat.add_mouse();
+            let button = mouse.press(Button::Left);
+            let window: &dyn Window = self;
+            window.resizing(true).await;
+            mouse.move_(dx, dy);
+            seat.cursor_at(x + dx, y + dy).await;
+            drop(button);
+            window.resizing(false).await;
+        })
+    }
+
+    fn drag_move<'a>(&'a self, dx: i32, dy: i32) -> Pin<Box<dyn Future<Output = ()> + 'a>> {
+        Box::pin(async move {
+            // The center of the part of the title bar to the left of the buttons.
+            let border = self.border.get() as i32;
+            let x = self.x.get() + border + (self.width.get() as i32 + MINIMIZE_BUTTON_X) / 2;
+            let y = self.y.get() + border + TITLE_HEIGHT as i32 / 2;
+            log::info!("Dragging the title bar of {} by {}x{}", self.id, dx, dy);
+            let seat = self.el.data.instance.default_seat();
+            seat.set_cursor_position(x, y);
+            seat.cursor_at(x, y).await;
+            let mouse = seat.add_mouse();
+            let button = mouse.press(Button::Left);
+            let window: &dyn Window = self;
+            window.dragging(true).await;
+            mouse.move_(dx, dy);
+            seat.cursor_at(x + dx, y + dy).await;
+            drop(button);
+            window.dragging(false).await;
+        })
+    }
+
     fn set_outer_position(&self, x: i32, y: i32) {
         log::info!("Setting outer position of {} to {}x{}", self.id, x, y);
         unsafe {
@@ -1409,7 +3187,7 @@ impl Window for Arc<XWindow> {
                 (ffi::XCB_CONFIG_WINDOW_X | ffi::XCB_CONFIG_WINDOW_Y) as _,
                 [x, y].as_ptr() as _,
             );
-            if let Err(e) = instance.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = instance.c.check_cookie(cookie) {
                 log::warn!("Could not configure window: {}", e);
             }
         }
@@ -1454,7 +3232,61 @@ impl Window for Arc<XWindow> {
             xcb.xcb_send_event(instance.c.c, 0, self.id, 0, &msg as *const _ as _);
             xcb.xcb_flush(instance.c.c);
         }
-        Box::pin(Changed(&self))
+        Box::pin(Changed(&self))
+    }
+
+    fn wm_set_maximized(&self, maximized: bool) {
+        log::info!(
+            "Asking the WM to set maximized of window {} to {}",
+            self.id,
+            maximized
+        );
+        let data = &self.el.data.instance.data;
+        data.wm_data
+            .lock()
+            .actions
+            .push((self.id, WmAction::SetMaximized(maximized)));
+        data.wm_wakeup.notify_one();
+    }
+
+    fn pager_request(&self, request: PagerRequest) {
+        log::info!("Sending pager request {:?} for window {}", request, self.id);
+        let instance = &self.el.data.instance;
+        let atoms = &instance.data.atoms;
+        let (type_, data32) = match request {
+            PagerRequest::Iconify => (atoms.wm_change_state, [3, 0, 0, 0, 0]),
+            PagerRequest::SetHidden(hidden) => (
+                atoms.net_wm_state,
+                [hidden as u32, atoms.net_wm_state_hidden, 0, 2, 0],
+            ),
+            PagerRequest::Activate => (atoms.net_active_window, [2, 0, 0, 0, 0]),
+            PagerRequest::SetFullscreen(fullscreen) => (
+                atoms.net_wm_state,
+                [fullscreen as u32, atoms.net_wm_state_fullscreen, 0, 2, 0],
+            ),
+        };
+        let c = XConnection::new(&instance.data.backend, instance.data.display);
+        unsafe {
+            let xcb = &instance.data.backend.xcb;
+            let event = ffi::xcb_client_message_event_t {
+                response_type: ffi::XCB_CLIENT_MESSAGE,
+                format: 32,
+                window: self.id,
+                type_,
+                data: ffi::xcb_client_message_data_t { data32 },
+                ..Default::default()
+            };
+            let cookie = xcb.xcb_send_event_checked(
+                c.c,
+                0,
+                c.screen.root,
+                ffi::XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT | ffi::XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY,
+                &event as *const _ as _,
+            );
+            if let Err(e) = c.check_cookie(cookie) {
+                panic!("Could not send pager request: {}", e);
+            }
+        }
     }
 
     fn request_redraw(&self, _scenario: usize) {
@@ -1478,11 +3310,38 @@ impl Window for Arc<XWindow> {
                 ffi::XCB_EVENT_MASK_EXPOSURE,
                 &msg as *const _ as _,
             );
-            if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = c.check_cookie(cookie) {
                 panic!("Could not send exposure event: {}", e);
             }
         }
     }
+
+    fn expose(&self, x: i32, y: i32, width: u32, height: u32) {
+        log::info!(
+            "Exposing {}x{} + {}x{} of window {}",
+            width,
+            height,
+            x,
+            y,
+            self.id
+        );
+        unsafe {
+            let xcb = &self.el.data.instance.data.backend.xcb;
+            let c = &self.el.data.instance.c;
+            let cookie = xcb.xcb_clear_area_checked(
+                c.c,
+                1,
+                self.id,
+                x as i16,
+                y as i16,
+                width as u16,
+                height as u16,
+            );
+            if let Err(e) = c.check_cookie(cookie) {
+                panic!("Could not expose window: {}", e);
+            }
+        }
+    }
 }
 
 impl WindowProperties for Arc<XWindow> {
@@ -1494,6 +3353,10 @@ impl WindowProperties for Arc<XWindow> {
         self.always_on_top.get()
     }
 
+    fn always_on_bottom(&self) -> bool {
+        self.always_on_bottom.get()
+    }
+
     fn decorations(&self) -> bool {
         self.decorations.get()
     }
@@ -1551,7 +3414,7 @@ impl WindowProperties for Arc<XWindow> {
     }
 
     fn attention(&self) -> bool {
-        self.urgency.get()
+        self.urgency.get() || self.demands_attention.get()
     }
 
     fn class(&self) -> Option<String> {
@@ -1570,13 +3433,61 @@ impl WindowProperties for Arc<XWindow> {
         self.dragging.get()
     }
 
+    fn resizing(&self) -> bool {
+        self.resizing.get()
+    }
+
     fn icon(&self) -> Option<BackendIcon> {
+        self.icon.borrow().first().cloned()
+    }
+
+    fn icon_frames(&self) -> Vec<BackendIcon> {
         self.icon.borrow().clone()
     }
 
     fn fullscreen(&self) -> bool {
         self.fullscreen.get()
     }
+
+    fn map_requests(&self) -> u32 {
+        self.map_requests.get()
+    }
+
+    fn blur(&self) -> Option<BlurRegion> {
+        self.blur.borrow().clone()
+    }
+
+    fn stacking_position(&self) -> Option<usize> {
+        self.stacking_position.get()
+    }
+
+    fn cursor_grabbed(&self) -> bool {
+        self.mapped.get() && self.el.data.instance.pointer_grab_window() == Some(self.id)
+    }
+
+    fn motif_decorations(&self) -> Option<bool> {
+        const MWM_HINTS_DECORATIONS: u32 = 1 << 1;
+        let instance = &self.el.data.instance;
+        let hints = unsafe {
+            xcb_dl_util::property::get_property::<u32>(
+                &instance.data.backend.xcb,
+                &instance.c.errors,
+                self.id,
+                instance.data.atoms.motif_wm_hints,
+                instance.data.atoms.motif_wm_hints,
+                false,
+                5,
+            )
+        };
+        let hints = match hints {
+            Ok(h) => h,
+            Err(e) => panic!("Could not retrieve _MOTIF_WM_HINTS: {}", e),
+        };
+        if hints.len() < 3 {
+            return None;
+        }
+        Some(hints[0] & MWM_HINTS_DECORATIONS == 0 || hints[2] != 0)
+    }
 }
 
 impl Drop for XWindow {
@@ -1591,9 +3502,59 @@ struct XSeat {
     pointer: ffi::xcb_input_device_id_t,
     keyboard: ffi::xcb_input_device_id_t,
     layout: Arc<Cell<Layout>>,
+    /// Whether the master devices have been removed.
+    removed: Cell<bool>,
 }
 
 impl XSeat {
+    /// Removes the master devices of the seat and the mouse created by `move_pointer`.
+    /// Does nothing if they have already been removed.
+    fn remove_master(&self) -> Result<(), XRequestError> {
+        if self.removed.replace(true) {
+            return Ok(());
+        }
+        let mouse = self
+            .instance
+            .move_pointer_mice
+            .borrow_mut()
+            .remove(&self.pointer);
+        if let Some(id) = mouse {
+            let msg = Message {
+                remove_device: RemoveDevice {
+                    ty: MT_REMOVE_DEVICE as _,
+                    id: id as _,
+                },
+            };
+            uapi::write(self.instance.data.sock(), &msg).unwrap();
+        }
+        unsafe {
+            let instance = &self.instance;
+            let xinput = &instance.data.backend.xinput;
+            #[repr(C)]
+            struct Change {
+                hc: ffi::xcb_input_hierarchy_change_t,
+                data: ffi::xcb_input_hierarchy_change_data_t__remove_master,
+            }
+            let change = Change {
+                hc: ffi::xcb_input_hierarchy_change_t {
+                    type_: ffi::XCB_INPUT_HIERARCHY_CHANGE_TYPE_REMOVE_MASTER as _,
+                    len: (mem::size_of::<Change>() / 4) as _,
+                },
+                data: ffi::xcb_input_hierarchy_change_data_t__remove_master {
+                    deviceid: self.keyboard,
+                    return_mode: ffi::XCB_INPUT_CHANGE_MODE_FLOAT as _,
+                    ..Default::default()
+                },
+            };
+            let cookie = xinput.xcb_input_xi_change_hierarchy_checked(instance.c.c, 1, &change.hc);
+            instance.c.check_cookie(cookie)
+        }
+    }
+
+    fn fake_input(&self, ty: u32, detail: u8, x: i16, y: i16) {
+        self.instance.fake_input(self.pointer, ty, detail, x, y);
+    }
+
     fn focus2(&self, window: ffi::xcb_window_t) {
         unsafe {
             let cookie = self
@@ -1602,22 +3563,51 @@ impl XSeat {
                 .backend
                 .xinput
                 .xcb_input_xi_set_focus_checked(self.instance.c.c, window, 0, self.keyboard);
-            if let Err(e) = self
-                .instance
-                .c
-                .errors
-                .check_cookie(&self.instance.data.backend.xcb, cookie)
-            {
+            if let Err(e) = self.instance.c.check_cookie(cookie) {
                 panic!("Could not set focus: {}", e);
             }
+            if self.keyboard == self.instance.core_kb {
+                let xcb = &self.instance.data.backend.xcb;
+                let c = &self.instance.c;
+                let cookie = xcb.xcb_change_property_checked(
+                    c.c,
+                    ffi::XCB_PROP_MODE_REPLACE as _,
+                    c.screen.root,
+                    self.instance.data.atoms.net_active_window,
+                    ffi::XCB_ATOM_WINDOW,
+                    32,
+                    1,
+                    &window as *const ffi::xcb_window_t as _,
+                );
+                if let Err(e) = c.check_cookie(cookie) {
+                    panic!("Could not set _NET_ACTIVE_WINDOW property: {}", e);
+                }
+            }
         }
     }
 }
 
 fn add_keyboard(seat: &Arc<XSeat>) -> Arc<XKeyboard> {
+    // XTest cannot create devices. All keyboards of a seat share its XTEST keyboard.
+    if seat.instance.uses_xtest() {
+        let id = seat.instance.xtest_device(seat.keyboard);
+        log::info!("Using XTEST keyboard {} of seat {}", id, seat.keyboard);
+        // Keys only repeat if requested via press_with_repeat.
+        seat.instance.set_key_repeat(id, None);
+        seat.instance.set_layout(id, seat.layout.get(), None);
+        return Arc::new(XKeyboard {
+            pressed_keys: Default::default(),
+            dev: XDevice {
+                seat: seat.clone(),
+                id,
+            },
+        });
+    }
     let id = seat.instance.add_keyboard();
     log::info!("Created keyboard {} on seat {}", id, seat.keyboard);
     seat.instance.assign_slave(id, seat.keyboard);
+    // Keys only repeat if requested via press_with_repeat.
+    seat.instance.set_key_repeat(id, None);
     seat.instance.set_layout(id, seat.layout.get(), None);
     seat.instance
         .set_layout(seat.keyboard, seat.layout.get(), None);
@@ -1636,9 +3626,19 @@ impl Seat for Arc<XSeat> {
     }
 
     fn add_mouse(&self) -> Box<dyn Mouse> {
-        let id = self.instance.add_mouse();
-        log::info!("Created mouse {} on seat {}", id, self.keyboard);
-        self.instance.assign_slave(id, self.pointer);
+        let id = match self.instance.uses_xtest() {
+            true => {
+                let id = self.instance.xtest_device(self.pointer);
+                log::info!("Using XTEST pointer {} of seat {}", id, self.keyboard);
+                id
+            }
+            false => {
+                let id = self.instance.add_mouse();
+                log::info!("Created mouse {} on seat {}", id, self.keyboard);
+                self.instance.assign_slave(id, self.pointer);
+                id
+            }
+        };
         Box::new(Arc::new(XMouse {
             pressed_buttons: Default::default(),
             dev: XDevice {
@@ -1660,6 +3660,18 @@ impl Seat for Arc<XSeat> {
         }))
     }
 
+    fn add_absolute_pointer(&self) -> Box<dyn AbsolutePointer> {
+        let id = self.instance.add_absolute_pointer();
+        log::info!("Created absolute pointer {} on seat {}", id, self.keyboard);
+        self.instance.assign_slave(id, self.pointer);
+        Box::new(XAbsolutePointer {
+            dev: XDevice {
+                seat: self.clone(),
+                id,
+            },
+        })
+    }
+
     fn focus(&self, window: &dyn Window) {
         let window: &Arc<XWindow> = window.any().downcast_ref().unwrap();
         log::info!("Focusing seat {} on window {}", self.keyboard, window.id);
@@ -1671,6 +3683,100 @@ impl Seat for Arc<XSeat> {
         self.focus2(0);
     }
 
+    fn is_focused(&self, window: &dyn Window) -> bool {
+        let window: &Arc<XWindow> = window.any().downcast_ref().unwrap();
+        let xcb = &self.instance.data.backend.xcb;
+        let xinput = &self.instance.data.backend.xinput;
+        let c = &self.instance.c;
+        unsafe {
+            let mut err = ptr::null_mut();
+            let reply = xinput.xcb_input_xi_get_focus_reply(
+                c.c,
+                xinput.xcb_input_xi_get_focus(c.c, self.keyboard),
+                &mut err,
+            );
+            let focus = match c.errors.check(xcb, reply, err) {
+                Ok(r) => r.focus,
+                Err(e) => panic!("Could not query focus: {}", e),
+            };
+            if focus != window.id {
+                log::debug!("Seat {} is focused on window {}", self.keyboard, focus);
+                return false;
+            }
+            if self.keyboard == self.instance.core_kb {
+                let active = xcb_dl_util::property::get_property::<u32>(
+                    xcb,
+                    &c.errors,
+                    c.screen.root,
+                    self.instance.data.atoms.net_active_window,
+                    ffi::XCB_ATOM_WINDOW,
+                    false,
+                    1,
+                );
+                match active {
+                    Ok(active) if active.first() == Some(&window.id) => {}
+                    Ok(active) => {
+                        log::debug!("_NET_ACTIVE_WINDOW is {:?}", active);
+                        return false;
+                    }
+                    Err(e) => panic!("Could not retrieve _NET_ACTIVE_WINDOW: {}", e),
+                }
+            }
+        }
+        true
+    }
+
+    fn remove(&self) {
+        assert_ne!(
+            self.keyboard, self.instance.core_kb,
+            "The default seat cannot be removed"
+        );
+        log::info!("Removing seat {}", self.keyboard);
+        if let Err(e) = self.remove_master() {
+            panic!("Could not remove master: {}", e);
+        }
+    }
+
+    fn move_pointer(&self, dx: i32, dy: i32) {
+        log::info!(
+            "Moving the pointer of seat {} by {}x{}",
+            self.keyboard,
+            dx,
+            dy
+        );
+        if self.instance.uses_xtest() {
+            // A detail of 1 makes the motion relative.
+            self.fake_input(ffi::XCB_MOTION_NOTIFY, 1, dx as _, dy as _);
+            crate::clock::input_injected();
+            return;
+        }
+        let id = *self
+            .instance
+            .move_pointer_mice
+            .borrow_mut()
+            .entry(self.pointer)
+            .or_insert_with(|| {
+                let id = self.instance.add_mouse();
+                log::info!(
+                    "Created mouse {} on seat {} to move the pointer",
+                    id,
+                    self.keyboard
+                );
+                self.instance.assign_slave(id, self.pointer);
+                id
+            });
+        let msg = Message {
+            mouse_move: MouseMove {
+                ty: MT_MOUSE_MOVE as _,
+                id: id as _,
+                dx,
+                dy,
+            },
+        };
+        uapi::write(self.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
+    }
+
     fn set_layout(&self, layout: Layout) {
         log::info!("Setting layout of seat {} to {:?}", self.keyboard, layout);
         self.instance
@@ -1681,7 +3787,6 @@ impl Seat for Arc<XSeat> {
     fn set_cursor_position(&self, x: i32, y: i32) {
         log::info!("Moving cursor of seat {} to {}x{}", self.keyboard, x, y);
         let xinput = &self.instance.data.backend.xinput;
-        let xcb = &self.instance.data.backend.xcb;
         let c = &self.instance.c;
         unsafe {
             let cookie = xinput.xcb_input_xi_warp_pointer_checked(
@@ -1696,7 +3801,7 @@ impl Seat for Arc<XSeat> {
                 y << 16,
                 self.pointer,
             );
-            if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = c.check_cookie(cookie) {
                 panic!("Could not warp pointer: {}", e);
             }
         }
@@ -1735,30 +3840,8 @@ impl Drop for XSeat {
         if self.keyboard == self.instance.core_kb {
             return;
         }
-        unsafe {
-            let instance = &self.instance;
-            let xinput = &instance.data.backend.xinput;
-            let xcb = &instance.data.backend.xcb;
-            #[repr(C)]
-            struct Change {
-                hc: ffi::xcb_input_hierarchy_change_t,
-                data: ffi::xcb_input_hierarchy_change_data_t__remove_master,
-            }
-            let change = Change {
-                hc: ffi::xcb_input_hierarchy_change_t {
-                    type_: ffi::XCB_INPUT_HIERARCHY_CHANGE_TYPE_REMOVE_MASTER as _,
-                    len: (mem::size_of::<Change>() / 4) as _,
-                },
-                data: ffi::xcb_input_hierarchy_change_data_t__remove_master {
-                    deviceid: self.keyboard,
-                    return_mode: ffi::XCB_INPUT_CHANGE_MODE_FLOAT as _,
-                    ..Default::default()
-                },
-            };
-            let cookie = xinput.xcb_input_xi_change_hierarchy_checked(instance.c.c, 1, &change.hc);
-            if let Err(e) = instance.c.errors.check_cookie(xcb, cookie) {
-                log::warn!("Could not remove master: {}", e);
-            }
+        if let Err(e) = self.remove_master() {
+            log::warn!("Could not remove master: {}", e);
         }
     }
 }
@@ -1770,13 +3853,16 @@ struct XDevice {
 
 impl Drop for XDevice {
     fn drop(&mut self) {
+        if self.seat.instance.uses_xtest() {
+            return;
+        }
         let msg = Message {
             remove_device: RemoveDevice {
                 ty: MT_REMOVE_DEVICE as _,
                 id: self.id as _,
             },
         };
-        uapi::write(self.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.seat.instance.data.sock(), &msg).unwrap();
     }
 }
 
@@ -1815,14 +3901,22 @@ impl Mouse for Arc<XMouse> {
                 return Box::new(p);
             }
         }
-        let msg = Message {
-            key_press: KeyPress {
-                ty: MT_BUTTON_PRESS as _,
-                id: self.dev.id as _,
-                key: map_button(button),
-            },
-        };
-        uapi::write(self.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        if self.dev.seat.instance.uses_xtest() {
+            self.dev
+                .seat
+                .fake_input(ffi::XCB_BUTTON_PRESS, map_core_button(button), 0, 0);
+            crate::clock::input_injected();
+        } else {
+            let msg = Message {
+                key_press: KeyPress {
+                    ty: MT_BUTTON_PRESS as _,
+                    id: self.dev.id as _,
+                    key: map_button(button),
+                },
+            };
+            uapi::write(self.dev.seat.instance.data.sock(), &msg).unwrap();
+            crate::clock::input_injected();
+        }
         let p = Arc::new(XPressedButton {
             mouse: self.clone(),
             button,
@@ -1839,6 +3933,14 @@ impl Mouse for Arc<XMouse> {
             dx,
             dy
         );
+        if self.dev.seat.instance.uses_xtest() {
+            // A detail of 1 makes the motion relative.
+            self.dev
+                .seat
+                .fake_input(ffi::XCB_MOTION_NOTIFY, 1, dx as _, dy as _);
+            crate::clock::input_injected();
+            return;
+        }
         let msg = Message {
             mouse_move: MouseMove {
                 ty: MT_MOUSE_MOVE as _,
@@ -1847,7 +3949,13 @@ impl Mouse for Arc<XMouse> {
                 dy,
             },
         };
-        uapi::write(self.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
+    }
+
+    fn move_to(&self, x: i32, y: i32) {
+        let (cx, cy) = self.dev.seat.cursor_position();
+        self.move_(x - cx, y - cy);
     }
 
     fn scroll(&self, dx: i32, dy: i32) {
@@ -1858,6 +3966,20 @@ impl Mouse for Arc<XMouse> {
             dx,
             dy
         );
+        if self.dev.seat.instance.uses_xtest() {
+            // Each click of buttons 4 to 7 scrolls by one line.
+            let clicks = [(dy, 4, 5), (dx, 6, 7)];
+            for (delta, positive, negative) in clicks {
+                let button = if delta > 0 { positive } else { negative };
+                for _ in 0..delta.abs() {
+                    let seat = &self.dev.seat;
+                    seat.fake_input(ffi::XCB_BUTTON_PRESS, button, 0, 0);
+                    seat.fake_input(ffi::XCB_BUTTON_RELEASE, button, 0, 0);
+                }
+            }
+            crate::clock::input_injected();
+            return;
+        }
         let msg = Message {
             mouse_move: MouseMove {
                 ty: MT_MOUSE_SCROLL as _,
@@ -1866,7 +3988,8 @@ impl Mouse for Arc<XMouse> {
                 dy: -dy,
             },
         };
-        uapi::write(self.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
     }
 }
 
@@ -1896,14 +4019,23 @@ impl Keyboard for Arc<XKeyboard> {
                 return Box::new(p);
             }
         }
-        let msg = Message {
-            key_press: KeyPress {
-                ty: MT_KEY_PRESS as _,
-                id: self.dev.id as _,
-                key: evdev::map_key(key),
-            },
-        };
-        uapi::write(self.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        if self.dev.seat.instance.uses_xtest() {
+            let keycode = evdev::map_key(key) + 8;
+            self.dev
+                .seat
+                .fake_input(ffi::XCB_KEY_PRESS, keycode as _, 0, 0);
+            crate::clock::input_injected();
+        } else {
+            let msg = Message {
+                key_press: KeyPress {
+                    ty: MT_KEY_PRESS as _,
+                    id: self.dev.id as _,
+                    key: evdev::map_key(key),
+                },
+            };
+            uapi::write(self.dev.seat.instance.data.sock(), &msg).unwrap();
+            crate::clock::input_injected();
+        }
         let p = Arc::new(XPressedKey {
             kb: self.clone(),
             key,
@@ -1911,6 +4043,62 @@ impl Keyboard for Arc<XKeyboard> {
         keys.insert(key, Arc::downgrade(&p));
         Box::new(p)
     }
+
+    fn layout(&self) -> Layout {
+        self.dev.seat.layout.get()
+    }
+
+    fn press_with_repeat(&self, key: Key, rate: u32, delay: Duration) -> Box<dyn PressedKey> {
+        log::info!(
+            "Enabling key repeat of keyboard {} with rate {} Hz and delay {:?}",
+            self.dev.id,
+            rate,
+            delay
+        );
+        let interval = (1000 / rate.max(1)) as u16;
+        let delay = delay.as_millis().min(u16::MAX as u128) as u16;
+        self.dev
+            .seat
+            .instance
+            .set_key_repeat(self.dev.id, Some((delay, interval)));
+        Box::new(XRepeatingKey {
+            kb: self.clone(),
+            key: Some(self.press(key)),
+        })
+    }
+
+    fn set_lock_state(&self, numlock: bool, capslock: bool) {
+        log::info!(
+            "Setting numlock of seat {} to {} and capslock to {}",
+            self.dev.seat.keyboard,
+            numlock,
+            capslock
+        );
+        self.dev
+            .seat
+            .instance
+            .set_lock_state(self.dev.seat.keyboard, numlock, capslock);
+    }
+}
+
+/// A pressed key whose keyboard has key repeat enabled until the key is released.
+struct XRepeatingKey {
+    kb: Arc<XKeyboard>,
+    key: Option<Box<dyn PressedKey>>,
+}
+
+impl PressedKey for XRepeatingKey {}
+
+impl Drop for XRepeatingKey {
+    fn drop(&mut self) {
+        self.key.take();
+        log::info!("Disabling key repeat of keyboard {}", self.kb.dev.id);
+        self.kb
+            .dev
+            .seat
+            .instance
+            .set_key_repeat(self.kb.dev.id, None);
+    }
 }
 
 struct XPressedButton {
@@ -1922,6 +4110,13 @@ impl PressedButton for Arc<XPressedButton> {}
 
 impl Drop for XPressedButton {
     fn drop(&mut self) {
+        let seat = &self.mouse.dev.seat;
+        if seat.instance.uses_xtest() {
+            let button = map_core_button(self.button);
+            seat.fake_input(ffi::XCB_BUTTON_RELEASE, button, 0, 0);
+            crate::clock::input_injected();
+            return;
+        }
         let msg = Message {
             key_press: KeyPress {
                 ty: MT_BUTTON_RELEASE as _,
@@ -1929,7 +4124,8 @@ impl Drop for XPressedButton {
                 key: map_button(self.button),
             },
         };
-        uapi::write(self.mouse.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.mouse.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
     }
 }
 
@@ -1943,6 +4139,13 @@ impl PressedKey for Arc<XPressedKey> {}
 impl Drop for XPressedKey {
     fn drop(&mut self) {
         log::info!("Releasing key {:?}", self.key);
+        let seat = &self.kb.dev.seat;
+        if seat.instance.uses_xtest() {
+            let keycode = evdev::map_key(self.key) + 8;
+            seat.fake_input(ffi::XCB_KEY_RELEASE, keycode as _, 0, 0);
+            crate::clock::input_injected();
+            return;
+        }
         let msg = Message {
             key_press: KeyPress {
                 ty: MT_KEY_RELEASE as _,
@@ -1950,7 +4153,8 @@ impl Drop for XPressedKey {
                 key: evdev::map_key(self.key),
             },
         };
-        uapi::write(self.kb.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.kb.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
     }
 }
 
@@ -1974,8 +4178,9 @@ impl Touchscreen for Arc<XTouch> {
                 y,
             },
         };
-        uapi::write(self.dev.seat.instance.data.sock.raw(), &msg).unwrap();
-        uapi::read(self.dev.seat.instance.data.sock.raw(), &mut msg).unwrap();
+        uapi::write(self.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
+        uapi::read(self.dev.seat.instance.data.sock(), &mut msg).unwrap();
         unsafe {
             assert_eq!(msg.ty, MT_TOUCH_DOWN_REPLY as _);
             Box::new(XFinger {
@@ -1986,6 +4191,38 @@ impl Touchscreen for Arc<XTouch> {
     }
 }
 
+struct XAbsolutePointer {
+    dev: XDevice,
+}
+
+impl Device for XAbsolutePointer {
+    fn id(&self) -> Box<dyn BackendDeviceId> {
+        Box::new(XDeviceId { id: self.dev.id })
+    }
+}
+
+impl AbsolutePointer for XAbsolutePointer {
+    fn move_to(&self, x: i32, y: i32) {
+        log::info!(
+            "Moving absolute pointer {} of seat {} to {}x{}",
+            self.dev.id,
+            self.dev.seat.keyboard,
+            x,
+            y
+        );
+        let msg = Message {
+            mouse_move: MouseMove {
+                ty: MT_TABLET_MOVE as _,
+                id: self.dev.id as _,
+                dx: x,
+                dy: y,
+            },
+        };
+        uapi::write(self.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
+    }
+}
+
 struct XFinger {
     touch: Arc<XTouch>,
     touch_id: u32,
@@ -2002,7 +4239,8 @@ impl Finger for XFinger {
                 y,
             },
         };
-        uapi::write(self.touch.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.touch.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
     }
 }
 
@@ -2015,7 +4253,19 @@ impl Drop for XFinger {
                 touch_id: self.touch_id,
             },
         };
-        uapi::write(self.touch.dev.seat.instance.data.sock.raw(), &msg).unwrap();
+        uapi::write(self.touch.dev.seat.instance.data.sock(), &msg).unwrap();
+        crate::clock::input_injected();
+    }
+}
+
+/// Maps a button to the core button number used by XTest.
+fn map_core_button(button: Button) -> u8 {
+    match button {
+        Button::Left => 1,
+        Button::Middle => 2,
+        Button::Right => 3,
+        Button::Back => 8,
+        Button::Forward => 9,
     }
 }
 
@@ -2071,6 +4321,13 @@ enum MessageType {
     MT_TOUCH_DOWN_REPLY,
     MT_TOUCH_UP,
     MT_TOUCH_MOVE,
+    MT_SET_PHYSICAL_SIZE,
+    MT_SET_PHYSICAL_SIZE_REPLY,
+    MT_CREATE_TABLET,
+    MT_CREATE_TABLET_REPLY,
+    MT_TABLET_MOVE,
+    MT_GET_POINTER_GRAB,
+    MT_GET_POINTER_GRAB_REPLY,
 }
 
 #[repr(C)]
@@ -2087,6 +4344,9 @@ union Message {
     touch_down: TouchDown,
     touch_down_reply: TouchDownReply,
     touch_up: TouchUp,
+    set_physical_size: SetPhysicalSize,
+    get_pointer_grab: GetPointerGrab,
+    get_pointer_grab_reply: GetPointerGrabReply,
 }
 
 unsafe impl Pod for Message {}
@@ -2098,6 +4358,20 @@ struct CreateKeyboardReply {
     id: u32,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct GetPointerGrab {
+    ty: u32,
+    pointer: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct GetPointerGrabReply {
+    ty: u32,
+    window: u32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 struct TouchDownReply {
@@ -2139,6 +4413,15 @@ struct EnableSecondMonitor {
     enable: u32,
 }
 
+#[repr(C)]
+#[derive(Copy, Clone)]
+struct SetPhysicalSize {
+    ty: u32,
+    output: u32,
+    mm_width: u32,
+    mm_height: u32,
+}
+
 #[repr(C)]
 #[derive(Copy, Clone)]
 struct GetVideoInfoReply {
@@ -2185,7 +4468,10 @@ struct Atoms {
     net_wm_ping: ffi::xcb_atom_t,
     utf8_string: ffi::xcb_atom_t,
     net_wm_state_above: ffi::xcb_atom_t,
+    net_wm_state_below: ffi::xcb_atom_t,
     net_wm_state_fullscreen: ffi::xcb_atom_t,
+    net_wm_state_demands_attention: ffi::xcb_atom_t,
+    net_wm_state_hidden: ffi::xcb_atom_t,
     net_frame_extents: ffi::xcb_atom_t,
     net_wm_state_maximized_horz: ffi::xcb_atom_t,
     net_wm_state_maximized_vert: ffi::xcb_atom_t,
@@ -2212,4 +4498,5 @@ struct Atoms {
     x_dnd_leave: ffi::xcb_atom_t,
     x_dnd_drop: ffi::xcb_atom_t,
     uri_list: ffi::xcb_atom_t,
+    kde_net_wm_blur_behind_region: ffi::xcb_atom_t,
 }
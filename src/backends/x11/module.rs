@@ -0,0 +1,173 @@
+//! Building the x11 module.
+//!
+//! The module is compiled with meson against the SDK of the installed Xorg server. The
+//! result is cached in `x11-module/install/<VERSION>-<HASH>` where VERSION is the version
+//! of the server and HASH is a hash of the sources of the module so that the module is
+//! only rebuilt when the server or the module is updated.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const MODULE_FILE: &str = "libwinit.so";
+
+fn module_root() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("x11-module")
+}
+
+/// Returns whether the module for the server at `x_path` is cached or can be built.
+pub fn available(x_path: &str) -> bool {
+    let version = match server_version(x_path) {
+        Ok(v) => v,
+        Err(_) => return false,
+    };
+    if cache_dir(&version).join(MODULE_FILE).exists() {
+        return true;
+    }
+    match can_build(&version) {
+        Ok(()) => true,
+        Err(e) => {
+            log::info!("Cannot build the x11 module: {}", e);
+            false
+        }
+    }
+}
+
+/// Checks that the module can be built for Xorg `version`.
+fn can_build(version: &str) -> Result<(), String> {
+    let sdk = match sdk_version() {
+        Ok(v) => v,
+        Err(e) => {
+            return Err(format!(
+                "Could not find the Xorg server SDK: {}\n\
+                 Install the development files of the Xorg server, e.g. xorg-server-devel or \
+                 xserver-xorg-dev, or set WINIT_IT_X_SERVER=xvfb to run without the module.",
+                e
+            ))
+        }
+    };
+    if sdk != version {
+        return Err(format!(
+            "The version of the Xorg server SDK ({}) differs from the version of the server ({})",
+            sdk, version
+        ));
+    }
+    match Command::new("meson").arg("--version").output() {
+        Ok(o) if o.status.success() => Ok(()),
+        Ok(o) => Err(format!("meson --version failed: {}", o.status)),
+        Err(e) => Err(format!("Could not run meson: {}", e)),
+    }
+}
+
+/// Returns the directory containing the module for the server at `x_path`. The module
+/// is built first if it has not been cached.
+pub fn ensure_built(x_path: &str) -> PathBuf {
+    let version = match server_version(x_path) {
+        Ok(v) => v,
+        Err(e) => panic!("Could not determine the version of {}: {}", x_path, e),
+    };
+    let dir = cache_dir(&version);
+    if dir.join(MODULE_FILE).exists() {
+        return dir;
+    }
+    log::info!("Building the x11 module for Xorg {}", version);
+    if let Err(e) = can_build(&version) {
+        panic!("Could not build the x11 module: {}", e);
+    }
+    let build_dir = module_root().join("build").join(&version);
+    if !build_dir.join("build.ninja").exists() {
+        meson(&[
+            "setup".as_ref(),
+            build_dir.as_os_str(),
+            module_root().as_os_str(),
+        ]);
+    }
+    meson(&["compile".as_ref(), "-C".as_ref(), build_dir.as_os_str()]);
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        panic!("Could not create {}: {}", dir.display(), e);
+    }
+    if let Err(e) = std::fs::copy(build_dir.join(MODULE_FILE), dir.join(MODULE_FILE)) {
+        panic!("Could not copy the module to {}: {}", dir.display(), e);
+    }
+    log::info!("Installed the x11 module in {}", dir.display());
+    dir
+}
+
+fn cache_dir(version: &str) -> PathBuf {
+    let key = format!("{}-{:016x}", version, sources_hash());
+    module_root().join("install").join(key)
+}
+
+/// Returns a hash of `meson.build` and the files in `src`.
+///
+/// The hash is FNV-1a so that it does not depend on the version of the standard library.
+fn sources_hash() -> u64 {
+    let root = module_root();
+    let src = root.join("src");
+    let mut files = vec![root.join("meson.build")];
+    match std::fs::read_dir(&src) {
+        Ok(dir) => {
+            for entry in dir {
+                match entry {
+                    Ok(e) => files.push(e.path()),
+                    Err(e) => panic!("Could not read {}: {}", src.display(), e),
+                }
+            }
+        }
+        Err(e) => panic!("Could not read {}: {}", src.display(), e),
+    }
+    files.sort();
+    let mut hash = 0xcbf29ce484222325u64;
+    for file in files {
+        let name = file
+            .strip_prefix(&root)
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+        let contents = match std::fs::read(&file) {
+            Ok(c) => c,
+            Err(e) => panic!("Could not read {}: {}", file.display(), e),
+        };
+        for &b in name.as_bytes().iter().chain([0].iter()).chain(&contents) {
+            hash ^= b as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
+    }
+    hash
+}
+
+/// Returns the version of the server at `x_path`, e.g. `1.21.1.4`.
+fn server_version(x_path: &str) -> Result<String, String> {
+    let output = match Command::new(x_path).arg("-version").output() {
+        Ok(o) => o,
+        Err(e) => return Err(e.to_string()),
+    };
+    // The version is printed to stderr.
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .find_map(|l| l.strip_prefix("X.Org X Server "))
+        .map(|v| v.trim().to_string())
+        .ok_or_else(|| "The output of -version does not contain a version".to_string())
+}
+
+/// Returns the version of the Xorg server SDK.
+fn sdk_version() -> Result<String, String> {
+    let res = Command::new("pkg-config")
+        .arg("--modversion")
+        .arg("xorg-server")
+        .output();
+    match res {
+        Ok(o) if o.status.success() => Ok(String::from_utf8_lossy(&o.stdout).trim().to_string()),
+        Ok(o) => Err(String::from_utf8_lossy(&o.stderr).trim().to_string()),
+        Err(e) => Err(format!("Could not run pkg-config: {}", e)),
+    }
+}
+
+fn meson(args: &[&std::ffi::OsStr]) {
+    let res = Command::new("meson").args(args).status();
+    match res {
+        Ok(s) if s.success() => {}
+        Ok(s) => panic!("meson {:?} failed: {}", args, s),
+        Err(e) => panic!("Could not run meson: {}", e),
+    }
+}
@@ -0,0 +1,358 @@
+use crate::backend::{ConnectionProxy, ProtocolStats, ProxyFaults};
+use parking_lot::Mutex;
+use std::any::Any;
+use std::io::{Read, Write};
+use std::mem;
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::Ordering::Relaxed;
+use std::sync::atomic::{AtomicBool, AtomicU64};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Instant;
+
+const FIRST_PROXY_DISPLAY: u32 = 100;
+const LAST_PROXY_DISPLAY: u32 = 999;
+
+/// A proxy between a client and the X server that counts the messages flowing through it
+/// and optionally injects faults.
+///
+/// Clients connect via TCP on `127.0.0.1:display()`. The proxy forwards the byte stream to
+/// the unix socket of the real server.
+pub(super) struct XProxy {
+    display: u32,
+    shared: Arc<Shared>,
+    accept_thread: Option<JoinHandle<()>>,
+}
+
+#[derive(Default)]
+struct Shared {
+    stop: AtomicBool,
+    requests: AtomicU64,
+    replies: AtomicU64,
+    events: AtomicU64,
+    errors: AtomicU64,
+    faults: Mutex<ProxyFaults>,
+    connections: Mutex<Vec<TcpStream>>,
+    /// The threads forwarding the proxied connections.
+    threads: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl XProxy {
+    pub(super) fn new(server_display: u32) -> Self {
+        let (listener, display) = (FIRST_PROXY_DISPLAY..=LAST_PROXY_DISPLAY)
+            .find_map(|d| {
+                TcpListener::bind(("127.0.0.1", (6000 + d) as u16))
+                    .ok()
+                    .map(|l| (l, d))
+            })
+            .expect("Could not find a free display for the proxy");
+        log::info!(
+            "Proxying display :{} via 127.0.0.1:{}",
+            server_display,
+            display
+        );
+        let shared = Arc::new(Shared::default());
+        let shared2 = shared.clone();
+        let accept_thread = std::thread::Builder::new()
+            .name(format!("proxy-{}", display))
+            .spawn(move || accept(listener, server_display, shared2))
+            .unwrap();
+        Self {
+            display,
+            shared,
+            accept_thread: Some(accept_thread),
+        }
+    }
+
+    pub(super) fn display(&self) -> String {
+        format!("127.0.0.1:{}", self.display)
+    }
+}
+
+impl Drop for XProxy {
+    fn drop(&mut self) {
+        self.shared.stop.store(true, Relaxed);
+        // Wake up the accepting thread.
+        let _ = TcpStream::connect(("127.0.0.1", (6000 + self.display) as u16));
+        if let Some(jh) = self.accept_thread.take() {
+            let _ = jh.join();
+        }
+        // The forwarding threads stop once their connections have been shut down.
+        close_connections(&self.shared);
+        for jh in mem::take(&mut *self.shared.threads.lock()) {
+            let _ = jh.join();
+        }
+    }
+}
+
+impl ConnectionProxy for XProxy {
+    fn stats(&self) -> ProtocolStats {
+        ProtocolStats {
+            requests: self.shared.requests.load(Relaxed),
+            replies: self.shared.replies.load(Relaxed),
+            events: self.shared.events.load(Relaxed),
+            errors: self.shared.errors.load(Relaxed),
+        }
+    }
+
+    fn reset_stats(&self) {
+        self.shared.requests.store(0, Relaxed);
+        self.shared.replies.store(0, Relaxed);
+        self.shared.events.store(0, Relaxed);
+        self.shared.errors.store(0, Relaxed);
+    }
+
+    fn set_faults(&self, faults: ProxyFaults) {
+        log::info!("Setting proxy faults to {:?}", faults);
+        *self.shared.faults.lock() = faults;
+    }
+
+    fn sever(&self) {
+        let n = close_connections(&self.shared);
+        log::info!("Severed {} proxied connections", n);
+    }
+
+    fn any(&self) -> &dyn Any {
+        self
+    }
+}
+
+/// Shuts down all proxied connections and returns their number.
+fn close_connections(shared: &Shared) -> usize {
+    let connections = mem::take(&mut *shared.connections.lock());
+    for connection in &connections {
+        Stream::shutdown(connection);
+    }
+    connections.len()
+}
+
+fn accept(listener: TcpListener, server_display: u32, shared: Arc<Shared>) {
+    for client in listener.incoming() {
+        if shared.stop.load(Relaxed) {
+            return;
+        }
+        let client = match client {
+            Ok(c) => c,
+            Err(e) => {
+                log::warn!("Could not accept proxy connection: {}", e);
+                continue;
+            }
+        };
+        let _ = client.set_nodelay(true);
+        if let Ok(c) = client.try_clone() {
+            shared.connections.lock().push(c);
+        }
+        let server = match UnixStream::connect(format!("/tmp/.X11-unix/X{}", server_display)) {
+            Ok(s) => s,
+            Err(e) => {
+                log::warn!("Could not connect to the X server: {}", e);
+                continue;
+            }
+        };
+        // The byte order of the connection is determined by the setup request of the
+        // client. The server does not send anything before it has received the request.
+        let (byte_order_tx, byte_order_rx) = std::sync::mpsc::channel();
+        let (client2, server2, shared2) = (
+            client.try_clone().unwrap(),
+            server.try_clone().unwrap(),
+            shared.clone(),
+        );
+        let c2s = std::thread::spawn(move || {
+            let counter = Counter::new(Direction::ClientToServer, ByteOrder::Send(byte_order_tx));
+            forward(client2, server2, &shared2, counter)
+        });
+        let shared2 = shared.clone();
+        let s2c = std::thread::spawn(move || {
+            let counter =
+                Counter::new(Direction::ServerToClient, ByteOrder::Receive(byte_order_rx));
+            forward(server, client, &shared2, counter)
+        });
+        let mut threads = shared.threads.lock();
+        threads.retain(|jh| !jh.is_finished());
+        threads.extend([c2s, s2c]);
+    }
+}
+
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Direction {
+    ClientToServer,
+    ServerToClient,
+}
+
+trait Stream: Read + Write {
+    fn shutdown(&self);
+}
+
+impl Stream for TcpStream {
+    fn shutdown(&self) {
+        let _ = TcpStream::shutdown(self, Shutdown::Both);
+    }
+}
+
+impl Stream for UnixStream {
+    fn shutdown(&self) {
+        let _ = UnixStream::shutdown(self, Shutdown::Both);
+    }
+}
+
+fn forward<R: Stream, W: Stream + Send + 'static>(
+    mut from: R,
+    to: W,
+    shared: &Arc<Shared>,
+    mut counter: Counter,
+) {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let shared2 = shared.clone();
+    let writer = std::thread::spawn(move || write_delayed(rx, to, &shared2));
+    let mut buf = [0; 4096];
+    loop {
+        let n = match from.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => n,
+        };
+        counter.feed(&buf[..n], shared);
+        if tx.send((Instant::now(), buf[..n].to_vec())).is_err() {
+            break;
+        }
+    }
+    drop(tx);
+    let _ = writer.join();
+    from.shutdown();
+}
+
+fn write_delayed<W: Stream>(rx: Receiver<(Instant, Vec<u8>)>, mut to: W, shared: &Shared) {
+    while let Ok((received, mut data)) = rx.recv() {
+        let faults = *shared.faults.lock();
+        if let Some(interval) = faults.batch_interval {
+            let deadline = received + interval;
+            loop {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                match rx.recv_timeout(timeout) {
+                    Ok((_, more)) => data.extend_from_slice(&more),
+                    Err(RecvTimeoutError::Timeout) => break,
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        }
+        let due = received + faults.latency;
+        let now = Instant::now();
+        if due > now {
+            std::thread::sleep(due - now);
+        }
+        if to.write_all(&data).is_err() {
+            break;
+        }
+    }
+    to.shutdown();
+}
+
+/// How the counter of one direction of a connection learns the byte order of the
+/// connection.
+enum ByteOrder {
+    /// Determine it from the setup request and send it to the other direction.
+    Send(Sender<bool>),
+    /// Receive it from the other direction.
+    Receive(Receiver<bool>),
+    /// The byte order is known. `true` if big endian.
+    Known(bool),
+}
+
+struct Counter {
+    dir: Direction,
+    setup_done: bool,
+    buf: Vec<u8>,
+    byte_order: ByteOrder,
+}
+
+impl Counter {
+    fn new(dir: Direction, byte_order: ByteOrder) -> Self {
+        Self {
+            dir,
+            setup_done: false,
+            buf: vec![],
+            byte_order,
+        }
+    }
+
+    /// Returns `true` if the connection is big endian or `None` if this is not yet known.
+    fn big_endian(&mut self) -> Option<bool> {
+        let big_endian = match &self.byte_order {
+            ByteOrder::Known(big_endian) => return Some(*big_endian),
+            ByteOrder::Send(tx) => {
+                let big_endian = *self.buf.first()? == b'B';
+                let _ = tx.send(big_endian);
+                big_endian
+            }
+            // If the other direction has stopped without sending the byte order, the
+            // server has never received a setup request and does not send anything that
+            // needs to be parsed.
+            ByteOrder::Receive(rx) => rx.recv().unwrap_or(false),
+        };
+        self.byte_order = ByteOrder::Known(big_endian);
+        Some(big_endian)
+    }
+
+    fn feed(&mut self, data: &[u8], shared: &Shared) {
+        self.buf.extend_from_slice(data);
+        let big_endian = match self.big_endian() {
+            Some(big_endian) => big_endian,
+            _ => return,
+        };
+        loop {
+            let len = match self.message_len(big_endian) {
+                Some(len) if len <= self.buf.len() => len,
+                _ => return,
+            };
+            if !self.setup_done {
+                self.setup_done = true;
+            } else if self.dir == Direction::ClientToServer {
+                shared.requests.fetch_add(1, Relaxed);
+            } else {
+                match self.buf[0] & 0x7f {
+                    0 => shared.errors.fetch_add(1, Relaxed),
+                    1 => shared.replies.fetch_add(1, Relaxed),
+                    _ => shared.events.fetch_add(1, Relaxed),
+                };
+            }
+            self.buf.drain(..len);
+        }
+    }
+
+    fn message_len(&self, big_endian: bool) -> Option<usize> {
+        let u16_at = |pos: usize| {
+            let b = self.buf.get(pos..pos + 2)?;
+            let b = [b[0], b[1]];
+            Some(if big_endian {
+                u16::from_be_bytes(b)
+            } else {
+                u16::from_le_bytes(b)
+            } as usize)
+        };
+        let u32_at = |pos: usize| {
+            let b = self.buf.get(pos..pos + 4)?;
+            let b = [b[0], b[1], b[2], b[3]];
+            Some(if big_endian {
+                u32::from_be_bytes(b)
+            } else {
+                u32::from_le_bytes(b)
+            } as usize)
+        };
+        let pad = |n: usize| (n + 3) & !3;
+        match (self.dir, self.setup_done) {
+            (Direction::ClientToServer, false) => Some(12 + pad(u16_at(6)?) + pad(u16_at(8)?)),
+            (Direction::ServerToClient, false) => Some(8 + 4 * u16_at(6)?),
+            (Direction::ClientToServer, true) => match u16_at(2)? {
+                // BIG-REQUESTS
+                0 => Some(4 * u32_at(4)?),
+                n => Some(4 * n),
+            },
+            (Direction::ServerToClient, true) => match self.buf.first()? & 0x7f {
+                // Replies and generic events
+                1 | 35 => Some(32 + 4 * u32_at(4)?),
+                _ => Some(32),
+            },
+        }
+    }
+}
@@ -1,6 +1,9 @@
 use super::XInstanceData;
-use crate::backend::BackendIcon;
-use crate::backends::x11::{Protocols, WindowState, XConnection, XWindow};
+use crate::backend::{BackendIcon, BlurRegion, FocusPolicy};
+use crate::backends::x11::{evdev, Protocols, WindowState, XConnection, XWindow};
+use crate::keyboard::Key;
+use crate::tlog;
+use crate::tlog::Sequence;
 use std::future::Future;
 use std::ptr;
 use std::sync::{Arc, Weak};
@@ -28,7 +31,7 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
         let first_randr_event =
             (*xcb.xcb_get_extension_data(c.c, xrandr.xcb_randr_id())).first_event;
         let cookie = xrandr.xcb_randr_select_input_checked(c.c, c.screen.root, 0xff);
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Can't listen for randr events: {}", e);
         }
         let events = ffi::XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT
@@ -42,12 +45,14 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             ffi::XCB_CW_EVENT_MASK,
             &events as *const ffi::xcb_event_mask_t as _,
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could not select wm events: {}", e);
         }
         let supported = [
             instance.atoms.net_client_list,
+            instance.atoms.net_client_list_stacking,
             instance.atoms.net_supporting_wm_check,
+            instance.atoms.net_active_window,
         ];
         let cookie = xcb.xcb_change_property_checked(
             c.c,
@@ -59,7 +64,7 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             supported.len() as _,
             supported.as_ptr() as _,
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could not set _NET_SUPPORTED property: {}", e);
         }
         let window_id = xcb.xcb_generate_id(c.c);
@@ -78,7 +83,7 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             0,
             ptr::null(),
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could not create child window: {}", e);
         }
         const WM_NAME: &str = "UNKNOWN WM";
@@ -92,7 +97,7 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             WM_NAME.len() as _,
             WM_NAME.as_ptr() as *const _,
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!("Could not set _NET_WM_NAME property on window: {}", e);
         }
         let cookie = xcb.xcb_change_property_checked(
@@ -105,7 +110,7 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             1,
             &window_id as *const _ as _,
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!(
                 "Could not set _NET_SUPPORTING_WM_CHECK property on root: {}",
                 e
@@ -121,7 +126,7 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             1,
             &window_id as *const _ as _,
         );
-        if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+        if let Err(e) = c.check_cookie(cookie) {
             panic!(
                 "Could not set _NET_SUPPORTING_WM_CHECK property on child: {}",
                 e
@@ -133,7 +138,11 @@ pub(super) fn run(instance: Arc<XInstanceData>) -> impl Future<Output = ()> {
             window_id,
             first_randr_event,
             moving: None,
+            resizing: None,
             crtcs: vec![],
+            last_title_click: None,
+            stacking: vec![],
+            shortcuts_grabbed: false,
         };
 
         wm.run()
@@ -146,7 +155,21 @@ struct Wm {
     window_id: ffi::xcb_window_t,
     first_randr_event: u8,
     moving: Option<Moving>,
+    resizing: Option<Resizing>,
     crtcs: Vec<Crtc>,
+    /// The frame and time of the last click on a title bar.
+    last_title_click: Option<(ffi::xcb_window_t, ffi::xcb_timestamp_t)>,
+    /// The managed windows from bottom to top in the order in which they were mapped.
+    stacking: Vec<ffi::xcb_window_t>,
+    /// Whether the `SHORTCUT_KEY` shortcut is grabbed.
+    shortcuts_grabbed: bool,
+}
+
+/// An action that the WM performs on its own initiative, i.e. not in response to a
+/// request of a client.
+#[derive(Copy, Clone, Debug)]
+pub(super) enum WmAction {
+    SetMaximized(bool),
 }
 
 struct Crtc {
@@ -164,6 +187,20 @@ struct Moving {
     start_window_y: i32,
 }
 
+struct Resizing {
+    win: Weak<XWindow>,
+    /// -1 if the left edge is being dragged, 1 if the right edge is being dragged.
+    horizontal: i32,
+    /// -1 if the top edge is being dragged, 1 if the bottom edge is being dragged.
+    vertical: i32,
+    start_pointer_x: i32,
+    start_pointer_y: i32,
+    start_window_x: i32,
+    start_window_y: i32,
+    start_width: i32,
+    start_height: i32,
+}
+
 impl Drop for Wm {
     fn drop(&mut self) {
         unsafe {
@@ -177,14 +214,35 @@ impl Drop for Wm {
 
 pub const TITLE_HEIGHT: u16 = 10;
 
+/// The maximum time between the clicks of a double click in milliseconds.
+const DOUBLE_CLICK_TIME: u32 = 500;
+
+/// The positions of the title bar buttons relative to the right edge of the frame. Each
+/// button is a `TITLE_HEIGHT` square.
+pub const CLOSE_BUTTON_X: i32 = -(TITLE_HEIGHT as i32);
+pub const MAXIMIZE_BUTTON_X: i32 = -2 * TITLE_HEIGHT as i32;
+pub const MINIMIZE_BUTTON_X: i32 = -3 * TITLE_HEIGHT as i32;
+
+/// The key that is grabbed together with Super if `WmConfig::super_shortcuts` is set.
+pub const SHORTCUT_KEY: Key = Key::KeyD;
+
+const TITLE_BAR_COLOR: u32 = 0x404040;
+const CLOSE_BUTTON_COLOR: u32 = 0xc03030;
+const MAXIMIZE_BUTTON_COLOR: u32 = 0x30c030;
+const MINIMIZE_BUTTON_COLOR: u32 = 0xc0c030;
+
 impl Wm {
     async fn run(mut self) {
         self.update_crtcs();
         self.update_client_list();
         let fd = AsyncFd::with_interest(self.c.fd, Interest::READABLE).unwrap();
+        let wakeup = self.instance.wm_wakeup.clone();
         loop {
             self.handle_events();
-            fd.readable().await.unwrap().clear_ready();
+            tokio::select! {
+                res = fd.readable() => res.unwrap().clear_ready(),
+                _ = wakeup.notified() => {}
+            }
         }
     }
 
@@ -228,6 +286,8 @@ impl Wm {
     }
 
     fn handle_events(&mut self) {
+        self.update_shortcut_grabs();
+        self.perform_actions();
         unsafe {
             loop {
                 let event = self.instance.backend.xcb.xcb_poll_for_event(self.c.c);
@@ -240,7 +300,8 @@ impl Wm {
                         panic!("The connection is in error: {}", e);
                     }
                 };
-                self.handle_event(&event);
+                let sequence = Sequence::Event(event.full_sequence);
+                tlog::with_sequence(sequence, || self.handle_event(&event));
             }
             self.instance.backend.xcb.xcb_flush(self.c.c);
         }
@@ -259,7 +320,11 @@ impl Wm {
             ffi::XCB_CLIENT_MESSAGE => self.handle_client_message(event),
             ffi::XCB_CONFIGURE_NOTIFY => self.handle_configure_notify(event),
             ffi::XCB_MOTION_NOTIFY => self.handle_motion_notify(event),
+            ffi::XCB_BUTTON_PRESS => self.handle_button_press(event),
             ffi::XCB_BUTTON_RELEASE => self.handle_button_release(event),
+            ffi::XCB_ENTER_NOTIFY => self.handle_enter_notify(event),
+            ffi::XCB_KEY_PRESS => self.handle_key_press(event),
+            ffi::XCB_KEY_RELEASE => {}
             ffi::XCB_MAPPING_NOTIFY => {}
             n if n == self.first_randr_event + ffi::XCB_RANDR_SCREEN_CHANGE_NOTIFY => {
                 self.handle_randr_screen_change_notify(event);
@@ -366,6 +431,9 @@ impl Wm {
         } else if event.atom == self.instance.atoms.wm_protocols {
             log::info!("WM_PROTOCOLS changed: {:?}", event.window);
             self.handle_wm_protocols(event.window);
+        } else if event.atom == self.instance.atoms.kde_net_wm_blur_behind_region {
+            log::info!("KDE_NET_WM_BLUR_BEHIND_REGION changed: {:?}", event.window);
+            self.handle_kde_net_wm_blur_behind_region(event.window);
         } else if event.atom == self.instance.atoms.net_supporting_wm_check {
             // ignored
         } else if event.atom == self.instance.atoms.net_supported {
@@ -374,6 +442,8 @@ impl Wm {
             // ignored
         } else if event.atom == self.instance.atoms.wm_state {
             // ignored
+        } else if event.atom == self.instance.atoms.net_wm_state {
+            // ignored
         } else {
             unsafe {
                 let xcb = &self.instance.backend.xcb;
@@ -420,7 +490,7 @@ impl Wm {
         };
         let mut unset = || {
             log::info!("NET_WM_ICON unset");
-            *win.icon.borrow_mut() = None;
+            win.icon.borrow_mut().clear();
             win.upgade();
             data.changed();
         };
@@ -439,30 +509,19 @@ impl Wm {
                 return;
             }
         };
-        if prop.len() < 2 {
-            log::warn!("NET_WM_ICON property has length < 2");
-            return;
-        }
-        let width = prop[0];
-        let height = prop[1];
-        let prop = &prop[2..];
-        if prop.len() != (width * height) as usize {
-            log::warn!("NET_WM_ICON property invalid length");
-            return;
-        }
-        let mut rgba = vec![];
-        for &pixel in prop {
-            rgba.push((pixel >> 16) as u8);
-            rgba.push((pixel >> 8) as u8);
-            rgba.push((pixel >> 0) as u8);
-            rgba.push((pixel >> 24) as u8);
-        }
-        log::info!("NET_WM_ICON set to {}x{}", width, height);
-        *win.icon.borrow_mut() = Some(BackendIcon {
-            rgba,
-            width,
-            height,
-        });
+        let frames = match parse_net_wm_icon(&prop) {
+            Ok(f) => f,
+            Err(e) => {
+                log::warn!("NET_WM_ICON property is invalid: {}", e);
+                return;
+            }
+        };
+        let sizes: Vec<_> = frames
+            .iter()
+            .map(|f| format!("{}x{}", f.width, f.height))
+            .collect();
+        log::info!("NET_WM_ICON set to {}", sizes.join(", "));
+        *win.icon.borrow_mut() = frames;
         win.upgade();
         data.changed();
     }
@@ -561,6 +620,51 @@ impl Wm {
         data.changed();
     }
 
+    fn handle_kde_net_wm_blur_behind_region(&mut self, window: ffi::xcb_window_t) {
+        let mut data = self.instance.wm_data.lock();
+        let win = match data.window(window) {
+            Some(win) => win,
+            None => {
+                return;
+            }
+        };
+        let prop = unsafe {
+            xcb_dl_util::property::get_property::<u32>(
+                &self.instance.backend.xcb,
+                &self.c.errors,
+                window,
+                self.instance.atoms.kde_net_wm_blur_behind_region,
+                ffi::XCB_ATOM_CARDINAL,
+                false,
+                10000,
+            )
+        };
+        let blur = match prop {
+            Err(XcbGetPropertyError::Unset) => None,
+            Ok(p) => match parse_blur_region(&p) {
+                Ok(region) => Some(region),
+                Err(e) => {
+                    log::warn!(
+                        "Could not parse KDE_NET_WM_BLUR_BEHIND_REGION property: {}",
+                        e
+                    );
+                    return;
+                }
+            },
+            Err(e) => {
+                log::warn!(
+                    "Could not retrieve KDE_NET_WM_BLUR_BEHIND_REGION property: {}",
+                    e
+                );
+                return;
+            }
+        };
+        log::info!("Blur updated for {}: {:?}", win.id, blur);
+        *win.blur.borrow_mut() = blur;
+        win.upgade();
+        data.changed();
+    }
+
     fn handle_wm_protocols(&mut self, window: ffi::xcb_window_t) {
         let mut data = self.instance.wm_data.lock();
         let win = match data.window(window) {
@@ -757,6 +861,10 @@ impl Wm {
     fn handle_motion_notify(&mut self, event: &ffi::xcb_generic_event_t) {
         let event = unsafe { &*(event as *const _ as *const ffi::xcb_motion_notify_event_t) };
         log::info!("Got motion event: {:?}", event);
+        if self.resizing.is_some() {
+            self.resize(event.root_x as i32, event.root_y as i32);
+            return;
+        }
         let moving = match &self.moving {
             Some(win) => win,
             _ => return,
@@ -765,6 +873,8 @@ impl Wm {
             Some(win) => win,
             _ => return,
         };
+        // A click that moved the window does not start a double click.
+        self.last_title_click = None;
         unsafe {
             let list = ffi::xcb_configure_window_value_list_t {
                 x: (event.root_x as i32 - moving.start_pointer_x) + moving.start_window_x,
@@ -778,7 +888,7 @@ impl Wm {
                 (ffi::XCB_CONFIG_WINDOW_X | ffi::XCB_CONFIG_WINDOW_Y) as _,
                 &list,
             );
-            let error = self.c.errors.check_cookie(xcb, cookie);
+            let error = self.c.check_cookie(cookie);
             if let Err(e) = error {
                 log::warn!("Could not drag parent window: {}", e);
             }
@@ -787,6 +897,264 @@ impl Wm {
         }
     }
 
+    fn handle_button_press(&mut self, event: &ffi::xcb_generic_event_t) {
+        let event = unsafe { &*(event as *const _ as *const ffi::xcb_button_press_event_t) };
+        log::info!("Got button press event: {:?}", event);
+        let client = self.instance.wm_data.lock().window(event.event);
+        if let Some(win) = client {
+            // The press activated the click-to-focus grab on the client window. Replay it so
+            // that the client receives it as if the grab did not exist.
+            log::info!("Window {} clicked", win.id);
+            self.focus(&win);
+            unsafe {
+                let xcb = &self.instance.backend.xcb;
+                let cookie = xcb.xcb_allow_events_checked(
+                    self.c.c,
+                    ffi::XCB_ALLOW_REPLAY_POINTER as _,
+                    event.time,
+                );
+                if let Err(e) = self.c.check_cookie(cookie) {
+                    log::warn!("Could not replay button press: {}", e);
+                }
+            }
+            return;
+        }
+        if event.detail != 1 {
+            return;
+        }
+        let (win, policy) = {
+            let data = self.instance.wm_data.lock();
+            match data.parent(event.event) {
+                Some(win) => (win, data.config.focus_policy),
+                _ => return,
+            }
+        };
+        if policy == FocusPolicy::ClickToFocus {
+            self.focus(&win);
+        }
+        // Coordinates outside of the frame belong to its border.
+        let (x, y) = (event.event_x as i32, event.event_y as i32);
+        let (width, height) = (
+            win.width.get() as i32,
+            win.height.get() as i32 + TITLE_HEIGHT as i32,
+        );
+        let horizontal = (x >= width) as i32 - (x < 0) as i32;
+        let vertical = (y >= height) as i32 - (y < 0) as i32;
+        if (horizontal, vertical) != (0, 0) {
+            self.last_title_click = None;
+            self.start_resize(&win, horizontal, vertical, event);
+            return;
+        }
+        if y >= TITLE_HEIGHT as i32 {
+            return;
+        }
+        let buttons_x = event.event_x as i32 - win.width.get() as i32;
+        if buttons_x >= CLOSE_BUTTON_X {
+            log::info!("Close button of {} clicked", win.id);
+            self.last_title_click = None;
+            self.close(&win);
+            return;
+        }
+        if buttons_x >= MAXIMIZE_BUTTON_X {
+            log::info!("Maximize button of {} clicked", win.id);
+            self.last_title_click = None;
+            self.toggle_maximized(&win);
+            return;
+        }
+        if buttons_x >= MINIMIZE_BUTTON_X {
+            log::info!("Minimize button of {} clicked", win.id);
+            self.last_title_click = None;
+            self.iconify(&win);
+            return;
+        }
+        if let Some((frame, time)) = self.last_title_click.take() {
+            if frame == event.event && event.time.wrapping_sub(time) <= DOUBLE_CLICK_TIME {
+                log::info!("Title bar of {} double clicked", win.id);
+                self.toggle_maximized(&win);
+                return;
+            }
+        }
+        self.last_title_click = Some((event.event, event.time));
+        self.start_move(&win, event.root_x as i32, event.root_y as i32);
+    }
+
+    fn perform_actions(&mut self) {
+        let actions = std::mem::take(&mut self.instance.wm_data.lock().actions);
+        for (window, action) in actions {
+            let win = match self.instance.wm_data.lock().window(window) {
+                Some(w) => w,
+                _ => continue,
+            };
+            log::info!("Performing {:?} on {}", action, window);
+            match action {
+                WmAction::SetMaximized(maximized) => self.set_maximized(&win, maximized),
+            }
+        }
+    }
+
+    fn toggle_maximized(&mut self, win: &XWindow) {
+        let maximized = win.maximized_vert.get() && win.maximized_horz.get();
+        self.set_maximized(win, !maximized);
+    }
+
+    fn set_maximized(&mut self, win: &XWindow, maximized: bool) {
+        let event = ffi::xcb_client_message_event_t {
+            response_type: ffi::XCB_CLIENT_MESSAGE,
+            format: 32,
+            window: win.id,
+            type_: self.instance.atoms.net_wm_state,
+            data: ffi::xcb_client_message_data_t {
+                data32: [
+                    maximized as u32,
+                    self.instance.atoms.net_wm_state_maximized_vert,
+                    self.instance.atoms.net_wm_state_maximized_horz,
+                    0,
+                    0,
+                ],
+            },
+            ..Default::default()
+        };
+        self.handle_net_wm_state(&event);
+    }
+
+    fn handle_enter_notify(&mut self, event: &ffi::xcb_generic_event_t) {
+        let event = unsafe { &*(event as *const _ as *const ffi::xcb_enter_notify_event_t) };
+        log::info!("Got enter event: {:?}", event);
+        if event.detail == ffi::XCB_NOTIFY_DETAIL_INFERIOR as u8 {
+            return;
+        }
+        let win = {
+            let data = self.instance.wm_data.lock();
+            if data.config.focus_policy != FocusPolicy::FocusFollowsMouse {
+                return;
+            }
+            match data.parent(event.event) {
+                Some(win) => win,
+                _ => return,
+            }
+        };
+        self.focus(&win);
+    }
+
+    /// Grabs or ungrabs Super+`SHORTCUT_KEY` on the root window if
+    /// `WmConfig::super_shortcuts` has changed.
+    ///
+    /// Desktop environments use such grabs for global shortcuts. While the grab is active,
+    /// the key events are delivered to the window manager instead of the focused window.
+    fn update_shortcut_grabs(&mut self) {
+        let grab = self.instance.wm_data.lock().config.super_shortcuts;
+        if grab == self.shortcuts_grabbed {
+            return;
+        }
+        self.shortcuts_grabbed = grab;
+        let xcb = &self.instance.backend.xcb;
+        let keycode = (evdev::map_key(SHORTCUT_KEY) + 8) as u8;
+        // Grab the key independent of the state of CapsLock and NumLock. The layouts put
+        // NumLock on mod3. Mod2 is AltGr.
+        let lock = ffi::XCB_MOD_MASK_LOCK | ffi::XCB_MOD_MASK_3;
+        for extra in [0, ffi::XCB_MOD_MASK_LOCK, ffi::XCB_MOD_MASK_3, lock] {
+            let modifiers = (ffi::XCB_MOD_MASK_4 | extra) as u16;
+            unsafe {
+                let cookie = match grab {
+                    true => xcb.xcb_grab_key_checked(
+                        self.c.c,
+                        1,
+                        self.c.screen.root,
+                        modifiers,
+                        keycode,
+                        ffi::XCB_GRAB_MODE_ASYNC as _,
+                        ffi::XCB_GRAB_MODE_ASYNC as _,
+                    ),
+                    false => {
+                        xcb.xcb_ungrab_key_checked(self.c.c, keycode, self.c.screen.root, modifiers)
+                    }
+                };
+                if let Err(e) = self.c.check_cookie(cookie) {
+                    panic!("Could not update the shortcut grab: {}", e);
+                }
+            }
+        }
+        log::info!("Shortcut grabbed: {}", grab);
+    }
+
+    fn handle_key_press(&mut self, event: &ffi::xcb_generic_event_t) {
+        let event = unsafe { &*(event as *const _ as *const ffi::xcb_key_press_event_t) };
+        log::info!("Got key press event: {:?}", event);
+        let mut data = self.instance.wm_data.lock();
+        data.shortcuts += 1;
+        data.changed();
+    }
+
+    /// Focuses the window for the default seat.
+    fn focus(&self, win: &XWindow) {
+        log::info!("Focusing window {}", win.id);
+        let xcb = &self.instance.backend.xcb;
+        unsafe {
+            let cookie = xcb.xcb_set_input_focus_checked(
+                self.c.c,
+                ffi::XCB_INPUT_FOCUS_POINTER_ROOT as _,
+                win.id,
+                0,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not focus window {}: {}", win.id, e);
+                return;
+            }
+            let cookie = xcb.xcb_change_property_checked(
+                self.c.c,
+                ffi::XCB_PROP_MODE_REPLACE as _,
+                self.c.screen.root,
+                self.instance.atoms.net_active_window,
+                ffi::XCB_ATOM_WINDOW,
+                32,
+                1,
+                &win.id as *const ffi::xcb_window_t as _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not set _NET_ACTIVE_WINDOW property: {}", e);
+            }
+        }
+        if win.demands_attention.replace(false) {
+            self.update_net_wm_state(win);
+            win.upgade();
+            self.instance.wm_data.lock().changed();
+        }
+    }
+
+    /// Marks the window as demanding attention instead of focusing it.
+    fn demand_attention(&self, win: &XWindow) {
+        log::info!("Window {} demands attention", win.id);
+        win.demands_attention.set(true);
+        self.update_net_wm_state(win);
+        win.upgade();
+    }
+
+    /// Asks the client to close the window or destroys it if the client does not support
+    /// WM_DELETE_WINDOW.
+    fn close(&self, win: &XWindow) {
+        let xcb = &self.instance.backend.xcb;
+        unsafe {
+            let cookie = if win.protocols.get().contains(Protocols::DELETE_WINDOW) {
+                let event = ffi::xcb_client_message_event_t {
+                    response_type: ffi::XCB_CLIENT_MESSAGE,
+                    format: 32,
+                    window: win.id,
+                    type_: self.instance.atoms.wm_protocols,
+                    data: ffi::xcb_client_message_data_t {
+                        data32: [self.instance.atoms.wm_delete_window, 0, 0, 0, 0],
+                    },
+                    ..Default::default()
+                };
+                xcb.xcb_send_event_checked(self.c.c, 0, win.id, 0, &event as *const _ as _)
+            } else {
+                xcb.xcb_destroy_window_checked(self.c.c, win.id)
+            };
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not close window {}: {}", win.id, e);
+            }
+        }
+    }
+
     fn handle_button_release(&mut self, event: &ffi::xcb_generic_event_t) {
         let event = unsafe { &*(event as *const _ as *const ffi::xcb_button_release_event_t) };
         log::info!("Got button release event: {:?}", event);
@@ -794,6 +1162,16 @@ impl Wm {
             return;
         }
         let mut data = self.instance.wm_data.lock();
+        if let Some(resizing) = self.resizing.take() {
+            if let Some(win) = resizing.win.upgrade() {
+                log::info!("Finished resizing {}", win.id);
+                win.resizing.set(false);
+                win.upgade();
+                data.changed();
+            }
+            self.ungrab_pointer();
+            return;
+        }
         let moving = match self.moving.take() {
             Some(win) => win,
             _ => return,
@@ -805,16 +1183,142 @@ impl Wm {
         win.dragging.set(false);
         win.upgade();
         data.changed();
+        self.ungrab_pointer();
+    }
+
+    fn ungrab_pointer(&self) {
         unsafe {
             let xcb = &self.instance.backend.xcb;
             let cookie = xcb.xcb_ungrab_pointer_checked(self.c.c, 0);
-            let error = self.c.errors.check_cookie(xcb, cookie);
+            let error = self.c.check_cookie(cookie);
             if let Err(e) = error {
                 log::warn!("Could not ungrab pointer: {}", e);
             }
         }
     }
 
+    /// Grabs the pointer so that motion and button release events are reported to the
+    /// window manager. Returns whether the grab succeeded.
+    fn grab_pointer(&self) -> bool {
+        unsafe {
+            let xcb = &self.instance.backend.xcb;
+            let mut err = ptr::null_mut();
+            let reply = xcb.xcb_grab_pointer_reply(
+                self.c.c,
+                xcb.xcb_grab_pointer(
+                    self.c.c,
+                    0,
+                    self.c.screen.root,
+                    (ffi::XCB_EVENT_MASK_BUTTON_RELEASE | ffi::XCB_EVENT_MASK_POINTER_MOTION) as _,
+                    1,
+                    1,
+                    0,
+                    0,
+                    0,
+                ),
+                &mut err,
+            );
+            let reply = match self.c.errors.check(xcb, reply, err) {
+                Ok(r) => r,
+                Err(e) => {
+                    log::warn!("Could not grab the pointer: {}", e);
+                    return false;
+                }
+            };
+            if reply.status != 0 {
+                log::warn!("Could not grab the pointer: status: {}", reply.status);
+                return false;
+            }
+            log::info!("Grabbed pointer");
+            true
+        }
+    }
+
+    fn start_resize(
+        &mut self,
+        win: &Arc<XWindow>,
+        horizontal: i32,
+        vertical: i32,
+        event: &ffi::xcb_button_press_event_t,
+    ) {
+        log::info!(
+            "Resizing {} via the frame border ({}, {})",
+            win.id,
+            horizontal,
+            vertical
+        );
+        if !self.grab_pointer() {
+            return;
+        }
+        let mut data = self.instance.wm_data.lock();
+        win.resizing.set(true);
+        win.upgade();
+        data.changed();
+        self.resizing = Some(Resizing {
+            win: Arc::downgrade(win),
+            horizontal,
+            vertical,
+            start_pointer_x: event.root_x as i32,
+            start_pointer_y: event.root_y as i32,
+            start_window_x: win.x.get(),
+            start_window_y: win.y.get(),
+            start_width: win.width.get() as i32,
+            start_height: win.height.get() as i32,
+        });
+    }
+
+    /// Resizes the window being resized so that the dragged edges follow the pointer. The
+    /// size is clamped to the size hints of the window.
+    fn resize(&self, root_x: i32, root_y: i32) {
+        let resizing = self.resizing.as_ref().unwrap();
+        let win = match resizing.win.upgrade() {
+            Some(win) => win,
+            _ => return,
+        };
+        let clamp = |start: i32, delta: i32, min: Option<u32>, max: Option<u32>| {
+            let mut size = start + delta;
+            if let Some(max) = max {
+                size = size.min(max as i32);
+            }
+            if let Some(min) = min {
+                size = size.max(min as i32);
+            }
+            size.max(1)
+        };
+        let width = clamp(
+            resizing.start_width,
+            resizing.horizontal * (root_x - resizing.start_pointer_x),
+            win.min_size.get().map(|s| s.0),
+            win.max_size.get().map(|s| s.0),
+        );
+        let height = clamp(
+            resizing.start_height,
+            resizing.vertical * (root_y - resizing.start_pointer_y),
+            win.min_size.get().map(|s| s.1),
+            win.max_size.get().map(|s| s.1),
+        );
+        // Dragging the left or top edge keeps the opposite edge in place.
+        let mut x = resizing.start_window_x;
+        if resizing.horizontal < 0 {
+            x += resizing.start_width - width;
+        }
+        let mut y = resizing.start_window_y;
+        if resizing.vertical < 0 {
+            y += resizing.start_height - height;
+        }
+        self.configure_frame(
+            &win,
+            [
+                x,
+                y,
+                width,
+                height + TITLE_HEIGHT as i32,
+                win.border.get() as i32,
+            ],
+            [0, TITLE_HEIGHT as i32, width, height],
+        );
+    }
+
     fn handle_configure_notify(&mut self, event: &ffi::xcb_generic_event_t) {
         let event = unsafe { &*(event as *const _ as *const ffi::xcb_configure_notify_event_t) };
         let mut data = self.instance.wm_data.lock();
@@ -847,7 +1351,7 @@ impl Wm {
                 let xcb = &self.instance.backend.xcb;
                 let cookie =
                     xcb.xcb_send_event_checked(self.c.c, 0, win.id, 0, &event as *const _ as _);
-                if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+                if let Err(e) = self.c.check_cookie(cookie) {
                     log::warn!("Could not send configure event to child: {}", e);
                 }
             }
@@ -890,66 +1394,105 @@ impl Wm {
                     event.value_mask,
                     &list,
                 );
-                if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+                if let Err(e) = self.c.check_cookie(cookie) {
                     log::warn!("Could not configure freestanding window: {}", e);
                 }
                 return;
             },
         };
+        let mut value_mask = event.value_mask;
+        let resize_mask = (ffi::XCB_CONFIG_WINDOW_WIDTH | ffi::XCB_CONFIG_WINDOW_HEIGHT) as u16;
+        if data.config.refuse_resize && value_mask & resize_mask != 0 {
+            log::info!("Refusing to resize window {}", event.window);
+            value_mask &= !resize_mask;
+            self.send_configure_notify(&win);
+        }
+        // Clamp the height so that the height of the frame still fits into a CARD16.
+        let height = event.height.min(u16::MAX - TITLE_HEIGHT);
+        if height != event.height {
+            log::info!("Clamping height of window {} to {}", event.window, height);
+        }
         unsafe {
-            list.height += TITLE_HEIGHT as u32;
+            list.height = (height + TITLE_HEIGHT) as u32;
             let cookie = xcb.xcb_configure_window_aux_checked(
                 self.c.c,
                 win.parent_id.get(),
-                event.value_mask,
+                value_mask,
                 &list,
             );
-            let error = self.c.errors.check_cookie(xcb, cookie);
+            let error = self.c.check_cookie(cookie);
             if let Err(e) = error {
                 log::warn!("Could not configure parent window: {}", e);
             }
             let list = ffi::xcb_configure_window_value_list_t {
                 width: event.width as _,
-                height: event.height as _,
+                height: height as _,
                 ..Default::default()
             };
             let cookie = xcb.xcb_configure_window_aux_checked(
                 self.c.c,
                 event.window,
-                event.value_mask
-                    & (ffi::XCB_CONFIG_WINDOW_WIDTH | ffi::XCB_CONFIG_WINDOW_HEIGHT) as u16,
+                value_mask & resize_mask,
                 &list,
             );
-            let error = self.c.errors.check_cookie(xcb, cookie);
+            let error = self.c.check_cookie(cookie);
             if let Err(e) = error {
                 log::warn!("Could not configure window: {}", e);
             }
-            if event.value_mask & ffi::XCB_CONFIG_WINDOW_X as u16 != 0 {
+            if value_mask & ffi::XCB_CONFIG_WINDOW_X as u16 != 0 {
                 win.x_to_be.set(event.x as _);
             }
-            if event.value_mask & ffi::XCB_CONFIG_WINDOW_Y as u16 != 0 {
+            if value_mask & ffi::XCB_CONFIG_WINDOW_Y as u16 != 0 {
                 win.y_to_be.set(event.y as _);
             }
-            if event.value_mask & ffi::XCB_CONFIG_WINDOW_WIDTH as u16 != 0 {
+            if value_mask & ffi::XCB_CONFIG_WINDOW_WIDTH as u16 != 0 {
                 win.width_to_be.set(event.width as _);
             }
-            if event.value_mask & ffi::XCB_CONFIG_WINDOW_HEIGHT as u16 != 0 {
-                win.height_to_be.set(event.width as _);
+            if value_mask & ffi::XCB_CONFIG_WINDOW_HEIGHT as u16 != 0 {
+                win.height_to_be.set(height as _);
             }
-            if event.value_mask & ffi::XCB_CONFIG_WINDOW_BORDER_WIDTH as u16 != 0 {
+            if value_mask & ffi::XCB_CONFIG_WINDOW_BORDER_WIDTH as u16 != 0 {
                 win.border_to_be.set(event.border_width as _);
             }
         }
     }
 
+    /// Sends a synthetic `ConfigureNotify` event with the current geometry of `win` to the
+    /// client. ICCCM 4.1.5 requires this if a configure request is not granted.
+    fn send_configure_notify(&self, win: &XWindow) {
+        let border = win.border.get() as i16;
+        let event = ffi::xcb_configure_notify_event_t {
+            response_type: ffi::XCB_CONFIGURE_NOTIFY,
+            event: win.id,
+            window: win.id,
+            x: win.x.get() as i16 + border,
+            y: win.y.get() as i16 + border + TITLE_HEIGHT as i16,
+            width: win.width.get() as u16,
+            height: win.height.get() as u16,
+            ..Default::default()
+        };
+        unsafe {
+            let xcb = &self.instance.backend.xcb;
+            let cookie =
+                xcb.xcb_send_event_checked(self.c.c, 0, win.id, 0, &event as *const _ as _);
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not send configure event to client: {}", e);
+            }
+        }
+    }
+
     fn handle_map_request(&mut self, event: &ffi::xcb_generic_event_t) {
         let event = unsafe { &*(event as *const _ as *const ffi::xcb_map_request_event_t) };
         log::info!("Map request: {}", event.window);
-        let data = self.instance.wm_data.lock();
+        self.apply_initial_net_wm_state(event.window);
+        let mut data = self.instance.wm_data.lock();
         let win = match data.window(event.window) {
             Some(w) => w,
             _ => return,
         };
+        win.map_requests.set(win.map_requests.get() + 1);
+        win.upgade();
+        data.changed();
         win.desired_state.set(WindowState::Normal);
         unsafe {
             for w in [win.parent_id.get(), event.window] {
@@ -958,15 +1501,52 @@ impl Wm {
                     .backend
                     .xcb
                     .xcb_map_window_checked(self.c.c, w);
-                let error = self
-                    .c
-                    .errors
-                    .check_cookie(&self.instance.backend.xcb, cookie);
+                let error = self.c.check_cookie(cookie);
                 if let Err(e) = error {
                     log::warn!("Could not map window: {}", e);
                 }
             }
         }
+        drop(data);
+        self.stacking.retain(|&w| w != event.window);
+        self.stacking.push(event.window);
+        self.restack();
+    }
+
+    /// Applies the _NET_WM_STATE property that clients set before mapping a window.
+    fn apply_initial_net_wm_state(&mut self, window: ffi::xcb_window_t) {
+        let res = unsafe {
+            xcb_dl_util::property::get_property::<u32>(
+                &self.instance.backend.xcb,
+                &self.c.errors,
+                window,
+                self.instance.atoms.net_wm_state,
+                ffi::XCB_ATOM_ATOM,
+                false,
+                10000,
+            )
+        };
+        let states = match res {
+            Ok(res) => res,
+            Err(e) => {
+                log::warn!("Could not retrieve _NET_WM_STATE property: {}", e);
+                return;
+            }
+        };
+        for &state in states.iter() {
+            log::info!("Initial _NET_WM_STATE of {}: {}", window, state);
+            let event = ffi::xcb_client_message_event_t {
+                response_type: ffi::XCB_CLIENT_MESSAGE,
+                format: 32,
+                window,
+                type_: self.instance.atoms.net_wm_state,
+                data: ffi::xcb_client_message_data_t {
+                    data32: [1, state, 0, 0, 0],
+                },
+                ..Default::default()
+            };
+            self.handle_net_wm_state(&event);
+        }
     }
 
     fn handle_map_notify(&mut self, event: &ffi::xcb_generic_event_t) {
@@ -976,6 +1556,7 @@ impl Wm {
         if let Some(win) = data.window(event.window) {
             win.current_state.set(WindowState::Normal);
             win.update_wm_state(&self.c);
+            self.update_net_wm_state(&win);
             if win.desired_state.get() != WindowState::Normal {
                 unsafe {
                     self.instance.backend.xcb.xcb_unmap_window(self.c.c, win.id);
@@ -984,6 +1565,15 @@ impl Wm {
             win.mapped.set(true);
             win.upgade();
             data.changed();
+            if win.desired_state.get() == WindowState::Normal {
+                if data.config.focus_stealing {
+                    drop(data);
+                    self.focus(&win);
+                } else if data.config.demand_attention {
+                    self.demand_attention(&win);
+                    data.changed();
+                }
+            }
         }
     }
 
@@ -998,16 +1588,22 @@ impl Wm {
                 win.current_state.set(WindowState::Withdrawn);
             }
             win.update_wm_state(&self.c);
+            self.update_net_wm_state(&win);
             win.mapped.set(false);
+            win.stacking_position.set(None);
             win.upgade();
             data.changed();
         }
+        drop(data);
+        if self.stacking.contains(&event.window) {
+            self.stacking.retain(|&w| w != event.window);
+            self.restack();
+        }
     }
 
     fn handle_net_wm_moveresize(&mut self, event: &ffi::xcb_client_message_event_t) {
-        let mut data = self.instance.wm_data.lock();
         let data32 = unsafe { event.data.data32 };
-        let win = match data.window(event.window) {
+        let win = match self.instance.wm_data.lock().window(event.window) {
             Some(w) => w,
             _ => return,
         };
@@ -1017,47 +1613,25 @@ impl Wm {
         if direction != 8 {
             return;
         }
-        unsafe {
-            let xcb = &self.instance.backend.xcb;
-            let mut err = ptr::null_mut();
-            let reply = xcb.xcb_grab_pointer_reply(
-                self.c.c,
-                xcb.xcb_grab_pointer(
-                    self.c.c,
-                    0,
-                    self.c.screen.root,
-                    (ffi::XCB_EVENT_MASK_BUTTON_RELEASE | ffi::XCB_EVENT_MASK_POINTER_MOTION) as _,
-                    1,
-                    1,
-                    0,
-                    0,
-                    0,
-                ),
-                &mut err,
-            );
-            let reply = match self.c.errors.check(xcb, reply, err) {
-                Ok(r) => r,
-                Err(e) => {
-                    log::warn!("Could not grab the pointer: {}", e);
-                    return;
-                }
-            };
-            if reply.status != 0 {
-                log::warn!("Could not grab the pointer: status: {}", reply.status);
-                return;
-            }
-            log::info!("Grabbed pointer");
+        self.start_move(&win, x_root as i32, y_root as i32);
+    }
+
+    /// Starts moving the window with the pointer until the left button is released.
+    fn start_move(&mut self, win: &Arc<XWindow>, x_root: i32, y_root: i32) {
+        if !self.grab_pointer() {
+            return;
         }
+        let mut data = self.instance.wm_data.lock();
         win.dragging.set(true);
         win.upgade();
         data.changed();
         assert!(self.moving.is_none());
         self.moving = Some(Moving {
-            start_pointer_x: x_root as i32,
-            start_pointer_y: y_root as i32,
+            start_pointer_x: x_root,
+            start_pointer_y: y_root,
             start_window_x: win.x.get(),
             start_window_y: win.y.get(),
-            win: Arc::downgrade(&win),
+            win: Arc::downgrade(win),
         });
     }
 
@@ -1069,17 +1643,34 @@ impl Wm {
             _ => return,
         };
         if data32[0] == 3 {
-            win.desired_state.set(WindowState::Iconic);
-            if win.mapped.get() {
-                unsafe {
-                    self.instance.backend.xcb.xcb_unmap_window(self.c.c, win.id);
-                }
-            }
+            self.iconify(&win);
         }
         win.upgade();
         data.changed();
     }
 
+    fn iconify(&self, win: &XWindow) {
+        log::info!("Iconifying {}", win.id);
+        win.desired_state.set(WindowState::Iconic);
+        if win.mapped.get() {
+            unsafe {
+                self.instance.backend.xcb.xcb_unmap_window(self.c.c, win.id);
+            }
+        }
+    }
+
+    fn deiconify(&self, win: &XWindow) {
+        log::info!("Deiconifying {}", win.id);
+        win.desired_state.set(WindowState::Normal);
+        unsafe {
+            let xcb = &self.instance.backend.xcb;
+            let cookie = xcb.xcb_map_window_checked(self.c.c, win.id);
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not map window {}: {}", win.id, e);
+            }
+        }
+    }
+
     fn handle_create_notify(&mut self, event: &ffi::xcb_generic_event_t) {
         let event = unsafe { &*(event as *const _ as *const ffi::xcb_create_notify_event_t) };
         log::info!(
@@ -1097,10 +1688,14 @@ impl Wm {
         };
         let c = self.c.c;
         let xcb = &self.instance.backend.xcb;
+        let border = event.border_width.max(data.config.border_width);
         unsafe {
             win.parent_id.set(xcb.xcb_generate_id(c));
-            let em =
-                ffi::XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY | ffi::XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT;
+            let em = ffi::XCB_EVENT_MASK_SUBSTRUCTURE_NOTIFY
+                | ffi::XCB_EVENT_MASK_SUBSTRUCTURE_REDIRECT
+                | ffi::XCB_EVENT_MASK_BUTTON_PRESS
+                | ffi::XCB_EVENT_MASK_BUTTON_RELEASE
+                | ffi::XCB_EVENT_MASK_ENTER_WINDOW;
             let cookie = xcb.xcb_create_window_checked(
                 c,
                 self.c.screen.root_depth,
@@ -1110,16 +1705,23 @@ impl Wm {
                 event.y,
                 event.width,
                 event.height + TITLE_HEIGHT,
-                event.border_width,
+                border,
                 ffi::XCB_WINDOW_CLASS_INPUT_OUTPUT as _,
                 self.c.screen.root_visual,
-                ffi::XCB_CW_EVENT_MASK,
-                &em as *const _ as _,
+                ffi::XCB_CW_BACK_PIXEL | ffi::XCB_CW_EVENT_MASK,
+                [TITLE_BAR_COLOR, em].as_ptr() as _,
             );
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 log::error!("Could not create parent window: {}", e);
                 return;
             }
+            for (x, color) in [
+                (CLOSE_BUTTON_X, CLOSE_BUTTON_COLOR),
+                (MAXIMIZE_BUTTON_X, MAXIMIZE_BUTTON_COLOR),
+                (MINIMIZE_BUTTON_X, MINIMIZE_BUTTON_COLOR),
+            ] {
+                self.create_button(win.parent_id.get(), event.width as i32 + x, color);
+            }
             log::info!("Reparenting {} under {}", event.window, win.parent_id.get());
             let cookie = xcb.xcb_reparent_window_checked(
                 c,
@@ -1128,7 +1730,7 @@ impl Wm {
                 0,
                 TITLE_HEIGHT as i16,
             );
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 log::error!("Could not reparent window: {}", e);
                 return;
             }
@@ -1139,9 +1741,28 @@ impl Wm {
                 ffi::XCB_CW_EVENT_MASK,
                 &events as *const _ as _,
             );
-            if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 log::warn!("Could not select events on window {}: {}", event.window, e);
             }
+            if data.config.focus_policy == FocusPolicy::ClickToFocus {
+                // Freeze the pointer on presses in the client window until the press has
+                // been replayed to the client.
+                let cookie = xcb.xcb_grab_button_checked(
+                    c,
+                    0,
+                    event.window,
+                    ffi::XCB_EVENT_MASK_BUTTON_PRESS as _,
+                    ffi::XCB_GRAB_MODE_SYNC as _,
+                    ffi::XCB_GRAB_MODE_ASYNC as _,
+                    0,
+                    0,
+                    ffi::XCB_BUTTON_INDEX_ANY as _,
+                    ffi::XCB_MOD_MASK_ANY as _,
+                );
+                if let Err(e) = self.c.check_cookie(cookie) {
+                    log::warn!("Could not grab buttons on window {}: {}", event.window, e);
+                }
+            }
             data.parents
                 .insert(win.parent_id.get(), Arc::downgrade(&win));
             data.window_to_parent.insert(win.id, win.parent_id.get());
@@ -1154,15 +1775,16 @@ impl Wm {
             self.handle_wm_hints(event.window);
             self.handle_wm_class(event.window);
             self.handle_wm_protocols(event.window);
+            self.handle_kde_net_wm_blur_behind_region(event.window);
         }
         win.x.set(event.x as _);
         win.y.set(event.y as _);
-        win.border.set(event.border_width as _);
+        win.border.set(border as _);
         win.width.set(event.width as _);
         win.height.set(event.height as _);
         win.x_to_be.set(event.x as _);
         win.y_to_be.set(event.y as _);
-        win.border_to_be.set(event.border_width as _);
+        win.border_to_be.set(border as _);
         win.width_to_be.set(event.width as _);
         win.height_to_be.set(event.height as _);
         win.created.set(true);
@@ -1171,6 +1793,40 @@ impl Wm {
         self.update_client_list();
     }
 
+    /// Creates a button in the title bar of `frame`. The button does not select any events.
+    /// Clicks are therefore reported for the frame.
+    fn create_button(&self, frame: ffi::xcb_window_t, x: i32, color: u32) {
+        let xcb = &self.instance.backend.xcb;
+        unsafe {
+            let id = xcb.xcb_generate_id(self.c.c);
+            // The buttons stay at the right end of the title bar when the frame is resized.
+            let values = [color, ffi::XCB_GRAVITY_NORTH_EAST];
+            let cookie = xcb.xcb_create_window_checked(
+                self.c.c,
+                self.c.screen.root_depth,
+                id,
+                frame,
+                x as i16,
+                0,
+                TITLE_HEIGHT,
+                TITLE_HEIGHT,
+                0,
+                ffi::XCB_WINDOW_CLASS_INPUT_OUTPUT as _,
+                self.c.screen.root_visual,
+                ffi::XCB_CW_BACK_PIXEL | ffi::XCB_CW_WIN_GRAVITY,
+                values.as_ptr() as _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not create button: {}", e);
+                return;
+            }
+            let cookie = xcb.xcb_map_window_checked(self.c.c, id);
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not map button: {}", e);
+            }
+        }
+    }
+
     fn update_client_list(&mut self) {
         let data = self.instance.wm_data.lock();
         let mut windows = vec![];
@@ -1192,11 +1848,7 @@ impl Wm {
                 windows.len() as _,
                 windows.as_ptr() as *const _,
             );
-            if let Err(e) = self
-                .c
-                .errors
-                .check_cookie(&self.instance.backend.xcb, cookie)
-            {
+            if let Err(e) = self.c.check_cookie(cookie) {
                 log::warn!("Could not update _NET_CLIENT_LIST: {}", e);
             }
         }
@@ -1224,16 +1876,21 @@ impl Wm {
         }
         if let Some(parent) = data.window_to_parent.remove(&event.window) {
             data.parents.remove(&parent);
+            data.changed();
             unsafe {
                 let xcb = &self.instance.backend.xcb;
                 let cookie = xcb.xcb_destroy_window_checked(self.c.c, parent);
-                if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
+                if let Err(e) = self.c.check_cookie(cookie) {
                     log::warn!("Could not destroy parent: {}", e);
                 }
             }
         }
         drop(data);
         self.update_client_list();
+        if self.stacking.contains(&event.window) {
+            self.stacking.retain(|&w| w != event.window);
+            self.restack();
+        }
     }
 
     fn handle_client_message(&mut self, event: &ffi::xcb_generic_event_t) {
@@ -1244,9 +1901,9 @@ impl Wm {
         } else if event.type_ == self.instance.atoms.wm_protocols && event.format == 32 {
             log::warn!("NET_WM_PROTOCOLS client message: {:?}", event);
             self.handle_net_wm_protocols(event);
-        // } else if event.type_ == self.instance.atoms.net_active_window && event.format == 32 {
-        //     log::warn!("NET_ACTIVE_WINDOW client message: {:?}", event);
-        //     self.handle_net_active_window(event);
+        } else if event.type_ == self.instance.atoms.net_active_window && event.format == 32 {
+            log::warn!("NET_ACTIVE_WINDOW client message: {:?}", event);
+            self.handle_net_active_window(event);
         } else if event.type_ == self.instance.atoms.net_wm_moveresize && event.format == 32 {
             log::warn!("NET_WM_MOVERESIZE client message: {:?}", event);
             self.handle_net_wm_moveresize(event);
@@ -1258,25 +1915,33 @@ impl Wm {
         }
     }
 
-    // fn handle_net_active_window(&mut self, event: &ffi::xcb_client_message_event_t) {
-    //     let mut data = self.instance.wm_data.lock();
-    //     let win = match data.window(event.window) {
-    //         Some(w) => w,
-    //         _ => return,
-    //     };
-    //     if win.state.get() == WindowState::Iconic {
-    //         win.state.set(WindowState::Normal);
-    //         win.update_wm_state();
-    //         unsafe {
-    //             self.instance
-    //                 .backend
-    //                 .xcb
-    //                 .xcb_map_window(self.instance.c, win.id);
-    //         }
-    //     }
-    //     win.upgade();
-    //     data.changed();
-    // }
+    fn handle_net_active_window(&mut self, event: &ffi::xcb_client_message_event_t) {
+        let (win, config) = {
+            let data = self.instance.wm_data.lock();
+            match data.window(event.window) {
+                Some(w) => (w, data.config),
+                _ => return,
+            }
+        };
+        // Activating an iconified window restores it. This is how clients unminimize
+        // their windows.
+        if win.desired_state.get() == WindowState::Iconic {
+            self.deiconify(&win);
+            return;
+        }
+        if !config.focus_stealing {
+            if config.demand_attention && win.mapped.get() {
+                self.demand_attention(&win);
+                self.instance.wm_data.lock().changed();
+            } else {
+                log::info!("Ignoring activation request of {}", event.window);
+            }
+            return;
+        }
+        if win.mapped.get() {
+            self.focus(&win);
+        }
+    }
 
     fn handle_net_wm_protocols(&mut self, event: &ffi::xcb_client_message_event_t) {
         let mut data = self.instance.wm_data.lock();
@@ -1295,15 +1960,41 @@ impl Wm {
             Some(w) => w,
             _ => return,
         };
+        let was_maximized = win.maximized_vert.get() && win.maximized_horz.get();
+        let was_above = win.always_on_top.get();
+        let was_below = win.always_on_bottom.get();
         for property in [data32[1], data32[2]] {
+            if property == self.instance.atoms.net_wm_state_hidden {
+                // Pagers minimize and restore windows by changing _NET_WM_STATE_HIDDEN.
+                let iconic = win.desired_state.get() == WindowState::Iconic;
+                let hidden = match data32[0] {
+                    0 => false,
+                    1 => true,
+                    2 => !iconic,
+                    _ => {
+                        log::warn!("Unknown _NET_WM_STATE operation {}", data32[0]);
+                        continue;
+                    }
+                };
+                if hidden && !iconic {
+                    self.iconify(&win);
+                } else if !hidden && iconic {
+                    self.deiconify(&win);
+                }
+                continue;
+            }
             let (name, cell) = if property == self.instance.atoms.net_wm_state_above {
                 ("always on top", &win.always_on_top)
+            } else if property == self.instance.atoms.net_wm_state_below {
+                ("always on bottom", &win.always_on_bottom)
             } else if property == self.instance.atoms.net_wm_state_maximized_vert {
                 ("maximized vert", &win.maximized_vert)
             } else if property == self.instance.atoms.net_wm_state_maximized_horz {
                 ("maximized horz", &win.maximized_horz)
             } else if property == self.instance.atoms.net_wm_state_fullscreen {
                 ("fullscreen", &win.fullscreen)
+            } else if property == self.instance.atoms.net_wm_state_demands_attention {
+                ("demands attention", &win.demands_attention)
             } else if property == 0 {
                 continue;
             } else {
@@ -1321,8 +2012,7 @@ impl Wm {
                 }
             }
             if property == self.instance.atoms.net_wm_state_fullscreen {
-                let xcb = &self.instance.backend.xcb;
-                let (v1, v2) = if cell.get() {
+                if cell.get() {
                     if !old {
                         win.pre_fs_x.set(win.x_to_be.get());
                         win.pre_fs_y.set(win.y_to_be.get());
@@ -1330,31 +2020,11 @@ impl Wm {
                         win.pre_fs_height.set(win.height_to_be.get());
                         win.pre_fs_border.set(win.border_to_be.get());
                     }
-                    let mut old_overlaps = false;
-                    let mut x = self.crtcs[0].x;
-                    let mut y = self.crtcs[0].y;
-                    let mut width = self.crtcs[0].width;
-                    let mut height = self.crtcs[0].height;
-                    for crtc in &self.crtcs {
-                        let overlaps = ((win.x_to_be.get() <= crtc.x
-                            && win.x_to_be.get() + win.width_to_be.get() as i32 > crtc.x)
-                            || (crtc.x <= win.x_to_be.get()
-                                && crtc.x + crtc.width > win.x_to_be.get()))
-                            && ((win.y_to_be.get() <= crtc.y
-                                && win.y_to_be.get() + win.height_to_be.get() as i32 > crtc.y)
-                                || (crtc.y <= win.y_to_be.get()
-                                    && crtc.y + crtc.height > win.y_to_be.get()));
-                        if overlaps && (!old_overlaps || (crtc.x, crtc.y) < (x, y)) {
-                            x = crtc.x;
-                            y = crtc.y;
-                            width = crtc.width;
-                            height = crtc.height;
-                            old_overlaps = true;
-                        }
-                    }
-                    ([x, y, width, height, 0], [0, 0, width, height])
+                    let (x, y, width, height) = self.output_of(&win);
+                    self.configure_frame(&win, [x, y, width, height, 0], [0, 0, width, height]);
                 } else {
-                    (
+                    self.configure_frame(
+                        &win,
                         [
                             win.pre_fs_x.get(),
                             win.pre_fs_y.get(),
@@ -1368,46 +2038,215 @@ impl Wm {
                             win.pre_fs_width.get() as i32,
                             win.pre_fs_height.get() as i32,
                         ],
-                    )
-                };
-                unsafe {
-                    log::info!("{:?}", v1);
-                    let cookie = xcb.xcb_configure_window_checked(
-                        self.c.c,
-                        win.parent_id.get(),
-                        (ffi::XCB_CONFIG_WINDOW_X
-                            | ffi::XCB_CONFIG_WINDOW_Y
-                            | ffi::XCB_CONFIG_WINDOW_WIDTH
-                            | ffi::XCB_CONFIG_WINDOW_HEIGHT
-                            | ffi::XCB_CONFIG_WINDOW_BORDER_WIDTH) as _,
-                        v1.as_ptr() as _,
-                    );
-                    if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
-                        log::warn!("Could not configure parent window: {}", e);
-                    }
-                    let cookie = xcb.xcb_configure_window_checked(
-                        self.c.c,
-                        win.id,
-                        (ffi::XCB_CONFIG_WINDOW_X
-                            | ffi::XCB_CONFIG_WINDOW_Y
-                            | ffi::XCB_CONFIG_WINDOW_WIDTH
-                            | ffi::XCB_CONFIG_WINDOW_HEIGHT) as _,
-                        v2.as_ptr() as _,
                     );
-                    if let Err(e) = self.c.errors.check_cookie(xcb, cookie) {
-                        log::warn!("Could not configure window: {}", e);
-                    }
-                    win.x_to_be.set(v1[0] as _);
-                    win.y_to_be.set(v1[1] as _);
-                    win.width_to_be.set(v2[2] as _);
-                    win.height_to_be.set(v2[3] as _);
-                    win.border_to_be.set(v1[4] as _);
                 }
             }
             log::info!("Window {} {}: {}", name, cell.get(), event.window);
         }
+        let maximized = win.maximized_vert.get() && win.maximized_horz.get();
+        if maximized != was_maximized && !win.fullscreen.get() {
+            if maximized {
+                win.pre_max_x.set(win.x_to_be.get());
+                win.pre_max_y.set(win.y_to_be.get());
+                win.pre_max_width.set(win.width_to_be.get());
+                win.pre_max_height.set(win.height_to_be.get());
+                win.pre_max_border.set(win.border_to_be.get());
+                let (x, y, width, height) = self.output_of(&win);
+                self.configure_frame(
+                    &win,
+                    [x, y, width, height, 0],
+                    [0, TITLE_HEIGHT as i32, width, height - TITLE_HEIGHT as i32],
+                );
+            } else {
+                // The size bounds are ignored while the window is maximized. They might
+                // have changed in the meantime.
+                let (width, height) =
+                    clamp_to_size_bounds(&win, win.pre_max_width.get(), win.pre_max_height.get());
+                self.configure_frame(
+                    &win,
+                    [
+                        win.pre_max_x.get(),
+                        win.pre_max_y.get(),
+                        width as i32,
+                        (height + TITLE_HEIGHT as u32) as i32,
+                        win.pre_max_border.get() as i32,
+                    ],
+                    [0, TITLE_HEIGHT as i32, width as i32, height as i32],
+                );
+            }
+        }
+        self.update_net_wm_state(&win);
         win.upgade();
         data.changed();
+        if win.always_on_top.get() != was_above || win.always_on_bottom.get() != was_below {
+            drop(data);
+            self.restack();
+        }
+    }
+
+    /// Restacks the frames so that windows that are always on top are above and windows
+    /// that are always on bottom are below all other windows and publishes the resulting
+    /// order in `_NET_CLIENT_LIST_STACKING`. Only mapped windows are part of the stacking
+    /// order.
+    fn restack(&mut self) {
+        let mut data = self.instance.wm_data.lock();
+        let mut windows: Vec<_> = self
+            .stacking
+            .iter()
+            .flat_map(|&w| data.window(w))
+            .filter(|w| !w.destroyed.get())
+            .collect();
+        windows.sort_by_key(|w| w.always_on_top.get() as i8 - w.always_on_bottom.get() as i8);
+        let xcb = &self.instance.backend.xcb;
+        for win in &windows {
+            let list = ffi::xcb_configure_window_value_list_t {
+                stack_mode: ffi::XCB_STACK_MODE_ABOVE as _,
+                ..Default::default()
+            };
+            unsafe {
+                let cookie = xcb.xcb_configure_window_aux_checked(
+                    self.c.c,
+                    win.parent_id.get(),
+                    ffi::XCB_CONFIG_WINDOW_STACK_MODE as _,
+                    &list,
+                );
+                if let Err(e) = self.c.check_cookie(cookie) {
+                    log::warn!("Could not restack the frame of {}: {}", win.id, e);
+                }
+            }
+        }
+        let ids: Vec<_> = windows.iter().map(|w| w.id).collect();
+        log::info!("Stacking order: {:?}", ids);
+        unsafe {
+            let cookie = xcb.xcb_change_property_checked(
+                self.c.c,
+                ffi::XCB_PROP_MODE_REPLACE as _,
+                self.c.screen.root,
+                self.instance.atoms.net_client_list_stacking,
+                ffi::XCB_ATOM_WINDOW,
+                32,
+                ids.len() as _,
+                ids.as_ptr() as *const _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not update _NET_CLIENT_LIST_STACKING: {}", e);
+            }
+        }
+        // Published after the property so that tests that observe the new positions also
+        // observe the new property.
+        for (position, win) in windows.iter().enumerate() {
+            if win.stacking_position.get() != Some(position) {
+                win.stacking_position.set(Some(position));
+                win.upgade();
+            }
+        }
+        data.changed();
+    }
+
+    /// Returns the geometry of the output that the window should be maximized or made
+    /// fullscreen on.
+    fn output_of(&self, win: &XWindow) -> (i32, i32, i32, i32) {
+        let mut old_overlaps = false;
+        let mut x = self.crtcs[0].x;
+        let mut y = self.crtcs[0].y;
+        let mut width = self.crtcs[0].width;
+        let mut height = self.crtcs[0].height;
+        for crtc in &self.crtcs {
+            let overlaps = ((win.x_to_be.get() <= crtc.x
+                && win.x_to_be.get() + win.width_to_be.get() as i32 > crtc.x)
+                || (crtc.x <= win.x_to_be.get() && crtc.x + crtc.width > win.x_to_be.get()))
+                && ((win.y_to_be.get() <= crtc.y
+                    && win.y_to_be.get() + win.height_to_be.get() as i32 > crtc.y)
+                    || (crtc.y <= win.y_to_be.get() && crtc.y + crtc.height > win.y_to_be.get()));
+            if overlaps && (!old_overlaps || (crtc.x, crtc.y) < (x, y)) {
+                x = crtc.x;
+                y = crtc.y;
+                width = crtc.width;
+                height = crtc.height;
+                old_overlaps = true;
+            }
+        }
+        (x, y, width, height)
+    }
+
+    /// Configures the frame with `[x, y, width, height, border]` and the window with
+    /// `[x, y, width, height]` relative to the frame.
+    fn configure_frame(&self, win: &XWindow, frame: [i32; 5], window: [i32; 4]) {
+        log::info!("Configuring frame of {}: {:?}", win.id, frame);
+        let xcb = &self.instance.backend.xcb;
+        unsafe {
+            let cookie = xcb.xcb_configure_window_checked(
+                self.c.c,
+                win.parent_id.get(),
+                (ffi::XCB_CONFIG_WINDOW_X
+                    | ffi::XCB_CONFIG_WINDOW_Y
+                    | ffi::XCB_CONFIG_WINDOW_WIDTH
+                    | ffi::XCB_CONFIG_WINDOW_HEIGHT
+                    | ffi::XCB_CONFIG_WINDOW_BORDER_WIDTH) as _,
+                frame.as_ptr() as _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not configure parent window: {}", e);
+            }
+            let cookie = xcb.xcb_configure_window_checked(
+                self.c.c,
+                win.id,
+                (ffi::XCB_CONFIG_WINDOW_X
+                    | ffi::XCB_CONFIG_WINDOW_Y
+                    | ffi::XCB_CONFIG_WINDOW_WIDTH
+                    | ffi::XCB_CONFIG_WINDOW_HEIGHT) as _,
+                window.as_ptr() as _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not configure window: {}", e);
+            }
+        }
+        win.x_to_be.set(frame[0] as _);
+        win.y_to_be.set(frame[1] as _);
+        win.width_to_be.set(window[2] as _);
+        win.height_to_be.set(window[3] as _);
+        win.border_to_be.set(frame[4] as _);
+    }
+
+    /// Writes the state of the window to its _NET_WM_STATE property.
+    fn update_net_wm_state(&self, win: &XWindow) {
+        let atoms = &self.instance.atoms;
+        let mut states = vec![];
+        for (set, atom) in [
+            (win.always_on_top.get(), atoms.net_wm_state_above),
+            (win.always_on_bottom.get(), atoms.net_wm_state_below),
+            (win.maximized_vert.get(), atoms.net_wm_state_maximized_vert),
+            (win.maximized_horz.get(), atoms.net_wm_state_maximized_horz),
+            (win.fullscreen.get(), atoms.net_wm_state_fullscreen),
+            (
+                win.current_state.get() == WindowState::Iconic,
+                atoms.net_wm_state_hidden,
+            ),
+            (
+                win.demands_attention.get(),
+                atoms.net_wm_state_demands_attention,
+            ),
+        ] {
+            if set {
+                states.push(atom);
+            }
+        }
+        let xcb = &self.instance.backend.xcb;
+        unsafe {
+            let cookie = xcb.xcb_change_property_checked(
+                self.c.c,
+                ffi::XCB_PROP_MODE_REPLACE as _,
+                win.id,
+                atoms.net_wm_state,
+                ffi::XCB_ATOM_ATOM,
+                32,
+                states.len() as _,
+                states.as_ptr() as _,
+            );
+            if let Err(e) = self.c.check_cookie(cookie) {
+                log::warn!("Could not update _NET_WM_STATE of {}: {}", win.id, e);
+            }
+        }
     }
 }
 
@@ -1436,9 +2275,80 @@ impl XWindow {
                 2,
                 [state, 0].as_ptr() as _,
             );
-            if let Err(e) = c.errors.check_cookie(xcb, cookie) {
+            if let Err(e) = c.check_cookie(cookie) {
                 log::warn!("Could not update WM_STATE property: {}", e);
             }
         }
     }
 }
+
+/// Parses the contents of a `_KDE_NET_WM_BLUR_BEHIND_REGION` property. The property is a
+/// list of `x, y, width, height` quadruples. An empty list requests blur behind the whole
+/// window.
+fn parse_blur_region(prop: &[u32]) -> Result<BlurRegion, String> {
+    if prop.is_empty() {
+        return Ok(BlurRegion::Window);
+    }
+    if prop.len() % 4 != 0 {
+        return Err(format!(
+            "The length of the property ({}) is not a multiple of 4",
+            prop.len()
+        ));
+    }
+    let rects = prop
+        .chunks_exact(4)
+        .map(|r| (r[0] as i32, r[1] as i32, r[2], r[3]))
+        .collect();
+    Ok(BlurRegion::Rects(rects))
+}
+
+/// Clamps the size of `win` to the bounds in its normal hints.
+fn clamp_to_size_bounds(win: &XWindow, mut width: u32, mut height: u32) -> (u32, u32) {
+    if let Some((max_width, max_height)) = win.max_size.get() {
+        width = width.min(max_width);
+        height = height.min(max_height);
+    }
+    if let Some((min_width, min_height)) = win.min_size.get() {
+        width = width.max(min_width);
+        height = height.max(min_height);
+    }
+    (width.max(1), height.max(1))
+}
+
+/// Parses the frames of a `_NET_WM_ICON` property. Each frame consists of its width, its
+/// height, and width * height ARGB pixels.
+fn parse_net_wm_icon(mut prop: &[u32]) -> Result<Vec<BackendIcon>, String> {
+    let mut frames = vec![];
+    while !prop.is_empty() {
+        if prop.len() < 2 {
+            return Err(format!("Frame {} has no size", frames.len()));
+        }
+        let (width, height) = (prop[0], prop[1]);
+        let len = width as usize * height as usize;
+        let pixels = match prop.get(2..2 + len) {
+            Some(p) => p,
+            None => {
+                return Err(format!(
+                    "Frame {} of size {}x{} is truncated",
+                    frames.len(),
+                    width,
+                    height
+                ))
+            }
+        };
+        let mut rgba = Vec::with_capacity(len * 4);
+        for &pixel in pixels {
+            rgba.push((pixel >> 16) as u8);
+            rgba.push((pixel >> 8) as u8);
+            rgba.push((pixel >> 0) as u8);
+            rgba.push((pixel >> 24) as u8);
+        }
+        frames.push(BackendIcon {
+            rgba,
+            width,
+            height,
+        });
+        prop = &prop[2 + len..];
+    }
+    Ok(frames)
+}
@@ -0,0 +1,150 @@
+use crate::args::Bisect;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+/// The verdict of a single bisection step.
+#[derive(Copy, Clone, Debug)]
+enum Verdict {
+    Good,
+    Bad,
+    /// The revision cannot be tested, e.g. because it does not build.
+    Skip,
+}
+
+/// Bisects the winit checkout with the harness as the oracle.
+///
+/// In each step, `cargo run` is invoked for the harness with winit patched to the
+/// checkout. The run passes if the exit status of the harness is 0 and fails if it is 1.
+/// Any other exit status, e.g. because the harness could not be built against the
+/// revision, skips the revision.
+pub fn run(bisect: &Bisect) {
+    let winit = match bisect.winit.canonicalize() {
+        Ok(p) => p,
+        Err(e) => panic!("Could not resolve {}: {}", bisect.winit.display(), e),
+    };
+    log::info!(
+        "Bisecting {} between {} (good) and {} (bad)",
+        winit.display(),
+        bisect.good,
+        bisect.bad
+    );
+    let output = git(&winit, &["bisect", "start", &bisect.bad, &bisect.good]);
+    let _reset = ResetOnDrop(&winit);
+    let mut output = match output {
+        Ok(o) => o,
+        Err(e) => {
+            log::error!("Could not start the bisection: {}", e);
+            return;
+        }
+    };
+    while !output.contains("is the first bad commit") {
+        let rev = match git(&winit, &["rev-parse", "HEAD"]) {
+            Ok(rev) => rev,
+            Err(e) => {
+                log::error!("Could not determine the revision under test: {}", e);
+                return;
+            }
+        };
+        log::info!("Testing winit revision {}", rev.trim());
+        let verdict = verdict(test_revision(&winit, &bisect.args));
+        log::info!("Revision {} is {:?}", rev.trim(), verdict);
+        let verdict = match verdict {
+            Verdict::Good => "good",
+            Verdict::Bad => "bad",
+            Verdict::Skip => "skip",
+        };
+        match git(&winit, &["bisect", verdict]) {
+            Ok(o) => output = o,
+            // git exits with a non-zero status if only skipped revisions are left.
+            Err(e) if e.contains("There are only 'skip'ped commits left to test.") => {
+                output = e;
+                break;
+            }
+            Err(e) => {
+                log::error!(
+                    "Could not mark revision {} as {}: {}",
+                    rev.trim(),
+                    verdict,
+                    e
+                );
+                return;
+            }
+        }
+    }
+    log::info!("Bisection finished:\n{}", output);
+}
+
+/// Resets the bisection of a checkout when dropped.
+///
+/// This restores the revision that was checked out before the bisection even if the
+/// bisection fails.
+struct ResetOnDrop<'a>(&'a Path);
+
+impl Drop for ResetOnDrop<'_> {
+    fn drop(&mut self) {
+        if let Err(e) = git(self.0, &["bisect", "reset"]) {
+            log::error!(
+                "Could not reset the bisection of {}: {}",
+                self.0.display(),
+                e
+            );
+        }
+    }
+}
+
+fn verdict(status: ExitStatus) -> Verdict {
+    match status.code() {
+        Some(0) => Verdict::Good,
+        Some(1) => Verdict::Bad,
+        _ => Verdict::Skip,
+    }
+}
+
+fn test_revision(winit: &Path, args: &[String]) -> ExitStatus {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let cargo = std::env::var_os("CARGO").unwrap_or_else(|| "cargo".into());
+    let mut cmd = Command::new(cargo);
+    // winit is a path dependency. The patch replaces the source of that dependency by
+    // the checkout. Cargo rejects patches that point to the source they patch.
+    let source = manifest_dir.join("../winit");
+    if source.canonicalize().ok().as_deref() != Some(winit) {
+        cmd.arg("--config").arg(format!(
+            "patch.{:?}.winit.path = {:?}",
+            format!("file://{}", source.display()),
+            winit.display().to_string(),
+        ));
+    }
+    let res = cmd
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(manifest_dir.join("Cargo.toml"))
+        .arg("--")
+        .args(args)
+        .status();
+    match res {
+        Ok(s) => s,
+        Err(e) => panic!("Could not run cargo: {}", e),
+    }
+}
+
+/// Runs git in `dir` and returns its stdout.
+///
+/// If git fails, the error contains its stdout and stderr.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let res = Command::new("git").arg("-C").arg(dir).args(args).output();
+    let output = match res {
+        Ok(o) => o,
+        Err(e) => panic!("Could not run git: {}", e),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+    if !output.status.success() {
+        return Err(format!(
+            "git {} failed with {}:\n{}{}",
+            args.join(" "),
+            output.status,
+            stdout,
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(stdout)
+}
@@ -0,0 +1,24 @@
+//! The clock of the harness.
+//!
+//! Injected input and received events are timestamped with the same clock so that tests
+//! can assert on the time between them, e.g. the delay of key repeats. The clock always
+//! runs in real time like winit and the display server.
+
+use std::time::Instant;
+
+pub fn now() -> Instant {
+    tokio::time::Instant::now().into_std()
+}
+
+/// Records that input has been injected into the display server. See `last_input`.
+pub fn input_injected() {
+    let now = now();
+    if crate::test::has_test_data() {
+        crate::test::with_test_data(|td| td.last_input.set(Some(now)));
+    }
+}
+
+/// Returns the time at which input was last injected by the current test.
+pub fn last_input() -> Option<Instant> {
+    crate::test::with_test_data(|td| td.last_input.get())
+}
@@ -0,0 +1,186 @@
+//! The interactive progress view enabled with `--progress`.
+//!
+//! While the view is active, the bottom of the terminal shows a progress bar for each
+//! backend together with the number of passed and failed tests and the names of the
+//! running tests. Log messages that are not written to the log file of a test are printed
+//! above the view. Messages below the warning level are suppressed since the view
+//! replaces them.
+
+use crate::report::Status;
+use colored::Colorize;
+use isnt::std_1::vec::IsntVecExt;
+use parking_lot::Mutex;
+use std::fmt::Write as _;
+use std::io::Write as _;
+
+static CONSOLE: Mutex<Option<Console>> = parking_lot::const_mutex(None);
+
+const BAR_WIDTH: usize = 30;
+
+struct Console {
+    backends: Vec<BackendProgress>,
+    /// The number of lines of the view currently on the terminal.
+    lines: usize,
+}
+
+struct BackendProgress {
+    name: String,
+    total: usize,
+    passed: usize,
+    failed: usize,
+    skipped: usize,
+    running: Vec<String>,
+}
+
+/// Enables the view if stderr is a terminal.
+pub fn enable() {
+    if unsafe { libc::isatty(2) } == 0 {
+        log::warn!("Ignoring --progress because stderr is not a terminal");
+        return;
+    }
+    *CONSOLE.lock() = Some(Console {
+        backends: vec![],
+        lines: 0,
+    });
+}
+
+/// Returns whether the view is active.
+pub fn active() -> bool {
+    CONSOLE.lock().is_some()
+}
+
+/// Stops updating the view. Its final state remains on the terminal.
+pub fn finish() {
+    CONSOLE.lock().take();
+}
+
+/// Prints a line to stderr above the view.
+pub fn eprintln(line: &str) {
+    let mut console = CONSOLE.lock();
+    match &mut *console {
+        Some(c) => {
+            c.clear();
+            eprintln!("{}", line);
+            c.draw();
+        }
+        None => eprintln!("{}", line),
+    }
+}
+
+pub fn start_backend(name: &str, total: usize) {
+    update(|c| {
+        c.backends.push(BackendProgress {
+            name: name.to_string(),
+            total,
+            passed: 0,
+            failed: 0,
+            skipped: 0,
+            running: vec![],
+        })
+    });
+}
+
+pub fn test_started(backend: &str, test: &str) {
+    update(|c| {
+        if let Some(b) = c.backend(backend) {
+            b.running.push(test.to_string());
+        }
+    });
+}
+
+pub fn test_finished(backend: &str, test: &str, status: Status) {
+    update(|c| {
+        if let Some(b) = c.backend(backend) {
+            b.running.retain(|t| t != test);
+            match status {
+                Status::Passed | Status::FlakyPass | Status::ManualVerification => b.passed += 1,
+                Status::Failed | Status::TimedOut | Status::ConnectionLost => b.failed += 1,
+                Status::NotRun => b.skipped += 1,
+            }
+        }
+    });
+}
+
+fn update(f: impl FnOnce(&mut Console)) {
+    if let Some(c) = &mut *CONSOLE.lock() {
+        c.clear();
+        f(c);
+        c.draw();
+    }
+}
+
+impl Console {
+    fn backend(&mut self, name: &str) -> Option<&mut BackendProgress> {
+        self.backends.iter_mut().rev().find(|b| b.name == name)
+    }
+
+    /// Moves the cursor to the first line of the view and erases the view.
+    fn clear(&mut self) {
+        if self.lines > 0 {
+            eprint!("\x1b[{}A\r\x1b[J", self.lines);
+            self.lines = 0;
+        }
+    }
+
+    fn draw(&mut self) {
+        let name_width = self
+            .backends
+            .iter()
+            .map(|b| b.name.len())
+            .max()
+            .unwrap_or(0);
+        let mut s = String::new();
+        for b in &self.backends {
+            let done = b.passed + b.failed + b.skipped;
+            let filled = match b.total {
+                0 => BAR_WIDTH,
+                _ => done * BAR_WIDTH / b.total,
+            };
+            let _ = writeln!(
+                s,
+                "{:width$} [{}{}] {:>4}/{} {} {} {}",
+                b.name,
+                "#".repeat(filled).green(),
+                "-".repeat(BAR_WIDTH - filled),
+                done,
+                b.total,
+                format!("{} passed", b.passed).green(),
+                match b.failed {
+                    0 => "0 failed".normal(),
+                    n => format!("{} failed", n).red().bold(),
+                },
+                format!("{} skipped", b.skipped).dimmed(),
+                width = name_width,
+            );
+            self.lines += 1;
+            if b.running.is_not_empty() {
+                // The line must not wrap or the view could not be erased.
+                let mut running = format!("{:width$} running: ", "", width = name_width);
+                running.push_str(&b.running.join(", "));
+                let _ = writeln!(s, "{}", truncate(&running, term_width()));
+                self.lines += 1;
+            }
+        }
+        let stderr = std::io::stderr();
+        let mut stderr = stderr.lock();
+        let _ = stderr.write_all(s.as_bytes());
+        let _ = stderr.flush();
+    }
+}
+
+fn term_width() -> usize {
+    unsafe {
+        let mut ws: libc::winsize = std::mem::zeroed();
+        match libc::ioctl(2, libc::TIOCGWINSZ, &mut ws) {
+            0 if ws.ws_col > 0 => ws.ws_col as usize,
+            _ => 80,
+        }
+    }
+}
+
+fn truncate(s: &str, width: usize) -> &str {
+    match s.char_indices().nth(width.saturating_sub(1)) {
+        Some((pos, _)) => &s[..pos],
+        None => s,
+    }
+}
@@ -1,4 +1,4 @@
-use parking_lot::ReentrantMutex;
+use parking_lot::{ReentrantMutex, ReentrantMutexGuard};
 use std::ffi::OsString;
 
 static ENV_LOCK: ReentrantMutex<()> = parking_lot::const_reentrant_mutex(());
@@ -14,16 +14,18 @@ pub fn reset_env() {
     }
 }
 
-pub fn set_env(var: &str, val: &str) -> impl Drop {
-    let reset = Reset(var.to_string(), std::env::var_os(var), ENV_LOCK.lock());
+/// Sets an environment variable. The previous value is restored when the returned guard
+/// is dropped.
+pub fn set_env(var: &str, val: &str) -> EnvGuard {
+    let reset = EnvGuard(var.to_string(), std::env::var_os(var), ENV_LOCK.lock());
     log::info!("Setting environment variable {} to {}", var, val);
     std::env::set_var(var, val);
     reset
 }
 
-struct Reset<T>(String, Option<OsString>, T);
+pub struct EnvGuard(String, Option<OsString>, ReentrantMutexGuard<'static, ()>);
 
-impl<T> Drop for Reset<T> {
+impl Drop for EnvGuard {
     fn drop(&mut self) {
         match &self.1 {
             Some(v) => {
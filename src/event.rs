@@ -1,8 +1,10 @@
+use crate::eventrecorder::serde_ext;
+use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::{
     AxisId, ButtonId, DeviceEvent as WDeviceEvent, DeviceId, ElementState, KeyEvent as WKeyEvent,
-    MouseButton, MouseScrollDelta, RawKeyEvent, Touch, TouchPhase,
+    MouseButton, MouseScrollDelta, RawKeyEvent, StartCause, Touch, TouchPhase,
 };
 use winit::event::{Event as WEvent, WindowEvent as WWindowEvent};
 use winit::keyboard;
@@ -11,19 +13,22 @@ use winit::keyboard::ModifiersState;
 use winit::platform::modifier_supplement::KeyEventExtModifierSupplement;
 use winit::window::{Theme, WindowId};
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct UserEvent(pub usize);
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct ModSupplement {
     pub text_with_all_modifiers: Option<String>,
+    #[serde(deserialize_with = "serde_ext::static_key")]
     pub key_without_modifiers: keyboard::Key<'static>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct KeyEvent {
     pub physical_key: keyboard::KeyCode,
+    #[serde(deserialize_with = "serde_ext::static_key")]
     pub logical_key: keyboard::Key<'static>,
+    #[serde(deserialize_with = "serde_ext::static_str")]
     pub text: Option<&'static str>,
     pub location: keyboard::KeyLocation,
     pub state: ElementState,
@@ -32,67 +37,75 @@ pub struct KeyEvent {
     pub mod_supplement: ModSupplement,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowKeyboardInput {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub event: KeyEvent,
     pub is_synthetic: bool,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowCursorMoved {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub position: PhysicalPosition<f64>,
     pub modifiers: ModifiersState,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowCursorEntered {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowCursorLeft {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowMouseWheel {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub delta: MouseScrollDelta,
     pub phase: TouchPhase,
     pub modifiers: ModifiersState,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowMouseInput {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub state: ElementState,
     pub button: MouseButton,
     pub modifiers: ModifiersState,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowTouchpadPressure {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub pressure: f32,
     pub stage: i64,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowAxisMotion {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub axis: AxisId,
     pub value: f64,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowScaleFactorChanged {
     pub scale_factor: f64,
     pub new_inner_size: PhysicalSize<u32>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum WindowEvent {
     Resized(PhysicalSize<u32>),
     Moved(PhysicalPosition<i32>),
@@ -111,60 +124,91 @@ pub enum WindowEvent {
     MouseInput(WindowMouseInput),
     TouchpadPressure(WindowTouchpadPressure),
     AxisMotion(WindowAxisMotion),
-    Touch(Touch),
+    Touch(#[serde(with = "serde_ext::TouchDef")] Touch),
     ScaleFactorChanged(WindowScaleFactorChanged),
     ThemeChanged(Theme),
     ReceivedImeText(String),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct WindowEventExt {
+    #[serde(with = "serde_ext::window_id")]
     pub window_id: WindowId,
     pub event: WindowEvent,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DeviceEventExt {
+    #[serde(with = "serde_ext::device_id")]
     pub device_id: DeviceId,
     pub event: DeviceEvent,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum Event {
     WindowEvent(WindowEventExt),
     DeviceEvent(DeviceEventExt),
     UserEvent(UserEvent),
-    RedrawRequested(WindowId),
+    RedrawRequested(#[serde(with = "serde_ext::window_id")] WindowId),
+    /// The event loop woke up for a reason other than `StartCause::Init`.
+    ///
+    /// The harness runs the winit event loop in many short iterations, each of which
+    /// starts with `StartCause::Init`. These are not reported.
+    NewEvents(#[serde(with = "serde_ext::StartCauseDef")] StartCause),
+    /// See `Instance::suspend`.
+    Suspended,
+    /// Reported when the event loop starts, if winit emits it there, and after each
+    /// `Instance::resume`.
+    ///
+    /// Some winit versions emit `Resumed` after `StartCause::Init` of every iteration of
+    /// the event loop. Only the first of these is reported since the others are artifacts
+    /// of running the event loop in many short iterations.
+    Resumed,
+    /// Only reported if enabled with `EventLoop::report_cleared_events`.
+    MainEventsCleared,
+    /// Only reported if enabled with `EventLoop::report_cleared_events`.
+    RedrawEventsCleared,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+impl Event {
+    /// Returns the window that the event belongs to, if any.
+    pub fn window_id(&self) -> Option<WindowId> {
+        match self {
+            Event::WindowEvent(we) => Some(we.window_id),
+            Event::RedrawRequested(id) => Some(*id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DeviceMouseMotion {
     pub delta: (f64, f64),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DeviceMouseWheel {
     pub delta: MouseScrollDelta,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DeviceMotion {
     pub axis: AxisId,
     pub value: f64,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DeviceButton {
     pub button: ButtonId,
     pub state: ElementState,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct DeviceText {
     pub codepoint: char,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DeviceEvent {
     Added,
     Removed,
@@ -172,7 +216,7 @@ pub enum DeviceEvent {
     MouseWheel(DeviceMouseWheel),
     Motion(DeviceMotion),
     Button(DeviceButton),
-    Key(RawKeyEvent),
+    Key(#[serde(with = "serde_ext::RawKeyEventDef")] RawKeyEvent),
     Text(DeviceText),
 }
 
@@ -301,7 +345,8 @@ pub fn map_window_event(e: WWindowEvent<'_>) -> WindowEvent {
 
 pub fn map_event(e: WEvent<'_, UserEvent>) -> Option<Event> {
     match e {
-        WEvent::NewEvents(_) => None,
+        WEvent::NewEvents(StartCause::Init) => None,
+        WEvent::NewEvents(v) => Some(Event::NewEvents(v)),
         WEvent::WindowEvent { window_id, event } => Some(Event::WindowEvent(WindowEventExt {
             window_id,
             event: map_window_event(event),
@@ -311,11 +356,11 @@ pub fn map_event(e: WEvent<'_, UserEvent>) -> Option<Event> {
             event: map_device_event(event),
         })),
         WEvent::UserEvent(v) => Some(Event::UserEvent(v)),
-        WEvent::Suspended => None,
-        WEvent::Resumed => None,
-        WEvent::MainEventsCleared => None,
+        WEvent::Suspended => Some(Event::Suspended),
+        WEvent::Resumed => Some(Event::Resumed),
+        WEvent::MainEventsCleared => Some(Event::MainEventsCleared),
         WEvent::RedrawRequested(v) => Some(Event::RedrawRequested(v)),
-        WEvent::RedrawEventsCleared => None,
+        WEvent::RedrawEventsCleared => Some(Event::RedrawEventsCleared),
         WEvent::LoopDestroyed => None,
     }
 }
@@ -328,10 +373,13 @@ pub struct KeyEventMatcher {
     pub location: Option<keyboard::KeyLocation>,
     pub state: Option<ElementState>,
     pub repeat: Option<bool>,
+    /// Only checked if winit supports modifier supplements.
+    pub key_without_modifiers: Option<keyboard::Key<'static>>,
+    /// Only checked if winit supports modifier supplements.
+    pub text_with_all_modifiers: Option<Option<&'static str>>,
 }
 
 impl KeyEventMatcher {
-    #[allow(dead_code)]
     pub fn matches(&self, kev: &KeyEvent) -> bool {
         if let Some(pk) = &self.physical_key {
             if pk != &kev.physical_key {
@@ -363,6 +411,49 @@ impl KeyEventMatcher {
                 return false;
             }
         }
+        #[cfg(have_mod_supplement)]
+        {
+            if let Some(lk) = &self.key_without_modifiers {
+                if lk != &kev.mod_supplement.key_without_modifiers {
+                    return false;
+                }
+            }
+            if let Some(lk) = &self.text_with_all_modifiers {
+                if *lk != kev.mod_supplement.text_with_all_modifiers.as_deref() {
+                    return false;
+                }
+            }
+        }
         true
     }
+
+    /// Returns the fields that are checked, e.g. `physical_key: KeyL, state: Pressed`.
+    pub fn describe(&self) -> String {
+        let mut fields = vec![];
+        if let Some(v) = &self.physical_key {
+            fields.push(format!("physical_key: {:?}", v));
+        }
+        if let Some(v) = &self.logical_key {
+            fields.push(format!("logical_key: {:?}", v));
+        }
+        if let Some(v) = &self.text {
+            fields.push(format!("text: {:?}", v));
+        }
+        if let Some(v) = &self.location {
+            fields.push(format!("location: {:?}", v));
+        }
+        if let Some(v) = &self.state {
+            fields.push(format!("state: {:?}", v));
+        }
+        if let Some(v) = &self.repeat {
+            fields.push(format!("repeat: {:?}", v));
+        }
+        if let Some(v) = &self.key_without_modifiers {
+            fields.push(format!("key_without_modifiers: {:?}", v));
+        }
+        if let Some(v) = &self.text_with_all_modifiers {
+            fields.push(format!("text_with_all_modifiers: {:?}", v));
+        }
+        fields.join(", ")
+    }
 }
@@ -0,0 +1,417 @@
+use crate::event::Event;
+use crate::eventstream::EventStream;
+use crate::test::with_test_data;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::{BTreeSet, VecDeque};
+use std::fs::File;
+use std::future::Future;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+use winit::event::DeviceId;
+use winit::window::WindowId;
+
+/// An event as written to a recording file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    /// Milliseconds since the recorder was created.
+    pub time_ms: f64,
+    pub event: Event,
+}
+
+/// Maps winit's window and device ids to the integers stored in recordings and back.
+///
+/// Ids are numbered in the order in which they first appear in a recording. Winit's ids
+/// cannot be created from these integers. When reading a recording, windows and devices
+/// that have not been bound to an id are mapped to `WindowId::from(index)` and
+/// `DeviceId::dummy()` respectively.
+#[derive(Clone)]
+struct Ids {
+    /// The time that instants are stored relative to.
+    start: Instant,
+    windows: Vec<Option<WindowId>>,
+    devices: Vec<Option<DeviceId>>,
+}
+
+thread_local! {
+    static IDS: RefCell<Option<Ids>> = RefCell::new(None);
+}
+
+impl Ids {
+    fn new(start: Instant) -> Self {
+        Self {
+            start,
+            windows: vec![],
+            devices: vec![],
+        }
+    }
+
+    /// Makes the ids available to the serde helpers while `f` runs.
+    fn scope<T>(&mut self, f: impl FnOnce() -> T) -> T {
+        IDS.with(|ids| {
+            let prev = ids.borrow_mut().replace(self.clone());
+            let res = f();
+            *self = ids.borrow_mut().take().unwrap();
+            *ids.borrow_mut() = prev;
+            res
+        })
+    }
+}
+
+fn with_ids<T>(f: impl FnOnce(&mut Ids) -> T) -> T {
+    IDS.with(|ids| match &mut *ids.borrow_mut() {
+        Some(ids) => f(ids),
+        None => panic!("Events can only be (de)serialized by the event recorder"),
+    })
+}
+
+fn index_of<T: PartialEq + Copy>(ids: &mut Vec<Option<T>>, id: T) -> usize {
+    match ids.iter().position(|i| *i == Some(id)) {
+        Some(idx) => idx,
+        None => {
+            ids.push(Some(id));
+            ids.len() - 1
+        }
+    }
+}
+
+fn bind<T>(ids: &mut Vec<Option<T>>, index: usize, id: T) {
+    if ids.len() <= index {
+        ids.resize_with(index + 1, || None);
+    }
+    ids[index] = Some(id);
+}
+
+/// The strings of deserialized events. Winit's key types borrow `'static` strings, so
+/// each distinct string is leaked once and shared by all events that contain it.
+static INTERNED: Mutex<BTreeSet<&'static str>> = parking_lot::const_mutex(BTreeSet::new());
+
+fn intern(s: &str) -> &'static str {
+    let mut interned = INTERNED.lock();
+    match interned.get(s).copied() {
+        Some(s) => s,
+        None => {
+            let s = Box::leak(s.to_string().into_boxed_str());
+            interned.insert(s);
+            s
+        }
+    }
+}
+
+/// Serde helpers for the winit types contained in [`Event`].
+pub(crate) mod serde_ext {
+    use super::{index_of, intern, with_ids};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use std::time::{Duration, Instant};
+    use winit::dpi::PhysicalPosition;
+    use winit::event::{DeviceId, ElementState, Force, RawKeyEvent, StartCause, Touch, TouchPhase};
+    use winit::keyboard::{Key, KeyCode};
+    use winit::window::WindowId;
+
+    pub mod window_id {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(id: &WindowId, s: S) -> Result<S::Ok, S::Error> {
+            with_ids(|ids| index_of(&mut ids.windows, *id)).serialize(s)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<WindowId, D::Error> {
+            let idx = usize::deserialize(d)?;
+            let id = with_ids(|ids| ids.windows.get(idx).copied().flatten());
+            Ok(id.unwrap_or_else(|| WindowId::from(idx as u64)))
+        }
+    }
+
+    pub mod device_id {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(id: &DeviceId, s: S) -> Result<S::Ok, S::Error> {
+            with_ids(|ids| index_of(&mut ids.devices, *id)).serialize(s)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<DeviceId, D::Error> {
+            let idx = usize::deserialize(d)?;
+            let id = with_ids(|ids| ids.devices.get(idx).copied().flatten());
+            Ok(id.unwrap_or_else(|| unsafe { DeviceId::dummy() }))
+        }
+    }
+
+    /// Instants are stored as nanoseconds relative to the start of the recording so that
+    /// they survive a round trip unchanged.
+    mod instant {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(time: &Instant, s: S) -> Result<S::Ok, S::Error> {
+            let start = with_ids(|ids| ids.start);
+            let ns = match time.checked_duration_since(start) {
+                Some(d) => d.as_nanos() as i64,
+                None => -(start.duration_since(*time).as_nanos() as i64),
+            };
+            ns.serialize(s)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Instant, D::Error> {
+            let ns = i64::deserialize(d)?;
+            let start = with_ids(|ids| ids.start);
+            let offset = Duration::from_nanos(ns.unsigned_abs());
+            match ns >= 0 {
+                true => Ok(start + offset),
+                false => Ok(start - offset),
+            }
+        }
+    }
+
+    mod instant_opt {
+        use super::*;
+
+        pub fn serialize<S: Serializer>(time: &Option<Instant>, s: S) -> Result<S::Ok, S::Error> {
+            #[derive(Serialize)]
+            struct Wrapper<'a>(#[serde(with = "super::instant")] &'a Instant);
+            time.as_ref().map(Wrapper).serialize(s)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Instant>, D::Error> {
+            #[derive(Deserialize)]
+            struct Wrapper(#[serde(with = "super::instant")] Instant);
+            Ok(Option::<Wrapper>::deserialize(d)?.map(|w| w.0))
+        }
+    }
+
+    /// Deserializes a key whose characters are interned to obtain a `'static` lifetime.
+    pub fn static_key<'de, D: Deserializer<'de>>(d: D) -> Result<Key<'static>, D::Error> {
+        let value = serde_json::Value::deserialize(d)?;
+        if let Some(c) = value.get("Character").and_then(|c| c.as_str()) {
+            return Ok(Key::Character(intern(c)));
+        }
+        Key::deserialize(value).map_err(D::Error::custom)
+    }
+
+    /// Deserializes a string that is interned to obtain a `'static` lifetime.
+    pub fn static_str<'de, D: Deserializer<'de>>(d: D) -> Result<Option<&'static str>, D::Error> {
+        let s = Option::<String>::deserialize(d)?;
+        Ok(s.map(|s| intern(&s)))
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "Touch")]
+    pub struct TouchDef {
+        #[serde(with = "device_id")]
+        pub device_id: DeviceId,
+        pub phase: TouchPhase,
+        pub location: PhysicalPosition<f64>,
+        pub force: Option<Force>,
+        pub id: u64,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "StartCause")]
+    pub enum StartCauseDef {
+        ResumeTimeReached {
+            #[serde(with = "instant")]
+            start: Instant,
+            #[serde(with = "instant")]
+            requested_resume: Instant,
+        },
+        WaitCancelled {
+            #[serde(with = "instant")]
+            start: Instant,
+            #[serde(with = "instant_opt")]
+            requested_resume: Option<Instant>,
+        },
+        Poll,
+        Init,
+    }
+
+    #[derive(Serialize, Deserialize)]
+    #[serde(remote = "RawKeyEvent")]
+    pub struct RawKeyEventDef {
+        pub physical_key: KeyCode,
+        pub state: ElementState,
+    }
+}
+
+/// Records the events of event streams.
+///
+/// Each event is appended to `<name>.events.jsonl` in the test directory as soon as it
+/// has been received. Window and device ids are stored as integers in the order in which
+/// they first appear. The events are also kept in memory and can be replayed with
+/// `replay`. Recordings can be replayed from the file with [`FileReplay`].
+pub struct EventRecorder {
+    start: Instant,
+    path: PathBuf,
+    file: File,
+    ids: Ids,
+    events: Vec<(Instant, Event)>,
+}
+
+struct Record<'a> {
+    recorder: &'a mut EventRecorder,
+    el: &'a mut dyn EventStream,
+}
+
+impl<'a> EventStream for Record<'a> {
+    fn event<'b>(&'b mut self) -> Pin<Box<dyn Future<Output = Event> + 'b>> {
+        Box::pin(async move { self.timed_event().await.1 })
+    }
+
+    fn timed_event<'b>(&'b mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'b>> {
+        Box::pin(async move {
+            let (time, event) = self.el.timed_event().await;
+            self.recorder.push(time, &event);
+            (time, event)
+        })
+    }
+}
+
+struct Replay<'a> {
+    events: std::slice::Iter<'a, (Instant, Event)>,
+}
+
+impl<'a> EventStream for Replay<'a> {
+    fn event<'b>(&'b mut self) -> Pin<Box<dyn Future<Output = Event> + 'b>> {
+        Box::pin(async move { self.timed_event().await.1 })
+    }
+
+    fn timed_event<'b>(&'b mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'b>> {
+        match self.events.next() {
+            Some(e) => Box::pin(std::future::ready(e.clone())),
+            None => panic!("Recording is exhausted"),
+        }
+    }
+
+    fn has_more(&self) -> bool {
+        self.events.len() > 0
+    }
+}
+
+impl EventRecorder {
+    pub fn new(name: &str) -> Self {
+        let path = with_test_data(|td| td.test_dir.join(format!("{}.events.jsonl", name)));
+        log::info!("Recording events to {}", path.display());
+        let file = match File::create(&path) {
+            Ok(f) => f,
+            Err(e) => panic!("Could not create {}: {}", path.display(), e),
+        };
+        let start = crate::clock::now();
+        Self {
+            start,
+            path,
+            file,
+            ids: Ids::new(start),
+            events: vec![],
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns a stream that forwards the events of `el` and records them.
+    pub fn record<'a>(&'a mut self, el: &'a mut dyn EventStream) -> Box<dyn EventStream + 'a> {
+        Box::new(Record { recorder: self, el })
+    }
+
+    /// Returns a stream that yields the recorded events with their original timestamps.
+    pub fn replay<'a>(&'a self) -> Box<dyn EventStream + 'a> {
+        Box::new(Replay {
+            events: self.events.iter(),
+        })
+    }
+
+    /// Returns a stream that reads the recorded events from the recording file. The ids in
+    /// the file are mapped back to the ids of the recorded windows and devices.
+    pub fn replay_file(&self) -> FileReplay {
+        let mut replay = FileReplay::open(&self.path);
+        replay.ids = self.ids.clone();
+        replay
+    }
+
+    fn push(&mut self, time: Instant, event: &Event) {
+        let recorded = RecordedEvent {
+            time_ms: time.saturating_duration_since(self.start).as_secs_f64() * 1000.0,
+            event: event.clone(),
+        };
+        let mut line = self.ids.scope(|| serde_json::to_string(&recorded)).unwrap();
+        line.push('\n');
+        if let Err(e) = self.file.write_all(line.as_bytes()) {
+            log::warn!("Could not write to {}: {}", self.path.display(), e);
+        }
+        self.events.push((time, recorded.event));
+    }
+}
+
+/// An event stream that replays a recording file written by an [`EventRecorder`].
+///
+/// The timestamps of the events are relative to the time at which the file was opened.
+/// Windows and devices of the recording can be bound to live ids with
+/// [`FileReplay::window`] and [`FileReplay::device`], e.g. to replay a recording through
+/// code that compares window ids.
+pub struct FileReplay {
+    path: PathBuf,
+    ids: Ids,
+    lines: VecDeque<String>,
+}
+
+impl FileReplay {
+    pub fn open(path: &Path) -> Self {
+        let file = match File::open(path) {
+            Ok(f) => f,
+            Err(e) => panic!("Could not open {}: {}", path.display(), e),
+        };
+        let lines = BufReader::new(file)
+            .lines()
+            .map(|line| match line {
+                Ok(l) => l,
+                Err(e) => panic!("Could not read {}: {}", path.display(), e),
+            })
+            .collect();
+        Self {
+            path: path.to_path_buf(),
+            ids: Ids::new(crate::clock::now()),
+            lines,
+        }
+    }
+
+    /// Maps the window with the given index in the recording to `id`.
+    pub fn window(mut self, index: usize, id: WindowId) -> Self {
+        bind(&mut self.ids.windows, index, id);
+        self
+    }
+
+    /// Maps the device with the given index in the recording to `id`.
+    pub fn device(mut self, index: usize, id: DeviceId) -> Self {
+        bind(&mut self.ids.devices, index, id);
+        self
+    }
+
+    fn next(&mut self) -> (Instant, Event) {
+        let line = match self.lines.pop_front() {
+            Some(l) => l,
+            None => panic!("Recording {} is exhausted", self.path.display()),
+        };
+        let recorded: RecordedEvent = match self.ids.scope(|| serde_json::from_str(&line)) {
+            Ok(e) => e,
+            Err(e) => panic!("Invalid line in {}: {}", self.path.display(), e),
+        };
+        let time = self.ids.start + Duration::from_secs_f64(recorded.time_ms / 1000.0);
+        (time, recorded.event)
+    }
+}
+
+impl EventStream for FileReplay {
+    fn event<'b>(&'b mut self) -> Pin<Box<dyn Future<Output = Event> + 'b>> {
+        Box::pin(async move { self.timed_event().await.1 })
+    }
+
+    fn timed_event<'b>(&'b mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'b>> {
+        Box::pin(std::future::ready(self.next()))
+    }
+
+    fn has_more(&self) -> bool {
+        !self.lines.is_empty()
+    }
+}
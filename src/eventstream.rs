@@ -4,22 +4,52 @@ use crate::event::{
     WindowEventExt, WindowKeyboardInput, WindowMouseInput, WindowMouseWheel,
     WindowScaleFactorChanged,
 };
+use crate::expect::Expect;
+use crate::test::{with_test_data, Coalescing};
 use std::future::Future;
 use std::path::PathBuf;
 use std::pin::Pin;
+use std::time::{Duration, Instant};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
-use winit::event::{RawKeyEvent, Touch};
-use winit::keyboard::ModifiersState;
+use winit::event::{ElementState, RawKeyEvent, StartCause, Touch};
+use winit::keyboard::{KeyCode, ModifiersState};
 use winit::window::WindowId;
 
 pub trait EventStream {
     fn event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = Event> + 'a>>;
+    /// Returns the next event together with the time at which the backend received it
+    /// according to `crate::clock`.
+    fn timed_event<'a>(&'a mut self) -> Pin<Box<dyn Future<Output = (Instant, Event)> + 'a>> {
+        Box::pin(async move {
+            let event = self.event().await;
+            (crate::clock::now(), event)
+        })
+    }
     fn has_more(&self) -> bool {
         true
     }
 }
 
 impl<'a> dyn EventStream + 'a {
+    /// Consumes events until all events of `expect` have been received. Returns the
+    /// matched events in the order in which they were added to `expect`.
+    #[tracing::instrument(level = "info", skip_all)]
+    pub async fn expect(&mut self, expect: Expect<'_>) -> Vec<Event> {
+        expect.run(self).await
+    }
+
+    /// Collects events until no event has arrived for `quiet_ms` milliseconds.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn events_until_quiet(&mut self, quiet_ms: u64) -> Vec<Event> {
+        let mut res = vec![];
+        let quiet = Duration::from_millis(quiet_ms);
+        while let Ok(event) = tokio::time::timeout(quiet, self.event()).await {
+            res.push(event);
+        }
+        res
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn user_event(&mut self) -> UserEvent {
         loop {
             if let Event::UserEvent(ue) = self.event().await {
@@ -44,8 +74,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn redraw_requested_event(&mut self) -> WindowId {
-        log::debug!("Awaiting window redraw_requested");
         loop {
             if let Event::RedrawRequested(we) = self.event().await {
                 return we;
@@ -53,8 +83,27 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    /// Waits for a `RedrawRequested` event of `window`.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn window_redraw_requested(&mut self, window: WindowId) {
+        loop {
+            if self.redraw_requested_event().await == window {
+                return;
+            }
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn new_events(&mut self) -> StartCause {
+        loop {
+            if let Event::NewEvents(cause) = self.event().await {
+                return cause;
+            }
+        }
+    }
+
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn device_added_event(&mut self) -> DeviceEventExt {
-        log::info!("Waiting for device added event");
         loop {
             let de = self.device_event().await;
             if de.event == DeviceEvent::Added {
@@ -63,8 +112,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn device_removed_event(&mut self) -> DeviceEventExt {
-        log::info!("Waiting for device removed event");
         loop {
             let de = self.device_event().await;
             if de.event == DeviceEvent::Removed {
@@ -73,8 +122,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn device_mouse_motion_event(&mut self) -> (DeviceEventExt, DeviceMouseMotion) {
-        log::info!("Waiting for device move event");
         loop {
             let de = self.device_event().await;
             if let DeviceEvent::MouseMotion(dm) = &de.event {
@@ -84,8 +133,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn device_mouse_wheel_event(&mut self) -> (DeviceEventExt, DeviceMouseWheel) {
-        log::info!("Waiting for device wheel event");
         loop {
             let de = self.device_event().await;
             if let DeviceEvent::MouseWheel(dm) = &de.event {
@@ -95,8 +144,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn device_key_event(&mut self) -> (DeviceEventExt, RawKeyEvent) {
-        log::info!("Waiting for device key event");
         loop {
             let de = self.device_event().await;
             if let DeviceEvent::Key(e) = de.event {
@@ -106,8 +155,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "info", skip(self))]
     pub async fn device_button_event(&mut self) -> (DeviceEventExt, DeviceButton) {
-        log::info!("Waiting for device button event");
         loop {
             let de = self.device_event().await;
             if let DeviceEvent::Button(e) = &de.event {
@@ -117,8 +166,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_destroyed_event(&mut self) -> WindowEventExt {
-        log::debug!("Awaiting window destroyed");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::Destroyed = &we.event {
@@ -128,8 +177,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_hovered_file(&mut self) -> (WindowEventExt, PathBuf) {
-        log::debug!("Awaiting hovered file");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::HoveredFile(mi) = &we.event {
@@ -139,8 +188,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_hovered_file_canceled(&mut self) -> WindowEventExt {
-        log::debug!("Awaiting hovered file cancelled");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::HoveredFileCancelled = &we.event {
@@ -150,8 +199,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_dropped_file(&mut self) -> (WindowEventExt, PathBuf) {
-        log::debug!("Awaiting dropped file");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::DroppedFile(wi) = &we.event {
@@ -161,8 +210,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_mouse_input_event(&mut self) -> (WindowEventExt, WindowMouseInput) {
-        log::debug!("Awaiting mouse input");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::MouseInput(mi) = &we.event {
@@ -172,8 +221,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_cursor_left(&mut self) -> (WindowEventExt, WindowCursorLeft) {
-        log::debug!("Awaiting cursor left");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::CursorLeft(cl) = &we.event {
@@ -183,10 +232,10 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_scale_factor_changed(
         &mut self,
     ) -> (WindowEventExt, WindowScaleFactorChanged) {
-        log::debug!("Awaiting scale factor changed");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::ScaleFactorChanged(cl) = &we.event {
@@ -196,8 +245,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_cursor_entered(&mut self) -> (WindowEventExt, WindowCursorEntered) {
-        log::debug!("Awaiting cursor entered");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::CursorEntered(cl) = &we.event {
@@ -207,8 +256,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_cursor_moved(&mut self) -> (WindowEventExt, WindowCursorMoved) {
-        log::debug!("Awaiting cursor moved");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::CursorMoved(cl) = &we.event {
@@ -218,8 +267,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_mouse_wheel(&mut self) -> (WindowEventExt, WindowMouseWheel) {
-        log::debug!("Awaiting mouse wheel");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::MouseWheel(cl) = &we.event {
@@ -229,8 +278,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_focus_event(&mut self) -> (WindowEventExt, bool) {
-        log::debug!("Awaiting window focus");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::Focused(v) = &we.event {
@@ -240,8 +289,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_move_event(&mut self) -> (WindowEventExt, PhysicalPosition<i32>) {
-        log::debug!("Awaiting window move");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::Moved(pos) = &we.event {
@@ -251,8 +300,26 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    /// Waits for `window` to be moved to `x`, `y`, subject to the geometry coalescing of the
+    /// test.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn window_moved_to(&mut self, window: WindowId, x: i32, y: i32) -> WindowEventExt {
+        let coalescing = with_test_data(|td| td.geometry_coalescing.get());
+        loop {
+            let (we, pos) = self.window_move_event().await;
+            if coalescing == Coalescing::Exact {
+                assert_eq!(we.window_id, window);
+                assert_eq!((pos.x, pos.y), (x, y));
+            }
+            if we.window_id == window && (pos.x, pos.y) == (x, y) {
+                return we;
+            }
+            log::debug!("Skipping intermediate move to {}x{}", pos.x, pos.y);
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_touch_event(&mut self) -> (WindowEventExt, Touch) {
-        log::debug!("Awaiting window touch");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::Touch(touch) = &we.event {
@@ -262,8 +329,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_resize_event(&mut self) -> (WindowEventExt, PhysicalSize<u32>) {
-        log::debug!("Awaiting window resize");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::Resized(pos) = &we.event {
@@ -273,8 +340,35 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    /// Waits for `window` to be resized to `width`, `height`, subject to the geometry
+    /// coalescing of the test.
+    #[tracing::instrument(level = "info", skip(self))]
+    pub async fn window_resized_to(
+        &mut self,
+        window: WindowId,
+        width: u32,
+        height: u32,
+    ) -> WindowEventExt {
+        let coalescing = with_test_data(|td| td.geometry_coalescing.get());
+        loop {
+            let (we, size) = self.window_resize_event().await;
+            if coalescing == Coalescing::Exact {
+                assert_eq!(we.window_id, window);
+                assert_eq!((size.width, size.height), (width, height));
+            }
+            if we.window_id == window && (size.width, size.height) == (width, height) {
+                return we;
+            }
+            log::debug!(
+                "Skipping intermediate resize to {}x{}",
+                size.width,
+                size.height
+            );
+        }
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_close_requested(&mut self) -> WindowEventExt {
-        log::debug!("Awaiting window delete");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::CloseRequested = &we.event {
@@ -284,8 +378,8 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_keyboard_input(&mut self) -> (WindowEventExt, WindowKeyboardInput) {
-        log::debug!("Awaiting keyboard input");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::KeyboardInput(ki) = &we.event {
@@ -296,8 +390,21 @@ impl<'a> dyn EventStream + 'a {
         }
     }
 
+    /// Awaits the next keyboard input and asserts that it is a repetition of `key`.
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn window_key_repeat(
+        &mut self,
+        key: KeyCode,
+    ) -> (WindowEventExt, WindowKeyboardInput) {
+        let (we, ki) = self.window_keyboard_input().await;
+        assert_eq!(ki.event.physical_key, key);
+        assert_eq!(ki.event.state, ElementState::Pressed);
+        assert!(ki.event.repeat, "Key event is not a repetition: {:?}", ki);
+        (we, ki)
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn window_modifiers(&mut self) -> (WindowEventExt, ModifiersState) {
-        log::debug!("Awaiting window modifiers");
         loop {
             let we = self.window_event().await;
             if let WindowEvent::ModifiersChanged(ki) = &we.event {
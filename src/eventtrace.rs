@@ -0,0 +1,171 @@
+use crate::backend::EventLoop;
+use crate::event::Event;
+use crate::test::with_test_data;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::fs::File;
+use std::path::Path;
+use std::time::Instant;
+
+#[derive(Serialize)]
+struct TracedEvent {
+    /// Milliseconds since the oldest event in the trace.
+    time_ms: f64,
+    kind: String,
+    event: String,
+}
+
+#[derive(Serialize)]
+struct EventLoopTrace {
+    event_loop: usize,
+    events: Vec<TracedEvent>,
+    /// The diff of the expected event kinds and the kinds of the events received since
+    /// they were set. See `set_expected_events`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    diff: Option<String>,
+}
+
+/// Writes the event trace of the current test into its directory unless it has already
+/// been written.
+///
+/// The panic hook calls this so that the trace shows the events at the time of the panic.
+/// Otherwise the runner calls it after a failed test has completed.
+pub fn dump_test() {
+    with_test_data(|td| {
+        if td.events_dumped.get() {
+            return;
+        }
+        // The panic might have happened while the test was modifying these.
+        let (event_loops, expected) =
+            match (td.event_loops.try_borrow(), td.expected_events.try_borrow()) {
+                (Ok(el), Ok(ex)) => (el, ex),
+                _ => {
+                    log::warn!("Could not write the event trace at this point");
+                    return;
+                }
+            };
+        td.events_dumped.set(true);
+        dump(&event_loops, expected.as_ref(), &td.test_dir);
+    })
+}
+
+/// Writes the recent events of all event loops to `events.json` in `dir` and logs them.
+/// If `expected` is set, the events received after the given time are diffed against the
+/// expected event kinds and the diff is included in the trace.
+pub fn dump(
+    event_loops: &[Box<dyn EventLoop>],
+    expected: Option<&(Instant, Vec<String>)>,
+    dir: &Path,
+) {
+    let recent: Vec<_> = event_loops.iter().map(|el| el.recent_events()).collect();
+    let start = recent
+        .iter()
+        .flatten()
+        .map(|(time, _)| *time)
+        .min()
+        .unwrap_or_else(crate::clock::now);
+    let traces: Vec<_> = recent
+        .iter()
+        .enumerate()
+        .map(|(idx, events)| EventLoopTrace {
+            event_loop: idx,
+            events: events
+                .iter()
+                .map(|(time, event)| TracedEvent {
+                    time_ms: ms_since(start, *time),
+                    kind: event_kind(event),
+                    event: format!("{:?}", event),
+                })
+                .collect(),
+            diff: expected.map(|(since, expected)| {
+                let actual: Vec<_> = events
+                    .iter()
+                    .filter(|(time, _)| time >= since)
+                    .map(|(_, event)| event_kind(event))
+                    .collect();
+                diff(expected, &actual)
+            }),
+        })
+        .collect();
+    let path = dir.join("events.json");
+    match File::create(&path) {
+        Ok(file) => {
+            if let Err(e) = serde_json::to_writer_pretty(file, &traces) {
+                log::warn!("Could not write {}: {}", path.display(), e);
+            }
+        }
+        Err(e) => log::warn!("Could not create {}: {}", path.display(), e),
+    }
+    for trace in &traces {
+        let mut msg = format!("Recent events of event loop {}:", trace.event_loop);
+        for event in &trace.events {
+            let _ = write!(msg, "\n  {:10.3} ms: {}", event.time_ms, event.event);
+        }
+        log::info!("{}", msg);
+        if let Some(diff) = &trace.diff {
+            log::info!(
+                "Expected events vs. recent events of event loop {}:\n{}",
+                trace.event_loop,
+                diff
+            );
+        }
+    }
+}
+
+fn ms_since(start: Instant, time: Instant) -> f64 {
+    time.saturating_duration_since(start).as_secs_f64() * 1000.0
+}
+
+/// Returns the name of the variant of the event, e.g. `Resized` or `DeviceAdded`.
+pub(crate) fn event_kind(event: &Event) -> String {
+    fn variant(debug: String) -> String {
+        let end = debug
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .unwrap_or(debug.len());
+        debug[..end].to_string()
+    }
+    match event {
+        Event::WindowEvent(we) => variant(format!("{:?}", we.event)),
+        Event::DeviceEvent(de) => format!("Device{}", variant(format!("{:?}", de.event))),
+        Event::UserEvent(_) => "UserEvent".to_string(),
+        Event::RedrawRequested(_) => "RedrawRequested".to_string(),
+        Event::NewEvents(cause) => format!("NewEvents{}", variant(format!("{:?}", cause))),
+        Event::Suspended => "Suspended".to_string(),
+        Event::Resumed => "Resumed".to_string(),
+        Event::MainEventsCleared => "MainEventsCleared".to_string(),
+        Event::RedrawEventsCleared => "RedrawEventsCleared".to_string(),
+    }
+}
+
+/// Returns a line-based diff of the sequences. Lines only in `expected` are prefixed with
+/// `-`, lines only in `actual` with `+`.
+fn diff(expected: &[String], actual: &[String]) -> String {
+    // lcs[i][j] is the length of the longest common subsequence of expected[i..] and
+    // actual[j..].
+    let mut lcs = vec![vec![0; actual.len() + 1]; expected.len() + 1];
+    for i in (0..expected.len()).rev() {
+        for j in (0..actual.len()).rev() {
+            lcs[i][j] = if expected[i] == actual[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let mut res = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < expected.len() || j < actual.len() {
+        if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+            let _ = writeln!(res, "  {}", expected[i]);
+            i += 1;
+            j += 1;
+        } else if j < actual.len() && (i == expected.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            let _ = writeln!(res, "+ {}", actual[j]);
+            j += 1;
+        } else {
+            let _ = writeln!(res, "- {}", expected[i]);
+            i += 1;
+        }
+    }
+    res
+}
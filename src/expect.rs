@@ -0,0 +1,280 @@
+//! Declarative expectations about sequences of events.
+//!
+//! ```ignore
+//! s.events
+//!     .expect(
+//!         Expect::ordered()
+//!             .modifiers(ModifiersState::SHIFT)
+//!             .key(KeyEventMatcher {
+//!                 physical_key: Some(KeyCode::KeyL),
+//!                 state: Some(ElementState::Pressed),
+//!                 ..Default::default()
+//!             })
+//!             .within(Duration::from_millis(500)),
+//!     )
+//!     .await;
+//!
+//! let events = expect_events!(s.events, unordered [
+//!     Event::WindowEvent(WindowEventExt { event: WindowEvent::Focused(true), .. }),
+//!     Event::WindowEvent(WindowEventExt { event: WindowEvent::Resized(size), .. })
+//!         if size.width == 100 => Duration::from_secs(1),
+//! ]);
+//! ```
+
+use crate::event::{Event, KeyEventMatcher, WindowEvent};
+use crate::eventstream::EventStream;
+use std::fmt::Write as _;
+use std::time::Duration;
+use tokio::time::Instant;
+use winit::keyboard::ModifiersState;
+
+/// The time to wait for each expected event unless overridden.
+pub const DEFAULT_EVENT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Builds an [`Expect`] from patterns over [`Event`] and consumes the events from the
+/// event stream. Evaluates to the matched events in the order of the patterns.
+///
+/// Each pattern can be followed by a guard (`if ...`) and a timeout (`=> duration`).
+/// The events are expected in order unless the list is prefixed with `unordered`.
+#[macro_export]
+macro_rules! expect_events {
+    (@build $events:expr, $expect:expr, $($p:pat $(if $g:expr)? $(=> $t:expr)?),* $(,)?) => {
+        $events
+            .expect(
+                $expect
+                $(
+                    .event(
+                        stringify!($p $(if $g)?),
+                        |e: &$crate::event::Event| matches!(e, $p $(if $g)?),
+                    )
+                    $(.within($t))?
+                )*
+            )
+            .await
+    };
+    ($events:expr, unordered [$($tt:tt)*]) => {
+        $crate::expect_events!(@build $events, $crate::expect::Expect::unordered(), $($tt)*)
+    };
+    ($events:expr, [$($tt:tt)*]) => {
+        $crate::expect_events!(@build $events, $crate::expect::Expect::ordered(), $($tt)*)
+    };
+}
+
+struct Expected<'a> {
+    description: String,
+    timeout: Duration,
+    matches: Box<dyn Fn(&Event) -> bool + 'a>,
+}
+
+enum Received {
+    Matched(usize, Event),
+    Skipped(Event),
+    Unexpected(Event),
+}
+
+/// A list of expected events that is consumed from an event stream with
+/// `EventStream::expect`.
+///
+/// Events that match none of the outstanding expectations are skipped unless they pass
+/// the filter set with [`Expect::strict`]. In ordered mode, an event that matches an
+/// expectation other than the next one is an error. If an expected event does not arrive
+/// within its timeout or an unexpected event arrives, the test fails with a diff of the
+/// expected and the received events.
+pub struct Expect<'a> {
+    ordered: bool,
+    timeout: Duration,
+    expected: Vec<Expected<'a>>,
+    strict: Option<Box<dyn Fn(&Event) -> bool + 'a>>,
+}
+
+impl<'a> Expect<'a> {
+    /// Expects the events in the order in which they are added.
+    pub fn ordered() -> Self {
+        Self::new(true)
+    }
+
+    /// Expects the events in any order.
+    pub fn unordered() -> Self {
+        Self::new(false)
+    }
+
+    fn new(ordered: bool) -> Self {
+        Self {
+            ordered,
+            timeout: DEFAULT_EVENT_TIMEOUT,
+            expected: vec![],
+            strict: None,
+        }
+    }
+
+    /// Sets the timeout of the events added after this call.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout of the most recently added event.
+    ///
+    /// In ordered mode, the timeout starts when the previous event has been matched. In
+    /// unordered mode, it starts when the events start being consumed.
+    pub fn within(mut self, timeout: Duration) -> Self {
+        match self.expected.last_mut() {
+            Some(e) => e.timeout = timeout,
+            None => panic!("within must be called after an event has been added"),
+        }
+        self
+    }
+
+    /// Fails on events that pass `filter` but do not match the next expectation.
+    pub fn strict(mut self, filter: impl Fn(&Event) -> bool + 'a) -> Self {
+        self.strict = Some(Box::new(filter));
+        self
+    }
+
+    /// Expects an event matching the predicate. `description` is used in diffs.
+    pub fn event(
+        mut self,
+        description: impl Into<String>,
+        matches: impl Fn(&Event) -> bool + 'a,
+    ) -> Self {
+        self.expected.push(Expected {
+            description: description.into(),
+            timeout: self.timeout,
+            matches: Box::new(matches),
+        });
+        self
+    }
+
+    /// Expects a window event of any window matching the predicate.
+    pub fn window_event(
+        self,
+        description: impl Into<String>,
+        matches: impl Fn(&WindowEvent) -> bool + 'a,
+    ) -> Self {
+        self.event(description, move |e| match e {
+            Event::WindowEvent(we) => matches(&we.event),
+            _ => false,
+        })
+    }
+
+    /// Expects a keyboard input event matching `matcher`.
+    pub fn key(self, matcher: KeyEventMatcher) -> Self {
+        let description = format!("KeyboardInput {}", matcher.describe());
+        self.window_event(
+            description,
+            move |e| matches!(e, WindowEvent::KeyboardInput(ki) if matcher.matches(&ki.event)),
+        )
+    }
+
+    /// Expects a `ModifiersChanged` event with the given modifiers.
+    pub fn modifiers(self, modifiers: ModifiersState) -> Self {
+        self.window_event(format!("ModifiersChanged({:?})", modifiers), move |e| {
+            *e == WindowEvent::ModifiersChanged(modifiers)
+        })
+    }
+
+    pub(crate) async fn run(self, events: &mut dyn EventStream) -> Vec<Event> {
+        let mut matched: Vec<Option<Event>> = vec![None; self.expected.len()];
+        let mut received = vec![];
+        let start = Instant::now();
+        let mut deadline = self.deadline(start, &matched);
+        while let Some((deadline, idx)) = deadline {
+            let event = match tokio::time::timeout_at(deadline, events.event()).await {
+                Ok(e) => e,
+                Err(_) => {
+                    let next = &self.expected[idx];
+                    self.fail(
+                        &format!(
+                            "Timed out after {:?} waiting for {}",
+                            next.timeout, next.description
+                        ),
+                        &received,
+                        &matched,
+                    );
+                }
+            };
+            let outstanding = self.outstanding(&matched);
+            let candidates = match self.ordered {
+                true => &outstanding[..1],
+                false => &outstanding[..],
+            };
+            if let Some(&idx) = candidates
+                .iter()
+                .find(|&&idx| (self.expected[idx].matches)(&event))
+            {
+                log::debug!(
+                    "Got expected event {}: {:?}",
+                    self.expected[idx].description,
+                    event
+                );
+                matched[idx] = Some(event.clone());
+                received.push(Received::Matched(idx, event));
+            } else {
+                let out_of_order = outstanding
+                    .iter()
+                    .any(|&idx| (self.expected[idx].matches)(&event));
+                let strict = self.strict.as_ref().map(|f| f(&event)).unwrap_or(false);
+                if out_of_order || strict {
+                    received.push(Received::Unexpected(event));
+                    self.fail("Received an unexpected event", &received, &matched);
+                }
+                received.push(Received::Skipped(event));
+                continue;
+            }
+            deadline = self.deadline(start, &matched);
+        }
+        matched.into_iter().map(|e| e.unwrap()).collect()
+    }
+
+    /// Returns the indices of the expectations that have not yet been matched.
+    fn outstanding(&self, matched: &[Option<Event>]) -> Vec<usize> {
+        (0..self.expected.len())
+            .filter(|&idx| matched[idx].is_none())
+            .collect()
+    }
+
+    /// Returns the time until which the next expected event must arrive and the index of
+    /// the expectation whose timeout expires at that time. `start` is the time at which
+    /// `run` started.
+    fn deadline(&self, start: Instant, matched: &[Option<Event>]) -> Option<(Instant, usize)> {
+        let outstanding = self.outstanding(matched);
+        match self.ordered {
+            true => {
+                let idx = *outstanding.first()?;
+                Some((Instant::now() + self.expected[idx].timeout, idx))
+            }
+            false => outstanding
+                .iter()
+                .map(|&idx| (start + self.expected[idx].timeout, idx))
+                .min(),
+        }
+    }
+
+    fn fail(&self, reason: &str, received: &[Received], matched: &[Option<Event>]) -> ! {
+        let mut diff = String::new();
+        for r in received {
+            let _ = match r {
+                Received::Matched(idx, event) => writeln!(
+                    diff,
+                    "  {}\n        {:?}",
+                    self.expected[*idx].description, event
+                ),
+                Received::Skipped(event) => writeln!(diff, "  ({:?})", event),
+                Received::Unexpected(event) => writeln!(diff, "+ {:?}", event),
+            };
+        }
+        for idx in self.outstanding(matched) {
+            let _ = writeln!(diff, "- {}", self.expected[idx].description);
+        }
+        panic!(
+            "{}\nExpected vs. received events ({}). Missing events are prefixed with `-`, \
+             unexpected events with `+`, skipped events are in parentheses:\n{}",
+            reason,
+            match self.ordered {
+                true => "ordered",
+                false => "unordered",
+            },
+            diff
+        );
+    }
+}
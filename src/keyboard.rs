@@ -20,6 +20,8 @@ pub enum Key {
     KeyC,
     KeyCapslock,
     KeyComma,
+    /// Mapped to `Multi_key` in all layouts.
+    KeyCompose,
     KeyD,
     KeyDelete,
     KeyDot,
@@ -114,4 +116,178 @@ pub enum Layout {
     Azerty,
     /// Qwerty with Left/Right shift swapped and Esc/Capslock swapped.
     QwertySwapped,
+    /// US international. Apostrophe, grave, and Shift+6 are dead keys.
+    UsIntl,
+}
+
+/// The modifier that has to be held while a key is pressed to produce a character.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Level {
+    Base,
+    Shift,
+    /// AltGr, i.e. `ISO_Level3_Shift`.
+    AltGr,
+}
+
+/// The characters produced by keys on the base, shift, and AltGr levels.
+const QWERTY_CHARS: &[(Key, &str)] = &[
+    (Key::Key1, "1!"),
+    (Key::Key2, "2@"),
+    (Key::Key3, "3#"),
+    (Key::Key4, "4$"),
+    (Key::Key5, "5%"),
+    (Key::Key6, "6^"),
+    (Key::Key7, "7&"),
+    (Key::Key8, "8*"),
+    (Key::Key9, "9("),
+    (Key::Key0, "0)"),
+    (Key::KeyMinus, "-_"),
+    (Key::KeyEqual, "=+"),
+    (Key::KeyQ, "qQ"),
+    (Key::KeyW, "wW"),
+    (Key::KeyE, "eE"),
+    (Key::KeyLeftbrace, "[{"),
+    (Key::KeyRightbrace, "]}"),
+    (Key::KeyA, "aA"),
+    (Key::KeySemicolon, ";:"),
+    (Key::KeyApostrophe, "'\""),
+    (Key::KeyGrave, "`~"),
+    (Key::KeyBackslash, "\\|"),
+    (Key::KeyZ, "zZ"),
+    (Key::KeyM, "mM"),
+    (Key::KeyComma, ",<"),
+    (Key::KeyDot, ".>"),
+    (Key::KeySlash, "/?"),
+];
+
+const AZERTY_CHARS: &[(Key, &str)] = &[
+    (Key::Key1, "&1"),
+    (Key::Key2, "é2~"),
+    (Key::Key3, "\"3#"),
+    (Key::Key4, "'4{"),
+    (Key::Key5, "(5["),
+    (Key::Key6, "-6|"),
+    (Key::Key7, "è7`"),
+    (Key::Key8, "_8\\"),
+    (Key::Key9, "ç9^"),
+    (Key::Key0, "à0@"),
+    (Key::KeyMinus, ")°]"),
+    (Key::KeyEqual, "=+}"),
+    (Key::KeyQ, "aA"),
+    (Key::KeyW, "zZ"),
+    (Key::KeyE, "eE€"),
+    (Key::KeyRightbrace, "$£¤"),
+    (Key::KeyA, "qQ"),
+    (Key::KeySemicolon, "mM"),
+    (Key::KeyApostrophe, "ù%"),
+    (Key::KeyGrave, "²"),
+    (Key::KeyBackslash, "*µ"),
+    (Key::KeyZ, "wW"),
+    (Key::KeyM, ",?"),
+    (Key::KeyComma, ";."),
+    (Key::KeyDot, ":/"),
+    (Key::KeySlash, "!§"),
+];
+
+/// Dead keys do not produce characters and are not listed.
+const US_INTL_CHARS: &[(Key, &str)] = &[
+    (Key::Key1, "1!"),
+    (Key::Key2, "2@"),
+    (Key::Key3, "3#"),
+    (Key::Key4, "4$"),
+    (Key::Key5, "5%"),
+    (Key::Key6, "6"),
+    (Key::Key7, "7&"),
+    (Key::Key8, "8*"),
+    (Key::Key9, "9("),
+    (Key::Key0, "0)"),
+    (Key::KeyMinus, "-_"),
+    (Key::KeyEqual, "=+"),
+    (Key::KeyQ, "qQ"),
+    (Key::KeyW, "wW"),
+    (Key::KeyE, "eEé"),
+    (Key::KeyLeftbrace, "[{"),
+    (Key::KeyRightbrace, "]}"),
+    (Key::KeyA, "aA"),
+    (Key::KeySemicolon, ";:"),
+    (Key::KeyBackslash, "\\|"),
+    (Key::KeyZ, "zZ"),
+    (Key::KeyM, "mM"),
+    (Key::KeyComma, ",<"),
+    (Key::KeyDot, ".>"),
+    (Key::KeySlash, "/?"),
+];
+
+/// Keys that produce the same characters in all layouts.
+const COMMON_CHARS: &[(Key, &str)] = &[
+    (Key::KeyR, "rR"),
+    (Key::KeyT, "tT"),
+    (Key::KeyY, "yY"),
+    (Key::KeyU, "uU"),
+    (Key::KeyI, "iI"),
+    (Key::KeyO, "oO"),
+    (Key::KeyP, "pP"),
+    (Key::KeyS, "sS"),
+    (Key::KeyD, "dD"),
+    (Key::KeyF, "fF"),
+    (Key::KeyG, "gG"),
+    (Key::KeyH, "hH"),
+    (Key::KeyJ, "jJ"),
+    (Key::KeyK, "kK"),
+    (Key::KeyL, "lL"),
+    (Key::KeyX, "xX"),
+    (Key::KeyC, "cC"),
+    (Key::KeyV, "vV"),
+    (Key::KeyB, "bB"),
+    (Key::KeyN, "nN"),
+    (Key::KeySpace, " "),
+    (Key::KeyTab, "\t"),
+    (Key::KeyEnter, "\n"),
+];
+
+/// The Compose sequences of the file returned by `compose_file`. Each entry contains the
+/// composed character, the name of its keysym, and the two characters that are typed
+/// after the Compose key together with the names of their keysyms.
+const COMPOSE_SEQUENCES: &[(char, &str, [(char, &str); 2])] = &[
+    ('é', "eacute", [('\'', "apostrophe"), ('e', "e")]),
+    ('è', "egrave", [('`', "grave"), ('e', "e")]),
+    ('ç', "ccedilla", [(',', "comma"), ('c', "c")]),
+    ('ñ', "ntilde", [('~', "asciitilde"), ('n', "n")]),
+];
+
+/// Returns the characters that are typed after the Compose key to produce `c` with the
+/// table returned by `compose_file`.
+pub fn compose_sequence(c: char) -> Option<[char; 2]> {
+    COMPOSE_SEQUENCES
+        .iter()
+        .find(|(composed, ..)| *composed == c)
+        .map(|(_, _, [(c1, _), (c2, _)])| [*c1, *c2])
+}
+
+/// Returns a Compose table in the format of libX11 that contains the sequences used by
+/// `Keyboard::compose`. Clients load it if `XCOMPOSEFILE` points to it.
+///
+/// The results are given as keysyms so that they do not depend on the locale.
+pub fn compose_file() -> String {
+    let mut file = String::new();
+    for (_, keysym, [(_, k1), (_, k2)]) in COMPOSE_SEQUENCES {
+        file.push_str(&format!("<Multi_key> <{}> <{}> : {}\n", k1, k2, keysym));
+    }
+    file
+}
+
+impl Layout {
+    /// Returns the key and level that produce `c` in this layout.
+    pub fn key_for_char(self, c: char) -> Option<(Key, Level)> {
+        let chars = match self {
+            Layout::Qwerty | Layout::QwertySwapped => QWERTY_CHARS,
+            Layout::Azerty => AZERTY_CHARS,
+            Layout::UsIntl => US_INTL_CHARS,
+        };
+        let levels = [Level::Base, Level::Shift, Level::AltGr];
+        chars.iter().chain(COMMON_CHARS).find_map(|(key, chars)| {
+            let level = chars.chars().position(|k| k == c)?;
+            Some((*key, levels[level]))
+        })
+    }
 }
@@ -0,0 +1,137 @@
+//! Integration tests for winit.
+//!
+//! The binary of this crate runs the tests in [`tests`]. Forks of winit can depend on this
+//! crate and pass additional tests to [`run`]:
+//!
+//! ```ignore
+//! mod my_test {
+//!     use winit_it::backend::Instance;
+//!
+//!     winit_it::test!(run);
+//!
+//!     async fn run(instance: &dyn Instance) {
+//!         let el = instance.create_event_loop();
+//!         let window = el.create_window(Default::default());
+//!         window.mapped(true).await;
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let mut tests = winit_it::tests::tests();
+//!     tests.push(Box::new(my_test::Test));
+//!     winit_it::run(tests);
+//! }
+//! ```
+
+use crate::report::Status;
+use crate::runner::Execution;
+use crate::tests::Test;
+use rayon::ThreadPoolBuilder;
+
+pub use event::{DeviceEvent, Event, UserEvent, WindowEvent};
+pub use eventstream::EventStream;
+pub use keyboard::{Key, Layout};
+
+mod args;
+pub mod backend;
+pub mod backends;
+mod bisect;
+pub mod clock;
+mod console;
+mod env;
+pub mod event;
+pub mod eventrecorder;
+mod eventstash;
+pub mod eventstream;
+mod eventtrace;
+#[macro_use]
+pub mod expect;
+pub mod keyboard;
+mod notify;
+pub mod report;
+pub mod runner;
+pub mod screenshot;
+#[macro_use]
+pub mod setup;
+pub mod sleep;
+mod task;
+pub mod test;
+mod testrun;
+pub mod tests;
+mod tlog;
+mod trend;
+
+/// Parses the command line and runs `tests` on all backends.
+pub fn run(tests: Vec<Box<dyn Test>>) {
+    let args = args::parse();
+    env::reset_env();
+    tlog::init();
+    if let Some(bisect) = &args.bisect {
+        bisect::run(bisect);
+        return;
+    }
+    if let Some(dirs) = &args.trend {
+        trend::run(dirs);
+        return;
+    }
+    if args.setup {
+        backends::setup();
+        return;
+    }
+    ThreadPoolBuilder::new()
+        .thread_name(|i| format!("rayon-{}", i))
+        .build_global()
+        .unwrap();
+    let selected = |name: &str| match args.backends.is_empty() {
+        true => backends::runs_by_default(name),
+        false => args.backends.iter().any(|n| n == name),
+    };
+    let backends: Vec<_> = backends::backends(&selected, &args.backend_libs)
+        .into_iter()
+        .filter(|b| selected(b.name()))
+        .collect();
+    for name in &args.backends {
+        if !backends.iter().any(|b| b.name() == name) {
+            eprintln!("Unknown backend: {}", name);
+            std::process::exit(2);
+        }
+    }
+    let tests: Vec<_> = tests
+        .into_iter()
+        .filter(|t| args.tests.matches(t.name()) && t.flags().contains(args.flags))
+        .collect();
+    if tests.is_empty() {
+        log::warn!("No tests match the given patterns");
+    }
+    let testrun_dir = testrun::create_testrun_dir();
+    let exec = Execution {
+        dir: testrun_dir,
+        timeout: args.timeout,
+        fail_on_trailing_events: args.fail_on_trailing_events,
+        retries: args.retries,
+        keep_failed_alive: args.keep_failed_alive,
+        junit: args.junit,
+    };
+    if args.progress {
+        console::enable();
+    }
+    let mut reports = vec![];
+    for backend in &backends {
+        reports.extend(runner::run_tests(&exec, &**backend, &tests));
+    }
+    console::finish();
+    runner::wait_for_detached_servers();
+    report::write(&exec.dir, &reports, runner::peak_rss_kb());
+    if let Some(url) = &args.webhook {
+        notify::post(url, &exec.dir, &reports);
+    }
+    let failed = reports.iter().any(|r| {
+        matches!(
+            r.status,
+            Status::Failed | Status::TimedOut | Status::ConnectionLost
+        )
+    });
+    if failed {
+        std::process::exit(1);
+    }
+}
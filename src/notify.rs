@@ -0,0 +1,131 @@
+use crate::report::{Status, TestReport};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[derive(Serialize)]
+struct Notification<'a> {
+    /// A human-readable summary. Slack incoming webhooks and Matrix hookshot webhooks
+    /// display this field.
+    text: String,
+    record_dir: &'a Path,
+    passed: usize,
+    failed: Vec<FailedTest<'a>>,
+}
+
+#[derive(Serialize)]
+struct FailedTest<'a> {
+    backend: &'a str,
+    test: &'a str,
+    status: Status,
+    message: Option<&'a str>,
+}
+
+/// Posts a summary of the run to the webhook at `url`.
+///
+/// The request is sent with curl. Failures are logged but do not affect the exit status.
+pub fn post(url: &str, dir: &Path, reports: &[TestReport]) {
+    let failed: Vec<_> = reports
+        .iter()
+        .filter(|r| {
+            matches!(
+                r.status,
+                Status::Failed | Status::TimedOut | Status::ConnectionLost
+            )
+        })
+        .map(|r| FailedTest {
+            backend: &r.backend,
+            test: &r.test,
+            status: r.status,
+            message: r.message.as_deref(),
+        })
+        .collect();
+    let passed = reports
+        .iter()
+        .filter(|r| matches!(r.status, Status::Passed | Status::FlakyPass))
+        .count();
+    let mut text = format!(
+        "winit-it: {} passed, {} failed\nRecords: {}",
+        passed,
+        failed.len(),
+        dir.display()
+    );
+    for test in &failed {
+        let _ = write!(
+            text,
+            "\n{}/{}: {}",
+            test.backend,
+            test.test,
+            test.status.name()
+        );
+    }
+    let notification = Notification {
+        text,
+        record_dir: dir,
+        passed,
+        failed,
+    };
+    let body = serde_json::to_string(&notification).unwrap();
+    // Webhook URLs contain secrets. They are passed via a config file on stdin so that they
+    // do not appear in the argument list of curl.
+    let config = format!(
+        "url = \"{}\"\ndata-binary = \"{}\"\n",
+        config_escape(url),
+        config_escape(&body)
+    );
+    log::info!("Posting the summary to {}", redact(url));
+    let child = Command::new("curl")
+        .args(["--silent", "--show-error", "--fail", "--max-time", "30"])
+        .args(["--header", "Content-Type: application/json"])
+        .args(["--config", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            log::error!("Could not run curl: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = child.stdin.take().unwrap().write_all(config.as_bytes()) {
+        log::error!("Could not send the summary to curl: {}", e);
+    }
+    match child.wait() {
+        Ok(s) if s.success() => {}
+        Ok(s) => log::error!("Could not post the summary: curl exited with {}", s),
+        Err(e) => log::error!("Could not wait for curl: {}", e),
+    }
+}
+
+/// Escapes `s` for use in a double-quoted parameter of a curl config file.
+fn config_escape(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => res.push_str("\\\\"),
+            '"' => res.push_str("\\\""),
+            '\n' => res.push_str("\\n"),
+            '\r' => res.push_str("\\r"),
+            '\t' => res.push_str("\\t"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+/// Returns the scheme and host of `url` without credentials, path, or query.
+fn redact(url: &str) -> String {
+    let (scheme, rest) = match url.split_once("://") {
+        Some((scheme, rest)) => (scheme, rest),
+        _ => return "<webhook>".to_string(),
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = match authority.rsplit_once('@') {
+        Some((_, host)) => host,
+        _ => authority,
+    };
+    format!("{}://{}/...", scheme, host)
+}
@@ -0,0 +1,313 @@
+use isnt::std_1::vec::IsntVecExt;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Passed,
+    /// The test passed after having failed at least once.
+    FlakyPass,
+    Failed,
+    TimedOut,
+    ConnectionLost,
+    ManualVerification,
+    NotRun,
+}
+
+impl Status {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Status::Passed => "passed",
+            Status::FlakyPass => "flaky pass",
+            Status::Failed => "failed",
+            Status::TimedOut => "timed out",
+            Status::ConnectionLost => "connection lost",
+            Status::ManualVerification => "manual verification",
+            Status::NotRun => "not run",
+        }
+    }
+
+    pub(crate) fn color(self) -> &'static str {
+        match self {
+            Status::Passed => "#c8f7c5",
+            Status::Failed | Status::TimedOut | Status::ConnectionLost => "#f7c5c5",
+            Status::FlakyPass | Status::ManualVerification => "#f7efc5",
+            Status::NotRun => "#e0e0e0",
+        }
+    }
+}
+
+/// The result of a single test on a single backend.
+///
+/// Paths are relative to the testrun directory.
+#[derive(Clone, Debug, Serialize)]
+pub struct TestReport {
+    pub backend: String,
+    pub test: String,
+    pub status: Status,
+    pub duration_ms: u64,
+    pub message: Option<String>,
+    pub log: Option<PathBuf>,
+    /// The logs of the failed attempts that preceded the reported one.
+    pub failed_attempt_logs: Vec<PathBuf>,
+    pub screenshots: Vec<PathBuf>,
+    pub resources: ResourceUsage,
+    /// The display server the test ran against.
+    pub server: Option<ServerInfo>,
+    /// The event queues of the event loops created by the test.
+    pub event_queues: Vec<EventQueueStats>,
+}
+
+/// The resources used while running a test.
+#[derive(Copy, Clone, Debug, Default, Serialize)]
+pub struct ResourceUsage {
+    /// CPU time of the thread that ran the test, including winit.
+    pub cpu_time_ms: u64,
+    /// CPU time of the display server.
+    pub server_cpu_time_ms: Option<u64>,
+    /// Peak resident set size of the display server of the test.
+    pub server_peak_rss_kb: Option<u64>,
+}
+
+/// Statistics of the queue of events that winit has delivered to an event loop but that
+/// the test has not yet consumed.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct EventQueueStats {
+    /// The largest number of events that were queued at the same time.
+    pub max_depth: usize,
+    /// The number of events received per event kind, e.g. `Resized`.
+    pub counts: BTreeMap<String, u64>,
+}
+
+impl EventQueueStats {
+    pub fn total(&self) -> u64 {
+        self.counts.values().sum()
+    }
+}
+
+/// The display server and its capabilities.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ServerInfo {
+    pub vendor: String,
+    pub version: String,
+    pub extensions: Vec<Extension>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Extension {
+    pub name: String,
+    /// The version supported by the server if the harness queried it.
+    pub version: Option<String>,
+}
+
+impl ServerInfo {
+    /// Logs the server and its extensions.
+    pub fn log(&self) {
+        log::info!("Display server: {} {}", self.vendor, self.version);
+        let mut msg = "Extensions:".to_string();
+        for ext in &self.extensions {
+            let _ = write!(msg, "\n  {}", ext.name);
+            if let Some(version) = &ext.version {
+                let _ = write!(msg, " {}", version);
+            }
+        }
+        log::info!("{}", msg);
+    }
+}
+
+impl TestReport {
+    pub fn new(
+        backend: &str,
+        test: &str,
+        status: Status,
+        duration: Duration,
+        message: Option<String>,
+        test_dir: &Path,
+    ) -> Self {
+        let rel_dir = Path::new(backend).join(test);
+        let log = match test_dir.join("log").exists() {
+            true => Some(rel_dir.join("log")),
+            false => None,
+        };
+        let mut screenshots: Vec<_> = std::fs::read_dir(test_dir.join("screenshots"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| rel_dir.join("screenshots").join(e.file_name()))
+            .collect();
+        screenshots.sort();
+        Self {
+            backend: backend.to_string(),
+            test: test.to_string(),
+            status,
+            duration_ms: duration.as_millis() as u64,
+            message,
+            log,
+            failed_attempt_logs: vec![],
+            screenshots,
+            resources: Default::default(),
+            server: None,
+            event_queues: vec![],
+        }
+    }
+}
+
+/// Writes `summary.json` and `index.html` into `dir`.
+///
+/// `peak_rss_kb` is the peak resident set size of the harness process during the run.
+pub fn write(dir: &Path, reports: &[TestReport], peak_rss_kb: u64) {
+    let json = serde_json::to_string_pretty(reports).unwrap();
+    if let Err(e) = std::fs::write(dir.join("summary.json"), json) {
+        log::error!("Could not write summary.json: {}", e);
+    }
+    log::info!("Peak RSS of the harness: {} MiB", peak_rss_kb / 1024);
+    if let Err(e) = std::fs::write(dir.join("index.html"), html(reports, peak_rss_kb)) {
+        log::error!("Could not write index.html: {}", e);
+    }
+    log::info!("Report written to {}", dir.join("index.html").display());
+}
+
+fn html(reports: &[TestReport], peak_rss_kb: u64) -> String {
+    let mut s = String::new();
+    s.push_str(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+        "<title>winit-it</title>\n<style>\n",
+        "table { border-collapse: collapse; }\n",
+        "td, th { border: 1px solid #888; padding: 4px 8px; text-align: left; }\n",
+        "img { max-width: 160px; max-height: 120px; }\n",
+        "</style>\n</head>\n<body>\n",
+    ));
+    let count = |status| reports.iter().filter(|r| r.status == status).count();
+    let _ = writeln!(
+        s,
+        "<p>{} passed, {} passed after retries, {} failed, {} timed out, {} lost the connection, {} require manual verification, {} not run</p>",
+        count(Status::Passed),
+        count(Status::FlakyPass),
+        count(Status::Failed),
+        count(Status::TimedOut),
+        count(Status::ConnectionLost),
+        count(Status::ManualVerification),
+        count(Status::NotRun),
+    );
+    let _ = writeln!(
+        s,
+        "<p>Peak RSS of the harness: {} MiB</p>",
+        peak_rss_kb / 1024
+    );
+    s.push_str("<table>\n<tr><th>Backend</th><th>Test</th><th>Status</th><th>Duration</th><th>CPU</th><th>Server CPU</th><th>Server peak RSS</th><th>Events</th><th>Log</th><th>Screenshots</th></tr>\n");
+    for report in reports {
+        let _ = write!(
+            s,
+            "<tr style=\"background-color: {}\"><td>{}</td><td>{}</td><td>{}</td><td>{} ms</td><td>{} ms</td><td>",
+            report.status.color(),
+            escape(&report.backend),
+            escape(&report.test),
+            report.status.name(),
+            report.duration_ms,
+            report.resources.cpu_time_ms,
+        );
+        if let Some(ms) = report.resources.server_cpu_time_ms {
+            let _ = write!(s, "{} ms", ms);
+        }
+        s.push_str("</td><td>");
+        if let Some(kb) = report.resources.server_peak_rss_kb {
+            let _ = write!(s, "{} MiB", kb / 1024);
+        }
+        s.push_str("</td><td>");
+        if report.event_queues.is_not_empty() {
+            let _ = write!(
+                s,
+                "{} (max queued: {})",
+                report.event_queues.iter().map(|q| q.total()).sum::<u64>(),
+                report
+                    .event_queues
+                    .iter()
+                    .map(|q| q.max_depth)
+                    .max()
+                    .unwrap_or(0),
+            );
+        }
+        s.push_str("</td><td>");
+        if let Some(log) = &report.log {
+            let _ = write!(s, "<a href=\"{}\">log</a>", escape_path(log));
+        }
+        for (i, log) in report.failed_attempt_logs.iter().enumerate() {
+            let _ = write!(s, " <a href=\"{}\">attempt {}</a>", escape_path(log), i + 1);
+        }
+        s.push_str("</td><td>");
+        for screenshot in &report.screenshots {
+            let path = escape_path(screenshot);
+            let _ = write!(s, "<a href=\"{0}\"><img src=\"{0}\"></a>", path);
+        }
+        s.push_str("</td></tr>\n");
+    }
+    s.push_str("</table>\n</body>\n</html>\n");
+    s
+}
+
+/// Writes `<dir>/<backend>.xml` in the JUnit XML format.
+pub fn write_junit(dir: &Path, backend: &str, reports: &[TestReport]) {
+    let count = |f: &dyn Fn(Status) -> bool| reports.iter().filter(|r| f(r.status)).count();
+    let total_ms: u64 = reports.iter().map(|r| r.duration_ms).sum();
+    let mut s = String::new();
+    s.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let _ = writeln!(
+        s,
+        "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" errors=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+        escape(backend),
+        reports.len(),
+        count(&|st| st == Status::Failed),
+        count(&|st| st == Status::TimedOut || st == Status::ConnectionLost),
+        count(&|st| st == Status::NotRun),
+        total_ms as f64 / 1000.0,
+    );
+    for report in reports {
+        let _ = write!(
+            s,
+            "  <testcase name=\"{}\" classname=\"{}\" time=\"{:.3}\"",
+            escape(&report.test),
+            escape(backend),
+            report.duration_ms as f64 / 1000.0,
+        );
+        let message = escape(report.message.as_deref().unwrap_or(""));
+        let _ = match report.status {
+            Status::Passed | Status::FlakyPass | Status::ManualVerification => {
+                writeln!(s, "/>")
+            }
+            Status::Failed => writeln!(
+                s,
+                ">\n    <failure message=\"{}\"/>\n  </testcase>",
+                message
+            ),
+            Status::TimedOut | Status::ConnectionLost => {
+                writeln!(s, ">\n    <error message=\"{}\"/>\n  </testcase>", message)
+            }
+            Status::NotRun => writeln!(
+                s,
+                ">\n    <skipped message=\"{}\"/>\n  </testcase>",
+                message
+            ),
+        };
+    }
+    s.push_str("</testsuite>\n");
+    let path = dir.join(format!("{}.xml", backend));
+    if let Err(e) = std::fs::create_dir_all(dir).and_then(|_| std::fs::write(&path, s)) {
+        log::error!("Could not write {}: {}", path.display(), e);
+    }
+}
+
+fn escape_path(path: &Path) -> String {
+    escape(&path.to_string_lossy())
+}
+
+pub(crate) fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
@@ -1,41 +1,79 @@
-use crate::backend::{non_requirement_flags, Backend, BackendFlags};
-use crate::test::TestData;
+use crate::backend::{non_requirement_flags, Backend, BackendFlags, EventLoop, Instance};
+use crate::console;
+use crate::event::{Event, WindowEvent};
+use crate::report;
+use crate::report::{EventQueueStats, ResourceUsage, ServerInfo, Status, TestReport};
+use crate::test::{ConnectionLost, Metric, TestData};
 use crate::tests::Test;
 use crate::tlog::LogState;
+use futures::{pin_mut, FutureExt};
 use isnt::std_1::vec::IsntVecExt;
 use parking_lot::Mutex;
 use rayon::prelude::*;
 use std::cell::{Cell, RefCell};
+use std::fmt::Write as _;
 use std::fs::OpenOptions;
 use std::panic::AssertUnwindSafe;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::time::Duration;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 use tokio::task::LocalSet;
 
 pub struct Execution {
     pub dir: PathBuf,
+    /// Overrides the timeouts of the tests.
+    pub timeout: Option<Duration>,
+    pub fail_on_trailing_events: bool,
+    /// How often failed tests are rerun.
+    pub retries: u32,
+    pub keep_failed_alive: Option<Duration>,
+    /// Directory into which a JUnit XML file is written for each backend.
+    pub junit: Option<PathBuf>,
 }
 
 struct BackendExecution {
     dir: PathBuf,
+    timeout: Option<Duration>,
+    fail_on_trailing_events: bool,
+    retries: u32,
+    keep_failed_alive: Option<Duration>,
     result: Mutex<BackendResult>,
 }
 
 #[derive(Default)]
 struct BackendResult {
     failed: Vec<String>,
+    flaky: Vec<String>,
+    timed_out: Vec<String>,
+    connection_lost: Vec<String>,
     not_run: Vec<(String, BackendFlags)>,
+    missing_api: Vec<(String, String)>,
     manual_verification: Vec<String>,
+    metrics: Vec<(String, Metric)>,
+    reports: Vec<TestReport>,
 }
 
-pub fn run_tests(exec: &Execution, backend: &dyn Backend, tests: &[Box<dyn Test>]) {
+pub fn run_tests(
+    exec: &Execution,
+    backend: &dyn Backend,
+    tests: &[Box<dyn Test>],
+) -> Vec<TestReport> {
     let be = BackendExecution {
         dir: exec.dir.join(backend.name()),
+        timeout: exec.timeout,
+        fail_on_trailing_events: exec.fail_on_trailing_events,
+        retries: exec.retries,
+        keep_failed_alive: exec.keep_failed_alive,
         result: Default::default(),
     };
     log::info!("Running tests for backend {}", backend.name());
-    let rto = |test: &Box<dyn Test>| run_test_outer(&be, backend, &**test);
+    console::start_backend(backend.name(), tests.len());
+    let rto = |test: &Box<dyn Test>| {
+        console::test_started(backend.name(), test.name());
+        let status = run_test_outer(&be, backend, &**test);
+        console::test_finished(backend.name(), test.name(), status);
+    };
     if backend.flags().contains(BackendFlags::MT_SAFE) {
         tests
             .par_iter()
@@ -55,44 +93,204 @@ pub fn run_tests(exec: &Execution, backend: &dyn Backend, tests: &[Box<dyn Test>
             log::warn!("  - {}. Missing flags: {:?}", test, flags);
         }
     }
+    if results.missing_api.is_not_empty() {
+        log::warn!("The following tests were not run because winit lacks the API they cover:");
+        for (test, api) in &results.missing_api {
+            log::warn!("  - {}. Missing API: {}", test, api);
+        }
+    }
     if results.manual_verification.is_not_empty() {
         log::warn!("The following tests require manual verification:");
         for test in &results.manual_verification {
             log::warn!("  - {}", test);
         }
     }
+    if results.metrics.is_not_empty() {
+        log::info!("Metrics:");
+        for (test, metric) in &results.metrics {
+            log::info!(
+                "  - {}/{}: {:.3} {}",
+                test,
+                metric.name,
+                metric.value,
+                metric.unit
+            );
+        }
+    }
+    if results.flaky.is_not_empty() {
+        log::warn!("The following tests passed only after being retried:");
+        for test in &results.flaky {
+            log::warn!("  - {}", test);
+        }
+    }
+    if results.connection_lost.is_not_empty() {
+        log::error!("The following tests lost the connection to the display server:");
+        for test in &results.connection_lost {
+            log::error!("  - {}", test);
+        }
+    }
+    if results.timed_out.is_not_empty() {
+        log::error!("The following tests timed out:");
+        for test in &results.timed_out {
+            log::error!("  - {}", test);
+        }
+    }
     if results.failed.is_not_empty() {
         log::error!("The following tests failed:");
         for test in &results.failed {
             log::error!("  - {}", test);
         }
     }
+    if let Some(dir) = &exec.junit {
+        report::write_junit(dir, backend.name(), &results.reports);
+    }
+    results.reports.clone()
 }
 
-fn run_test_outer(be: &BackendExecution, backend: &dyn Backend, test: &dyn Test) {
-    let failed = std::panic::catch_unwind(AssertUnwindSafe(|| {
-        let missing_flags = test.flags() & !backend.flags() & !non_requirement_flags();
-        if !missing_flags.is_empty() {
-            be.result
-                .lock()
-                .not_run
-                .push((test.name().to_string(), missing_flags));
-            return false;
-        }
+fn run_test_outer(be: &BackendExecution, backend: &dyn Backend, test: &dyn Test) -> Status {
+    let test_dir = be.dir.join(test.name());
+    if let Some(api) = test.missing_api() {
+        let mut result = be.result.lock();
+        result
+            .missing_api
+            .push((test.name().to_string(), api.to_string()));
+        result.reports.push(TestReport::new(
+            backend.name(),
+            test.name(),
+            Status::NotRun,
+            Duration::ZERO,
+            Some(format!("Missing winit API: {}", api)),
+            &test_dir,
+        ));
+        return Status::NotRun;
+    }
+    let mock = BackendFlags::MOCK;
+    if test.flags().contains(mock) != backend.flags().contains(mock) {
+        // Not added to `not_run` since every test would be listed for one of the backends.
+        let msg = match test.flags().contains(mock) {
+            true => "Runs only on the mock backend",
+            false => "Requires a display server",
+        };
+        be.result.lock().reports.push(TestReport::new(
+            backend.name(),
+            test.name(),
+            Status::NotRun,
+            Duration::ZERO,
+            Some(msg.to_string()),
+            &test_dir,
+        ));
+        return Status::NotRun;
+    }
+    let missing_flags = test.flags() & !backend.flags() & !non_requirement_flags();
+    if !missing_flags.is_empty() {
+        let mut result = be.result.lock();
+        result
+            .not_run
+            .push((test.name().to_string(), missing_flags));
+        result.reports.push(TestReport::new(
+            backend.name(),
+            test.name(),
+            Status::NotRun,
+            Duration::ZERO,
+            Some(format!("Missing flags: {:?}", missing_flags)),
+            &test_dir,
+        ));
+        return Status::NotRun;
+    }
+    let retries = match test.flaky() {
+        true => be.retries.max(1),
+        false => be.retries,
+    };
+    let start = Instant::now();
+    let mut message;
+    let mut resources = ResourceUsage::default();
+    let mut server = None;
+    let mut event_queues = vec![];
+    let mut failed_attempt_logs = vec![];
+    let mut attempt = 1;
+    let mut status = loop {
         log::info!("Running test {}", test.name());
-        run_test(&be, backend, test)
-    }));
-    if failed.unwrap_or(true) {
-        be.result.lock().failed.push(test.name().to_string());
-    } else if test.flags().contains(BackendFlags::MANUAL_VERIFICATION) {
-        be.result
-            .lock()
-            .manual_verification
-            .push(test.name().to_string());
+        message = None;
+        let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            run_test(
+                &be,
+                backend,
+                test,
+                &mut message,
+                &mut resources,
+                &mut server,
+                &mut event_queues,
+            )
+        }));
+        let status = match res {
+            Err(e) if e.is::<ConnectionLost>() => Status::ConnectionLost,
+            Err(_) => Status::Failed,
+            Ok(status) => status,
+        };
+        if status == Status::Passed || attempt > retries {
+            break status;
+        }
+        // Keep the files of the failed attempt next to those of the next attempt.
+        let name = format!("{}.attempt-{}", test.name(), attempt);
+        match std::fs::rename(&test_dir, be.dir.join(&name)) {
+            Ok(_) => failed_attempt_logs.push(Path::new(backend.name()).join(name).join("log")),
+            Err(e) => log::error!("Could not move the files of the failed attempt: {}", e),
+        }
+        attempt += 1;
+        log::warn!(
+            "Test {} failed. Retrying (attempt {}/{})",
+            test.name(),
+            attempt,
+            retries + 1
+        );
+    };
+    let duration = start.elapsed();
+    if status == Status::Passed {
+        if test.flags().contains(BackendFlags::MANUAL_VERIFICATION) {
+            status = Status::ManualVerification;
+        } else if attempt > 1 {
+            status = Status::FlakyPass;
+        }
+    }
+    let mut result = be.result.lock();
+    match status {
+        Status::ConnectionLost => result.connection_lost.push(test.name().to_string()),
+        Status::Failed => result.failed.push(test.name().to_string()),
+        Status::TimedOut => result.timed_out.push(test.name().to_string()),
+        Status::ManualVerification => result.manual_verification.push(test.name().to_string()),
+        Status::FlakyPass => result.flaky.push(test.name().to_string()),
+        _ => {}
     }
+    let mut report = TestReport::new(
+        backend.name(),
+        test.name(),
+        status,
+        duration,
+        message,
+        &test_dir,
+    );
+    report.failed_attempt_logs = failed_attempt_logs;
+    report.resources = resources;
+    report.server = server;
+    report.event_queues = event_queues;
+    result.reports.push(report);
+    status
 }
 
-fn run_test(exec: &BackendExecution, backend: &dyn Backend, test: &dyn Test) -> bool {
+/// Runs a test and returns whether it passed, failed, or timed out. `message` is set to the
+/// first error logged by the test. `resources` is set to the resources used by the test.
+/// `server` is set to the display server the test ran against. `event_queues` is set to the
+/// statistics of the event queues of the test.
+fn run_test(
+    exec: &BackendExecution,
+    backend: &dyn Backend,
+    test: &dyn Test,
+    message: &mut Option<String>,
+    resources: &mut ResourceUsage,
+    server: &mut Option<ServerInfo>,
+    event_queues: &mut Vec<EventQueueStats>,
+) -> Status {
+    let cpu_start = thread_cpu_time();
     let test_dir = exec.dir.join(test.name());
     std::fs::create_dir_all(&test_dir).unwrap();
     let td = TestData {
@@ -108,36 +306,312 @@ fn run_test(exec: &BackendExecution, backend: &dyn Backend, test: &dyn Test) ->
         next_image_id: Default::default(),
         error: Cell::new(false),
         instance: RefCell::new(None),
+        metrics: Default::default(),
+        geometry_coalescing: Default::default(),
+        first_error: Default::default(),
+        event_loops: Default::default(),
+        expecting_panic: Cell::new(false),
+        last_input: Cell::new(None),
+        expected_events: Default::default(),
+        events_dumped: Default::default(),
+        tasks: Default::default(),
+        next_task_id: Default::default(),
+        spans: Default::default(),
+        open_spans: Default::default(),
     };
-    crate::test::set_test_data_and_run(&td, || {
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .on_thread_park(|| {
-                crate::test::with_test_data(|td| {
-                    td.instance.borrow().as_ref().unwrap().before_poll();
+    let timeout = exec.timeout.unwrap_or_else(|| test.timeout());
+    let timed_out = Cell::new(false);
+    let server_cpu_time = Cell::new(None);
+    let server_peak_rss_kb = Cell::new(None);
+    let server_info = RefCell::new(None);
+    let queue_stats = RefCell::new(vec![]);
+    let res = std::panic::catch_unwind(AssertUnwindSafe(|| {
+        crate::test::set_test_data_and_run(&td, || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .on_thread_park(|| {
+                    crate::test::with_test_data(|td| {
+                        // The instance is dropped before the remaining tasks are awaited.
+                        if let Some(instance) = td.instance.borrow().as_ref() {
+                            instance.before_poll();
+                        }
+                    })
                 })
-            })
-            .enable_all()
-            .build()
-            .unwrap();
-        rt.block_on(async {
-            let ls = LocalSet::new();
-            ls.run_until(async {
-                let instance = Rc::new(backend.instantiate());
-                *td.instance.borrow_mut() = Some(instance.clone());
-                if tokio::time::timeout(Duration::from_secs(5), test.run(&**instance))
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async {
+                let mut ls = LocalSet::new();
+                let mut detached = false;
+                let (leak_check, res) = ls
+                    .run_until(async {
+                        let instance = Rc::new(backend.instantiate());
+                        let leak_check = instance.leak_check();
+                        if let Some(info) = instance.server_info() {
+                            info.log();
+                            *server_info.borrow_mut() = Some(info);
+                        }
+                        *td.instance.borrow_mut() = Some(instance.clone());
+                        let test_fut = AssertUnwindSafe(test.run(&**instance)).catch_unwind();
+                        pin_mut!(test_fut);
+                        let res = tokio::time::timeout(timeout, &mut test_fut).await;
+                        if res.is_err() {
+                            log::error!("Test timed out after {:?}", timeout);
+                            timed_out.set(true);
+                            capture_timeout_diagnostics(&**instance, &td.test_dir);
+                        }
+                        if let Ok(Ok(())) = res {
+                            let event_loops = td.event_loops.borrow();
+                            drain_trailing_events(&event_loops, exec.fail_on_trailing_events).await;
+                        }
+                        server_cpu_time.set(instance.server_cpu_time());
+                        server_peak_rss_kb.set(instance.server_peak_rss_kb());
+                        if td.error.get() || !matches!(res, Ok(Ok(_))) {
+                            crate::eventtrace::dump_test();
+                        }
+                        if let Some(duration) = exec.keep_failed_alive {
+                            if td.error.get() || !matches!(res, Ok(Ok(_))) {
+                                detached = keep_alive(&**instance, duration);
+                            }
+                        }
+                        drop(test_fut);
+                        crate::tlog::log_span_summary();
+                        let stats: Vec<_> = td
+                            .event_loops
+                            .borrow()
+                            .iter()
+                            .map(|el| el.queue_stats())
+                            .collect();
+                        log_event_queue_stats(&stats);
+                        *queue_stats.borrow_mut() = stats;
+                        drop(td.event_loops.take());
+                        report_leaks(leak_check.windows().await);
+                        *td.instance.borrow_mut() = None;
+                        drop(instance);
+                        (leak_check, res)
+                    })
+                    .await;
+                if tokio::time::timeout(TASK_CLEANUP_TIMEOUT, &mut ls)
                     .await
                     .is_err()
                 {
-                    log::error!("Test timed out");
+                    report_leaks(
+                        crate::task::live_tasks()
+                            .into_iter()
+                            .map(|name| format!("Task {} is still running", name))
+                            .collect(),
+                    );
+                }
+                drop(ls);
+                if !detached {
+                    report_leaks(leak_check.instance());
+                }
+                if let Ok(Err(e)) = res {
+                    std::panic::resume_unwind(e);
                 }
-                *td.instance.borrow_mut() = None;
-            })
-            .await;
-            ls.await;
-        });
-        if td.error.get() {
-            log::error!("Test failed due to previous error");
+            });
+            if td.error.get() {
+                log::error!("Test failed due to previous error");
+            }
+        })
+    }));
+    exec.result.lock().metrics.extend(
+        td.metrics
+            .take()
+            .into_iter()
+            .map(|m| (test.name().to_string(), m)),
+    );
+    *message = td.first_error.take();
+    *server = server_info.take();
+    *event_queues = queue_stats.take();
+    *resources = ResourceUsage {
+        cpu_time_ms: thread_cpu_time().saturating_sub(cpu_start).as_millis() as u64,
+        server_cpu_time_ms: server_cpu_time.get().map(|d| d.as_millis() as u64),
+        server_peak_rss_kb: server_peak_rss_kb.get(),
+    };
+    if let Err(e) = res {
+        std::panic::resume_unwind(e);
+    }
+    if timed_out.get() {
+        Status::TimedOut
+    } else if td.error.get() {
+        Status::Failed
+    } else {
+        Status::Passed
+    }
+}
+
+/// The threads holding the display servers of failed tests. See `keep_alive`.
+static DETACHED_SERVERS: Mutex<Vec<JoinHandle<()>>> = parking_lot::const_mutex(Vec::new());
+
+/// Moves the display server of a failed test to a thread that terminates it after
+/// `duration` so that the remaining tests can continue in the meantime. Returns whether
+/// the server has been detached from the instance.
+fn keep_alive(instance: &dyn Instance, duration: Duration) -> bool {
+    let server = match instance.detach_server() {
+        Some(s) => s,
+        _ => {
+            log::warn!("The backend cannot keep the display server of the failed test alive");
+            return false;
         }
-    });
-    td.error.get()
+    };
+    log::warn!(
+        "Keeping the display server of the failed test alive for {:?}",
+        duration
+    );
+    let jh = std::thread::Builder::new()
+        .name("keep-failed-alive".to_string())
+        .spawn(move || {
+            std::thread::sleep(duration);
+            drop(server);
+        })
+        .unwrap();
+    DETACHED_SERVERS.lock().push(jh);
+    true
+}
+
+/// Waits until the display servers kept alive by `--keep-failed-alive` have been
+/// terminated.
+pub fn wait_for_detached_servers() {
+    let servers = std::mem::take(&mut *DETACHED_SERVERS.lock());
+    if !servers.is_empty() {
+        log::info!(
+            "Waiting for {} display servers of failed tests to be terminated",
+            servers.len()
+        );
+    }
+    for jh in servers {
+        let _ = jh.join();
+    }
+}
+
+/// The number of events of a single kind above which an event loop is considered to have
+/// been flooded.
+const EVENT_FLOOD_THRESHOLD: u64 = 500;
+
+/// Logs the statistics of the event queues of a test. Floods of events are logged as
+/// warnings so that they are noticed even if the test passes.
+fn log_event_queue_stats(stats: &[EventQueueStats]) {
+    for (idx, stats) in stats.iter().enumerate() {
+        let mut counts: Vec<_> = stats.counts.iter().collect();
+        counts.sort_by(|a, b| b.1.cmp(a.1));
+        let mut msg = format!(
+            "Event loop {} received {} events, at most {} were queued:",
+            idx,
+            stats.total(),
+            stats.max_depth
+        );
+        for (kind, count) in &counts {
+            let _ = write!(msg, "\n  {:6} {}", count, kind);
+        }
+        log::info!("{}", msg);
+        for (kind, &count) in counts {
+            if count > EVENT_FLOOD_THRESHOLD {
+                log::warn!(
+                    "Event loop {} received {} {} events. This might be an event flood.",
+                    idx,
+                    count,
+                    kind
+                );
+            }
+        }
+    }
+}
+
+/// Collects the state of a timed out test before it is aborted.
+///
+/// Takes a final screenshot, lists the awaiters and tasks that are still pending, and lets
+/// the backend dump its internal state into the `diagnostics` directory of the test.
+fn capture_timeout_diagnostics(instance: &dyn Instance, test_dir: &Path) {
+    crate::screenshot::take_screenshot(instance);
+    let dir = test_dir.join("diagnostics");
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        log::warn!("Could not create {}: {}", dir.display(), e);
+        return;
+    }
+    let mut pending = String::new();
+    for (elapsed, span) in crate::tlog::pending_spans() {
+        let _ = writeln!(pending, "awaiter {} (pending for {:?})", span, elapsed);
+    }
+    for task in crate::task::live_tasks() {
+        let _ = writeln!(pending, "task {}", task);
+    }
+    log::info!(
+        "Pending at the time of the timeout:\n{}",
+        pending.trim_end()
+    );
+    if let Err(e) = std::fs::write(dir.join("pending"), pending) {
+        log::warn!("Could not write the pending awaiters: {}", e);
+    }
+    instance.dump_diagnostics(&dir);
+    log::info!("Wrote diagnostics to {}", dir.display());
+}
+
+/// How long the tasks of a test have to terminate after its instance has been dropped.
+const TASK_CLEANUP_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Logs resources that outlived the test as errors.
+fn report_leaks(leaks: Vec<String>) {
+    for leak in leaks {
+        log::error!("Resource leak: {}", leak);
+    }
+}
+
+/// How long to wait for trailing events after a test has completed.
+const TRAILING_EVENTS_PERIOD: Duration = Duration::from_millis(200);
+
+/// Logs the window events that arrive in the event loops after a test has completed.
+///
+/// Events that arrived before the test completed but were not consumed by it are ignored,
+/// as are device events and `Destroyed` events, which are expected during teardown, and the
+/// markers at the end of event loop iterations.
+async fn drain_trailing_events(event_loops: &[Box<dyn EventLoop>], fail: bool) {
+    let connected = || {
+        event_loops
+            .iter()
+            .filter(|el| el.disconnected().now_or_never().is_none())
+    };
+    for el in connected() {
+        let mut events = el.events();
+        while events.event().now_or_never().is_some() {}
+    }
+    tokio::time::sleep(TRAILING_EVENTS_PERIOD).await;
+    for el in connected() {
+        let mut events = el.events();
+        while let Some(event) = events.event().now_or_never() {
+            match &event {
+                Event::DeviceEvent(_) => continue,
+                Event::MainEventsCleared | Event::RedrawEventsCleared => continue,
+                Event::WindowEvent(we) if we.event == WindowEvent::Destroyed => continue,
+                _ => {}
+            }
+            if fail {
+                log::error!("Trailing event after the test completed: {:?}", event);
+            } else {
+                log::warn!("Trailing event after the test completed: {:?}", event);
+            }
+        }
+    }
+}
+
+/// Returns the peak resident set size of the harness process in KiB.
+///
+/// This covers all tests of the run, including winit, since they share the process.
+pub fn peak_rss_kb() -> u64 {
+    rusage(libc::RUSAGE_SELF).ru_maxrss as u64
+}
+
+fn rusage(who: libc::c_int) -> libc::rusage {
+    unsafe {
+        let mut usage = std::mem::zeroed();
+        libc::getrusage(who, &mut usage);
+        usage
+    }
+}
+
+fn thread_cpu_time() -> Duration {
+    let usage = rusage(libc::RUSAGE_THREAD);
+    let time = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+    time(usage.ru_utime) + time(usage.ru_stime)
 }
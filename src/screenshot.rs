@@ -1,12 +1,166 @@
 use crate::backend::Instance;
-use byteorder::{WriteBytesExt, LE};
+use byteorder::{ByteOrder, WriteBytesExt, LE};
 use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::Ordering::Relaxed;
 
 const ENABLED: bool = true;
 
+/// An image with 4 bytes per pixel in BGRA order.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
+}
+
+impl Image {
+    /// Creates an image containing the pattern drawn by `Window::present_test_pattern`.
+    pub fn test_pattern(seed: u32, width: u32, height: u32) -> Self {
+        let mut data = Vec::with_capacity(width as usize * height as usize * 4);
+        for y in 0..height {
+            for x in 0..width {
+                let [r, g, b, a] = crate::backend::test_pattern(seed, x, y);
+                data.extend_from_slice(&[b, g, r, a]);
+            }
+        }
+        Self {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Creates an image in which every pixel has the color `rgba`.
+    pub fn filled(width: u32, height: u32, rgba: [u8; 4]) -> Self {
+        let [r, g, b, a] = rgba;
+        Self {
+            width,
+            height,
+            data: [b, g, r, a].repeat(width as usize * height as usize),
+        }
+    }
+
+    /// Returns the pixel at `(x, y)` in RGBA order.
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let off = 4 * (y * self.width + x) as usize;
+        let p = &self.data[off..off + 4];
+        [p[2], p[1], p[0], p[3]]
+    }
+
+    /// Writes the image to the screenshots directory of the test.
+    pub fn log(&self) {
+        log_image(&self.data, self.width, self.height);
+    }
+
+    /// Asserts that the image differs from `other` by at most `tolerance` in every color
+    /// channel. Alpha is ignored.
+    ///
+    /// A mismatch is logged as an error together with both images. It fails the test
+    /// without aborting it.
+    pub fn assert_similar_to(&self, other: &Image, tolerance: u8) {
+        if (self.width, self.height) != (other.width, other.height) {
+            log::error!(
+                "Image has size {}x{} but expected {}x{}",
+                self.width,
+                self.height,
+                other.width,
+                other.height
+            );
+            self.log();
+            other.log();
+            return;
+        }
+        let mismatched = self
+            .data
+            .chunks(4)
+            .zip(other.data.chunks(4))
+            .filter(|(a, b)| (0..3).any(|i| a[i].abs_diff(b[i]) > tolerance))
+            .count();
+        if mismatched > 0 {
+            log::error!(
+                "{} of {} pixels differ by more than {} from the expected image",
+                mismatched,
+                self.width * self.height,
+                tolerance
+            );
+            self.log();
+            other.log();
+        }
+    }
+
+    /// Compares the image to the golden image `name` of the current test.
+    ///
+    /// Golden images are stored under `testdata/golden/<backend>/<test>/<name>.bmp`. If
+    /// `WINIT_IT_UPDATE_GOLDEN` is set, the image is stored as the new golden image
+    /// instead. A missing golden image fails the test.
+    pub fn assert_similar(&self, name: &str, tolerance: u8) {
+        let path = golden_path(name);
+        if std::env::var_os("WINIT_IT_UPDATE_GOLDEN").is_some() {
+            log::warn!("Updating golden image {}", path.display());
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            write_bmp(&path, &self.data, self.width, self.height);
+            return;
+        }
+        if !path.exists() {
+            log::error!(
+                "Golden image {} does not exist. Set WINIT_IT_UPDATE_GOLDEN=1 to create it",
+                path.display()
+            );
+            self.log();
+            return;
+        }
+        let golden = match read_bmp(&path) {
+            Ok(g) => g,
+            Err(e) => {
+                log::error!("Could not read golden image {}: {}", path.display(), e);
+                return;
+            }
+        };
+        log::info!("Comparing to golden image {}", path.display());
+        self.assert_similar_to(&golden, tolerance);
+    }
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    crate::test::with_test_data(|td| {
+        let mut components = td.test_dir.iter().rev();
+        let test = components.next().unwrap();
+        let backend = components.next().unwrap();
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("testdata/golden")
+            .join(backend)
+            .join(test)
+            .join(format!("{}.bmp", name))
+    })
+}
+
+/// Reads a BMP file written by `write_bmp`.
+fn read_bmp(path: &Path) -> Result<Image, String> {
+    let buf = std::fs::read(path).map_err(|e| e.to_string())?;
+    if buf.len() < 14 + 40 || &buf[..2] != b"BM" {
+        return Err("Not a BMP file".to_string());
+    }
+    let offset = LE::read_u32(&buf[10..]) as usize;
+    let width = LE::read_u32(&buf[18..]);
+    let height = LE::read_i32(&buf[22..]);
+    let bpp = LE::read_u16(&buf[28..]);
+    if height >= 0 || bpp != 32 {
+        return Err("Unsupported BMP format".to_string());
+    }
+    let height = height.unsigned_abs();
+    let len = width as usize * height as usize * 4;
+    match buf.get(offset..offset + len) {
+        Some(data) => Ok(Image {
+            width,
+            height,
+            data: data.to_vec(),
+        }),
+        None => Err("Truncated BMP file".to_string()),
+    }
+}
+
 pub fn take_screenshot(instance: &dyn Instance) {
     if !ENABLED {
         log::info!("Taking screenshots is disabled");
@@ -21,36 +175,38 @@ pub fn log_image(buf: &[u8], width: u32, height: u32) {
         let screenshots = td.test_dir.join("screenshots");
         std::fs::create_dir_all(&screenshots).unwrap();
         let file = format!("{:03}.bmp", id);
-        let full_path = screenshots.join(&file);
         let rel_path = Path::new("screenshots").join(&file);
-        let mut w = BufWriter::new(
-            OpenOptions::new()
-                .create(true)
-                .truncate(true)
-                .write(true)
-                .open(&full_path)
-                .unwrap(),
-        );
-        let _ = w.write_u8(0x42);
-        let _ = w.write_u8(0x4d);
-        let _ = w.write_u32::<LE>(14 + 40 + buf.len() as u32);
-        let _ = w.write_u32::<LE>(0);
-        let _ = w.write_u32::<LE>(14 + 40);
-
-        let _ = w.write_u32::<LE>(40);
-        let _ = w.write_u32::<LE>(width);
-        let _ = w.write_i32::<LE>(-(height as i32));
-        let _ = w.write_u16::<LE>(1);
-        let _ = w.write_u16::<LE>(32);
-        let _ = w.write_u32::<LE>(0);
-        let _ = w.write_u32::<LE>(0);
-        let _ = w.write_u32::<LE>(0);
-        let _ = w.write_u32::<LE>(0);
-        let _ = w.write_u32::<LE>(0);
-        let _ = w.write_u32::<LE>(0);
-
-        let _ = w.write_all(buf);
-
+        write_bmp(&screenshots.join(&file), buf, width, height);
         log::info!("Took screenshot {}", rel_path.display());
     })
 }
+
+fn write_bmp(path: &Path, buf: &[u8], width: u32, height: u32) {
+    let mut w = BufWriter::new(
+        OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)
+            .unwrap(),
+    );
+    let _ = w.write_u8(0x42);
+    let _ = w.write_u8(0x4d);
+    let _ = w.write_u32::<LE>(14 + 40 + buf.len() as u32);
+    let _ = w.write_u32::<LE>(0);
+    let _ = w.write_u32::<LE>(14 + 40);
+
+    let _ = w.write_u32::<LE>(40);
+    let _ = w.write_u32::<LE>(width);
+    let _ = w.write_i32::<LE>(-(height as i32));
+    let _ = w.write_u16::<LE>(1);
+    let _ = w.write_u16::<LE>(32);
+    let _ = w.write_u32::<LE>(0);
+    let _ = w.write_u32::<LE>(0);
+    let _ = w.write_u32::<LE>(0);
+    let _ = w.write_u32::<LE>(0);
+    let _ = w.write_u32::<LE>(0);
+    let _ = w.write_u32::<LE>(0);
+
+    let _ = w.write_all(buf);
+}
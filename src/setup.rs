@@ -0,0 +1,71 @@
+use crate::backend::{EventLoop, Instance, Seat, Window};
+use crate::eventstream::EventStream;
+use winit::window::WindowBuilder;
+
+/// Creates an event loop with a single window and establishes the given preconditions
+/// before handing out the event stream.
+///
+/// ```ignore
+/// let s = setup!(instance, Mapped, Focused);
+/// kb.press(KeyL);
+/// let (_, ki) = s.events.window_keyboard_input().await;
+/// ```
+#[macro_export]
+macro_rules! setup {
+    ($instance:expr $(, $pre:ident)* $(,)?) => {
+        $crate::setup::Setup::new(
+            $instance,
+            Default::default(),
+            &[$($crate::setup::Precondition::$pre),*],
+        )
+        .await
+    };
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Precondition {
+    /// The window has been mapped by the window manager.
+    Mapped,
+    /// The window is mapped and has the keyboard focus of the default seat, as reported by
+    /// winit and confirmed by the display server.
+    Focused,
+}
+
+pub struct Setup {
+    pub el: Box<dyn EventLoop>,
+    pub events: Box<dyn EventStream>,
+    pub window: Box<dyn Window>,
+    pub seat: Box<dyn Seat>,
+}
+
+impl Setup {
+    pub async fn new(
+        instance: &dyn Instance,
+        builder: WindowBuilder,
+        preconditions: &[Precondition],
+    ) -> Self {
+        let el = instance.create_event_loop();
+        let mut events = el.events();
+        let window = el.create_window(builder);
+        let seat = instance.default_seat();
+        for precondition in preconditions {
+            log::info!("Establishing precondition {:?}", precondition);
+            match precondition {
+                Precondition::Mapped => {
+                    window.mapped(true).await;
+                }
+                Precondition::Focused => {
+                    window.mapped(true).await;
+                    seat.focus_and_wait(&*window, &mut *events).await;
+                }
+            }
+        }
+        log::info!("All preconditions established");
+        Self {
+            el,
+            events,
+            window,
+            seat,
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use crate::test::{has_test_data, with_test_data};
+use std::future::Future;
+use tokio::task::JoinHandle;
+
+/// Spawns a task on the local set of the test.
+///
+/// The task is registered in the test data until it completes or is dropped so that tasks
+/// that outlive the test can be reported by name.
+pub fn spawn_local<F>(name: &'static str, f: F) -> JoinHandle<F::Output>
+where
+    F: Future + 'static,
+    F::Output: 'static,
+{
+    let id = with_test_data(|td| {
+        let id = td.next_task_id.get();
+        td.next_task_id.set(id + 1);
+        td.tasks.borrow_mut().insert(id, name);
+        id
+    });
+    let guard = TaskGuard(id);
+    tokio::task::spawn_local(async move {
+        let _guard = guard;
+        f.await
+    })
+}
+
+struct TaskGuard(u64);
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if has_test_data() {
+            with_test_data(|td| td.tasks.borrow_mut().remove(&self.0));
+        }
+    }
+}
+
+/// Returns the names of the tasks that are still alive.
+pub fn live_tasks() -> Vec<&'static str> {
+    with_test_data(|td| td.tasks.borrow().values().copied().collect())
+}
@@ -1,12 +1,18 @@
-use crate::backend::Instance;
-use crate::tlog::LogState;
+use crate::backend::{EventLoop, Instance};
+use crate::tlog::{LogState, SpanStats};
+use futures::FutureExt;
 use parking_lot::Mutex;
+use std::any::Any;
 use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
 use std::panic::AssertUnwindSafe;
 use std::path::PathBuf;
 use std::ptr;
 use std::rc::Rc;
 use std::sync::atomic::AtomicUsize;
+use std::time::Instant;
 
 pub struct TestData {
     pub test_dir: PathBuf,
@@ -14,8 +20,57 @@ pub struct TestData {
     pub log_state: Mutex<LogState>,
     pub error: Cell<bool>,
     pub instance: RefCell<Option<Rc<Box<dyn Instance>>>>,
+    pub metrics: RefCell<Vec<Metric>>,
+    pub geometry_coalescing: Cell<Coalescing>,
+    pub first_error: RefCell<Option<String>>,
+    /// The event loops created by the test. They are kept alive after the test has
+    /// completed so that trailing events can be observed.
+    pub event_loops: RefCell<Vec<Box<dyn EventLoop>>>,
+    /// Set while a panic is expected. See `expect_failure`.
+    pub expecting_panic: Cell<bool>,
+    pub last_input: Cell<Option<Instant>>,
+    /// The event kinds the test expects to receive after the given time. Used to annotate
+    /// the event trace written on failure. See `set_expected_events`.
+    pub expected_events: RefCell<Option<(Instant, Vec<String>)>>,
+    /// Set once the event trace has been written. See `eventtrace::dump_test`.
+    pub events_dumped: Cell<bool>,
+    /// The tasks spawned by the test that are still alive. See `task::spawn_local`.
+    pub tasks: RefCell<HashMap<u64, &'static str>>,
+    pub next_task_id: Cell<u64>,
+    /// The durations of the spans of the test by span stack. See `tlog::log_span_summary`.
+    pub spans: RefCell<HashMap<String, SpanStats>>,
+    /// The spans of the test that have not been closed yet, i.e. the awaiters that are
+    /// still pending. See `tlog::pending_spans`.
+    pub open_spans: RefCell<HashMap<u64, (Instant, String)>>,
 }
 
+/// How strictly geometry event expectations are checked.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Coalescing {
+    /// The next geometry event of the window must have the expected value.
+    Exact,
+    /// Intermediate geometry events are skipped until one has the expected value.
+    Final,
+}
+
+impl Default for Coalescing {
+    fn default() -> Self {
+        Coalescing::Exact
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Metric {
+    pub name: String,
+    pub value: f64,
+    pub unit: &'static str,
+}
+
+/// Panic payload used when a test cannot continue because the connection to the display
+/// server has been lost.
+#[derive(Debug)]
+pub struct ConnectionLost;
+
 thread_local! {
     static TEST: Cell<*const TestData> = Cell::new(ptr::null());
 }
@@ -43,3 +98,92 @@ pub fn with_test_data<T, F: FnOnce(&TestData) -> T>(f: F) -> T {
 pub fn has_test_data() -> bool {
     TEST.with(|t| !t.get().is_null())
 }
+
+pub fn record_metric(name: &str, value: f64, unit: &'static str) {
+    log::info!("Metric {}: {:.3} {}", name, value, unit);
+    with_test_data(|td| {
+        td.metrics.borrow_mut().push(Metric {
+            name: name.to_string(),
+            value,
+            unit,
+        })
+    })
+}
+
+pub fn set_geometry_coalescing(coalescing: Coalescing) {
+    log::info!("Setting geometry coalescing to {:?}", coalescing);
+    with_test_data(|td| td.geometry_coalescing.set(coalescing))
+}
+
+/// Declares the kinds of events, e.g. `Resized` or `KeyboardInput`, that the test expects
+/// to receive from now on. If the test fails, the recent events of all event loops are
+/// diffed against this sequence.
+pub fn set_expected_events(kinds: &[&str]) {
+    log::info!("Expecting events {:?}", kinds);
+    let kinds = kinds.iter().map(|k| k.to_string()).collect();
+    with_test_data(|td| *td.expected_events.borrow_mut() = Some((crate::clock::now(), kinds)))
+}
+
+/// Asserts that `f` returns an error or panics.
+///
+/// A success is logged as an error, which fails the test without aborting it. Panics are
+/// caught and logged without a backtrace. `what` describes the operation in the log.
+pub fn expect_failure<T: Debug, E: Debug, F: FnOnce() -> Result<T, E>>(what: &str, f: F) {
+    log::info!("Expecting {} to fail", what);
+    let res = with_test_data(|td| {
+        td.expecting_panic.set(true);
+        let res = std::panic::catch_unwind(AssertUnwindSafe(f));
+        td.expecting_panic.set(false);
+        res
+    });
+    match res {
+        Ok(Ok(v)) => log::error!("{} succeeded unexpectedly: {:?}", what, v),
+        Ok(Err(e)) => log::info!("{} failed as expected: {:?}", what, e),
+        Err(e) if e.is::<ConnectionLost>() => std::panic::resume_unwind(e),
+        Err(_) => log::info!("{} panicked as expected", what),
+    }
+}
+
+/// Asserts that `f` panics and returns the panic message.
+///
+/// Like `expect_failure`, a success is logged as an error and the panic is logged without
+/// a backtrace.
+pub async fn expect_panic<T: Debug, F: Future<Output = T>>(what: &str, f: F) -> String {
+    log::info!("Expecting {} to panic", what);
+    with_test_data(|td| td.expecting_panic.set(true));
+    let res = AssertUnwindSafe(f).catch_unwind().await;
+    with_test_data(|td| td.expecting_panic.set(false));
+    match res {
+        Ok(v) => {
+            log::error!("{} succeeded unexpectedly: {:?}", what, v);
+            String::new()
+        }
+        Err(e) if e.is::<ConnectionLost>() => std::panic::resume_unwind(e),
+        Err(e) => {
+            log::info!("{} panicked as expected", what);
+            panic_message(&*e)
+        }
+    }
+}
+
+/// Runs `f` while panics are expected, e.g. panics of winit that the backend catches.
+/// Such panics are logged without a backtrace and do not fail the test.
+pub async fn with_expected_panics<T, F: Future<Output = T>>(what: &str, f: F) -> T {
+    log::info!("Expecting panics while {}", what);
+    with_test_data(|td| td.expecting_panic.set(true));
+    let res = f.await;
+    with_test_data(|td| td.expecting_panic.set(false));
+    res
+}
+
+/// Returns the message of a panic payload or an empty string if the payload is not a
+/// string.
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<String>() {
+        Some(s) => s.clone(),
+        None => payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .unwrap_or_default(),
+    }
+}
@@ -0,0 +1,133 @@
+use chrono::Local;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The format of the timestamp at the start of the names of testrun directories.
+pub const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// Creates the directory of this invocation below `testruns/records` and points the
+/// `latest` and `latest-<pid>` symlinks at it.
+///
+/// Multiple invocations can run at the same time. Updates of the symlinks are serialized via
+/// `testruns/lock`.
+pub fn create_testrun_dir() -> PathBuf {
+    let testruns_dir = testruns_dir();
+    let pid = std::process::id();
+    let testrun_dir = testruns_dir.join("records").join(format!(
+        "{} {:x}",
+        Local::now().format(TIMESTAMP_FORMAT),
+        pid
+    ));
+    std::fs::create_dir_all(&testrun_dir).unwrap();
+    let _lock = lock(&testruns_dir.join("lock"));
+    remove_stale_links(&testruns_dir);
+    link(&testrun_dir, &testruns_dir.join(format!("latest-{}", pid)));
+    link(&testrun_dir, &testruns_dir.join("latest"));
+    testrun_dir
+}
+
+/// The directory containing the records of all invocations.
+pub fn testruns_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("testruns")
+}
+
+/// Opens `path` and acquires an exclusive lock on it.
+///
+/// On platforms without `flock` the file is opened without a lock.
+pub fn lock(path: &Path) -> File {
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .open(path)
+        .unwrap();
+    #[cfg(unix)]
+    unsafe {
+        use std::os::unix::io::AsRawFd;
+        uapi::map_err!(libc::flock(file.as_raw_fd(), libc::LOCK_EX)).unwrap();
+    }
+    file
+}
+
+/// Returns whether the process `pid` is still running.
+///
+/// Errs on the side of `true` if this cannot be determined.
+fn is_running(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        Path::new("/proc").join(pid.to_string()).exists()
+    }
+    #[cfg(all(unix, not(target_os = "linux")))]
+    {
+        let res = unsafe { libc::kill(pid as libc::pid_t, 0) };
+        res == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = pid;
+        true
+    }
+}
+
+fn symlink(target: &Path, path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(target, path)
+    }
+    #[cfg(windows)]
+    {
+        std::os::windows::fs::symlink_dir(target, path)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = (target, path);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+/// Removes `latest-<pid>` symlinks of invocations that are no longer running.
+fn remove_stale_links(testruns_dir: &Path) {
+    let entries = match std::fs::read_dir(testruns_dir) {
+        Ok(e) => e,
+        Err(e) => {
+            log::warn!("Could not read {}: {}", testruns_dir.display(), e);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let pid = match name.to_str().and_then(|n| n.strip_prefix("latest-")) {
+            Some(pid) => pid,
+            _ => continue,
+        };
+        match pid.parse::<u32>() {
+            Ok(pid) if !is_running(pid) => {}
+            _ => continue,
+        }
+        log::info!("Removing stale link {}", entry.path().display());
+        let _ = std::fs::remove_file(entry.path());
+    }
+}
+
+/// Atomically replaces `path` by a symlink to `target`.
+fn link(target: &Path, path: &Path) {
+    let tmp = path.with_file_name(format!(
+        ".{}.{}",
+        path.file_name().unwrap().to_string_lossy(),
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&tmp);
+    if let Err(e) = symlink(target, &tmp) {
+        log::warn!("Could not create symlink {}: {}", tmp.display(), e);
+        return;
+    }
+    if let Err(e) = std::fs::rename(&tmp, path) {
+        log::warn!(
+            "Could not rename {} to {}: {}",
+            tmp.display(),
+            path.display(),
+            e
+        );
+        let _ = std::fs::remove_file(&tmp);
+    }
+}
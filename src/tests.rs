@@ -1,79 +1,201 @@
+/// Defines a test named after the enclosing module.
+///
+/// `$f` is an `async fn(&dyn Instance)`. The optional second argument contains the
+/// backend flags required by the test. The optional third argument is the timeout of the
+/// test. Tests that are known to fail intermittently are marked with a leading `#[flaky]`,
+/// e.g. `test!(#[flaky] run, BackendFlags::CREATE_SEAT)`.
+#[macro_export]
 macro_rules! test {
-    ($f:ident) => {
-        test!($f, crate::backend::BackendFlags::empty());
+    (@impl $flaky:expr, $f:ident) => {
+        $crate::test!(@impl $flaky, $f, $crate::backend::BackendFlags::empty());
     };
-    ($f:ident, $flags:expr) => {
+    (@impl $flaky:expr, $f:ident, $flags:expr) => {
+        $crate::test!(@impl $flaky, $f, $flags, $crate::tests::DEFAULT_TIMEOUT);
+    };
+    (@impl $flaky:expr, $f:ident, $flags:expr, $timeout:expr) => {
         pub struct Test;
 
-        impl super::Test for Test {
+        impl $crate::tests::Test for Test {
             fn name(&self) -> &str {
                 module_path!().trim_start_matches("winit_it::tests::")
             }
 
             fn run<'a>(
                 &'a self,
-                instance: &'a dyn Instance,
+                instance: &'a dyn $crate::backend::Instance,
             ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + 'a>> {
                 Box::pin($f(instance))
             }
 
-            fn flags(&self) -> crate::backend::BackendFlags {
+            fn flags(&self) -> $crate::backend::BackendFlags {
                 $flags
             }
+
+            fn timeout(&self) -> std::time::Duration {
+                $timeout
+            }
+
+            fn flaky(&self) -> bool {
+                $flaky
+            }
+        }
+    };
+    (#[flaky] $($args:tt)*) => {
+        $crate::test!(@impl true, $($args)*);
+    };
+    ($($args:tt)*) => {
+        $crate::test!(@impl false, $($args)*);
+    };
+}
+
+/// Defines a placeholder for a test named after the enclosing module whose winit API does
+/// not exist in the winit version in use. The test is reported as not run.
+///
+/// `$api` names the missing API, e.g. `"Window::set_blur"`.
+#[macro_export]
+macro_rules! missing_api {
+    ($api:expr) => {
+        pub struct Test;
+
+        impl $crate::tests::Test for Test {
+            fn name(&self) -> &str {
+                module_path!().trim_start_matches("winit_it::tests::")
+            }
+
+            fn run<'a>(
+                &'a self,
+                _instance: &'a dyn $crate::backend::Instance,
+            ) -> std::pin::Pin<Box<dyn std::future::Future<Output = ()> + 'a>> {
+                unreachable!();
+            }
+
+            fn missing_api(&self) -> Option<&str> {
+                Some($api)
+            }
         }
     };
 }
 
+mod absolute_pointer;
 mod always_on_top;
 mod available_monitors;
+mod background_color;
+mod bench_create_window;
+mod blur;
 #[cfg(target_os = "linux")]
 mod class;
+mod client_windows;
+mod close_button;
+mod configure_monitors;
+mod connection_loss;
+mod connection_reconnect;
+mod content_protected;
+mod control_flow_poll;
+mod control_flow_wait_until;
+mod cross_thread_setters;
 mod current_monitor;
+mod current_monitor_move;
+mod cursor_enter_on_move;
 mod cursor_grab;
+mod cursor_grab_unmapped;
 mod cursor_icon;
+mod cursor_icons;
 mod cursor_position;
 mod cursor_visible;
+mod dead_keys;
 mod decorations;
+mod degenerate_size_bounds;
 mod delete_window;
 mod destroyed;
 mod device_added;
+mod device_hotplug_stress;
 mod device_key;
 mod device_mouse;
+mod device_mouse_hotplug;
 mod dnd;
+mod drag_move;
+mod drag_resize;
 mod drag_window;
+mod extreme_sizes;
 mod focused;
 mod focused_multi_seat;
 mod fullscreen;
 mod fullscreen2;
+mod harness_awaiters;
+mod harness_event_stream;
+mod harness_event_trace;
+mod harness_expect;
 mod icon;
+mod icon_frames;
+mod ime_compose;
+mod implicit_grab;
+mod key_repeat;
+mod lock_state;
 mod logical_cursor_position;
 mod logical_inner_size;
 mod logical_size_bounds;
 mod maximize;
+mod maximize_button;
 mod minimize;
+mod minimize_via_wm;
+mod modifier_taps;
+mod negative_position_at_creation;
+mod pager_fullscreen;
+mod pager_minimize;
 mod physical_inner_size;
 mod physical_outer_position;
 mod physical_size_bounds;
 #[cfg(target_os = "linux")]
 mod ping;
 mod primary_monitor;
+mod protocol_efficiency;
+mod rapid_set_position;
+mod record_replay;
+mod redraw_on_expose;
 mod redraw_requested;
+mod redraw_requested_cross_thread;
+mod redraw_requested_once;
 mod reset_dead_keys;
 mod resizable;
+mod scale_factor;
+mod scale_factor_override;
+mod seat_layouts;
+mod seat_removal_pressed_keys;
 mod set_position;
 mod set_size;
+mod size_bounds_while_maximized;
+mod slow_connection;
+mod state_at_creation;
+mod suspend_resume;
+mod test_pattern;
 mod title;
+mod title_double_click;
 mod touch;
 mod transparency;
+mod type_text;
+mod unaccelerated_motion;
 mod urgency;
 mod user_event;
+mod video_modes;
 mod visible;
+mod visible_initially_false;
+mod visible_silence;
+mod window_event_filtering;
 mod window_keyboard;
+mod window_level;
 mod window_mouse;
+mod wm_focus_policies;
+mod wm_focus_steal_prevention;
+mod wm_maximize;
+mod wm_refuse_resize;
+mod wm_super_shortcut;
 
 use crate::backend::{BackendFlags, Instance};
 use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub trait Test: Sync {
     fn name(&self) -> &str;
@@ -82,6 +204,20 @@ pub trait Test: Sync {
     fn flags(&self) -> BackendFlags {
         BackendFlags::empty()
     }
+
+    fn timeout(&self) -> Duration {
+        DEFAULT_TIMEOUT
+    }
+
+    /// The winit API covered by the test if it does not exist in the winit version in use.
+    fn missing_api(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether the test is known to fail intermittently and should be retried on failure.
+    fn flaky(&self) -> bool {
+        false
+    }
 }
 
 pub fn tests() -> Vec<Box<dyn Test>> {
@@ -133,5 +269,72 @@ pub fn tests() -> Vec<Box<dyn Test>> {
         Box::new(fullscreen2::Test),
         Box::new(touch::Test),
         Box::new(redraw_requested::Test),
+        Box::new(client_windows::Test),
+        Box::new(bench_create_window::Test),
+        Box::new(protocol_efficiency::Test),
+        Box::new(slow_connection::Test),
+        Box::new(connection_loss::Test),
+        Box::new(connection_reconnect::Test),
+        Box::new(device_hotplug_stress::Test),
+        Box::new(seat_removal_pressed_keys::Test),
+        Box::new(device_mouse_hotplug::Test),
+        Box::new(test_pattern::Test),
+        Box::new(cursor_grab_unmapped::Test),
+        Box::new(background_color::Test),
+        Box::new(cursor_icons::Test),
+        Box::new(visible_silence::Test),
+        Box::new(visible_initially_false::Test),
+        Box::new(state_at_creation::Test),
+        Box::new(configure_monitors::Test),
+        Box::new(negative_position_at_creation::Test),
+        Box::new(video_modes::Test),
+        Box::new(rapid_set_position::Test),
+        Box::new(extreme_sizes::Test),
+        Box::new(scale_factor::Test),
+        Box::new(degenerate_size_bounds::Test),
+        Box::new(record_replay::Test),
+        Box::new(title_double_click::Test),
+        Box::new(close_button::Test),
+        Box::new(redraw_requested_cross_thread::Test),
+        Box::new(cross_thread_setters::Test),
+        Box::new(blur::Test),
+        Box::new(seat_layouts::Test),
+        Box::new(content_protected::Test),
+        Box::new(type_text::Test),
+        Box::new(key_repeat::Test),
+        Box::new(dead_keys::Test),
+        Box::new(lock_state::Test),
+        Box::new(unaccelerated_motion::Test),
+        Box::new(absolute_pointer::Test),
+        Box::new(cursor_enter_on_move::Test),
+        Box::new(scale_factor_override::Test),
+        Box::new(wm_focus_policies::Test),
+        Box::new(maximize_button::Test),
+        Box::new(drag_resize::Test),
+        Box::new(current_monitor_move::Test),
+        Box::new(drag_move::Test),
+        Box::new(minimize_via_wm::Test),
+        Box::new(harness_awaiters::Test),
+        Box::new(harness_event_stream::Test),
+        Box::new(harness_event_trace::Test),
+        Box::new(harness_expect::Test),
+        Box::new(modifier_taps::Test),
+        Box::new(wm_super_shortcut::Test),
+        Box::new(implicit_grab::Test),
+        Box::new(pager_minimize::Test),
+        Box::new(wm_maximize::Test),
+        Box::new(control_flow_poll::Test),
+        Box::new(control_flow_wait_until::Test),
+        Box::new(pager_fullscreen::Test),
+        Box::new(redraw_requested_once::Test),
+        Box::new(wm_refuse_resize::Test),
+        Box::new(size_bounds_while_maximized::Test),
+        Box::new(redraw_on_expose::Test),
+        Box::new(wm_focus_steal_prevention::Test),
+        Box::new(suspend_resume::Test),
+        Box::new(icon_frames::Test),
+        Box::new(ime_compose::Test),
+        Box::new(window_event_filtering::Test),
+        Box::new(window_level::Test),
     ]
 }
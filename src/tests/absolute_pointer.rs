@@ -0,0 +1,40 @@
+use crate::backend::{BackendFlags, Instance};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::ABSOLUTE_POINTER);
+
+async fn run(instance: &dyn Instance) {
+    let seat = instance.default_seat();
+    let pointer = seat.add_absolute_pointer();
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 200,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(100, 100);
+    window.outer_position(100, 100).await;
+
+    let (ox, oy) = window.inner_offset();
+    for (x, y) in [(10, 10), (150, 20), (3, 170), (199, 199)] {
+        pointer.move_to(100 + ox + x, 100 + oy + y);
+        loop {
+            let (we, cm) = events.window_cursor_moved().await;
+            assert_eq!(we.window_id, window.winit_id());
+            if cm.position
+                == (PhysicalPosition {
+                    x: x as f64,
+                    y: y as f64,
+                })
+            {
+                break;
+            }
+            log::info!("Cursor moved to {:?}", cm.position);
+        }
+        seat.cursor_at(100 + ox + x, 100 + oy + y).await;
+    }
+}
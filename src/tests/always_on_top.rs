@@ -1,7 +1,10 @@
 use crate::backend::{BackendFlags, Instance};
 use winit::window::WindowBuilder;
 
-test!(run, BackendFlags::WINIT_SET_ALWAYS_ON_TOP);
+test!(
+    run,
+    BackendFlags::WINIT_SET_ALWAYS_ON_TOP | BackendFlags::STACKING_ORDER
+);
 
 async fn run(instance: &dyn Instance) {
     let el = instance.create_event_loop();
@@ -21,4 +24,17 @@ async fn run(instance: &dyn Instance) {
         window.winit_set_always_on_top(false);
         window.always_on_top(false).await;
     }
+
+    {
+        let top = el.create_window(WindowBuilder::default().with_always_on_top(true));
+        let other = el.create_window(Default::default());
+        top.mapped(true).await;
+        other.mapped(true).await;
+        top.above(&*other).await;
+        instance.assert_stacking_order(&[&*other, &*top]);
+        other.winit_set_always_on_top(true);
+        top.winit_set_always_on_top(false);
+        other.above(&*top).await;
+        instance.assert_stacking_order(&[&*top, &*other]);
+    }
 }
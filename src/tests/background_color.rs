@@ -0,0 +1,20 @@
+use crate::backend::{BackendFlags, Instance};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::CAPTURE_WINDOW);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_background_color(100, 100, 150);
+
+    let image = instance.capture_window(&*window);
+    assert_eq!(image.pixel(50, 50), [100, 100, 150, 255]);
+    image.assert_similar("background", 0);
+}
@@ -0,0 +1,34 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::test::record_metric;
+use std::time::Instant;
+
+test!(run, BackendFlags::SINGLE_THREADED);
+
+const NUM_WINDOWS: usize = 20;
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let start = Instant::now();
+    for _ in 0..NUM_WINDOWS {
+        let id = {
+            let window = el.create_window(Default::default());
+            window.mapped(true).await;
+            window.winit_id()
+        };
+        loop {
+            let we = events.window_destroyed_event().await;
+            if we.window_id == id {
+                break;
+            }
+        }
+    }
+    let elapsed = start.elapsed();
+
+    record_metric(
+        "create_map_destroy",
+        elapsed.as_secs_f64() * 1000.0 / NUM_WINDOWS as f64,
+        "ms/window",
+    );
+}
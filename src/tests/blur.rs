@@ -0,0 +1,20 @@
+#[cfg(not(winit_blur))]
+missing_api!("Window::set_blur");
+
+#[cfg(winit_blur)]
+test!(run, crate::backend::BackendFlags::WINIT_SET_BLUR);
+
+#[cfg(winit_blur)]
+async fn run(instance: &dyn crate::backend::Instance) {
+    use crate::backend::BlurRegion;
+
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    window.blur(None).await;
+    window.winit_set_blur(true);
+    window.blur(Some(&BlurRegion::Window)).await;
+    window.winit_set_blur(false);
+    window.blur(None).await;
+}
@@ -0,0 +1,28 @@
+use crate::backend::{BackendFlags, Instance};
+
+test!(run, BackendFlags::CLIENT_WINDOWS);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    instance.num_client_windows(0).await;
+
+    let (id1, id2) = {
+        let window1 = el.create_window(Default::default());
+        let window2 = el.create_window(Default::default());
+        window1.mapped(true).await;
+        window2.mapped(true).await;
+        let mut expected = vec![u64::from(window1.winit_id()), u64::from(window2.winit_id())];
+        expected.sort_unstable();
+        assert_eq!(instance.client_windows(), expected);
+        (window1.winit_id(), window2.winit_id())
+    };
+
+    for _ in 0..2 {
+        let we = events.window_destroyed_event().await;
+        assert!(we.window_id == id1 || we.window_id == id2);
+    }
+
+    instance.num_client_windows(0).await;
+}
@@ -0,0 +1,32 @@
+use crate::backend::{BackendFlags, Button, Instance};
+use crate::event::{Event, WindowEvent};
+use crate::test::set_expected_events;
+
+test!(run, BackendFlags::CLOSE_BUTTON);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Mapped);
+    let mouse = s.seat.add_mouse();
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    set_expected_events(&["CloseRequested"]);
+    let (x, y) = s.window.close_button_position();
+    s.seat.set_cursor_position(x, y);
+    drop(mouse.press(Button::Left));
+
+    let we = s.events.window_close_requested().await;
+    assert_eq!(we.window_id, s.window.winit_id());
+    for event in s.events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            match &we.event {
+                WindowEvent::CloseRequested => log::error!("Received a second CloseRequested"),
+                WindowEvent::MouseInput(_) => {
+                    log::error!("Close button click leaked to the client: {:?}", event)
+                }
+                _ => {}
+            }
+        }
+    }
+    s.window.mapped(true).await;
+}
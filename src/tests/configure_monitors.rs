@@ -0,0 +1,74 @@
+use crate::backend::{BackendFlags, EventLoop, Instance, MonitorConfig};
+use crate::sleep::sleep_ms;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::monitor::MonitorHandle;
+
+test!(run, BackendFlags::CONFIGURE_MONITORS);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let layouts = [
+        vec![
+            monitor(0, 0, 1024, 768, 60),
+            monitor(1024, 100, 800, 600, 75),
+        ],
+        vec![monitor(640, 0, 800, 600, 50), monitor(0, 0, 640, 480, 30)],
+        vec![monitor(0, 0, 1280, 720, 60)],
+        vec![monitor(0, 0, 1024, 768, 60)],
+    ];
+    for layout in &layouts {
+        instance.configure_monitors(layout);
+        check_layout(&*el, layout).await;
+    }
+}
+
+fn monitor(x: i32, y: i32, width: u32, height: u32, refresh_rate: u16) -> MonitorConfig {
+    MonitorConfig {
+        x,
+        y,
+        width,
+        height,
+        refresh_rate,
+        scale: 1.0,
+    }
+}
+
+async fn check_layout(el: &dyn EventLoop, layout: &[MonitorConfig]) {
+    log::info!("Waiting for the monitors to match {:?}", layout);
+    let monitors = loop {
+        let monitors = el.available_monitors();
+        if monitors.len() == layout.len() && layout.iter().all(|c| find(&monitors, c).is_some()) {
+            break monitors;
+        }
+        sleep_ms(10).await;
+    };
+    for config in layout {
+        let monitor = find(&monitors, config).unwrap();
+        let modes: Vec<_> = monitor.video_modes().collect();
+        let mode = modes.iter().find(|m| {
+            m.size() == PhysicalSize::new(config.width, config.height)
+                && m.refresh_rate() == config.refresh_rate
+        });
+        if mode.is_none() {
+            log::error!(
+                "Monitor {:?} has no mode matching {:?}: {:?}",
+                monitor.name(),
+                config,
+                modes
+            );
+        }
+    }
+    let primary = el.primary_monitor().unwrap();
+    assert_eq!(
+        primary.position(),
+        PhysicalPosition::new(layout[0].x, layout[0].y)
+    );
+}
+
+fn find<'a>(monitors: &'a [MonitorHandle], config: &MonitorConfig) -> Option<&'a MonitorHandle> {
+    monitors.iter().find(|m| {
+        m.position() == PhysicalPosition::new(config.x, config.y)
+            && m.size() == PhysicalSize::new(config.width, config.height)
+    })
+}
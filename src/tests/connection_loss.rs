@@ -0,0 +1,21 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::test::with_expected_panics;
+
+test!(run, BackendFlags::CONNECTION_PROXY);
+
+async fn run(instance: &dyn Instance) {
+    let (el, proxy) = instance.create_proxied_event_loop();
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+
+    // winit aborts the event loop when it notices that the connection has been lost.
+    with_expected_panics("severing the connection", async {
+        proxy.sever();
+        el.disconnected().await;
+    })
+    .await;
+    match el.connection_error() {
+        Some(e) => log::info!("winit reported the lost connection: {}", e),
+        None => panic!("winit did not report the lost connection"),
+    }
+}
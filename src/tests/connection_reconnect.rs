@@ -0,0 +1,42 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::keyboard::Key::KeyA;
+use crate::test::with_expected_panics;
+use winit::event::ElementState;
+use winit::keyboard::KeyCode;
+
+test!(run, BackendFlags::CONNECTION_PROXY);
+
+async fn run(instance: &dyn Instance) {
+    let (el, proxy) = instance.create_proxied_event_loop();
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+
+    with_expected_panics("severing the connection", async {
+        proxy.sever();
+        el.disconnected().await;
+    })
+    .await;
+    drop(window);
+    drop(el);
+
+    log::info!("Reconnecting through the proxy");
+    proxy.reset_stats();
+    let el = instance.reconnect_proxied_event_loop(&*proxy);
+    let mut events = el.events();
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    let stats = proxy.stats();
+    log::info!("Protocol stats after reconnecting: {:?}", stats);
+    assert!(stats.requests > 0);
+    assert_eq!(stats.errors, 0);
+
+    let seat = instance.default_seat();
+    seat.focus_and_wait(&*window, &mut *events).await;
+    let kb = seat.add_keyboard();
+    kb.press(KeyA);
+    for state in [ElementState::Pressed, ElementState::Released] {
+        let (_, ki) = events.window_keyboard_input().await;
+        assert_eq!(ki.event.physical_key, KeyCode::KeyA);
+        assert_eq!(ki.event.state, state);
+    }
+}
@@ -0,0 +1,21 @@
+#[cfg(not(winit_content_protected))]
+missing_api!("Window::set_content_protected");
+
+#[cfg(winit_content_protected)]
+test!(
+    run,
+    crate::backend::BackendFlags::WINIT_SET_CONTENT_PROTECTED
+);
+
+#[cfg(winit_content_protected)]
+async fn run(instance: &dyn crate::backend::Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    window.content_protected(false).await;
+    window.winit_set_content_protected(true);
+    window.content_protected(true).await;
+    window.winit_set_content_protected(false);
+    window.content_protected(false).await;
+}
@@ -0,0 +1,38 @@
+use crate::backend::Instance;
+use crate::event::Event;
+use winit::event::StartCause;
+use winit::event_loop::ControlFlow;
+
+test!(run);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    log::info!("Checking that the event loop does not wake up on its own");
+    el.barrier().await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::NewEvents(cause) = event {
+            panic!("Event loop woke up with {:?} while waiting", cause);
+        }
+    }
+
+    log::info!("Checking that the event loop wakes up continuously in Poll mode");
+    el.set_control_flow(ControlFlow::Poll);
+    for _ in 0..10 {
+        assert_eq!(events.new_events().await, StartCause::Poll);
+    }
+
+    log::info!("Checking that the wakeups stop after switching back to Wait");
+    el.set_control_flow(ControlFlow::Wait);
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::NewEvents(cause) = event {
+            panic!(
+                "Event loop woke up with {:?} after leaving Poll mode",
+                cause
+            );
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::backend::Instance;
+use crate::event::Event;
+use std::time::{Duration, Instant};
+use winit::event::StartCause;
+use winit::event_loop::ControlFlow;
+
+test!(run);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+
+    let deadline = Instant::now() + Duration::from_millis(300);
+    log::info!("Waiting until {:?}", deadline);
+    el.set_control_flow(ControlFlow::WaitUntil(deadline));
+    let (time, cause) = loop {
+        if let (time, Event::NewEvents(cause)) = events.timed_event().await {
+            break (time, cause);
+        }
+    };
+    match cause {
+        StartCause::ResumeTimeReached {
+            requested_resume, ..
+        } => assert_eq!(requested_resume, deadline),
+        _ => panic!("Expected ResumeTimeReached, got {:?}", cause),
+    }
+    assert!(
+        time >= deadline,
+        "The event loop woke up {:?} before the deadline",
+        deadline - time
+    );
+
+    log::info!("Checking that the deadline is only reported once");
+    for event in events.events_until_quiet(200).await {
+        if let Event::NewEvents(cause) = event {
+            panic!("Event loop woke up with {:?} after the deadline", cause);
+        }
+    }
+
+    log::info!("Checking that the wakeups stop after switching back to Wait");
+    el.set_control_flow(ControlFlow::Wait);
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    for event in events.events_until_quiet(400).await {
+        if let Event::NewEvents(cause) = event {
+            panic!(
+                "Event loop woke up with {:?} after leaving WaitUntil",
+                cause
+            );
+        }
+    }
+}
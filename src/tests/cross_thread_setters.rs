@@ -0,0 +1,99 @@
+use crate::backend::{BackendFlags, Instance};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+use std::time::Duration;
+use winit::dpi::PhysicalSize;
+
+test!(
+    run,
+    BackendFlags::MT_SAFE
+        | BackendFlags::WINIT_SET_TITLE
+        | BackendFlags::WINIT_SET_INNER_SIZE
+        | BackendFlags::WINIT_SET_MINIMIZED,
+    Duration::from_secs(30)
+);
+
+const THREADS: usize = 4;
+/// How long the setters are called.
+const DURATION: Duration = Duration::from_secs(3);
+/// The number of calls per thread between two round trips through the event loop. The
+/// event loop has to be dispatched regularly since the server would otherwise have to
+/// buffer an unbounded number of events.
+const CALLS_PER_BURST: usize = 50;
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(THREADS)
+        .thread_name(|i| format!("setter-{}", i))
+        .build()
+        .unwrap();
+
+    // Each setter is called while the lock of its property is held so that the stored value
+    // is the one that was applied last.
+    let title = Mutex::new(String::new());
+    let size = Mutex::new((0, 0));
+    let minimized = Mutex::new(false);
+    let calls = AtomicUsize::new(0);
+
+    let winit = window.winit();
+    let start = crate::clock::now();
+    let mut burst = 0;
+    while start.elapsed() < DURATION {
+        log::info!(
+            "Starting burst {} ({} calls so far)",
+            burst,
+            calls.load(Relaxed)
+        );
+        pool.scope(|s| {
+            for thread in 0..THREADS {
+                let (title, size, minimized, calls) = (&title, &size, &minimized, &calls);
+                s.spawn(move |_| {
+                    for i in 0..CALLS_PER_BURST {
+                        let n = burst * CALLS_PER_BURST + i;
+                        match (thread + i) % 3 {
+                            0 => {
+                                let mut title = title.lock();
+                                *title = format!("thread {} call {}", thread, n);
+                                winit.set_title(&title);
+                            }
+                            1 => {
+                                let mut size = size.lock();
+                                *size = (100 + 10 * thread as u32, 100 + (n % 200) as u32);
+                                winit.set_inner_size(PhysicalSize::new(size.0, size.1));
+                            }
+                            _ => {
+                                let mut minimized = minimized.lock();
+                                *minimized = (thread + n) % 2 == 0;
+                                winit.set_minimized(*minimized);
+                            }
+                        }
+                        calls.fetch_add(1, Relaxed);
+                    }
+                });
+            }
+        });
+        el.barrier().await;
+        burst += 1;
+    }
+    log::info!("Made {} calls in {} bursts", calls.load(Relaxed), burst);
+
+    let title = title.into_inner();
+    let (width, height) = size.into_inner();
+    let minimized = minimized.into_inner();
+    log::info!(
+        "Last applied values: title {:?}, size {}x{}, minimized {}",
+        title,
+        width,
+        height,
+        minimized
+    );
+    window.title(&title).await;
+    window.inner_size(width, height).await;
+    window.minimized(minimized).await;
+    window.winit_inner_size(width, height).await;
+}
@@ -0,0 +1,74 @@
+use crate::backend::{BackendFlags, EventLoop, Instance, Window};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::SECOND_MONITOR | BackendFlags::SET_OUTER_POSITION
+);
+
+const LEFT: PhysicalPosition<i32> = PhysicalPosition { x: 0, y: 0 };
+const RIGHT: PhysicalPosition<i32> = PhysicalPosition { x: 1024, y: 0 };
+
+async fn run(instance: &dyn Instance) {
+    instance.enable_second_monitor(true);
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+    el.num_available_monitors(2).await;
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 150,
+    }));
+    window.mapped(true).await;
+
+    log::info!("Moving the window onto the first monitor");
+    move_to(&*window, &mut *events, 100, 100).await;
+    check_monitor(&*el, &*window, LEFT);
+
+    log::info!("Moving the window so that it mostly overlaps the first monitor");
+    move_to(&*window, &mut *events, 1024 - 150, 100).await;
+    check_monitor(&*el, &*window, LEFT);
+
+    log::info!("Moving the window fully onto the second monitor");
+    move_to(&*window, &mut *events, 1100, 100).await;
+    check_monitor(&*el, &*window, RIGHT);
+    assert!(window.winit().scale_factor() > 1.0);
+
+    log::info!("Moving the window back onto the first monitor");
+    move_to(&*window, &mut *events, 100, 100).await;
+    check_monitor(&*el, &*window, LEFT);
+    assert_eq!(window.winit().scale_factor(), 1.0);
+}
+
+/// Moves the window and checks that all subsequent `Moved` events report the new position.
+async fn move_to(window: &dyn Window, events: &mut dyn EventStream, x: i32, y: i32) {
+    window.set_outer_position(x, y);
+    events.window_moved_to(window.winit_id(), x, y).await;
+    window.winit_outer_position(x, y).await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Moved(pos) = &we.event {
+                assert_eq!(*pos, PhysicalPosition { x, y });
+            }
+        }
+    }
+}
+
+/// Checks that the current monitor of the window is the monitor at `position` and that the
+/// monitor handles have not moved.
+fn check_monitor(el: &dyn EventLoop, window: &dyn Window, position: PhysicalPosition<i32>) {
+    let current = window.winit().current_monitor().unwrap();
+    assert_eq!(current.position(), position);
+    assert_eq!(current.scale_factor(), window.winit().scale_factor());
+    let mut positions: Vec<_> = el
+        .available_monitors()
+        .iter()
+        .map(|m| (m.position().x, m.position().y))
+        .collect();
+    positions.sort_unstable();
+    assert_eq!(positions, [(LEFT.x, LEFT.y), (RIGHT.x, RIGHT.y)]);
+}
@@ -0,0 +1,42 @@
+use crate::backend::{BackendFlags, Instance};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::SET_OUTER_POSITION);
+
+/// The stationary position of the cursor.
+const CURSOR: (i32, i32) = (200, 200);
+
+async fn run(instance: &dyn Instance) {
+    let seat = instance.default_seat();
+    seat.set_cursor_position(CURSOR.0, CURSOR.1);
+    seat.cursor_at(CURSOR.0, CURSOR.1).await;
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(400, 400);
+    window.outer_position(400, 400).await;
+
+    let (ox, oy) = window.inner_offset();
+    let (x, y) = (CURSOR.0 - ox - 20, CURSOR.1 - oy - 20);
+    log::info!("Moving the window under the cursor");
+    window.set_outer_position(x, y);
+    window.outer_position(x, y).await;
+    let (we, ce) = events.window_cursor_entered().await;
+    assert_eq!(we.window_id, window.winit_id());
+    assert!(seat.is(ce.device_id));
+
+    log::info!("Moving the window away from the cursor");
+    window.set_outer_position(400, 400);
+    window.outer_position(400, 400).await;
+    let (we, cl) = events.window_cursor_left().await;
+    assert_eq!(we.window_id, window.winit_id());
+    assert!(seat.is(cl.device_id));
+    assert_eq!(seat.cursor_position(), CURSOR);
+}
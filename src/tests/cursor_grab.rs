@@ -2,9 +2,14 @@ use crate::backend::{BackendFlags, Instance};
 use winit::dpi::PhysicalSize;
 use winit::window::WindowBuilder;
 
-test!(run, BackendFlags::X11);
+test!(run, BackendFlags::WINIT_SET_CURSOR_GRAB);
 
 async fn run(instance: &dyn Instance) {
+    let grab_window = instance
+        .backend()
+        .flags()
+        .contains(BackendFlags::POINTER_GRAB_WINDOW);
+
     let seat = instance.create_seat();
     let pointer = seat.add_mouse();
 
@@ -22,10 +27,42 @@ async fn run(instance: &dyn Instance) {
 
     window.winit_set_cursor_grab(true);
     instance.cursor_grabbed(true).await;
+    if grab_window {
+        assert!(window.properties().cursor_grabbed());
+        let other = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+            width: 100,
+            height: 100,
+        }));
+        other.mapped(true).await;
+        assert!(!other.properties().cursor_grabbed());
+        drop(other);
+    }
 
     pointer.move_(1, 1);
     events.window_cursor_moved().await;
 
+    let pos = window.winit().inner_position().unwrap();
+    let (x1, y1) = (pos.x, pos.y);
+    let (x2, y2) = (x1 + 100, y1 + 100);
+    for (dx, dy) in [(1000, 0), (0, 1000), (-1000, 0), (0, -1000)] {
+        seat.move_pointer(dx, dy);
+        el.barrier().await;
+        let (x, y) = seat.cursor_position();
+        log::info!("Cursor position after moving by {}x{}: {}x{}", dx, dy, x, y);
+        if x < x1 || x >= x2 || y < y1 || y >= y2 {
+            log::error!("The cursor left the window while it was grabbed");
+        }
+    }
+
     window.winit_set_cursor_grab(false);
     instance.cursor_grabbed(false).await;
+    if grab_window {
+        assert!(!window.properties().cursor_grabbed());
+    }
+
+    seat.move_pointer(1000, 0);
+    el.barrier().await;
+    if seat.cursor_position().0 < x2 {
+        log::error!("The cursor did not leave the window after the grab was released");
+    }
 }
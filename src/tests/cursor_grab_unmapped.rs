@@ -0,0 +1,22 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::test::expect_failure;
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::X11);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(WindowBuilder::new().with_visible(false).with_inner_size(
+        PhysicalSize {
+            width: 100,
+            height: 100,
+        },
+    ));
+
+    expect_failure("grabbing the cursor of an unmapped window", || {
+        window.winit().set_cursor_grab(true)
+    });
+    instance.cursor_grabbed(false).await;
+}
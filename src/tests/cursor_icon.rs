@@ -2,7 +2,10 @@ use crate::backend::{BackendFlags, Instance};
 use winit::dpi::PhysicalSize;
 use winit::window::{CursorIcon, WindowBuilder};
 
-test!(run, BackendFlags::MANUAL_VERIFICATION);
+test!(
+    run,
+    BackendFlags::WINIT_SET_CURSOR_ICON | BackendFlags::MANUAL_VERIFICATION
+);
 
 async fn run(instance: &dyn Instance) {
     let seat = instance.default_seat();
@@ -0,0 +1,80 @@
+use crate::backend::{BackendFlags, Instance};
+use std::time::Duration;
+use winit::dpi::PhysicalSize;
+use winit::window::{CursorIcon, WindowBuilder};
+
+test!(
+    run,
+    BackendFlags::WINIT_SET_CURSOR_ICON,
+    Duration::from_secs(30)
+);
+
+/// The cursor names that winit may use for each icon. Later names are fallbacks.
+const ICONS: &[(CursorIcon, &[&str])] = &[
+    (CursorIcon::Default, &["left_ptr"]),
+    (CursorIcon::Crosshair, &["crosshair"]),
+    (CursorIcon::Hand, &["hand2", "hand1"]),
+    (CursorIcon::Arrow, &["arrow"]),
+    (CursorIcon::Move, &["move"]),
+    (CursorIcon::Text, &["text", "xterm"]),
+    (CursorIcon::Wait, &["watch"]),
+    (CursorIcon::Help, &["question_arrow"]),
+    (CursorIcon::Progress, &["left_ptr_watch"]),
+    (CursorIcon::NotAllowed, &["crossed_circle"]),
+    (CursorIcon::ContextMenu, &["context-menu"]),
+    (CursorIcon::Cell, &["plus"]),
+    (CursorIcon::VerticalText, &["vertical-text"]),
+    (CursorIcon::Alias, &["link"]),
+    (CursorIcon::Copy, &["copy"]),
+    (CursorIcon::NoDrop, &["no-drop", "circle"]),
+    (CursorIcon::Grab, &["openhand", "grab"]),
+    (CursorIcon::Grabbing, &["closedhand", "grabbing"]),
+    (CursorIcon::AllScroll, &["all-scroll"]),
+    (CursorIcon::ZoomIn, &["zoom-in"]),
+    (CursorIcon::ZoomOut, &["zoom-out"]),
+    (CursorIcon::EResize, &["right_side"]),
+    (CursorIcon::NResize, &["top_side"]),
+    (CursorIcon::NeResize, &["top_right_corner"]),
+    (CursorIcon::NwResize, &["top_left_corner"]),
+    (CursorIcon::SResize, &["bottom_side"]),
+    (CursorIcon::SeResize, &["bottom_right_corner"]),
+    (CursorIcon::SwResize, &["bottom_left_corner"]),
+    (CursorIcon::WResize, &["left_side"]),
+    (CursorIcon::EwResize, &["h_double_arrow"]),
+    (CursorIcon::NsResize, &["v_double_arrow"]),
+    (CursorIcon::NwseResize, &["bd_double_arrow", "size_bdiag"]),
+    (CursorIcon::NeswResize, &["fd_double_arrow", "size_fdiag"]),
+    (CursorIcon::ColResize, &["split_h", "h_double_arrow"]),
+    (CursorIcon::RowResize, &["split_v", "v_double_arrow"]),
+];
+
+async fn run(instance: &dyn Instance) {
+    let seat = instance.default_seat();
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(100, 100);
+    window.outer_position(100, 100).await;
+    seat.set_cursor_position(window.inner_offset().0 + 150, window.inner_offset().1 + 150);
+    events.window_cursor_entered().await;
+
+    for &(icon, names) in ICONS {
+        window.winit_set_cursor_icon(icon);
+        let res = tokio::time::timeout(Duration::from_secs(1), instance.cursor_icon_in(names));
+        match res.await {
+            Ok(name) => log::info!("{:?} is displayed as {}", icon, name),
+            Err(_) => log::error!(
+                "{:?} is displayed as {:?} instead of one of {:?}",
+                icon,
+                instance.cursor_icon(),
+                names
+            ),
+        }
+    }
+}
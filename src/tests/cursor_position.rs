@@ -1,5 +1,4 @@
 use crate::backend::{BackendFlags, Instance};
-use crate::sleep::sleep_ms;
 use winit::dpi::PhysicalPosition;
 
 test!(run, BackendFlags::WINIT_SET_CURSOR_POSITION);
@@ -16,12 +15,7 @@ async fn run(instance: &dyn Instance) {
     window.outer_position(100, 100).await;
     window.winit_set_cursor_position(PhysicalPosition { x: 20, y: 30 });
 
-    loop {
-        let pos = seat.cursor_position();
-        if pos == (120 + window.inner_offset().0, 130 + window.inner_offset().1) {
-            break;
-        }
-        log::info!("cursor position = {:?}", pos);
-        sleep_ms(10).await;
-    }
+    let (x, y) = (120 + window.inner_offset().0, 130 + window.inner_offset().1);
+    seat.cursor_at(x, y).await;
+    instance.cursor_at(x, y).await;
 }
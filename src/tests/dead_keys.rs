@@ -0,0 +1,64 @@
+use crate::backend::{Instance, Keyboard};
+use crate::eventstream::EventStream;
+use crate::keyboard::Key::{self, *};
+use crate::keyboard::Layout;
+use winit::event::ElementState;
+use winit::keyboard::{Key as WKey, KeyCode};
+
+test!(run);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+    s.seat.set_layout(Layout::UsIntl);
+
+    let sequences = [
+        (KeyApostrophe, false, "é"),
+        (KeyApostrophe, true, "ë"),
+        (KeyGrave, false, "è"),
+        (KeyGrave, true, "ẽ"),
+        (Key6, true, "ê"),
+    ];
+    for (dead, shift, composed) in sequences {
+        log::info!("Testing {:?} (shift: {}) + e", dead, shift);
+        press_dead_key(&mut *s.events, &*kb, dead, shift).await;
+        assert_eq!(press_e(&mut *s.events, &*kb).await, composed);
+    }
+
+    log::info!("Testing dead key reset");
+    press_dead_key(&mut *s.events, &*kb, KeyApostrophe, false).await;
+    s.window.reset_dead_keys();
+    assert_eq!(press_e(&mut *s.events, &*kb).await, "e");
+}
+
+/// Presses and releases `key`, optionally with shift held, and asserts that winit
+/// reports it as a dead key without text.
+async fn press_dead_key(events: &mut dyn EventStream, kb: &dyn Keyboard, key: Key, shift: bool) {
+    {
+        let _shift = shift.then(|| kb.press(KeyLeftshift));
+        kb.press(key);
+    }
+    let mut released = false;
+    while !released {
+        let (_, ki) = events.window_keyboard_input().await;
+        if ki.event.physical_key == KeyCode::ShiftLeft {
+            continue;
+        }
+        assert!(matches!(ki.event.logical_key, WKey::Dead(_)));
+        assert_eq!(ki.event.text, None);
+        released = ki.event.state == ElementState::Released;
+    }
+}
+
+/// Presses and releases `e` and returns the text of the press.
+async fn press_e(events: &mut dyn EventStream, kb: &dyn Keyboard) -> String {
+    kb.press(KeyE);
+    let (_, ki) = events.window_keyboard_input().await;
+    assert_eq!(ki.event.physical_key, KeyCode::KeyE);
+    assert_eq!(ki.event.state, ElementState::Pressed);
+    let text = ki.event.text.unwrap_or("").to_string();
+    let (_, ki) = events.window_keyboard_input().await;
+    assert_eq!(ki.event.physical_key, KeyCode::KeyE);
+    assert_eq!(ki.event.state, ElementState::Released);
+    text
+}
@@ -8,17 +8,24 @@ async fn run(instance: &dyn Instance) {
 
     {
         let window = el.create_window(Default::default());
-        window.decorations(true).await;
+        let before = window.decorations(true).await;
+        assert_ne!(window.properties().motif_decorations(), Some(false));
         window.winit_set_decorations(false);
-        window.decorations(false).await;
+        let after = window.decorations(false).await;
+        assert_eq!(window.properties().motif_decorations(), Some(false));
+        // Removing the decorations must not change the size of the client area.
+        assert_eq!((after.width, after.height), (before.width, before.height));
         window.winit_set_decorations(true);
         window.decorations(true).await;
+        assert_ne!(window.properties().motif_decorations(), Some(false));
     }
 
     {
         let window = el.create_window(WindowBuilder::default().with_decorations(false));
         window.decorations(false).await;
+        assert_eq!(window.properties().motif_decorations(), Some(false));
         window.winit_set_decorations(true);
         window.decorations(true).await;
+        assert_ne!(window.properties().motif_decorations(), Some(false));
     }
 }
@@ -0,0 +1,76 @@
+use crate::backend::{BackendFlags, EventLoop, Instance, Window, NONE_SIZE};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::WINIT_SET_SIZE_BOUNDS);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 300,
+        height: 200,
+    }));
+    window.mapped(true).await;
+    window.inner_size(300, 200).await;
+
+    log::info!("Testing min size equal to max size");
+    window.winit_set_min_size(Some(PhysicalSize {
+        width: 300,
+        height: 200,
+    }));
+    window.winit_set_max_size(Some(PhysicalSize {
+        width: 300,
+        height: 200,
+    }));
+    window.min_size(Some((300, 200))).await;
+    window.max_size(Some((300, 200))).await;
+    window.resizable(false).await;
+
+    log::info!("Testing min size greater than max size");
+    window.winit_set_min_size(Some(PhysicalSize {
+        width: 500,
+        height: 400,
+    }));
+    window.min_size(Some((500, 400))).await;
+    window.max_size(Some((300, 200))).await;
+    window.resizable(true).await;
+
+    window.winit_set_min_size(NONE_SIZE);
+    window.winit_set_max_size(NONE_SIZE);
+    window.min_size(None).await;
+    window.max_size(None).await;
+
+    for (width, height) in [(0, 0), (0, 100), (100, 0), (u32::MAX, u32::MAX)] {
+        log::info!("Testing bounds {}x{}", width, height);
+        window.winit_set_min_size(Some(PhysicalSize { width, height }));
+        window.winit_set_max_size(Some(PhysicalSize { width, height }));
+        check_bounds(&*el, &*window, &format!("{}x{}", width, height)).await;
+        window.winit_set_min_size(NONE_SIZE);
+        window.winit_set_max_size(NONE_SIZE);
+        window.min_size(None).await;
+        window.max_size(None).await;
+    }
+}
+
+async fn check_bounds(el: &dyn EventLoop, window: &dyn Window, marker: &str) {
+    // The window manager processes property changes in order. Once it has seen the title
+    // that is set after the size hints, it has also processed the size hints.
+    window.winit_set_title(marker);
+    el.barrier().await;
+    let props = window.title(marker).await;
+    let (min, max) = (props.min_size, props.max_size);
+    log::info!("Min size: {:?}, max size: {:?}", min, max);
+    for (name, size) in [("min", min), ("max", max)] {
+        if let Some((width, height)) = size {
+            if width > i32::MAX as u32 || height > i32::MAX as u32 {
+                log::error!("winit wrote a negative {} size: {:?}", name, size);
+            }
+        }
+    }
+    if let Some((width, height)) = max {
+        if width == 0 || height == 0 {
+            log::error!("winit wrote an empty max size: {:?}", max);
+        }
+    }
+}
@@ -2,7 +2,7 @@ use crate::backend::{BackendFlags, Instance};
 
 test!(
     run,
-    BackendFlags::DEVICE_ADDED | BackendFlags::DEVICE_REMOVED
+    BackendFlags::DEVICE_ADDED | BackendFlags::DEVICE_REMOVED | BackendFlags::DISTINCT_DEVICES
 );
 
 async fn run(instance: &dyn Instance) {
@@ -0,0 +1,64 @@
+use crate::backend::{BackendFlags, Instance, Keyboard};
+use crate::keyboard::Key;
+use winit::event::ElementState;
+use winit::keyboard::KeyCode;
+
+test!(
+    run,
+    BackendFlags::DEVICE_ADDED | BackendFlags::DEVICE_REMOVED | BackendFlags::DISTINCT_DEVICES
+);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let seat = instance.default_seat();
+
+    let typist = seat.add_keyboard();
+    let typist_id = typist.id();
+    let de = events.device_added_event().await;
+    assert!(typist_id.is(de.device_id));
+
+    log::info!("Adding and removing keyboards one at a time");
+    for _ in 0..20 {
+        let kb = seat.add_keyboard();
+        let kb_id = kb.id();
+        let de = events.device_added_event().await;
+        assert!(kb_id.is(de.device_id));
+
+        typist.press(Key::KeyA);
+        for state in [ElementState::Pressed, ElementState::Released] {
+            let (de, ke) = events.device_key_event().await;
+            assert!(typist_id.is(de.device_id));
+            assert_eq!(ke.physical_key, KeyCode::KeyA);
+            assert_eq!(ke.state, state);
+        }
+
+        drop(kb);
+        let de = events.device_removed_event().await;
+        assert!(kb_id.is(de.device_id));
+    }
+
+    log::info!("Adding and removing keyboards in bursts");
+    for _ in 0..3 {
+        let kbs: Vec<Box<dyn Keyboard>> = (0..10).map(|_| seat.add_keyboard()).collect();
+        for kb in &kbs {
+            let de = events.device_added_event().await;
+            assert!(kb.id().is(de.device_id));
+        }
+        let ids: Vec<_> = kbs.iter().map(|kb| kb.id()).collect();
+        drop(kbs);
+        for id in &ids {
+            let de = events.device_removed_event().await;
+            assert!(id.is(de.device_id));
+        }
+
+        typist.press(Key::KeyS);
+        for state in [ElementState::Pressed, ElementState::Released] {
+            let (de, ke) = events.device_key_event().await;
+            assert!(typist_id.is(de.device_id));
+            assert_eq!(ke.physical_key, KeyCode::KeyS);
+            assert_eq!(ke.state, state);
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::WindowEvent;
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::DEVICE_ADDED | BackendFlags::DEVICE_REMOVED | BackendFlags::DISTINCT_DEVICES
+);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let seat = instance.default_seat();
+    seat.set_cursor_position(500, 500);
+
+    log::info!("Testing added and removed events of mice");
+    let mouse1 = seat.add_mouse();
+    let mouse2 = seat.add_mouse();
+    let mouse1_id = mouse1.id();
+    let mouse2_id = mouse2.id();
+
+    let dev1 = events.device_added_event().await;
+    let dev2 = events.device_added_event().await;
+    assert!(mouse1_id.is(dev1.device_id));
+    assert!(mouse2_id.is(dev2.device_id));
+    assert!(!mouse1_id.is(dev2.device_id));
+
+    drop(mouse2);
+    let dev2 = events.device_removed_event().await;
+    assert!(mouse2_id.is(dev2.device_id));
+
+    log::info!("Testing removal of the mouse under the cursor");
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(-window.inner_offset().0, -window.inner_offset().1);
+    window
+        .outer_position(-window.inner_offset().0, -window.inner_offset().1)
+        .await;
+
+    mouse1.move_to(50, 50);
+    let (we, _) = events.window_cursor_entered().await;
+    assert_eq!(we.window_id, window.winit_id());
+
+    drop(mouse1);
+    let dev1 = events.device_removed_event().await;
+    assert!(mouse1_id.is(dev1.device_id));
+
+    let mouse3 = seat.add_mouse();
+    let dev3 = events.device_added_event().await;
+    assert!(mouse3.id().is(dev3.device_id));
+    mouse3.move_(1, 1);
+    loop {
+        let we = events.window_event().await;
+        match &we.event {
+            WindowEvent::CursorMoved(cm) => {
+                assert_eq!(we.window_id, window.winit_id());
+                assert_eq!((cm.position.x, cm.position.y), (51.0, 51.0));
+                break;
+            }
+            WindowEvent::CursorEntered(_) | WindowEvent::CursorLeft(_) => {
+                panic!("Unexpected enter/leave event: {:?}", we.event);
+            }
+            _ => {}
+        }
+    }
+}
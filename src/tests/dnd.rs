@@ -21,7 +21,7 @@ async fn run(instance: &dyn Instance) {
     let path = instance.create_dnd_path("test.txt");
 
     {
-        let process = instance.start_dnd_process(&path);
+        let process = instance.start_dnd_process(&[path.as_path()]);
         process.drag_to(50, 50);
 
         let (_, hf) = events.window_hovered_file().await;
@@ -36,7 +36,7 @@ async fn run(instance: &dyn Instance) {
     {
         el.barrier().await;
 
-        let process = instance.start_dnd_process(&path);
+        let process = instance.start_dnd_process(&[path.as_path()]);
         process.drag_to(50, 50);
 
         let (_, hf) = events.window_hovered_file().await;
@@ -50,7 +50,7 @@ async fn run(instance: &dyn Instance) {
     {
         el.barrier().await;
 
-        let process = instance.start_dnd_process(&path);
+        let process = instance.start_dnd_process(&[path.as_path()]);
         process.drag_to(50, 50);
 
         let (_, hf) = events.window_hovered_file().await;
@@ -60,4 +60,24 @@ async fn run(instance: &dyn Instance) {
 
         events.window_hovered_file_canceled().await;
     }
+
+    {
+        el.barrier().await;
+
+        let path2 = instance.create_dnd_path("test2.txt");
+        let process = instance.start_dnd_process(&[path.as_path(), path2.as_path()]);
+        process.drag_to(50, 50);
+
+        let (_, hf) = events.window_hovered_file().await;
+        assert_eq!(hf, path);
+        let (_, hf) = events.window_hovered_file().await;
+        assert_eq!(hf, path2);
+
+        process.do_drop();
+
+        let (_, hf) = events.window_dropped_file().await;
+        assert_eq!(hf, path);
+        let (_, hf) = events.window_dropped_file().await;
+        assert_eq!(hf, path2);
+    }
 }
@@ -0,0 +1,76 @@
+use crate::backend::{BackendFlags, Button, Instance, Window};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::DRAG_MOVE | BackendFlags::SET_OUTER_POSITION
+);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 150,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(100, 100);
+    window.outer_position(100, 100).await;
+    window.winit_outer_position(100, 100).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+
+    log::info!("Dragging the title bar");
+    window.drag_move(50, 30).await;
+    check_moves(&*window, &mut *events, (100, 100), (150, 130)).await;
+
+    log::info!("Dragging the title bar a second time");
+    window.drag_move(-70, 20).await;
+    check_moves(&*window, &mut *events, (150, 130), (80, 150)).await;
+    assert_eq!(window.properties().maximized(), Some(false));
+
+    log::info!("Dragging via Window::drag_window");
+    let seat = instance.default_seat();
+    let mouse = seat.add_mouse();
+    let (left, top) = window.inner_offset();
+    seat.set_cursor_position(80 + left + 20, 150 + top + 20);
+    let button = mouse.press(Button::Left);
+    events.window_mouse_input_event().await;
+    window.winit().drag_window().unwrap();
+    window.dragging(true).await;
+    mouse.move_(10, 5);
+    mouse.move_(30, 15);
+    drop(button);
+    window.dragging(false).await;
+    check_moves(&*window, &mut *events, (80, 150), (120, 170)).await;
+}
+
+/// Checks that the `Moved` events lie on the path from `from` to `to` in order and that the
+/// window ends up at `to`.
+async fn check_moves(
+    window: &dyn Window,
+    events: &mut dyn EventStream,
+    from: (i32, i32),
+    to: (i32, i32),
+) {
+    window.outer_position(to.0, to.1).await;
+    window.winit_outer_position(to.0, to.1).await;
+    let mut last = from;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Moved(pos) = &we.event {
+                assert_eq!(we.window_id, window.winit_id());
+                let pos = (pos.x, pos.y);
+                let between = |a: i32, b: i32, c: i32| a.min(c) <= b && b <= a.max(c);
+                assert!(between(last.0, pos.0, to.0), "{:?}", event);
+                assert!(between(last.1, pos.1, to.1), "{:?}", event);
+                last = pos;
+            }
+        }
+    }
+    assert_eq!(last, to);
+}
@@ -0,0 +1,56 @@
+use crate::backend::{BackendFlags, Instance, ResizeEdge, WmConfig};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::DRAG_RESIZE
+        | BackendFlags::SET_OUTER_POSITION
+        | BackendFlags::WINIT_SET_RESIZABLE
+);
+
+async fn run(instance: &dyn Instance) {
+    instance.configure_wm(WmConfig {
+        border_width: 6,
+        ..Default::default()
+    });
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 150,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(100, 100);
+    window.outer_position(100, 100).await;
+
+    log::info!("Dragging the right edge");
+    window.drag_resize_edge(ResizeEdge::Right, 50, 0).await;
+    window.inner_size(250, 150).await;
+    events.window_resized_to(window.winit_id(), 250, 150).await;
+    window.winit_inner_size(250, 150).await;
+    window.outer_position(100, 100).await;
+
+    log::info!("Dragging the top left corner");
+    window.drag_resize_edge(ResizeEdge::TopLeft, -20, -30).await;
+    window.inner_size(270, 180).await;
+    events.window_resized_to(window.winit_id(), 270, 180).await;
+    window.winit_inner_size(270, 180).await;
+    window.outer_position(80, 70).await;
+
+    log::info!("Dragging the bottom edge of a non-resizable window");
+    window.winit_set_resizable(false);
+    window.resizable(false).await;
+    window.drag_resize_edge(ResizeEdge::Bottom, 0, 40).await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Resized(..) = &we.event {
+                panic!("Non-resizable window was resized: {:?}", event);
+            }
+        }
+    }
+    window.inner_size(270, 180).await;
+}
@@ -0,0 +1,52 @@
+use crate::backend::{BackendFlags, Instance};
+use winit::dpi::PhysicalSize;
+
+test!(run, BackendFlags::WINIT_SET_INNER_SIZE);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+
+    log::info!("Testing 1x1");
+    window.winit_set_inner_size(PhysicalSize {
+        width: 1,
+        height: 1,
+    });
+    window.inner_size(1, 1).await;
+    window.winit_inner_size(1, 1).await;
+
+    log::info!("Testing sizes larger than the screen");
+    let mon = window.winit().current_monitor().unwrap();
+    let (width, height) = (mon.size().width * 3, mon.size().height * 2);
+    window.winit_set_inner_size(PhysicalSize { width, height });
+    window.inner_size(width, height).await;
+    window.winit_inner_size(width, height).await;
+
+    for (width, height) in [
+        (u16::MAX as u32 - 1, u16::MAX as u32 - 1),
+        (u16::MAX as u32, u16::MAX as u32),
+        (u16::MAX as u32 + 1, u16::MAX as u32 + 1),
+        (u32::MAX, u32::MAX),
+        (0, 0),
+    ] {
+        log::info!("Testing {}x{}", width, height);
+        window.winit_set_inner_size(PhysicalSize { width, height });
+        el.barrier().await;
+        // The size may be clamped but winit must agree with the window manager.
+        let props = window.properties();
+        let (actual_width, actual_height) = (props.width(), props.height());
+        log::info!("Actual size: {}x{}", actual_width, actual_height);
+        assert!(actual_width >= 1 && actual_height >= 1);
+        window.winit_inner_size(actual_width, actual_height).await;
+    }
+
+    log::info!("Testing that the window can still be resized");
+    window.winit_set_inner_size(PhysicalSize {
+        width: 200,
+        height: 100,
+    });
+    window.inner_size(200, 100).await;
+    window.winit_inner_size(200, 100).await;
+}
@@ -16,18 +16,21 @@ async fn run(instance: &dyn Instance) {
     let (we, focus) = events.window_focus_event().await;
     assert_eq!(we.window_id, window.winit_id());
     assert!(focus);
+    assert!(seat.is_focused(&*window));
 
     seat.un_focus();
 
     let (we, focus) = events.window_focus_event().await;
     assert_eq!(we.window_id, window.winit_id());
     assert!(!focus);
+    assert!(!seat.is_focused(&*window));
 
     seat.focus(&*window);
 
     let (we, focus) = events.window_focus_event().await;
     assert_eq!(we.window_id, window.winit_id());
     assert!(focus);
+    assert!(seat.is_focused(&*window));
 
     let window2 = el.create_window(Default::default());
     window2.mapped(true).await;
@@ -41,10 +44,13 @@ async fn run(instance: &dyn Instance) {
     let (we, focus) = events.window_focus_event().await;
     assert_eq!(we.window_id, window2.winit_id());
     assert!(focus);
+    assert!(seat.is_focused(&*window2));
+    assert!(!seat.is_focused(&*window));
 
     seat.un_focus();
 
     let (we, focus) = events.window_focus_event().await;
     assert_eq!(we.window_id, window2.winit_id());
     assert!(!focus);
+    assert!(!seat.is_focused(&*window2));
 }
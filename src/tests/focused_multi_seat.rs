@@ -12,17 +12,9 @@ async fn run(instance: &dyn Instance) {
     let window = el.create_window(Default::default());
     window.mapped(true).await;
 
-    seat.focus(&*window);
-
-    let (we, focus) = events.window_focus_event().await;
-    assert_eq!(we.window_id, window.winit_id());
-    assert!(focus);
-
-    seat2.focus(&*window);
-
-    let (we, focus) = events.window_focus_event().await;
-    assert_eq!(we.window_id, window.winit_id());
-    assert!(focus);
+    seat.focus_and_wait(&*window, &mut *events).await;
+    seat2.focus_and_wait(&*window, &mut *events).await;
+    assert!(seat.is_focused(&*window));
 
     seat.un_focus();
 
@@ -69,6 +61,8 @@ async fn run(instance: &dyn Instance) {
     let (we, focus) = events.window_focus_event().await;
     assert_eq!(we.window_id, window2.winit_id());
     assert!(focus);
+    assert!(seat.is_focused(&*window2));
+    assert!(seat2.is_focused(&*window2));
 
     seat.un_focus();
 
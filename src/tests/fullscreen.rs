@@ -1,8 +1,8 @@
-use crate::backend::Instance;
+use crate::backend::{BackendFlags, Instance};
 use winit::dpi::PhysicalSize;
 use winit::window::{Fullscreen, WindowBuilder};
 
-test!(run);
+test!(run, BackendFlags::WINIT_SET_FULLSCREEN);
 
 async fn run(instance: &dyn Instance) {
     let el = instance.create_event_loop();
@@ -20,10 +20,14 @@ async fn run(instance: &dyn Instance) {
     let modes: Vec<_> = mon.video_modes().collect();
 
     window.winit_set_fullscreen(Some(Fullscreen::Borderless(None)));
+    window.fullscreen(true).await;
+    window.winit_fullscreen(true).await;
     window.outer_position(0, 0).await;
     window.inner_size(mon.size().width, mon.size().height).await;
 
     window.winit_set_fullscreen(None);
+    window.fullscreen(false).await;
+    window.winit_fullscreen(false).await;
     window.outer_position(100, 100).await;
     window.inner_size(500, 400).await;
 
@@ -33,6 +37,8 @@ async fn run(instance: &dyn Instance) {
 
     for mode in modes.clone() {
         window.winit_set_fullscreen(Some(Fullscreen::Exclusive(mode.clone())));
+        window.fullscreen(true).await;
+        window.winit_fullscreen(true).await;
         window.outer_position(0, 0).await;
         window
             .inner_size(mode.size().width, mode.size().height)
@@ -41,6 +47,8 @@ async fn run(instance: &dyn Instance) {
         assert_eq!(mon.size(), mode.size());
 
         window.winit_set_fullscreen(None);
+        window.fullscreen(false).await;
+        window.winit_fullscreen(false).await;
         window.outer_position(100, 100).await;
         window.inner_size(500, 400).await;
         let mon = window.winit().current_monitor().unwrap();
@@ -51,6 +59,8 @@ async fn run(instance: &dyn Instance) {
 
     for mode in modes.clone() {
         window.winit_set_fullscreen(Some(Fullscreen::Exclusive(mode.clone())));
+        window.fullscreen(true).await;
+        window.winit_fullscreen(true).await;
         window.outer_position(0, 0).await;
         window
             .inner_size(mode.size().width, mode.size().height)
@@ -60,6 +70,8 @@ async fn run(instance: &dyn Instance) {
     }
 
     window.winit_set_fullscreen(None);
+    window.fullscreen(false).await;
+    window.winit_fullscreen(false).await;
     window.outer_position(100, 100).await;
     window.inner_size(500, 400).await;
     let mon = window.winit().current_monitor().unwrap();
@@ -2,7 +2,10 @@ use crate::backend::{BackendFlags, Instance};
 use winit::dpi::PhysicalSize;
 use winit::window::{Fullscreen, WindowBuilder};
 
-test!(run, BackendFlags::SECOND_MONITOR);
+test!(
+    run,
+    BackendFlags::SECOND_MONITOR | BackendFlags::WINIT_SET_FULLSCREEN
+);
 
 async fn run(instance: &dyn Instance) {
     instance.enable_second_monitor(true);
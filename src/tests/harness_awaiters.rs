@@ -0,0 +1,45 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::backends::mock::mock_window;
+use crate::sleep::sleep_ms;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::MOCK);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let window = el.create_window(
+        WindowBuilder::default()
+            .with_title("abc")
+            .with_visible(false),
+    );
+    let mock = mock_window(&*window);
+
+    // Awaiters return immediately if the property already has the value.
+    window.title("abc").await;
+    window.mapped(false).await;
+
+    // Awaiters are woken up by concurrent changes and only return once all changes they
+    // wait for have been applied.
+    let change = async {
+        sleep_ms(10).await;
+        mock.set_properties(|p| p.width = 100);
+        sleep_ms(10).await;
+        mock.set_properties(|p| {
+            p.height = 200;
+            p.mapped = true;
+        });
+    };
+    let (snapshot, ()) = futures::join!(window.inner_size(100, 200), change);
+    assert!(snapshot.mapped);
+    assert_eq!((snapshot.width, snapshot.height), (100, 200));
+
+    // Awaiters of different properties of the same window can wait concurrently.
+    let change = async {
+        sleep_ms(10).await;
+        mock.set_properties(|p| {
+            p.title = Some("def".to_string());
+            p.maximized = Some(true);
+        });
+    };
+    futures::join!(window.title("def"), window.maximized(true), change);
+}
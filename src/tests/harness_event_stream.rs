@@ -0,0 +1,57 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::backends::mock::mock_window;
+use crate::event::{Event, UserEvent, WindowEvent};
+use winit::dpi::PhysicalSize;
+
+test!(run, BackendFlags::MOCK);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let window = el.create_window(Default::default());
+    let mock = mock_window(&*window);
+    let mut events = el.events();
+
+    // Combinators skip events of other kinds.
+    mock.send_window_event(WindowEvent::Focused(true));
+    mock.send_event(Event::UserEvent(UserEvent(1)));
+    assert_eq!(events.user_event().await, UserEvent(1));
+
+    mock.send_event(Event::UserEvent(UserEvent(2)));
+    mock.send_window_event(WindowEvent::Resized(PhysicalSize::new(1, 2)));
+    let we = events.window_event().await;
+    assert_eq!(we.window_id, mock.winit_id());
+    assert_eq!(we.event, WindowEvent::Resized(PhysicalSize::new(1, 2)));
+
+    // Window event streams skip the events that do not belong to the window. These stay
+    // in the queue for the other streams.
+    let mut window_events = el.window_events(mock.winit_id());
+    mock.send_event(Event::UserEvent(UserEvent(3)));
+    mock.send_event(Event::RedrawRequested(mock.winit_id()));
+    assert_eq!(
+        window_events.event().await,
+        Event::RedrawRequested(mock.winit_id())
+    );
+    drop(window_events);
+    assert_eq!(events.user_event().await, UserEvent(3));
+
+    // Events are delivered in order.
+    mock.send_window_event(WindowEvent::Focused(false));
+    window.delete();
+    mock.send_window_event(WindowEvent::Destroyed);
+    let events = events.events_until_quiet(50).await;
+    let events: Vec<_> = events
+        .into_iter()
+        .map(|e| match e {
+            Event::WindowEvent(we) => we.event,
+            e => panic!("Unexpected event {:?}", e),
+        })
+        .collect();
+    assert_eq!(
+        events,
+        [
+            WindowEvent::Focused(false),
+            WindowEvent::CloseRequested,
+            WindowEvent::Destroyed,
+        ]
+    );
+}
@@ -0,0 +1,46 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::backends::mock::mock_window;
+use crate::event::{Event, UserEvent, WindowEvent};
+use crate::sleep::sleep_ms;
+use crate::test::{set_expected_events, with_test_data};
+
+test!(run, BackendFlags::MOCK);
+
+/// Produces the event trace that the runner writes when a test fails after it has
+/// declared the events it expects.
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let window = el.create_window(Default::default());
+    let mock = mock_window(&*window);
+
+    mock.send_window_event(WindowEvent::Focused(false));
+    sleep_ms(10).await;
+    set_expected_events(&["Focused", "Resized"]);
+    mock.send_window_event(WindowEvent::Focused(true));
+    mock.send_event(Event::UserEvent(UserEvent(1)));
+
+    let dir = with_test_data(|td| td.test_dir.join("trace"));
+    if let Err(e) = std::fs::create_dir_all(&dir) {
+        panic!("Could not create {}: {}", dir.display(), e);
+    }
+    with_test_data(|td| {
+        crate::eventtrace::dump(
+            &td.event_loops.borrow(),
+            td.expected_events.borrow().as_ref(),
+            &dir,
+        )
+    });
+
+    let path = dir.join("events.json");
+    let trace: serde_json::Value = match std::fs::read(&path) {
+        Ok(trace) => serde_json::from_slice(&trace).unwrap(),
+        Err(e) => panic!("Could not read {}: {}", path.display(), e),
+    };
+    let trace = &trace[0];
+    assert_eq!(trace["events"].as_array().unwrap().len(), 4);
+    // Only the events received after `set_expected_events` are diffed.
+    assert_eq!(
+        trace["diff"].as_str().unwrap(),
+        "  Focused\n+ UserEvent\n- Resized\n"
+    );
+}
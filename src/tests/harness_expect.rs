@@ -0,0 +1,111 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::backends::mock::mock_window;
+use crate::event::{Event, UserEvent, WindowEvent, WindowEventExt};
+use crate::expect::Expect;
+use crate::test::expect_panic;
+use std::time::Duration;
+use winit::dpi::PhysicalSize;
+
+test!(run, BackendFlags::MOCK);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let window = el.create_window(Default::default());
+    let mock = mock_window(&*window);
+    let mut events = el.events();
+    let resized = |width| WindowEvent::Resized(PhysicalSize::new(width, 1));
+
+    log::info!("Ordered events with skipped events in between");
+    mock.send_window_event(WindowEvent::Focused(true));
+    mock.send_event(Event::UserEvent(UserEvent(1)));
+    mock.send_window_event(resized(2));
+    let matched = expect_events!(
+        events,
+        [Event::UserEvent(UserEvent(1)), Event::WindowEvent(we) if we.event == resized(2)]
+    );
+    assert_eq!(matched.len(), 2);
+    assert!(matches!(&matched[1], Event::WindowEvent(we) if we.event == resized(2)));
+
+    log::info!("Unordered events");
+    mock.send_window_event(resized(3));
+    mock.send_event(Event::UserEvent(UserEvent(2)));
+    let matched = expect_events!(events, unordered [
+        Event::UserEvent(UserEvent(2)),
+        Event::WindowEvent(WindowEventExt { event: WindowEvent::Resized(size), .. })
+            if size.width == 3 => Duration::from_secs(1),
+    ]);
+    // The matched events are returned in the order of the expectations.
+    assert_eq!(matched[0], Event::UserEvent(UserEvent(2)));
+
+    log::info!("Out-of-order events in ordered mode");
+    mock.send_window_event(resized(4));
+    mock.send_window_event(WindowEvent::Focused(false));
+    let msg = expect_panic(
+        "consuming out-of-order events",
+        events.expect(
+            Expect::ordered()
+                .window_event("Focused(false)", |e| *e == WindowEvent::Focused(false))
+                .window_event("Resized", |e| matches!(e, WindowEvent::Resized(_))),
+        ),
+    )
+    .await;
+    assert!(msg.contains("unexpected event"), "{}", msg);
+    assert!(msg.contains("+ WindowEvent"), "{}", msg);
+    assert!(msg.contains("- Focused(false)"), "{}", msg);
+    events.events_until_quiet(10).await;
+
+    log::info!("Strict filter");
+    mock.send_event(Event::UserEvent(UserEvent(3)));
+    mock.send_window_event(WindowEvent::Focused(true));
+    let msg = expect_panic(
+        "consuming an event rejected by the strict filter",
+        events.expect(
+            Expect::ordered()
+                .strict(|e| matches!(e, Event::UserEvent(_)))
+                .window_event("Focused(true)", |e| *e == WindowEvent::Focused(true)),
+        ),
+    )
+    .await;
+    assert!(msg.contains("+ UserEvent(UserEvent(3))"), "{}", msg);
+    events.events_until_quiet(10).await;
+
+    log::info!("Ordered timeout");
+    mock.send_window_event(WindowEvent::Focused(true));
+    let msg = expect_panic(
+        "waiting for an event that is never sent",
+        events.expect(
+            Expect::ordered()
+                .window_event("Focused(true)", |e| *e == WindowEvent::Focused(true))
+                .event("UserEvent(4)", |e| *e == Event::UserEvent(UserEvent(4)))
+                .within(Duration::from_millis(50)),
+        ),
+    )
+    .await;
+    assert!(
+        msg.contains("Timed out after 50ms waiting for UserEvent(4)"),
+        "{}",
+        msg
+    );
+    assert!(msg.contains("  Focused(true)"), "{}", msg);
+    assert!(msg.contains("- UserEvent(4)"), "{}", msg);
+
+    log::info!("Unordered timeout reports the expectation that timed out");
+    let msg = expect_panic(
+        "waiting for events that are never sent",
+        events.expect(
+            Expect::unordered()
+                .event("UserEvent(5)", |e| *e == Event::UserEvent(UserEvent(5)))
+                .within(Duration::from_secs(10))
+                .event("UserEvent(6)", |e| *e == Event::UserEvent(UserEvent(6)))
+                .within(Duration::from_millis(50)),
+        ),
+    )
+    .await;
+    assert!(
+        msg.contains("Timed out after 50ms waiting for UserEvent(6)"),
+        "{}",
+        msg
+    );
+    assert!(msg.contains("- UserEvent(5)"), "{}", msg);
+    assert!(msg.contains("- UserEvent(6)"), "{}", msg);
+}
@@ -0,0 +1,46 @@
+use crate::backend::{BackendFlags, BackendIcon, Instance};
+
+test!(run, BackendFlags::WINIT_SET_ICON);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let (width, height) = (64, 64);
+    let rgba = (0..width * height * 4).map(|i| i as u8).collect();
+    let icon = BackendIcon {
+        rgba,
+        width,
+        height,
+    };
+
+    let window = el.create_window(Default::default());
+    window.icon(None).await;
+    window.winit_set_window_icon(Some(icon.clone().into()));
+    window.await_property(|p| p.icon().is_some()).await;
+
+    let frames = window.properties().icon_frames();
+    let sizes: Vec<_> = frames.iter().map(|f| (f.width, f.height)).collect();
+    log::info!("Icon frames: {:?}", sizes);
+    for frame in &frames {
+        assert_eq!(
+            frame.rgba.len(),
+            frame.width as usize * frame.height as usize * 4
+        );
+    }
+    if frames.len() == 1 {
+        // winit does not scale the icon.
+        assert!(frames[0] == icon, "The icon frame differs from the icon");
+    } else {
+        // winit scales the icon to the sizes commonly used by panels.
+        for size in [16, 32] {
+            let frame = BackendIcon::closest(&frames, size, size).unwrap();
+            assert_eq!((frame.width, frame.height), (size, size));
+        }
+        let frame = BackendIcon::closest(&frames, width, height).unwrap();
+        assert!(frame.width <= width && frame.height <= height);
+    }
+
+    window.winit_set_window_icon(None);
+    window.icon(None).await;
+    assert!(window.properties().icon_frames().is_empty());
+}
@@ -0,0 +1,41 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use crate::keyboard::compose_file;
+use crate::test::with_test_data;
+
+test!(
+    run,
+    BackendFlags::EVENT_LOOP_ENV | BackendFlags::SINGLE_THREADED
+);
+
+const TEXT: &str = "éèçñ";
+
+/// Compose sequences are handled by the input method of Xlib, which commits the composed
+/// characters to winit.
+async fn run(instance: &dyn Instance) {
+    let path = with_test_data(|td| td.test_dir.join("Compose"));
+    if let Err(e) = std::fs::write(&path, compose_file()) {
+        panic!("Could not write {}: {}", path.display(), e);
+    }
+    let el = instance.create_event_loop_with_env(&[
+        ("XCOMPOSEFILE", path.to_str().unwrap()),
+        ("XMODIFIERS", "@im=none"),
+    ]);
+    let mut events = el.events();
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    let seat = instance.default_seat();
+    seat.focus_and_wait(&*window, &mut *events).await;
+
+    seat.compose(TEXT).await;
+    let mut text = String::new();
+    while text.chars().count() < TEXT.chars().count() {
+        if let Event::WindowEvent(we) = events.event().await {
+            if let WindowEvent::ReceivedImeText(t) = we.event {
+                assert_eq!(we.window_id, window.winit_id());
+                text.push_str(&t);
+            }
+        }
+    }
+    assert_eq!(text, TEXT);
+}
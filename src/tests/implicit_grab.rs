@@ -0,0 +1,78 @@
+use crate::backend::{BackendFlags, Button, Instance};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, MouseButton};
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::SET_OUTER_POSITION);
+
+async fn run(instance: &dyn Instance) {
+    let seat = instance.default_seat();
+    let mouse = seat.add_mouse();
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(300, 300);
+    window.outer_position(300, 300).await;
+    let (ox, oy) = window.inner_offset();
+    let (ix, iy) = (300 + ox, 300 + oy);
+
+    seat.set_cursor_position(ix + 50, iy + 50);
+    seat.cursor_at(ix + 50, iy + 50).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+
+    log::info!("Pressing the left button inside the window");
+    let pressed = mouse.press(Button::Left);
+    let (we, mi) = events.window_mouse_input_event().await;
+    assert_eq!(we.window_id, window.winit_id());
+    assert_eq!(mi.button, MouseButton::Left);
+    assert_eq!(mi.state, ElementState::Pressed);
+
+    // Right of, below, and left of the window.
+    let path = [(200, 0), (0, 200), (-400, 0)];
+    let (mut x, mut y) = (50, 50);
+    for (dx, dy) in path {
+        x += dx;
+        y += dy;
+        log::info!("Dragging the cursor to {}x{} relative to the window", x, y);
+        mouse.move_(dx, dy);
+        let (we, cm) = events.window_cursor_moved().await;
+        assert_eq!(we.window_id, window.winit_id());
+        assert!(seat.is(cm.device_id));
+        assert_eq!(
+            cm.position,
+            PhysicalPosition {
+                x: x as f64,
+                y: y as f64
+            }
+        );
+    }
+
+    log::info!("Releasing the button outside of the window");
+    drop(pressed);
+    let (we, mi) = events.window_mouse_input_event().await;
+    assert_eq!(we.window_id, window.winit_id());
+    assert_eq!(mi.button, MouseButton::Left);
+    assert_eq!(mi.state, ElementState::Released);
+
+    log::info!("Checking that the window no longer receives motion");
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    mouse.move_(10, 10);
+    seat.cursor_at(ix + x + 10, iy + y + 10).await;
+    el.barrier().await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = event {
+            if let WindowEvent::CursorMoved(cm) = we.event {
+                panic!("Window received motion after the release: {:?}", cm);
+            }
+        }
+    }
+}
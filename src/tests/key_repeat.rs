@@ -0,0 +1,75 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use crate::keyboard::Key::KeyL;
+use std::time::Duration;
+use winit::event::ElementState;
+use winit::keyboard::KeyCode;
+
+test!(run, BackendFlags::KEY_REPEAT);
+
+const RATE: u32 = 50;
+const DELAY: Duration = Duration::from_millis(200);
+const REPEATS: u32 = 5;
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+
+    let key = kb.press_with_repeat(KeyL, RATE, DELAY);
+    let pressed = crate::clock::last_input().unwrap();
+    let (_, ki) = s.events.window_keyboard_input().await;
+    assert_eq!(ki.event.physical_key, KeyCode::KeyL);
+    assert_eq!(ki.event.state, ElementState::Pressed);
+    assert!(!ki.event.repeat);
+
+    // The repeats are timestamped when they are received, with the clock that
+    // timestamped the injection of the press.
+    let mut last_repeat = pressed;
+    let mut repeats = 0;
+    while repeats < REPEATS {
+        let (time, event) = s.events.timed_event().await;
+        if let Event::WindowEvent(we) = event {
+            if let WindowEvent::KeyboardInput(ki) = we.event {
+                assert_eq!(we.window_id, s.window.winit_id());
+                assert_eq!(ki.event.physical_key, KeyCode::KeyL);
+                assert_eq!(ki.event.state, ElementState::Pressed);
+                assert!(ki.event.repeat, "Key event is not a repetition: {:?}", ki);
+                last_repeat = time;
+                repeats += 1;
+            }
+        }
+    }
+    // The server cannot repeat faster than requested.
+    let min = DELAY + Duration::from_millis(1000 / RATE as u64) * (REPEATS - 1);
+    let elapsed = last_repeat - pressed;
+    if elapsed < min {
+        log::error!(
+            "{} repeats arrived after {:?}, expected at least {:?}",
+            REPEATS,
+            elapsed,
+            min
+        );
+    }
+    drop(key);
+
+    // Skip repetitions that were generated before the release.
+    loop {
+        let (_, ki) = s.events.window_keyboard_input().await;
+        assert_eq!(ki.event.physical_key, KeyCode::KeyL);
+        if ki.event.state == ElementState::Released {
+            assert!(!ki.event.repeat);
+            break;
+        }
+        assert!(ki.event.repeat);
+    }
+
+    // Keys pressed with `press` do not repeat.
+    let key = kb.press(KeyL);
+    let (_, ki) = s.events.window_keyboard_input().await;
+    assert_eq!(ki.event.state, ElementState::Pressed);
+    assert!(!ki.event.repeat);
+    crate::sleep::sleep_ms((DELAY * 2).as_millis() as u64).await;
+    drop(key);
+    let (_, ki) = s.events.window_keyboard_input().await;
+    assert_eq!(ki.event.state, ElementState::Released);
+}
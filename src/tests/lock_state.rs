@@ -0,0 +1,40 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::keyboard::Key::{KeyA, KeyKp1};
+use winit::event::ElementState;
+use winit::keyboard::{Key as WKey, KeyCode, KeyLocation};
+
+test!(run, BackendFlags::LOCK_STATE);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+
+    for (capslock, text) in [(true, "A"), (false, "a")] {
+        log::info!("Testing KeyA with capslock {}", capslock);
+        kb.set_lock_state(false, capslock);
+        kb.press(KeyA);
+        let (_, ki) = s.events.window_keyboard_input().await;
+        assert_eq!(ki.event.physical_key, KeyCode::KeyA);
+        assert_eq!(ki.event.state, ElementState::Pressed);
+        assert_eq!(ki.event.text, Some(text));
+        let (_, ki) = s.events.window_keyboard_input().await;
+        assert_eq!(ki.event.state, ElementState::Released);
+    }
+
+    for (numlock, logical_key, text) in [
+        (true, WKey::Character("1"), Some("1")),
+        (false, WKey::End, None),
+    ] {
+        log::info!("Testing KeyKp1 with numlock {}", numlock);
+        kb.set_lock_state(numlock, false);
+        kb.press(KeyKp1);
+        let (_, ki) = s.events.window_keyboard_input().await;
+        assert_eq!(ki.event.physical_key, KeyCode::Numpad1);
+        assert_eq!(ki.event.state, ElementState::Pressed);
+        assert_eq!(ki.event.logical_key, logical_key);
+        assert_eq!(ki.event.location, KeyLocation::Numpad);
+        assert_eq!(ki.event.text, text);
+        let (_, ki) = s.events.window_keyboard_input().await;
+        assert_eq!(ki.event.state, ElementState::Released);
+    }
+}
@@ -10,7 +10,8 @@ async fn run(instance: &dyn Instance) {
         let window = el.create_window(Default::default());
         window.minimized(false).await;
         window.winit_set_maximized(true);
-        window.maximized(true).await;
+        let p = window.maximized(true).await;
+        assert_eq!(p.minimized, Some(false));
         window.winit_set_maximized(false);
         window.maximized(false).await;
     }
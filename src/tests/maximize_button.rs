@@ -0,0 +1,47 @@
+use crate::backend::{BackendFlags, Button, Instance};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+
+test!(run, BackendFlags::MAXIMIZE_BUTTON);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Mapped);
+    let mouse = s.seat.add_mouse();
+    s.window.maximized(false).await;
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    let click = || {
+        let (x, y) = s.window.maximize_button_position();
+        s.seat.set_cursor_position(x, y);
+        drop(mouse.press(Button::Left));
+    };
+
+    log::info!("Maximizing via the maximize button");
+    click();
+    s.window.maximized(true).await;
+    s.window.await_winit(|w| w.is_maximized()).await;
+    check_events(&mut *s.events).await;
+
+    log::info!("Restoring via the maximize button");
+    click();
+    s.window.maximized(false).await;
+    s.window.await_winit(|w| !w.is_maximized()).await;
+    check_events(&mut *s.events).await;
+}
+
+async fn check_events(events: &mut dyn EventStream) {
+    let mut resized = false;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            match &we.event {
+                WindowEvent::MouseInput(_) => {
+                    log::error!("Maximize button click leaked to the client: {:?}", event);
+                }
+                WindowEvent::Resized(_) => resized = true,
+                _ => {}
+            }
+        }
+    }
+    assert!(resized);
+}
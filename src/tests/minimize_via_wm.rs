@@ -0,0 +1,38 @@
+use crate::backend::{BackendFlags, Button, Instance};
+
+test!(
+    run,
+    BackendFlags::MINIMIZE_BUTTON | BackendFlags::WINIT_SET_MINIMIZED
+);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Mapped);
+    let mouse = s.seat.add_mouse();
+    s.window.minimized(false).await;
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    log::info!("Minimizing via the minimize button");
+    let (x, y) = s.window.minimize_button_position();
+    s.seat.set_cursor_position(x, y);
+    drop(mouse.press(Button::Left));
+    s.window.minimized(true).await;
+    s.window.mapped(false).await;
+    // winit derives the minimized state from _NET_WM_STATE_HIDDEN.
+    #[cfg(winit_is_minimized)]
+    s.window
+        .await_winit(|w| w.is_minimized() == Some(true))
+        .await;
+
+    log::info!("Restoring via winit");
+    s.el.barrier().await;
+    s.window.winit_set_minimized(false);
+    s.window.minimized(false).await;
+    s.window.mapped(true).await;
+    #[cfg(winit_is_minimized)]
+    s.window
+        .await_winit(|w| w.is_minimized() == Some(false))
+        .await;
+    let id = s.events.redraw_requested_event().await;
+    assert_eq!(id, s.window.winit_id());
+}
@@ -0,0 +1,120 @@
+use crate::backend::Instance;
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use crate::keyboard::Key;
+use crate::keyboard::Key::{
+    KeyLeftalt, KeyLeftctrl, KeyLeftmeta, KeyLeftshift, KeyRightalt, KeyRightctrl, KeyRightmeta,
+    KeyRightshift,
+};
+use winit::event::ElementState;
+use winit::keyboard::{Key as WKey, KeyCode, KeyLocation, ModifiersState};
+
+test!(run);
+
+/// Applications that e.g. open a menu when Alt is tapped alone rely on the exact sequence
+/// of events produced by such a tap.
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+
+    let modifiers = [
+        (
+            KeyLeftshift,
+            KeyCode::ShiftLeft,
+            WKey::Shift,
+            ModifiersState::SHIFT,
+        ),
+        (
+            KeyRightshift,
+            KeyCode::ShiftRight,
+            WKey::Shift,
+            ModifiersState::SHIFT,
+        ),
+        (
+            KeyLeftctrl,
+            KeyCode::ControlLeft,
+            WKey::Control,
+            ModifiersState::CONTROL,
+        ),
+        (
+            KeyRightctrl,
+            KeyCode::ControlRight,
+            WKey::Control,
+            ModifiersState::CONTROL,
+        ),
+        (KeyLeftalt, KeyCode::AltLeft, WKey::Alt, ModifiersState::ALT),
+        (
+            KeyRightalt,
+            KeyCode::AltRight,
+            WKey::Alt,
+            ModifiersState::ALT,
+        ),
+        (
+            KeyLeftmeta,
+            KeyCode::SuperLeft,
+            WKey::Super,
+            ModifiersState::SUPER,
+        ),
+        (
+            KeyRightmeta,
+            KeyCode::SuperRight,
+            WKey::Super,
+            ModifiersState::SUPER,
+        ),
+    ];
+    for (key, code, logical, mods) in modifiers {
+        log::info!("Tapping {:?}", key);
+        // Tap twice to check that the first tap leaves no state behind.
+        for _ in 0..2 {
+            kb.press(key);
+            // 0: Key pressed
+            // 1: Modifiers changed
+            // 2: Key released
+            // 3: Modifiers changed
+            for i in 0..4 {
+                let event = keyboard_event(&mut *s.events).await;
+                log::info!("Event {}: {:?}", i, event);
+                match (i, event) {
+                    (0 | 2, WindowEvent::KeyboardInput(ki)) => {
+                        let state = match i {
+                            0 => ElementState::Pressed,
+                            _ => ElementState::Released,
+                        };
+                        assert_eq!(ki.event.state, state);
+                        assert_eq!(ki.event.physical_key, code);
+                        assert_eq!(ki.event.logical_key, logical);
+                        assert_eq!(ki.event.text, None);
+                        assert_eq!(ki.event.location, location(key));
+                        assert_eq!(ki.event.repeat, false);
+                    }
+                    (1, WindowEvent::ModifiersChanged(m)) => assert_eq!(m, mods),
+                    (3, WindowEvent::ModifiersChanged(m)) => {
+                        assert_eq!(m, ModifiersState::empty())
+                    }
+                    (i, event) => panic!("Unexpected event {}: {:?}", i, event),
+                }
+            }
+        }
+    }
+}
+
+fn location(key: Key) -> KeyLocation {
+    match key {
+        KeyLeftshift | KeyLeftctrl | KeyLeftalt | KeyLeftmeta => KeyLocation::Left,
+        _ => KeyLocation::Right,
+    }
+}
+
+/// Returns the next `KeyboardInput` or `ModifiersChanged` event of the window.
+async fn keyboard_event(events: &mut dyn EventStream) -> WindowEvent {
+    loop {
+        if let Event::WindowEvent(we) = events.event().await {
+            if matches!(
+                we.event,
+                WindowEvent::KeyboardInput(_) | WindowEvent::ModifiersChanged(_)
+            ) {
+                return we.event;
+            }
+        }
+    }
+}
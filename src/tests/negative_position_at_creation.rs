@@ -0,0 +1,28 @@
+use crate::backend::{BackendFlags, Instance};
+use winit::dpi::PhysicalPosition;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::WINIT_SET_OUTER_POSITION);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    for (x, y) in [(-100, -50), (-20, 200), (300, -40)] {
+        for decorations in [true, false] {
+            log::info!(
+                "Creating window at {}x{} with decorations {}",
+                x,
+                y,
+                decorations
+            );
+            let window = el.create_window(
+                WindowBuilder::default()
+                    .with_decorations(decorations)
+                    .with_position(PhysicalPosition { x, y }),
+            );
+            window.mapped(true).await;
+            window.outer_position(x, y).await;
+            window.winit_outer_position(x, y).await;
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use crate::backend::{BackendFlags, Instance, PagerRequest};
+use crate::test::{set_geometry_coalescing, Coalescing};
+
+test!(run, BackendFlags::PAGER);
+
+async fn run(instance: &dyn Instance) {
+    // Only the final size is of interest.
+    set_geometry_coalescing(Coalescing::Final);
+    let mut s = setup!(instance, Mapped);
+    let windowed = s.window.fullscreen(false).await;
+    s.window.winit_fullscreen(false).await;
+    let mon = s.window.winit().current_monitor().unwrap().size();
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    log::info!("Entering fullscreen via _NET_WM_STATE");
+    s.window.pager_request(PagerRequest::SetFullscreen(true));
+    s.window.fullscreen(true).await;
+    s.window.outer_position(0, 0).await;
+    s.events
+        .window_resized_to(s.window.winit_id(), mon.width, mon.height)
+        .await;
+    s.window.winit_fullscreen(true).await;
+
+    log::info!("Leaving fullscreen via _NET_WM_STATE");
+    s.el.barrier().await;
+    s.window.pager_request(PagerRequest::SetFullscreen(false));
+    s.window.fullscreen(false).await;
+    s.window.outer_position(windowed.x, windowed.y).await;
+    s.events
+        .window_resized_to(s.window.winit_id(), windowed.width, windowed.height)
+        .await;
+    s.window.winit_fullscreen(false).await;
+}
@@ -0,0 +1,55 @@
+use crate::backend::{BackendFlags, Instance, PagerRequest, Window};
+
+test!(run, BackendFlags::PAGER);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    s.window.minimized(false).await;
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    log::info!("Iconifying via WM_CHANGE_STATE");
+    s.window.pager_request(PagerRequest::Iconify);
+    s.window.minimized(true).await;
+    s.window.mapped(false).await;
+    let (we, focused) = s.events.window_focus_event().await;
+    assert_eq!(we.window_id, s.window.winit_id());
+    assert!(!focused);
+    winit_minimized(&*s.window, true).await;
+
+    log::info!("Restoring via _NET_ACTIVE_WINDOW");
+    s.el.barrier().await;
+    s.window.pager_request(PagerRequest::Activate);
+    s.window.minimized(false).await;
+    s.window.mapped(true).await;
+    winit_minimized(&*s.window, false).await;
+    let id = s.events.redraw_requested_event().await;
+    assert_eq!(id, s.window.winit_id());
+
+    log::info!("Hiding via _NET_WM_STATE");
+    s.el.barrier().await;
+    s.window.pager_request(PagerRequest::SetHidden(true));
+    s.window.minimized(true).await;
+    s.window.mapped(false).await;
+    winit_minimized(&*s.window, true).await;
+
+    log::info!("Showing via _NET_WM_STATE");
+    s.el.barrier().await;
+    s.window.pager_request(PagerRequest::SetHidden(false));
+    s.window.minimized(false).await;
+    s.window.mapped(true).await;
+    winit_minimized(&*s.window, false).await;
+    let id = s.events.redraw_requested_event().await;
+    assert_eq!(id, s.window.winit_id());
+}
+
+/// Waits for winit to report the minimized state. winit derives it from
+/// _NET_WM_STATE_HIDDEN.
+async fn winit_minimized(window: &dyn Window, minimized: bool) {
+    #[cfg(winit_is_minimized)]
+    window
+        .await_winit(|w| w.is_minimized() == Some(minimized))
+        .await;
+    #[cfg(not(winit_is_minimized))]
+    let _ = (window, minimized);
+}
@@ -0,0 +1,33 @@
+use crate::backend::{BackendFlags, ConnectionProxy, Instance};
+use crate::test::record_metric;
+
+test!(run, BackendFlags::CONNECTION_PROXY);
+
+async fn run(instance: &dyn Instance) {
+    let (el, proxy) = instance.create_proxied_event_loop();
+
+    proxy.reset_stats();
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    report(&*proxy, "create_window");
+
+    proxy.reset_stats();
+    window.winit_set_title("abc");
+    window.title("abc").await;
+    report(&*proxy, "set_title");
+}
+
+fn report(proxy: &dyn ConnectionProxy, operation: &str) {
+    let stats = proxy.stats();
+    log::info!("Protocol stats for {}: {:?}", operation, stats);
+    record_metric(
+        &format!("{}_requests", operation),
+        stats.requests as f64,
+        "requests",
+    );
+    record_metric(
+        &format!("{}_replies", operation),
+        stats.replies as f64,
+        "replies",
+    );
+}
@@ -0,0 +1,54 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::PhysicalPosition;
+
+test!(run, BackendFlags::WINIT_SET_OUTER_POSITION);
+
+const STEPS: i32 = 50;
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    events.events_until_quiet(100).await;
+
+    let target = |i: i32| (10 + 10 * i, 20 + 5 * i);
+    for i in 0..STEPS {
+        let (x, y) = target(i);
+        window.winit_set_outer_position(PhysicalPosition { x, y });
+    }
+    let (final_x, final_y) = target(STEPS - 1);
+
+    // Moves may be coalesced but must never go backwards.
+    let mut prev = (i32::MIN, i32::MIN);
+    loop {
+        let (we, pos) = events.window_move_event().await;
+        assert_eq!(we.window_id, window.winit_id());
+        log::info!("Moved to {}x{}", pos.x, pos.y);
+        if pos.x < prev.0 || pos.y < prev.1 {
+            log::error!(
+                "Window moved backwards from {}x{} to {}x{}",
+                prev.0,
+                prev.1,
+                pos.x,
+                pos.y
+            );
+        }
+        prev = (pos.x, pos.y);
+        if prev == (final_x, final_y) {
+            break;
+        }
+    }
+
+    window.outer_position(final_x, final_y).await;
+    window.winit_outer_position(final_x, final_y).await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Moved(pos) = &we.event {
+                log::error!("Unexpected move after the final move: {:?}", pos);
+            }
+        }
+    }
+}
@@ -0,0 +1,101 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::backends::mock::mock_window;
+use crate::event::{Event, KeyEvent, UserEvent, WindowEvent, WindowKeyboardInput};
+use crate::eventrecorder::{EventRecorder, FileReplay};
+use winit::event::{DeviceId, ElementState};
+use winit::keyboard::{Key, KeyCode, KeyLocation};
+
+test!(run, BackendFlags::MOCK);
+
+fn key_q(state: ElementState) -> WindowEvent {
+    WindowEvent::KeyboardInput(WindowKeyboardInput {
+        device_id: unsafe { DeviceId::dummy() },
+        event: KeyEvent {
+            physical_key: KeyCode::KeyQ,
+            logical_key: Key::Character("q"),
+            text: match state {
+                ElementState::Pressed => Some("q"),
+                ElementState::Released => None,
+            },
+            location: KeyLocation::Standard,
+            state,
+            repeat: false,
+            #[cfg(have_mod_supplement)]
+            mod_supplement: crate::event::ModSupplement {
+                text_with_all_modifiers: None,
+                key_without_modifiers: Key::Character("q"),
+            },
+        },
+        is_synthetic: false,
+    })
+}
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let window = el.create_window(Default::default());
+    let mock = mock_window(&*window);
+    let mut events = el.events();
+    events.events_until_quiet(10).await;
+
+    let mut recorder = EventRecorder::new("keyboard");
+    let mut original = vec![];
+    {
+        let mut events = recorder.record(&mut *events);
+        mock.send_window_event(WindowEvent::Focused(true));
+        mock.send_window_event(key_q(ElementState::Pressed));
+        mock.send_window_event(key_q(ElementState::Released));
+        for _ in 0..2 {
+            let (we, _) = events.window_keyboard_input().await;
+            assert_eq!(we.window_id, mock.winit_id());
+        }
+        mock.send_event(Event::UserEvent(UserEvent(1)));
+        mock.send_event(Event::RedrawRequested(mock.winit_id()));
+        original.extend(events.events_until_quiet(50).await);
+    }
+    assert_eq!(original.len(), 2);
+
+    log::info!("Replaying the recording");
+    let mut replay = recorder.replay();
+    let mut replayed = vec![];
+    while replay.has_more() {
+        replayed.push(replay.timed_event().await);
+    }
+    for pair in replayed.windows(2) {
+        assert!(pair[0].0 <= pair[1].0, "Timestamps are not monotonic");
+    }
+    let events: Vec<_> = replayed.iter().map(|(_, e)| e.clone()).collect();
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[3..], original[..]);
+
+    log::info!("Replaying the recording file");
+    let mut file = recorder.replay_file();
+    let mut from_file = vec![];
+    while file.has_more() {
+        from_file.push(file.event().await);
+    }
+    assert_eq!(from_file, events);
+
+    log::info!("Replaying the recording file with only the window bound");
+    let mut file = FileReplay::open(recorder.path()).window(0, mock.winit_id());
+    let mut keys = vec![];
+    while file.has_more() {
+        if let Event::WindowEvent(we) = file.event().await {
+            if let WindowEvent::KeyboardInput(ki) = we.event {
+                assert_eq!(we.window_id, mock.winit_id());
+                keys.push(ki.event);
+            }
+        }
+    }
+    assert_eq!(keys.len(), 2);
+    assert_eq!(keys[0].physical_key, KeyCode::KeyQ);
+    assert_eq!(keys[0].state, ElementState::Pressed);
+    assert_eq!(keys[1].physical_key, KeyCode::KeyQ);
+    assert_eq!(keys[1].state, ElementState::Released);
+
+    // Deserialized strings are interned instead of being leaked for every event.
+    let (a, b) = match (&keys[0].logical_key, &keys[1].logical_key) {
+        (Key::Character(a), Key::Character(b)) => (*a, *b),
+        k => panic!("Unexpected logical keys {:?}", k),
+    };
+    assert!(std::ptr::eq(a, b));
+}
@@ -0,0 +1,46 @@
+use crate::backend::Instance;
+use crate::event::Event;
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 200,
+    }));
+    window.mapped(true).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    let id = window.winit_id();
+
+    log::info!("Exposing the whole window");
+    window.expose(0, 0, 0, 0);
+    events.window_redraw_requested(id).await;
+
+    log::info!("Exposing a region of the window");
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    window.expose(50, 50, 20, 20);
+    events.window_redraw_requested(id).await;
+
+    log::info!("Exposing all quadrants of the window");
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    for (x, y) in [(0, 0), (100, 0), (0, 100), (100, 100)] {
+        window.expose(x, y, 100, 100);
+    }
+    el.barrier().await;
+    let redraws = events
+        .events_until_quiet(200)
+        .await
+        .into_iter()
+        .filter(|e| *e == Event::RedrawRequested(id))
+        .count();
+    assert!(redraws >= 1, "No redraw after exposure");
+    assert!(redraws <= 4, "{} redraws for 4 exposures", redraws);
+}
@@ -0,0 +1,44 @@
+use crate::backend::{BackendFlags, Instance};
+use std::time::Duration;
+
+test!(run, BackendFlags::MT_SAFE);
+
+/// How long the event may take to arrive after the redraw has been requested.
+const MAX_DELAY: Duration = Duration::from_millis(500);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .thread_name(|_| "redraw".to_string())
+        .build()
+        .unwrap();
+
+    for _ in 0..3 {
+        el.barrier().await;
+        events.events_until_quiet(100).await;
+
+        log::info!("Requesting a redraw from another thread");
+        let winit = window.winit();
+        let test_thread = std::thread::current().id();
+        pool.scope(|s| {
+            s.spawn(|_| {
+                assert_ne!(std::thread::current().id(), test_thread);
+                winit.request_redraw();
+            })
+        });
+
+        match tokio::time::timeout(MAX_DELAY, events.redraw_requested_event()).await {
+            Ok(id) => assert_eq!(id, window.winit_id()),
+            Err(_) => log::error!(
+                "RedrawRequested was not delivered within {:?} of the request",
+                MAX_DELAY
+            ),
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use crate::backend::Instance;
+use crate::event::Event;
+use crate::eventstream::EventStream;
+use winit::window::WindowId;
+
+test!(run);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    el.report_cleared_events(true);
+    let mut events = el.events();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    let id = window.winit_id();
+
+    log::info!("Requesting several redraws before the event loop runs");
+    for _ in 0..3 {
+        window.winit_request_redraw();
+    }
+    assert_eq!(redraws(&mut *events, id).await, 1);
+
+    log::info!("Requesting a redraw via an expose event");
+    el.barrier().await;
+    window.expose(0, 0, 0, 0);
+    assert_eq!(redraws(&mut *events, id).await, 1);
+
+    log::info!("Checking that no redraws arrive without a request");
+    el.barrier().await;
+    assert_eq!(redraws(&mut *events, id).await, 0);
+}
+
+/// Returns the number of `RedrawRequested` events of `window` until the event loop is
+/// quiet. Checks that they are delivered between `MainEventsCleared` and
+/// `RedrawEventsCleared`.
+async fn redraws(events: &mut dyn EventStream, window: WindowId) -> usize {
+    let mut redraws = 0;
+    let mut redraw_phase = false;
+    for event in events.events_until_quiet(200).await {
+        match event {
+            Event::MainEventsCleared => redraw_phase = true,
+            Event::RedrawEventsCleared => redraw_phase = false,
+            Event::RedrawRequested(id) => {
+                assert!(redraw_phase, "RedrawRequested outside of the redraw phase");
+                if id == window {
+                    redraws += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+    redraws
+}
@@ -7,12 +7,10 @@ use winit::keyboard::{Key as WKey, KeyCode, KeyLocation};
 test!(run);
 
 async fn run(instance: &dyn Instance) {
-    let el = instance.create_event_loop();
-    let mut events = el.events();
-    let window = el.create_window(Default::default());
-    window.mapped(true).await;
-    let seat = instance.default_seat();
-    seat.focus(&*window);
+    let mut s = setup!(instance, Focused);
+    let events = &mut s.events;
+    let window = &s.window;
+    let seat = &s.seat;
     let kb = seat.add_keyboard();
     seat.set_layout(Layout::Azerty);
 
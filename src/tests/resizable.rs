@@ -9,16 +9,21 @@ async fn run(instance: &dyn Instance) {
     {
         let window = el.create_window(Default::default());
         window.resizable(true).await;
+        assert!(window.winit().is_resizable());
         window.winit_set_resizable(false);
+        assert!(!window.winit().is_resizable());
         window.resizable(false).await;
         window.winit_set_resizable(true);
+        assert!(window.winit().is_resizable());
         window.resizable(true).await;
     }
 
     {
         let window = el.create_window(WindowBuilder::default().with_resizable(false));
         window.resizable(false).await;
+        assert!(!window.winit().is_resizable());
         window.winit_set_resizable(true);
+        assert!(window.winit().is_resizable());
         window.resizable(true).await;
     }
 }
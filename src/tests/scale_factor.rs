@@ -0,0 +1,39 @@
+use crate::backend::{BackendFlags, Instance};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::SET_SCALE_FACTOR);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 400,
+        height: 300,
+    }));
+    window.mapped(true).await;
+    window.inner_size(400, 300).await;
+    assert_eq!(window.winit().scale_factor(), 1.0);
+
+    for (scale_factor, width, height) in [(2.0, 800, 600), (1.5, 600, 450), (1.0, 400, 300)] {
+        log::info!("Testing scale factor {}", scale_factor);
+        instance.set_scale_factor(scale_factor);
+        let (we, sfc) = events.window_scale_factor_changed().await;
+        assert_eq!(we.window_id, window.winit_id());
+        assert_eq!(sfc.scale_factor, scale_factor);
+        assert_eq!(sfc.new_inner_size, PhysicalSize { width, height });
+        assert_eq!(window.winit().scale_factor(), scale_factor);
+        window.inner_size(width, height).await;
+        window.winit_inner_size(width, height).await;
+        let monitor = window.winit().current_monitor().unwrap();
+        assert_eq!(monitor.scale_factor(), scale_factor);
+    }
+
+    log::info!("Testing that new event loops use the scale factor");
+    instance.set_scale_factor(2.0);
+    let el2 = instance.create_event_loop();
+    let window2 = el2.create_window(Default::default());
+    window2.mapped(true).await;
+    assert_eq!(window2.winit().scale_factor(), 2.0);
+}
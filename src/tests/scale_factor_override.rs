@@ -0,0 +1,49 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::LogicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::EVENT_LOOP_ENV | BackendFlags::SINGLE_THREADED
+);
+
+async fn run(instance: &dyn Instance) {
+    let set_scale_factor = instance
+        .backend()
+        .flags()
+        .contains(BackendFlags::SET_SCALE_FACTOR);
+
+    for (scale_factor, width, height) in [(1.0, 400, 300), (1.25, 500, 375), (2.0, 800, 600)] {
+        log::info!("Testing scale factor override {}", scale_factor);
+        let el = instance
+            .create_event_loop_with_env(&[("WINIT_X11_SCALE_FACTOR", &scale_factor.to_string())]);
+        let mut events = el.events();
+
+        let window = el.create_window(WindowBuilder::new().with_inner_size(LogicalSize {
+            width: 400.0,
+            height: 300.0,
+        }));
+        window.mapped(true).await;
+        assert_eq!(window.winit().scale_factor(), scale_factor);
+        window.inner_size(width, height).await;
+        window.winit_inner_size(width, height).await;
+        let monitor = window.winit().current_monitor().unwrap();
+        assert_eq!(monitor.scale_factor(), scale_factor);
+
+        if set_scale_factor {
+            log::info!("Testing that the override takes precedence over the display server");
+            instance.set_scale_factor(3.0);
+            for event in events.events_until_quiet(500).await {
+                if let Event::WindowEvent(we) = &event {
+                    if let WindowEvent::ScaleFactorChanged(..) = &we.event {
+                        panic!("Scale factor changed despite the override: {:?}", event);
+                    }
+                }
+            }
+            assert_eq!(window.winit().scale_factor(), scale_factor);
+            window.inner_size(width, height).await;
+            instance.set_scale_factor(1.0);
+        }
+    }
+}
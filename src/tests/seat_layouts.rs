@@ -0,0 +1,56 @@
+use crate::backend::{BackendFlags, Instance, Keyboard, Window};
+use crate::eventstream::EventStream;
+use crate::keyboard::Key::KeyQ;
+use crate::keyboard::Layout;
+use winit::event::ElementState;
+use winit::keyboard::Key as WKey;
+
+test!(run, BackendFlags::CREATE_SEAT);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let seat1 = instance.create_seat();
+    let seat2 = instance.create_seat();
+    let kb1 = seat1.add_keyboard();
+    let kb2 = seat2.add_keyboard();
+
+    let window1 = el.create_window(Default::default());
+    let window2 = el.create_window(Default::default());
+    window1.mapped(true).await;
+    window2.mapped(true).await;
+
+    seat1.focus_and_wait(&*window1, &mut *events).await;
+    seat2.focus_and_wait(&*window2, &mut *events).await;
+    assert!(seat1.is_focused(&*window1));
+    assert!(seat2.is_focused(&*window2));
+
+    seat2.set_layout(Layout::Azerty);
+
+    press_q(&mut *events, &*kb1, &*window1, "q").await;
+    press_q(&mut *events, &*kb2, &*window2, "a").await;
+
+    seat1.set_layout(Layout::Azerty);
+    seat2.set_layout(Layout::Qwerty);
+
+    press_q(&mut *events, &*kb1, &*window1, "a").await;
+    press_q(&mut *events, &*kb2, &*window2, "q").await;
+}
+
+/// Presses and releases Q on `kb` and checks that `window` receives the key with the given
+/// logical key.
+async fn press_q(
+    events: &mut dyn EventStream,
+    kb: &dyn Keyboard,
+    window: &dyn Window,
+    logical_key: &str,
+) {
+    kb.press(KeyQ);
+    for state in [ElementState::Pressed, ElementState::Released] {
+        let (we, ki) = events.window_keyboard_input().await;
+        assert_eq!(we.window_id, window.winit_id());
+        assert_eq!(ki.event.state, state);
+        assert_eq!(ki.event.logical_key, WKey::Character(logical_key));
+    }
+}
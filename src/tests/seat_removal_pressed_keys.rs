@@ -0,0 +1,51 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::keyboard::Key::{KeyL, KeyLeftshift};
+use winit::event::ElementState;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+test!(run, BackendFlags::CREATE_SEAT | BackendFlags::REMOVE_SEAT);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+
+    let seat = instance.default_seat();
+    let seat2 = instance.create_seat();
+    let kb2 = seat2.add_keyboard();
+    seat2.focus_and_wait(&*window, &mut *events).await;
+
+    log::info!("Holding shift on the second seat");
+    let _shift = kb2.press(KeyLeftshift);
+    loop {
+        let (_, mods) = events.window_modifiers().await;
+        if mods == ModifiersState::SHIFT {
+            break;
+        }
+    }
+
+    log::info!("Removing the second seat");
+    seat2.remove();
+    loop {
+        let (_, mods) = events.window_modifiers().await;
+        if mods.is_empty() {
+            break;
+        }
+    }
+
+    log::info!("Checking that the default seat is not affected");
+    let kb = seat.add_keyboard();
+    seat.focus_and_wait(&*window, &mut *events).await;
+    kb.press(KeyL);
+    for state in [ElementState::Pressed, ElementState::Released] {
+        let (we, ki) = events.window_keyboard_input().await;
+        assert_eq!(we.window_id, window.winit_id());
+        assert_eq!(ki.event.physical_key, KeyCode::KeyL);
+        assert_eq!(ki.event.state, state);
+        if state == ElementState::Pressed {
+            assert_eq!(ki.event.text, Some("l"));
+        }
+    }
+}
@@ -10,14 +10,10 @@ async fn run(instance: &dyn Instance) {
         let window = el.create_window(Default::default());
         window.mapped(true).await;
         window.set_outer_position(100, 200);
-        let (w, ev) = events.window_move_event().await;
-        assert_eq!(w.window_id, window.winit_id());
-        assert_eq!((ev.x, ev.y), (100, 200));
+        events.window_moved_to(window.winit_id(), 100, 200).await;
         window.winit_outer_position(100, 200).await;
         window.set_outer_position(-300, -400);
-        let (w, ev) = events.window_move_event().await;
-        assert_eq!(w.window_id, window.winit_id());
-        assert_eq!((ev.x, ev.y), (-300, -400));
+        events.window_moved_to(window.winit_id(), -300, -400).await;
         window.winit_outer_position(-300, -400).await;
     }
 }
@@ -1,8 +1,10 @@
 use crate::backend::{BackendFlags, Instance};
+use crate::test::{set_geometry_coalescing, Coalescing};
 
 test!(run, BackendFlags::SET_INNER_SIZE);
 
 async fn run(instance: &dyn Instance) {
+    set_geometry_coalescing(Coalescing::Final);
     let el = instance.create_event_loop();
     let mut events = el.events();
 
@@ -10,14 +12,10 @@ async fn run(instance: &dyn Instance) {
         let window = el.create_window(Default::default());
         window.mapped(true).await;
         window.set_inner_size(100, 200);
-        let (w, ev) = events.window_resize_event().await;
-        assert_eq!(w.window_id, window.winit_id());
-        assert_eq!((ev.width, ev.height), (100, 200));
+        events.window_resized_to(window.winit_id(), 100, 200).await;
         window.winit_inner_size(100, 200).await;
         window.set_inner_size(300, 400);
-        let (w, ev) = events.window_resize_event().await;
-        assert_eq!(w.window_id, window.winit_id());
-        assert_eq!((ev.width, ev.height), (300, 400));
+        events.window_resized_to(window.winit_id(), 300, 400).await;
         window.winit_inner_size(300, 400).await;
     }
 }
@@ -0,0 +1,88 @@
+use crate::backend::{BackendFlags, EventLoop, Instance, Window, NONE_SIZE};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use crate::test::{set_geometry_coalescing, Coalescing};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::WINIT_SET_MAXIMIZED | BackendFlags::WINIT_SET_SIZE_BOUNDS
+);
+
+async fn run(instance: &dyn Instance) {
+    // Only the final size is of interest.
+    set_geometry_coalescing(Coalescing::Final);
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 300,
+        height: 200,
+    }));
+    window.mapped(true).await;
+    window.inner_size(300, 200).await;
+
+    log::info!("Setting a minimum size while maximized");
+    window.winit_set_maximized(true);
+    let maximized = window.maximized(true).await;
+    events
+        .window_resized_to(window.winit_id(), maximized.width, maximized.height)
+        .await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    window.winit_set_min_size(Some(PhysicalSize {
+        width: 400,
+        height: 350,
+    }));
+    window.min_size(Some((400, 350))).await;
+    no_resize(&*el, &mut *events, &*window).await;
+    window.inner_size(maximized.width, maximized.height).await;
+
+    log::info!("Restoring, the minimum size applies");
+    window.winit_set_maximized(false);
+    window.maximized(false).await;
+    window.inner_size(400, 350).await;
+    events.window_resized_to(window.winit_id(), 400, 350).await;
+
+    log::info!("Setting a maximum size while maximized");
+    window.winit_set_maximized(true);
+    window.maximized(true).await;
+    events
+        .window_resized_to(window.winit_id(), maximized.width, maximized.height)
+        .await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+    window.winit_set_min_size(NONE_SIZE);
+    window.winit_set_max_size(Some(PhysicalSize {
+        width: 250,
+        height: 150,
+    }));
+    window.min_size(None).await;
+    window.max_size(Some((250, 150))).await;
+    no_resize(&*el, &mut *events, &*window).await;
+    window.inner_size(maximized.width, maximized.height).await;
+
+    log::info!("Restoring, the maximum size applies");
+    window.winit_set_maximized(false);
+    window.maximized(false).await;
+    window.inner_size(250, 150).await;
+    events.window_resized_to(window.winit_id(), 250, 150).await;
+    window
+        .await_winit(|w| w.inner_size() == PhysicalSize::new(250, 150))
+        .await;
+}
+
+/// Checks that the window is not resized.
+async fn no_resize(el: &dyn EventLoop, events: &mut dyn EventStream, window: &dyn Window) {
+    el.barrier().await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = event {
+            if let WindowEvent::Resized(size) = we.event {
+                if we.window_id == window.winit_id() {
+                    panic!("Maximized window was resized to {:?}", size);
+                }
+            }
+        }
+    }
+}
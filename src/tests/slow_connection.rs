@@ -0,0 +1,57 @@
+use crate::backend::{BackendFlags, Instance, ProxyFaults};
+use crate::keyboard::Key::{KeyA, KeyD, KeyS};
+use crate::test::set_expected_events;
+use std::time::Duration;
+use winit::event::ElementState;
+use winit::keyboard::KeyCode;
+
+test!(run, BackendFlags::CONNECTION_PROXY);
+
+async fn run(instance: &dyn Instance) {
+    let (el, proxy) = instance.create_proxied_event_loop();
+    let mut events = el.events();
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    let seat = instance.default_seat();
+    seat.focus_and_wait(&*window, &mut *events).await;
+    let kb = seat.add_keyboard();
+
+    let configs = [
+        ProxyFaults {
+            latency: Duration::from_millis(20),
+            batch_interval: None,
+        },
+        ProxyFaults {
+            latency: Duration::from_millis(0),
+            batch_interval: Some(Duration::from_millis(50)),
+        },
+        ProxyFaults {
+            latency: Duration::from_millis(10),
+            batch_interval: Some(Duration::from_millis(30)),
+        },
+    ];
+
+    for faults in configs {
+        proxy.set_faults(faults);
+
+        log::info!("Testing that key events arrive in order");
+        set_expected_events(&["KeyboardInput"; 6]);
+        for key in [KeyA, KeyS, KeyD] {
+            kb.press(key);
+        }
+        for code in [KeyCode::KeyA, KeyCode::KeyS, KeyCode::KeyD] {
+            for state in [ElementState::Pressed, ElementState::Released] {
+                let (_, ki) = events.window_keyboard_input().await;
+                assert_eq!(ki.event.physical_key, code);
+                assert_eq!(ki.event.state, state);
+            }
+        }
+
+        log::info!("Testing that property changes round-trip");
+        let title = format!("{:?}", faults);
+        window.winit_set_title(&title);
+        window.title(&title).await;
+    }
+
+    proxy.set_faults(Default::default());
+}
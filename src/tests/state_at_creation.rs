@@ -0,0 +1,42 @@
+use crate::backend::{BackendFlags, Instance};
+use std::time::Duration;
+use winit::window::{Fullscreen, WindowBuilder};
+
+test!(
+    run,
+    BackendFlags::WINIT_SET_MAXIMIZED | BackendFlags::WINIT_SET_FULLSCREEN
+);
+
+/// How long after mapping the window manager may observe the initial state.
+const MAX_DELAY: Duration = Duration::from_millis(100);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    {
+        log::info!("Testing with_maximized");
+        let window = el.create_window(WindowBuilder::default().with_maximized(true));
+        let mapped = window.mapped(true).await;
+        let maximized = window.maximized(true).await;
+        if maximized.time.duration_since(mapped.time) > MAX_DELAY {
+            log::error!("The window was not maximized immediately after being mapped");
+        }
+        assert!(window.winit().is_maximized());
+    }
+
+    {
+        log::info!("Testing with_fullscreen");
+        let window = el.create_window(
+            WindowBuilder::default().with_fullscreen(Some(Fullscreen::Borderless(None))),
+        );
+        let mapped = window.mapped(true).await;
+        let fullscreen = window.fullscreen(true).await;
+        if fullscreen.time.duration_since(mapped.time) > MAX_DELAY {
+            log::error!("The window was not fullscreen immediately after being mapped");
+        }
+        assert!(window.winit().fullscreen().is_some());
+        let mon = window.winit().current_monitor().unwrap();
+        window.outer_position(0, 0).await;
+        window.inner_size(mon.size().width, mon.size().height).await;
+    }
+}
@@ -0,0 +1,53 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::Event;
+
+test!(run, BackendFlags::SUSPEND);
+
+/// The event loop starts resumed. Afterwards Suspended and Resumed alternate, starting
+/// with Suspended.
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    log::info!("Waiting for the initial Resumed");
+    loop {
+        match events.event().await {
+            Event::Resumed => break,
+            Event::Suspended => panic!("Suspended before the initial Resumed"),
+            _ => {}
+        }
+    }
+
+    let window = el.create_window(Default::default());
+    window.mapped(true).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+
+    for i in 0..2 {
+        log::info!("Suspend/resume cycle {}", i);
+        instance.suspend();
+        loop {
+            match events.event().await {
+                Event::Suspended => break,
+                Event::Resumed => panic!("Resumed before Suspended"),
+                _ => {}
+            }
+        }
+        instance.resume();
+        loop {
+            match events.event().await {
+                Event::Resumed => break,
+                Event::Suspended => panic!("Suspended twice"),
+                _ => {}
+            }
+        }
+    }
+
+    log::info!("Checking that there are no further lifecycle events");
+    el.barrier().await;
+    for event in events.events_until_quiet(200).await {
+        if matches!(event, Event::Suspended | Event::Resumed) {
+            panic!("Unexpected lifecycle event {:?}", event);
+        }
+    }
+}
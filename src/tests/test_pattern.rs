@@ -0,0 +1,34 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::screenshot::Image;
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::TEST_PATTERN | BackendFlags::CAPTURE_WINDOW | BackendFlags::SET_INNER_SIZE
+);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(100, 100);
+    window.outer_position(100, 100).await;
+
+    window.present_test_pattern(1).unwrap();
+    instance
+        .capture_window(&*window)
+        .assert_similar_to(&Image::test_pattern(1, 200, 100), 0);
+
+    window.set_inner_size(300, 150);
+    window.inner_size(300, 150).await;
+
+    window.present_test_pattern(2).unwrap();
+    instance
+        .capture_window(&*window)
+        .assert_similar_to(&Image::test_pattern(2, 300, 150), 0);
+}
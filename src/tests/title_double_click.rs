@@ -0,0 +1,66 @@
+use crate::backend::{BackendFlags, Button, Instance};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use winit::dpi::PhysicalSize;
+
+test!(
+    run,
+    BackendFlags::TITLE_BAR_MAXIMIZE | BackendFlags::SET_OUTER_POSITION
+);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Mapped);
+    let mouse = s.seat.add_mouse();
+
+    s.window.set_outer_position(100, 100);
+    s.window.outer_position(100, 100).await;
+    s.window.maximized(false).await;
+    let (left, top) = s.window.inner_offset();
+    let size = s.window.winit().inner_size();
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    let double_click = || {
+        s.seat
+            .set_cursor_position(100 + left + size.width as i32 / 2, 100 + top / 2);
+        for _ in 0..2 {
+            drop(mouse.press(Button::Left));
+        }
+    };
+
+    let monitor = s.window.winit().current_monitor().unwrap();
+
+    log::info!("Maximizing via the title bar");
+    double_click();
+    s.window.maximized(true).await;
+    s.window.await_winit(|w| w.is_maximized()).await;
+    let (_, top) = s.window.inner_offset();
+    let maximized_size = PhysicalSize {
+        width: monitor.size().width,
+        height: monitor.size().height - top as u32,
+    };
+    check_events(&mut *s.events, maximized_size).await;
+
+    log::info!("Restoring via the title bar");
+    double_click();
+    s.window.maximized(false).await;
+    s.window.await_winit(|w| !w.is_maximized()).await;
+    s.window.outer_position(100, 100).await;
+    check_events(&mut *s.events, size).await;
+}
+
+async fn check_events(events: &mut dyn EventStream, size: PhysicalSize<u32>) {
+    let mut resized = None;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            match &we.event {
+                WindowEvent::MouseInput(_) => {
+                    log::error!("Title bar click leaked to the client: {:?}", event);
+                }
+                WindowEvent::Resized(s) => resized = Some(*s),
+                _ => {}
+            }
+        }
+    }
+    assert_eq!(resized, Some(size));
+}
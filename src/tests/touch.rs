@@ -1,9 +1,9 @@
-use crate::backend::Instance;
+use crate::backend::{BackendFlags, Instance};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
 use winit::event::TouchPhase;
 use winit::window::WindowBuilder;
 
-test!(run);
+test!(run, BackendFlags::TOUCH);
 
 async fn run(instance: &dyn Instance) {
     let seat = instance.default_seat();
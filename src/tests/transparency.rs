@@ -1,13 +1,36 @@
 use crate::backend::{BackendFlags, Instance};
+use crate::screenshot::Image;
+use winit::dpi::PhysicalSize;
 use winit::window::WindowBuilder;
 
-test!(run, BackendFlags::WINIT_TRANSPARENCY);
+test!(
+    run,
+    BackendFlags::WINIT_TRANSPARENCY | BackendFlags::CAPTURE_WINDOW
+);
 
 async fn run(instance: &dyn Instance) {
     let el = instance.create_event_loop();
+    let size = PhysicalSize {
+        width: 100,
+        height: 100,
+    };
 
-    {
-        let window = el.create_window(WindowBuilder::default().with_transparent(true));
-        assert!(window.properties().supports_transparency());
+    for transparent in [false, true] {
+        log::info!("Testing transparent = {}", transparent);
+        let window = el.create_window(
+            WindowBuilder::new()
+                .with_inner_size(size)
+                .with_transparent(transparent),
+        );
+        window.mapped(true).await;
+        assert_eq!(window.properties().supports_transparency(), transparent);
+        // The background color has no alpha channel. In a window with an alpha channel,
+        // it is therefore fully transparent.
+        window.set_background_color(100, 100, 150);
+
+        let image = instance.capture_window(&*window);
+        let alpha = if transparent { 0 } else { 255 };
+        assert_eq!(image.pixel(50, 50), [100, 100, 150, alpha]);
+        image.assert_similar_to(&Image::filled(100, 100, [100, 100, 150, alpha]), 0);
     }
 }
@@ -0,0 +1,26 @@
+use crate::backend::Instance;
+use crate::keyboard::Layout;
+use winit::event::ElementState;
+
+test!(run);
+
+const TEXT: &str = "Hello, World! 1+1=2 (@home)";
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+
+    for layout in [Layout::Qwerty, Layout::Azerty] {
+        s.seat.set_layout(layout);
+        kb.type_text(TEXT).await;
+        let mut text = String::new();
+        while text.len() < TEXT.len() {
+            let (we, ki) = s.events.window_keyboard_input().await;
+            assert_eq!(we.window_id, s.window.winit_id());
+            if ki.event.state == ElementState::Pressed {
+                text.push_str(ki.event.text.unwrap_or(""));
+            }
+        }
+        assert_eq!(text, TEXT);
+    }
+}
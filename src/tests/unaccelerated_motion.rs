@@ -0,0 +1,57 @@
+use crate::backend::Instance;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::window::WindowBuilder;
+
+test!(run);
+
+/// Relative motions of increasing size. With pointer acceleration, the larger ones would
+/// move the cursor further than requested.
+const MOTIONS: &[(i32, i32)] = &[
+    (1, 0),
+    (0, 1),
+    (3, -2),
+    (25, 40),
+    (-30, -35),
+    (120, 90),
+    (-1, -1),
+];
+
+async fn run(instance: &dyn Instance) {
+    let seat = instance.default_seat();
+    let mouse = seat.add_mouse();
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 300,
+        height: 300,
+    }));
+    window.mapped(true).await;
+
+    let (ox, oy) = window.inner_offset();
+    let (mut x, mut y) = (10, 10);
+    seat.set_cursor_position(ox + x, oy + y);
+    loop {
+        let (_, cm) = events.window_cursor_moved().await;
+        if cm.position == (PhysicalPosition { x: 10.0, y: 10.0 }) {
+            break;
+        }
+    }
+
+    for &(dx, dy) in MOTIONS {
+        mouse.move_(dx, dy);
+        x += dx;
+        y += dy;
+        let (we, cm) = events.window_cursor_moved().await;
+        assert_eq!(we.window_id, window.winit_id());
+        assert_eq!(
+            cm.position,
+            PhysicalPosition {
+                x: x as f64,
+                y: y as f64
+            }
+        );
+        assert_eq!(seat.cursor_position(), (ox + x, oy + y));
+    }
+}
@@ -0,0 +1,53 @@
+use crate::backend::{BackendFlags, EventLoop, Instance};
+use crate::sleep::sleep_ms;
+use winit::dpi::PhysicalSize;
+
+test!(run, BackendFlags::SET_MONITOR_MODE);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    el.num_available_monitors(1).await;
+
+    check_modes(&*el, &[(1024, 768, 60)]).await;
+
+    instance.set_monitor_mode(0, 1280, 720, 50_000);
+    wait_for_size(&*el, 1280, 720).await;
+    check_modes(&*el, &[(1024, 768, 60), (1280, 720, 50)]).await;
+
+    instance.set_monitor_mode(0, 640, 480, 75_000);
+    wait_for_size(&*el, 640, 480).await;
+    check_modes(&*el, &[(1024, 768, 60), (1280, 720, 50), (640, 480, 75)]).await;
+
+    instance.set_monitor_mode(0, 1024, 768, 60_000);
+    wait_for_size(&*el, 1024, 768).await;
+    check_modes(&*el, &[(1024, 768, 60), (1280, 720, 50), (640, 480, 75)]).await;
+}
+
+async fn wait_for_size(el: &dyn EventLoop, width: u32, height: u32) {
+    log::info!(
+        "Waiting for the monitor size to become {}x{}",
+        width,
+        height
+    );
+    loop {
+        let monitors = el.available_monitors();
+        if monitors[0].size() == PhysicalSize::new(width, height) {
+            return;
+        }
+        sleep_ms(10).await;
+    }
+}
+
+async fn check_modes(el: &dyn EventLoop, expected: &[(u32, u32, u16)]) {
+    let monitor = el.available_monitors().pop().unwrap();
+    let modes: Vec<_> = monitor
+        .video_modes()
+        .map(|m| (m.size().width, m.size().height, m.refresh_rate()))
+        .collect();
+    log::info!("Video modes: {:?}", modes);
+    for mode in expected {
+        if !modes.contains(mode) {
+            log::error!("Mode {:?} is missing", mode);
+        }
+    }
+}
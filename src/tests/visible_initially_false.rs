@@ -0,0 +1,49 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::WINIT_SET_VISIBLE);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_visible(false).with_inner_size(
+        PhysicalSize {
+            width: 100,
+            height: 100,
+        },
+    ));
+    events.events_until_quiet(200).await;
+    assert!(!window.properties().mapped());
+    assert_eq!(window.properties().map_requests(), 0);
+
+    window.winit_set_visible(true);
+    window.mapped(true).await;
+    assert_eq!(window.properties().map_requests(), 1);
+
+    let (mut focused, mut resized, mut moved) = (0, 0, 0);
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if we.window_id != window.winit_id() {
+                continue;
+            }
+            match we.event {
+                WindowEvent::Focused(_) => focused += 1,
+                WindowEvent::Resized(_) => resized += 1,
+                WindowEvent::Moved(_) => moved += 1,
+                _ => continue,
+            }
+            log::info!("Event after mapping: {:?}", event);
+        }
+    }
+    if focused > 1 || resized > 1 || moved > 1 {
+        log::error!(
+            "Mapping produced {} Focused, {} Resized, and {} Moved events instead of at most one each",
+            focused,
+            resized,
+            moved
+        );
+    }
+}
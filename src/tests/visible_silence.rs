@@ -0,0 +1,74 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use crate::keyboard::Key::KeyL;
+
+test!(
+    run,
+    BackendFlags::WINIT_SET_VISIBLE | BackendFlags::SET_OUTER_POSITION
+);
+
+async fn run(instance: &dyn Instance) {
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+    let mouse = s.seat.add_mouse();
+
+    s.window.set_outer_position(100, 100);
+    s.window.outer_position(100, 100).await;
+    let (left, top) = s.window.inner_offset();
+
+    s.window.winit_set_visible(false);
+    s.window.mapped(false).await;
+    s.events.events_until_quiet(100).await;
+
+    let inject = || {
+        s.seat.set_cursor_position(100 + left + 50, 100 + top + 50);
+        mouse.move_(10, 10);
+        mouse.move_(-20, 0);
+        kb.press(KeyL);
+    };
+
+    inject();
+    let id = s.window.winit_id();
+    for event in s.events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if we.window_id != id {
+                continue;
+            }
+            match we.event {
+                WindowEvent::CursorMoved(_)
+                | WindowEvent::CursorEntered(_)
+                | WindowEvent::Focused(_)
+                | WindowEvent::KeyboardInput(_) => {
+                    log::error!("Hidden window received an event: {:?}", event);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    log::info!("Checking that the same input reaches the window once it is visible");
+    s.window.winit_set_visible(true);
+    s.window.mapped(true).await;
+    s.seat.focus_and_wait(&*s.window, &mut *s.events).await;
+    s.events.events_until_quiet(100).await;
+
+    inject();
+    let (mut moved, mut keys) = (false, 0);
+    for event in s.events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if we.window_id != id {
+                continue;
+            }
+            match we.event {
+                WindowEvent::CursorMoved(_) => moved = true,
+                WindowEvent::KeyboardInput(_) => keys += 1,
+                _ => {}
+            }
+        }
+    }
+    assert!(moved, "The visible window received no CursorMoved event");
+    assert_eq!(
+        keys, 2,
+        "The visible window did not receive the key press and release"
+    );
+}
@@ -0,0 +1,49 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::PhysicalSize;
+
+test!(run, BackendFlags::WINIT_SET_INNER_SIZE);
+
+async fn run(instance: &dyn Instance) {
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window1 = el.create_window(Default::default());
+    let window2 = el.create_window(Default::default());
+    window1.mapped(true).await;
+    window2.mapped(true).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+
+    log::info!("Resizing both windows");
+    window1.winit_set_inner_size(PhysicalSize::new(150, 150));
+    window1.inner_size(150, 150).await;
+    window2.winit_set_inner_size(PhysicalSize::new(250, 250));
+    window2.inner_size(250, 250).await;
+    el.barrier().await;
+
+    log::info!("Consuming the events of the second window first");
+    let mut events2 = el.window_events(window2.winit_id());
+    let (we, size) = events2.window_resize_event().await;
+    assert_eq!(we.window_id, window2.winit_id());
+    assert_eq!(size, PhysicalSize::new(250, 250));
+
+    log::info!("Checking that the events of the first window are still queued");
+    loop {
+        let we = window1.next_event().await;
+        assert_eq!(we.window_id, window1.winit_id());
+        if let WindowEvent::Resized(size) = we.event {
+            assert_eq!(size, PhysicalSize::new(150, 150));
+            break;
+        }
+    }
+
+    log::info!("Checking that the resize of the first window has been removed from the queue");
+    for event in events.events_until_quiet(100).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Resized(_) = we.event {
+                assert_ne!(we.window_id, window1.winit_id());
+            }
+        }
+    }
+}
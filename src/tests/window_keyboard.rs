@@ -1,59 +1,106 @@
 use crate::backend::Instance;
+use crate::event::{Event, KeyEventMatcher, WindowEvent};
+use crate::expect::Expect;
 use crate::keyboard::Key::{
     KeyEsc, KeyL, KeyLeftbrace, KeyLeftctrl, KeyLeftshift, KeyQ, KeyRightalt, KeyRightctrl,
 };
 use crate::keyboard::Layout;
-use winit::event::ElementState;
+use winit::event::ElementState::{self, Pressed, Released};
 use winit::keyboard::{Key as WKey, KeyCode, KeyLocation, ModifiersState};
 
 test!(run);
 
+/// Matches a key that does not produce text.
+fn key(
+    physical_key: KeyCode,
+    logical_key: WKey<'static>,
+    location: KeyLocation,
+    state: ElementState,
+) -> KeyEventMatcher {
+    KeyEventMatcher {
+        physical_key: Some(physical_key),
+        logical_key: Some(logical_key.clone()),
+        text: Some(None),
+        location: Some(location),
+        state: Some(state),
+        repeat: Some(false),
+        key_without_modifiers: Some(logical_key),
+        text_with_all_modifiers: Some(None),
+    }
+}
+
+/// Matches the press of a key that produces `text`. `unmodified` is the logical key
+/// without modifiers.
+fn text_press(
+    physical_key: KeyCode,
+    logical_key: &'static str,
+    unmodified: &'static str,
+    text: &'static str,
+    text_with_all_modifiers: &'static str,
+) -> KeyEventMatcher {
+    KeyEventMatcher {
+        text: Some(Some(text)),
+        text_with_all_modifiers: Some(Some(text_with_all_modifiers)),
+        ..text_release(physical_key, logical_key, unmodified)
+    }
+}
+
+/// Matches the release of a key that produced text.
+fn text_release(
+    physical_key: KeyCode,
+    logical_key: &'static str,
+    unmodified: &'static str,
+) -> KeyEventMatcher {
+    KeyEventMatcher {
+        key_without_modifiers: Some(WKey::Character(unmodified)),
+        ..key(
+            physical_key,
+            WKey::Character(logical_key),
+            KeyLocation::Standard,
+            Released,
+        )
+    }
+}
+
+/// Expects events in order and fails on key events that do not match.
+///
+/// `ModifiersChanged` is not strict since backends may report the same modifiers more than
+/// once, e.g. when the state of a key and the modifier state arrive separately.
+fn keyboard() -> Expect<'static> {
+    Expect::ordered().strict(|e| match e {
+        Event::WindowEvent(we) => matches!(we.event, WindowEvent::KeyboardInput(_)),
+        _ => false,
+    })
+}
+
 async fn run(instance: &dyn Instance) {
-    let el = instance.create_event_loop();
-    let mut events = el.events();
-    let window = el.create_window(Default::default());
-    window.mapped(true).await;
-    let seat = instance.default_seat();
-    seat.focus(&*window);
+    let mut s = setup!(instance, Focused);
+    let events = &mut s.events;
+    let seat = &s.seat;
     let kb = seat.add_keyboard();
 
+    let shift = |state| key(KeyCode::ShiftLeft, WKey::Shift, KeyLocation::Left, state);
+    let rctrl = |state| {
+        key(
+            KeyCode::ControlRight,
+            WKey::Control,
+            KeyLocation::Right,
+            state,
+        )
+    };
+
     {
         log::info!("Testing L");
         // L Press
         // L Release
         kb.press(KeyL);
-        for i in 0..2 {
-            let (_, ki) = events.window_keyboard_input().await;
-            assert_eq!(ki.event.physical_key, KeyCode::KeyL);
-            assert_eq!(ki.event.logical_key, WKey::Character("l"));
-            assert_eq!(ki.event.location, KeyLocation::Standard);
-            if i == 0 {
-                assert_eq!(ki.event.text, Some("l"));
-                assert_eq!(ki.event.state, ElementState::Pressed);
-            } else {
-                assert_eq!(ki.event.text, None);
-                assert_eq!(ki.event.state, ElementState::Released);
-            }
-            assert_eq!(ki.event.repeat, false);
-            #[cfg(have_mod_supplement)]
-            {
-                assert_eq!(
-                    ki.event.mod_supplement.key_without_modifiers,
-                    WKey::Character("l")
-                );
-                if i == 0 {
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        Some("l")
-                    );
-                } else {
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        None
-                    );
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(text_press(KeyCode::KeyL, "l", "l", "l", "l"))
+                    .key(text_release(KeyCode::KeyL, "l", "l")),
+            )
+            .await;
     }
 
     {
@@ -66,71 +113,17 @@ async fn run(instance: &dyn Instance) {
             let _shift = kb.press(KeyLeftshift);
             kb.press(KeyL);
         }
-        // 0: Shift pressed
-        // 1: Modifiers changed
-        // 2: L pressed
-        // 3: L released
-        // 4: Shift released
-        // 5: Modifiers changed
-        for i in 0..6 {
-            if i == 1 || i == 5 {
-                let (_, mo) = events.window_modifiers().await;
-                if i == 1 {
-                    assert_eq!(mo, ModifiersState::SHIFT);
-                } else {
-                    assert_eq!(mo, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                assert_eq!(ki.event.repeat, false);
-                if i == 0 || i == 4 {
-                    assert_eq!(ki.event.physical_key, KeyCode::ShiftLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Shift);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Left);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Shift);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyL);
-                    assert_eq!(ki.event.logical_key, WKey::Character("L"));
-                    if i == 2 {
-                        assert_eq!(ki.event.text, Some("L"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("l")
-                        );
-                        if i == 2 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("L")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if i == 0 || i == 2 {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(shift(Pressed))
+                    .modifiers(ModifiersState::SHIFT)
+                    .key(text_press(KeyCode::KeyL, "L", "l", "L", "L"))
+                    .key(text_release(KeyCode::KeyL, "L", "l"))
+                    .key(shift(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
 
     {
@@ -144,69 +137,17 @@ async fn run(instance: &dyn Instance) {
             let _l = kb.press(KeyL);
             drop(shift);
         }
-        // 0: Shift pressed
-        // 1: Modifiers changed
-        // 2: L pressed
-        // 3: Shift released
-        // 4: Modifiers changed
-        // 5: L released
-        for i in 0..6 {
-            if i == 1 || i == 4 {
-                let (_, mo) = events.window_modifiers().await;
-                if i == 1 {
-                    assert_eq!(mo, ModifiersState::SHIFT);
-                } else {
-                    assert_eq!(mo, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                assert_eq!(ki.event.repeat, false);
-                if i == 0 || i == 3 {
-                    assert_eq!(ki.event.physical_key, KeyCode::ShiftLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Shift);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Left);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Shift);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyL);
-                    #[cfg(have_mod_supplement)]
-                    assert_eq!(
-                        ki.event.mod_supplement.key_without_modifiers,
-                        WKey::Character("l")
-                    );
-                    if i == 2 {
-                        assert_eq!(ki.event.logical_key, WKey::Character("L"));
-                        assert_eq!(ki.event.text, Some("L"));
-                        #[cfg(have_mod_supplement)]
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            Some("L")
-                        );
-                    } else {
-                        assert_eq!(ki.event.logical_key, WKey::Character("l"));
-                        assert_eq!(ki.event.text, None);
-                        #[cfg(have_mod_supplement)]
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                }
-                if i == 0 || i == 2 {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(shift(Pressed))
+                    .modifiers(ModifiersState::SHIFT)
+                    .key(text_press(KeyCode::KeyL, "L", "l", "L", "L"))
+                    .key(shift(Released))
+                    .modifiers(ModifiersState::empty())
+                    .key(text_release(KeyCode::KeyL, "l", "l")),
+            )
+            .await;
     }
 
     {
@@ -219,71 +160,17 @@ async fn run(instance: &dyn Instance) {
             let _shift = kb.press(KeyRightctrl);
             kb.press(KeyL);
         }
-        // 0: Ctrl pressed
-        // 1: Modifiers changed
-        // 2: L pressed
-        // 3: L released
-        // 4: Ctrl released
-        // 5: Modifiers changed
-        for i in 0..6 {
-            if i == 1 || i == 5 {
-                let (_, mo) = events.window_modifiers().await;
-                if i == 1 {
-                    assert_eq!(mo, ModifiersState::CONTROL);
-                } else {
-                    assert_eq!(mo, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                assert_eq!(ki.event.repeat, false);
-                if i == 0 || i == 4 {
-                    assert_eq!(ki.event.physical_key, KeyCode::ControlRight);
-                    assert_eq!(ki.event.logical_key, WKey::Control);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Right);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Control);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyL);
-                    assert_eq!(ki.event.logical_key, WKey::Character("l"));
-                    if i == 2 {
-                        assert_eq!(ki.event.text, Some("l"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("l")
-                        );
-                        if i == 2 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("\x0c")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if i == 0 || i == 2 {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(rctrl(Pressed))
+                    .modifiers(ModifiersState::CONTROL)
+                    .key(text_press(KeyCode::KeyL, "l", "l", "l", "\x0c"))
+                    .key(text_release(KeyCode::KeyL, "l", "l"))
+                    .key(rctrl(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
     {
         log::info!("Testing Ctrl-Shift-L");
@@ -299,90 +186,21 @@ async fn run(instance: &dyn Instance) {
             kb.press(KeyL);
             drop(ctrl);
         }
-        // 0: Ctrl pressed
-        // 1: Modifiers changed
-        // 2: Shift pressed
-        // 3: Modifiers changed
-        // 4: L pressed
-        // 5: L released
-        // 6: Ctrl released
-        // 7: Modifiers changed
-        // 8: Shift released
-        // 9: Modifiers changed
-        for i in 0..10 {
-            if matches!(i, 1 | 3 | 7 | 9) {
-                let (_, mo) = events.window_modifiers().await;
-                match i {
-                    1 => assert_eq!(mo, ModifiersState::CONTROL),
-                    3 => assert_eq!(mo, ModifiersState::CONTROL | ModifiersState::SHIFT),
-                    7 => assert_eq!(mo, ModifiersState::SHIFT),
-                    9 => assert_eq!(mo, ModifiersState::empty()),
-                    _ => unreachable!(),
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                assert_eq!(ki.event.repeat, false);
-                if matches!(i, 0 | 6) {
-                    assert_eq!(ki.event.physical_key, KeyCode::ControlRight);
-                    assert_eq!(ki.event.logical_key, WKey::Control);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Right);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Control);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else if matches!(i, 2 | 8) {
-                    assert_eq!(ki.event.physical_key, KeyCode::ShiftLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Shift);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Left);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Shift);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyL);
-                    assert_eq!(ki.event.logical_key, WKey::Character("L"));
-                    if i == 4 {
-                        assert_eq!(ki.event.text, Some("L"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("l")
-                        );
-                        if i == 4 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("\x0c")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if matches!(i, 0 | 2 | 4) {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(rctrl(Pressed))
+                    .modifiers(ModifiersState::CONTROL)
+                    .key(shift(Pressed))
+                    .modifiers(ModifiersState::CONTROL | ModifiersState::SHIFT)
+                    .key(text_press(KeyCode::KeyL, "L", "l", "L", "\x0c"))
+                    .key(text_release(KeyCode::KeyL, "L", "l"))
+                    .key(rctrl(Released))
+                    .modifiers(ModifiersState::SHIFT)
+                    .key(shift(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
 
     {
@@ -392,40 +210,16 @@ async fn run(instance: &dyn Instance) {
         {
             kb.press(KeyRightalt);
         }
-        // 0: Alt pressed
-        // 1: Modifiers changed
-        // 2: Alt released
-        // 3: Modifiers changed
-        for i in 0..4 {
-            if i == 1 || i == 3 {
-                let (_, mo) = events.window_modifiers().await;
-                if i == 1 {
-                    assert_eq!(mo, ModifiersState::ALT);
-                } else {
-                    assert_eq!(mo, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                assert_eq!(ki.event.repeat, false);
-                assert_eq!(ki.event.physical_key, KeyCode::AltRight);
-                assert_eq!(ki.event.logical_key, WKey::Alt);
-                assert_eq!(ki.event.text, None);
-                assert_eq!(ki.event.location, KeyLocation::Right);
-                #[cfg(have_mod_supplement)]
-                {
-                    assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Alt);
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        None
-                    );
-                }
-                if i == 0 {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        let alt = |state| key(KeyCode::AltRight, WKey::Alt, KeyLocation::Right, state);
+        events
+            .expect(
+                keyboard()
+                    .key(alt(Pressed))
+                    .modifiers(ModifiersState::ALT)
+                    .key(alt(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
     {
         log::info!("Testing Ctrl-Shift-L");
@@ -441,95 +235,35 @@ async fn run(instance: &dyn Instance) {
             kb.press(KeyL);
             drop(ctrl);
         }
-        // 0: Ctrl pressed
-        // 1: Modifiers changed
-        // 2: Shift pressed
-        // 3: Modifiers changed
-        // 4: L pressed
-        // 5: L released
-        // 6: Ctrl released
-        // 7: Modifiers changed
-        // 8: Shift released
-        // 9: Modifiers changed
-        for i in 0..10 {
-            if matches!(i, 1 | 3 | 7 | 9) {
-                let (_, mo) = events.window_modifiers().await;
-                match i {
-                    1 => assert_eq!(mo, ModifiersState::CONTROL),
-                    3 => assert_eq!(mo, ModifiersState::CONTROL | ModifiersState::SHIFT),
-                    7 => assert_eq!(mo, ModifiersState::SHIFT),
-                    9 => assert_eq!(mo, ModifiersState::empty()),
-                    _ => unreachable!(),
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                assert_eq!(ki.event.repeat, false);
-                if matches!(i, 0 | 6) {
-                    assert_eq!(ki.event.physical_key, KeyCode::ControlRight);
-                    assert_eq!(ki.event.logical_key, WKey::Control);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Right);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Control);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else if matches!(i, 2 | 8) {
-                    assert_eq!(ki.event.physical_key, KeyCode::ShiftLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Shift);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Left);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Shift);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyL);
-                    assert_eq!(ki.event.logical_key, WKey::Character("L"));
-                    if i == 4 {
-                        assert_eq!(ki.event.text, Some("L"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("l")
-                        );
-                        if i == 4 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("\x0c")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if matches!(i, 0 | 2 | 4) {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(rctrl(Pressed))
+                    .modifiers(ModifiersState::CONTROL)
+                    .key(shift(Pressed))
+                    .modifiers(ModifiersState::CONTROL | ModifiersState::SHIFT)
+                    .key(text_press(KeyCode::KeyL, "L", "l", "L", "\x0c"))
+                    .key(text_release(KeyCode::KeyL, "L", "l"))
+                    .key(rctrl(Released))
+                    .modifiers(ModifiersState::SHIFT)
+                    .key(shift(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
 
     log::info!("Switching to Azerty layout.");
     seat.set_layout(Layout::Azerty);
 
+    let dead_circumflex = |state| {
+        key(
+            KeyCode::BracketLeft,
+            WKey::Dead(Some('^')),
+            KeyLocation::Standard,
+            state,
+        )
+    };
+
     {
         log::info!("Testing A (KeyQ)");
         // Q Press
@@ -537,42 +271,13 @@ async fn run(instance: &dyn Instance) {
         {
             kb.press(KeyQ);
         }
-        // 0: Q pressed
-        // 1: Q released
-        for i in 0..2 {
-            let (_, ki) = events.window_keyboard_input().await;
-            assert_eq!(ki.event.physical_key, KeyCode::KeyQ);
-            assert_eq!(ki.event.logical_key, WKey::Character("a"));
-            if i == 0 {
-                assert_eq!(ki.event.text, Some("a"));
-            } else {
-                assert_eq!(ki.event.text, None);
-            }
-            assert_eq!(ki.event.location, KeyLocation::Standard);
-            #[cfg(have_mod_supplement)]
-            {
-                assert_eq!(
-                    ki.event.mod_supplement.key_without_modifiers,
-                    WKey::Character("a")
-                );
-                if i == 0 {
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        Some("a")
-                    );
-                } else {
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        None
-                    );
-                }
-            }
-            if i == 0 {
-                assert_eq!(ki.event.state, ElementState::Pressed);
-            } else {
-                assert_eq!(ki.event.state, ElementState::Released);
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(text_press(KeyCode::KeyQ, "a", "a", "a", "a"))
+                    .key(text_release(KeyCode::KeyQ, "a", "a")),
+            )
+            .await;
     }
     {
         log::info!("Testing â (KeyLeftbrace, KeyQ)");
@@ -584,67 +289,15 @@ async fn run(instance: &dyn Instance) {
             kb.press(KeyLeftbrace);
             kb.press(KeyQ);
         }
-        // 0: LeftBrace pressed
-        // 1: LeftBrace released
-        // 2: Q pressed
-        // 3: Q released
-        for i in 0..4 {
-            let (_, ki) = events.window_keyboard_input().await;
-            if matches!(i, 0 | 1) {
-                assert_eq!(ki.event.physical_key, KeyCode::BracketLeft);
-                assert_eq!(ki.event.logical_key, WKey::Dead(Some('^')));
-                assert_eq!(ki.event.text, None);
-                assert_eq!(ki.event.location, KeyLocation::Standard);
-                #[cfg(have_mod_supplement)]
-                {
-                    assert_eq!(
-                        ki.event.mod_supplement.key_without_modifiers,
-                        WKey::Dead(Some('^'))
-                    );
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        None
-                    );
-                }
-                if i == 0 {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            } else {
-                assert_eq!(ki.event.physical_key, KeyCode::KeyQ);
-                assert_eq!(ki.event.logical_key, WKey::Character("a"));
-                if i == 2 {
-                    assert_eq!(ki.event.text, Some("â"));
-                } else {
-                    assert_eq!(ki.event.text, None);
-                }
-                assert_eq!(ki.event.location, KeyLocation::Standard);
-                #[cfg(have_mod_supplement)]
-                {
-                    assert_eq!(
-                        ki.event.mod_supplement.key_without_modifiers,
-                        WKey::Character("a")
-                    );
-                    if i == 2 {
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            Some("â")
-                        );
-                    } else {
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                }
-            }
-            if matches!(i, 0 | 2) {
-                assert_eq!(ki.event.state, ElementState::Pressed);
-            } else {
-                assert_eq!(ki.event.state, ElementState::Released);
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(dead_circumflex(Pressed))
+                    .key(dead_circumflex(Released))
+                    .key(text_press(KeyCode::KeyQ, "a", "a", "â", "â"))
+                    .key(text_release(KeyCode::KeyQ, "a", "a")),
+            )
+            .await;
     }
     {
         log::info!("Testing Â (KeyLeftbrace, KeyShiftLeft, KeyQ)");
@@ -659,88 +312,19 @@ async fn run(instance: &dyn Instance) {
             let _shift = kb.press(KeyLeftshift);
             kb.press(KeyQ);
         }
-        // 0: LeftBrace pressed
-        // 1: LeftBrace released
-        // 2: LeftShift pressed
-        // 3: ModifiersChanged
-        // 4: Q pressed
-        // 5: Q released
-        // 6: LeftShift released
-        // 7: ModifiersChanged
-        for i in 0..8 {
-            if matches!(i, 3 | 7) {
-                let (_, mc) = events.window_modifiers().await;
-                if i == 3 {
-                    assert_eq!(mc, ModifiersState::SHIFT);
-                } else {
-                    assert_eq!(mc, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                if matches!(i, 0 | 1) {
-                    assert_eq!(ki.event.physical_key, KeyCode::BracketLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Dead(Some('^')));
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Dead(Some('^'))
-                        );
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else if matches!(i, 2 | 6) {
-                    assert_eq!(ki.event.physical_key, KeyCode::ShiftLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Shift);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Left);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Shift);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyQ);
-                    assert_eq!(ki.event.logical_key, WKey::Character("A"));
-                    if i == 4 {
-                        assert_eq!(ki.event.text, Some("Â"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("a")
-                        );
-                        if i == 4 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("Â")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if matches!(i, 0 | 2 | 4) {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        events
+            .expect(
+                keyboard()
+                    .key(dead_circumflex(Pressed))
+                    .key(dead_circumflex(Released))
+                    .key(shift(Pressed))
+                    .modifiers(ModifiersState::SHIFT)
+                    .key(text_press(KeyCode::KeyQ, "A", "a", "Â", "Â"))
+                    .key(text_release(KeyCode::KeyQ, "A", "a"))
+                    .key(shift(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
     {
         log::info!("Testing Ctrl-â (KeyLeftctrl, KeyLeftbrace, KeyQ)");
@@ -755,88 +339,27 @@ async fn run(instance: &dyn Instance) {
             kb.press(KeyLeftbrace);
             kb.press(KeyQ);
         }
-        // 0: LeftCtrl pressed
-        // 1: ModifiersChanged
-        // 2: LeftBrace pressed
-        // 3: LeftBrace released
-        // 4: Q pressed
-        // 5: Q released
-        // 6: LeftCtrl released
-        // 7: ModifiersChanged
-        for i in 0..8 {
-            if matches!(i, 1 | 7) {
-                let (_, mc) = events.window_modifiers().await;
-                if i == 1 {
-                    assert_eq!(mc, ModifiersState::CONTROL);
-                } else {
-                    assert_eq!(mc, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                if matches!(i, 2 | 3) {
-                    assert_eq!(ki.event.physical_key, KeyCode::BracketLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Dead(Some('^')));
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Dead(Some('^'))
-                        );
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else if matches!(i, 0 | 6) {
-                    assert_eq!(ki.event.physical_key, KeyCode::ControlLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Control);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Left);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Control);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyQ);
-                    assert_eq!(ki.event.logical_key, WKey::Character("a"));
-                    if i == 4 {
-                        assert_eq!(ki.event.text, Some("â"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("a")
-                        );
-                        if i == 4 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("â")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if matches!(i, 0 | 2 | 4) {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        let lctrl = |state| {
+            key(
+                KeyCode::ControlLeft,
+                WKey::Control,
+                KeyLocation::Left,
+                state,
+            )
+        };
+        events
+            .expect(
+                keyboard()
+                    .key(lctrl(Pressed))
+                    .modifiers(ModifiersState::CONTROL)
+                    .key(dead_circumflex(Pressed))
+                    .key(dead_circumflex(Released))
+                    .key(text_press(KeyCode::KeyQ, "a", "a", "â", "â"))
+                    .key(text_release(KeyCode::KeyQ, "a", "a"))
+                    .key(lctrl(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
 
     log::info!("Switching to QwertySwapped layout.");
@@ -855,64 +378,25 @@ async fn run(instance: &dyn Instance) {
             kb.press(KeyQ);
             kb.press(KeyEsc);
         }
-        // 0: Esc pressed
-        // 1: Esc released
-        // 2: Q pressed
-        // 3: Q released
-        // 4: Esc pressed
-        // 5: Esc released
-        for i in 0..6 {
-            let (_, ki) = events.window_keyboard_input().await;
-            if matches!(i, 0 | 1 | 4 | 5) {
-                assert_eq!(ki.event.physical_key, KeyCode::Escape);
-                assert_eq!(ki.event.logical_key, WKey::CapsLock);
-                assert_eq!(ki.event.text, None);
-                assert_eq!(ki.event.location, KeyLocation::Standard);
-                #[cfg(have_mod_supplement)]
-                {
-                    assert_eq!(
-                        ki.event.mod_supplement.key_without_modifiers,
-                        WKey::CapsLock
-                    );
-                    assert_eq!(
-                        ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                        None
-                    );
-                }
-            } else {
-                assert_eq!(ki.event.physical_key, KeyCode::KeyQ);
-                assert_eq!(ki.event.logical_key, WKey::Character("Q"));
-                if i == 2 {
-                    assert_eq!(ki.event.text, Some("Q"));
-                } else {
-                    assert_eq!(ki.event.text, None);
-                }
-                assert_eq!(ki.event.location, KeyLocation::Standard);
-                #[cfg(have_mod_supplement)]
-                {
-                    assert_eq!(
-                        ki.event.mod_supplement.key_without_modifiers,
-                        WKey::Character("q")
-                    );
-                    if i == 2 {
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            Some("Q")
-                        );
-                    } else {
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                }
-            }
-            if matches!(i, 0 | 2 | 4) {
-                assert_eq!(ki.event.state, ElementState::Pressed);
-            } else {
-                assert_eq!(ki.event.state, ElementState::Released);
-            }
-        }
+        let caps_lock = |state| {
+            key(
+                KeyCode::Escape,
+                WKey::CapsLock,
+                KeyLocation::Standard,
+                state,
+            )
+        };
+        events
+            .expect(
+                keyboard()
+                    .key(caps_lock(Pressed))
+                    .key(caps_lock(Released))
+                    .key(text_press(KeyCode::KeyQ, "Q", "q", "Q", "Q"))
+                    .key(text_release(KeyCode::KeyQ, "Q", "q"))
+                    .key(caps_lock(Pressed))
+                    .key(caps_lock(Released)),
+            )
+            .await;
     }
 
     {
@@ -925,70 +409,18 @@ async fn run(instance: &dyn Instance) {
             let _shift = kb.press(KeyLeftshift);
             kb.press(KeyQ);
         }
-        // 0: Leftshift pressed
-        // 1: ModifiersChanged
-        // 2: Q pressed
-        // 3: Q released
-        // 4: Leftshift released
-        // 5: ModifiersChanged
-        for i in 0..6 {
-            if matches!(i, 1 | 5) {
-                let (_, mc) = events.window_modifiers().await;
-                if i == 1 {
-                    assert_eq!(mc, ModifiersState::SHIFT);
-                } else {
-                    assert_eq!(mc, ModifiersState::empty());
-                }
-            } else {
-                let (_, ki) = events.window_keyboard_input().await;
-                if matches!(i, 0 | 4) {
-                    // assert_eq!(ki.event.physical_key, KeyCode::ShiftRight);
-                    assert_eq!(ki.event.physical_key, KeyCode::ShiftLeft);
-                    assert_eq!(ki.event.logical_key, WKey::Shift);
-                    assert_eq!(ki.event.text, None);
-                    assert_eq!(ki.event.location, KeyLocation::Right);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(ki.event.mod_supplement.key_without_modifiers, WKey::Shift);
-                        assert_eq!(
-                            ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                            None
-                        );
-                    }
-                } else {
-                    assert_eq!(ki.event.physical_key, KeyCode::KeyQ);
-                    assert_eq!(ki.event.logical_key, WKey::Character("Q"));
-                    if i == 2 {
-                        assert_eq!(ki.event.text, Some("Q"));
-                    } else {
-                        assert_eq!(ki.event.text, None);
-                    }
-                    assert_eq!(ki.event.location, KeyLocation::Standard);
-                    #[cfg(have_mod_supplement)]
-                    {
-                        assert_eq!(
-                            ki.event.mod_supplement.key_without_modifiers,
-                            WKey::Character("q")
-                        );
-                        if i == 2 {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                Some("Q")
-                            );
-                        } else {
-                            assert_eq!(
-                                ki.event.mod_supplement.text_with_all_modifiers.as_deref(),
-                                None
-                            );
-                        }
-                    }
-                }
-                if matches!(i, 0 | 2) {
-                    assert_eq!(ki.event.state, ElementState::Pressed);
-                } else {
-                    assert_eq!(ki.event.state, ElementState::Released);
-                }
-            }
-        }
+        // let shift = |state| key(KeyCode::ShiftRight, WKey::Shift, KeyLocation::Right, state);
+        let shift = |state| key(KeyCode::ShiftLeft, WKey::Shift, KeyLocation::Right, state);
+        events
+            .expect(
+                keyboard()
+                    .key(shift(Pressed))
+                    .modifiers(ModifiersState::SHIFT)
+                    .key(text_press(KeyCode::KeyQ, "Q", "q", "Q", "Q"))
+                    .key(text_release(KeyCode::KeyQ, "Q", "q"))
+                    .key(shift(Released))
+                    .modifiers(ModifiersState::empty()),
+            )
+            .await;
     }
 }
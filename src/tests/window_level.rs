@@ -0,0 +1,48 @@
+#[cfg(not(winit_window_level))]
+missing_api!("Window::set_window_level");
+
+#[cfg(winit_window_level)]
+test!(
+    run,
+    crate::backend::BackendFlags::WINIT_SET_WINDOW_LEVEL
+        | crate::backend::BackendFlags::STACKING_ORDER
+);
+
+#[cfg(winit_window_level)]
+async fn run(instance: &dyn crate::backend::Instance) {
+    use winit::window::{WindowBuilder, WindowLevel};
+
+    let el = instance.create_event_loop();
+
+    {
+        let window = el
+            .create_window(WindowBuilder::default().with_window_level(WindowLevel::AlwaysOnBottom));
+        window.always_on_bottom(true).await;
+        window.winit_set_window_level(WindowLevel::Normal);
+        window.always_on_bottom(false).await;
+    }
+
+    let window = el.create_window(Default::default());
+    let other = el.create_window(Default::default());
+    window.mapped(true).await;
+    other.mapped(true).await;
+    other.above(&*window).await;
+
+    log::info!("Cycling the window through all levels");
+    window.winit_set_window_level(WindowLevel::AlwaysOnTop);
+    window.always_on_top(true).await;
+    window.above(&*other).await;
+    window.winit_set_window_level(WindowLevel::AlwaysOnBottom);
+    window.always_on_bottom(true).await;
+    assert!(!window.properties().always_on_top());
+    other.above(&*window).await;
+    window.winit_set_window_level(WindowLevel::Normal);
+    window.always_on_bottom(false).await;
+    assert!(!window.properties().always_on_top());
+
+    log::info!("Moving the other window to the bottom");
+    other.winit_set_window_level(WindowLevel::AlwaysOnBottom);
+    other.always_on_bottom(true).await;
+    window.above(&*other).await;
+    instance.assert_stacking_order(&[&*other, &*window]);
+}
@@ -1,4 +1,5 @@
 use crate::backend::{Button, Instance};
+use crate::event::{Event, WindowEvent};
 use crate::eventstash::EventStash;
 use crate::keyboard::Key;
 use std::collections::HashSet;
@@ -27,7 +28,7 @@ async fn run(instance: &dyn Instance) {
         .outer_position(-window.inner_offset().0, -window.inner_offset().1)
         .await;
 
-    seat.focus(&*window);
+    seat.focus_and_wait(&*window, &mut *events).await;
 
     {
         log::info!("Checking button presses inside and outside of window");
@@ -81,17 +82,20 @@ async fn run(instance: &dyn Instance) {
 
         mouse1.scroll(1, 2);
 
+        // Backends may split the scroll into several events, e.g. XTest scrolls by one
+        // line per click.
         let (we, cl) = events.window_mouse_wheel().await;
-        assert_eq!(we.window_id, window.winit_id());
-        assert!(seat.is(cl.device_id));
-        assert_eq!(cl.phase, TouchPhase::Moved);
-        let mut delta = match cl.delta {
-            MouseScrollDelta::LineDelta(dx, dy) => (dx, dy),
-            _ => unreachable!(),
-        };
-        if delta != (1.0, 2.0) {
-            let (we, cl) = events.window_mouse_wheel().await;
-            assert_eq!(we.window_id, window.winit_id());
+        let mut wheel = vec![(we.window_id, cl)];
+        for event in events.events_until_quiet(200).await {
+            if let Event::WindowEvent(we) = event {
+                if let WindowEvent::MouseWheel(cl) = we.event {
+                    wheel.push((we.window_id, cl));
+                }
+            }
+        }
+        let mut delta = (0.0, 0.0);
+        for (window_id, cl) in wheel {
+            assert_eq!(window_id, window.winit_id());
             assert!(seat.is(cl.device_id));
             assert_eq!(cl.phase, TouchPhase::Moved);
             match cl.delta {
@@ -143,7 +147,7 @@ async fn run(instance: &dyn Instance) {
         el.barrier().await;
         seat.un_focus();
         kb1.press(Key::KeyEsc);
-        seat.focus(&*window);
+        seat.focus_and_wait(&*window, &mut *events).await;
         kb1.press(Key::KeyA);
         let (_, ke) = events.window_keyboard_input().await;
         assert_eq!(ke.event.physical_key, KeyCode::KeyA);
@@ -171,7 +175,7 @@ async fn run(instance: &dyn Instance) {
         w2.mapped(true).await;
         w2.set_outer_position(300, 300);
         w2.outer_position(300, 300).await;
-        seat.focus(&*window);
+        seat.focus_and_wait(&*window, &mut *events).await;
         {
             seat.set_cursor_position(310 + w2.inner_offset().0, 310 + w2.inner_offset().1);
             loop {
@@ -0,0 +1,110 @@
+use crate::backend::{
+    BackendFlags, Button, EventLoop, FocusPolicy, Instance, Seat, Window, WmConfig,
+};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(
+    run,
+    BackendFlags::WM_FOCUS_POLICIES | BackendFlags::SET_OUTER_POSITION
+);
+
+async fn run(instance: &dyn Instance) {
+    let seat = instance.default_seat();
+    seat.set_cursor_position(0, 0);
+
+    log::info!("Testing click to focus");
+    instance.configure_wm(WmConfig {
+        focus_policy: FocusPolicy::ClickToFocus,
+        focus_stealing: false,
+        ..Default::default()
+    });
+    {
+        let el = instance.create_event_loop();
+        let mut events = el.events();
+        let window1 = create_window(&*el, 100, 100).await;
+        let window2 = create_window(&*el, 300, 100).await;
+        assert_no_focus_change(&mut *events).await;
+
+        move_into(&*seat, &*window1).await;
+        assert_no_focus_change(&mut *events).await;
+        let mouse = seat.add_mouse();
+        drop(mouse.press(Button::Left));
+        seat.focused(&*window1, &mut *events).await;
+        let (we, _) = events.window_mouse_input_event().await;
+        assert_eq!(we.window_id, window1.winit_id());
+        assert!(seat.is_focused(&*window1));
+
+        move_into(&*seat, &*window2).await;
+        drop(mouse.press(Button::Left));
+        seat.focused(&*window2, &mut *events).await;
+    }
+
+    log::info!("Testing focus follows mouse");
+    seat.set_cursor_position(0, 0);
+    instance.configure_wm(WmConfig {
+        focus_policy: FocusPolicy::FocusFollowsMouse,
+        focus_stealing: false,
+        ..Default::default()
+    });
+    {
+        let el = instance.create_event_loop();
+        let mut events = el.events();
+        let window1 = create_window(&*el, 100, 100).await;
+        let window2 = create_window(&*el, 300, 100).await;
+        assert_no_focus_change(&mut *events).await;
+
+        move_into(&*seat, &*window1).await;
+        seat.focused(&*window1, &mut *events).await;
+        move_into(&*seat, &*window2).await;
+        seat.focused(&*window2, &mut *events).await;
+    }
+
+    log::info!("Testing focus stealing");
+    seat.set_cursor_position(0, 0);
+    instance.configure_wm(WmConfig {
+        focus_policy: FocusPolicy::Manual,
+        focus_stealing: true,
+        ..Default::default()
+    });
+    {
+        let el = instance.create_event_loop();
+        let mut events = el.events();
+        let window1 = create_window(&*el, 100, 100).await;
+        seat.focused(&*window1, &mut *events).await;
+        let window2 = create_window(&*el, 300, 100).await;
+        seat.focused(&*window2, &mut *events).await;
+    }
+}
+
+async fn create_window(el: &dyn EventLoop, x: i32, y: i32) -> Box<dyn Window> {
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(x, y);
+    window.outer_position(x, y).await;
+    window
+}
+
+/// Moves the pointer to the center of the window.
+async fn move_into(seat: &dyn Seat, window: &dyn Window) {
+    let props = window.properties();
+    let (ox, oy) = window.inner_offset();
+    let (x, y) = (props.x() + ox + 50, props.y() + oy + 50);
+    seat.set_cursor_position(x, y);
+    seat.cursor_at(x, y).await;
+}
+
+async fn assert_no_focus_change(events: &mut dyn EventStream) {
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Focused(..) = &we.event {
+                panic!("The window manager changed the focus: {:?}", event);
+            }
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::backend::{
+    BackendFlags, Button, EventLoop, FocusPolicy, Instance, Seat, Window, WmConfig,
+};
+use crate::event::{Event, WindowEvent};
+use crate::eventstream::EventStream;
+use winit::dpi::PhysicalSize;
+use winit::window::{UserAttentionType, WindowBuilder};
+
+test!(
+    run,
+    BackendFlags::WM_FOCUS_POLICIES
+        | BackendFlags::SET_OUTER_POSITION
+        | BackendFlags::WINIT_SET_ATTENTION
+);
+
+/// Desktop environments prevent new windows from stealing the focus and mark them as
+/// demanding attention instead.
+async fn run(instance: &dyn Instance) {
+    instance.configure_wm(WmConfig {
+        focus_policy: FocusPolicy::ClickToFocus,
+        focus_stealing: false,
+        demand_attention: true,
+        ..Default::default()
+    });
+    let seat = instance.default_seat();
+    seat.set_cursor_position(0, 0);
+    let mouse = seat.add_mouse();
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window1 = create_window(&*el, 100, 100).await;
+    window1.attention(true).await;
+
+    log::info!("Focusing the first window by clicking it");
+    move_into(&*seat, &*window1).await;
+    drop(mouse.press(Button::Left));
+    focus_event(&mut *events, &*window1, true).await;
+    window1.attention(false).await;
+    assert!(seat.is_focused(&*window1));
+
+    log::info!("Mapping a second window");
+    let window2 = create_window(&*el, 300, 100).await;
+    window2.attention(true).await;
+    el.barrier().await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Focused(..) = &we.event {
+                panic!("The new window changed the focus: {:?}", event);
+            }
+        }
+    }
+    assert!(seat.is_focused(&*window1));
+
+    log::info!("Clearing the urgency of the second window");
+    window2.winit_set_attention(Some(UserAttentionType::Informational));
+    window2.winit_set_attention(None);
+    el.barrier().await;
+    // The mark of the WM is independent of the urgency requested by the client.
+    assert!(window2.properties().attention());
+
+    log::info!("Focusing the second window by clicking it");
+    move_into(&*seat, &*window2).await;
+    drop(mouse.press(Button::Left));
+    focus_event(&mut *events, &*window1, false).await;
+    focus_event(&mut *events, &*window2, true).await;
+    window2.attention(false).await;
+    assert!(seat.is_focused(&*window2));
+}
+
+async fn create_window(el: &dyn EventLoop, x: i32, y: i32) -> Box<dyn Window> {
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 100,
+        height: 100,
+    }));
+    window.mapped(true).await;
+    window.set_outer_position(x, y);
+    window.outer_position(x, y).await;
+    window
+}
+
+/// Moves the pointer to the center of the window.
+async fn move_into(seat: &dyn Seat, window: &dyn Window) {
+    let props = window.properties();
+    let (ox, oy) = window.inner_offset();
+    let (x, y) = (props.x() + ox + 50, props.y() + oy + 50);
+    seat.set_cursor_position(x, y);
+    seat.cursor_at(x, y).await;
+}
+
+/// Waits until winit reports that the focus of `window` has changed to `focused`.
+async fn focus_event(events: &mut dyn EventStream, window: &dyn Window, focused: bool) {
+    loop {
+        let (we, f) = events.window_focus_event().await;
+        if we.window_id == window.winit_id() && f == focused {
+            return;
+        }
+    }
+}
@@ -0,0 +1,32 @@
+use crate::backend::{BackendFlags, Instance};
+use crate::test::{set_geometry_coalescing, Coalescing};
+
+test!(run, BackendFlags::WM_ACTIONS);
+
+async fn run(instance: &dyn Instance) {
+    // Only the final size is of interest.
+    set_geometry_coalescing(Coalescing::Final);
+    let mut s = setup!(instance, Mapped);
+    let restored = s.window.maximized(false).await;
+    s.el.barrier().await;
+    s.events.events_until_quiet(100).await;
+
+    log::info!("Maximizing on the initiative of the WM");
+    s.window.wm_set_maximized(true);
+    let maximized = s.window.maximized(true).await;
+    assert!(maximized.width > restored.width);
+    assert!(maximized.height > restored.height);
+    s.events
+        .window_resized_to(s.window.winit_id(), maximized.width, maximized.height)
+        .await;
+    s.window.await_winit(|w| w.is_maximized()).await;
+
+    log::info!("Restoring on the initiative of the WM");
+    s.el.barrier().await;
+    s.window.wm_set_maximized(false);
+    s.window.maximized(false).await;
+    s.events
+        .window_resized_to(s.window.winit_id(), restored.width, restored.height)
+        .await;
+    s.window.await_winit(|w| !w.is_maximized()).await;
+}
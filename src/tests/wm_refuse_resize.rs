@@ -0,0 +1,56 @@
+use crate::backend::{BackendFlags, Instance, WmConfig};
+use crate::event::{Event, WindowEvent};
+use winit::dpi::PhysicalSize;
+use winit::window::WindowBuilder;
+
+test!(run, BackendFlags::WM_REFUSE_RESIZE);
+
+async fn run(instance: &dyn Instance) {
+    instance.configure_wm(WmConfig {
+        refuse_resize: true,
+        ..Default::default()
+    });
+
+    let el = instance.create_event_loop();
+    let mut events = el.events();
+
+    let window = el.create_window(WindowBuilder::new().with_inner_size(PhysicalSize {
+        width: 200,
+        height: 150,
+    }));
+    window.mapped(true).await;
+    window.inner_size(200, 150).await;
+    el.barrier().await;
+    events.events_until_quiet(100).await;
+
+    log::info!("Requesting a resize that the WM refuses");
+    window.winit_set_inner_size(PhysicalSize {
+        width: 300,
+        height: 250,
+    });
+    el.barrier().await;
+    for event in events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = &event {
+            if let WindowEvent::Resized(size) = we.event {
+                assert_eq!(we.window_id, window.winit_id());
+                assert_eq!(
+                    size,
+                    PhysicalSize {
+                        width: 200,
+                        height: 150
+                    },
+                    "winit reported the requested size instead of the actual size"
+                );
+            }
+        }
+    }
+    let props = window.properties();
+    assert_eq!((props.width(), props.height()), (200, 150));
+    assert_eq!(
+        window.winit().inner_size(),
+        PhysicalSize {
+            width: 200,
+            height: 150
+        }
+    );
+}
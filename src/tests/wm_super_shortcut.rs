@@ -0,0 +1,59 @@
+use crate::backend::{BackendFlags, Instance, WmConfig};
+use crate::event::{Event, WindowEvent};
+use crate::keyboard::Key::{KeyD, KeyLeftmeta};
+use winit::event::ElementState;
+use winit::keyboard::{KeyCode, ModifiersState};
+
+test!(run, BackendFlags::WM_SHORTCUTS);
+
+/// Desktop environments grab Super shortcuts. The client must only see the Super events
+/// that reach it and must not report phantom releases while the grab is active.
+async fn run(instance: &dyn Instance) {
+    instance.configure_wm(WmConfig {
+        super_shortcuts: true,
+        ..Default::default()
+    });
+    let mut s = setup!(instance, Focused);
+    let kb = s.seat.add_keyboard();
+
+    log::info!("Pressing D without Super");
+    kb.press(KeyD);
+    let (_, ki) = s.events.window_keyboard_input().await;
+    assert_eq!(ki.event.physical_key, KeyCode::KeyD);
+    assert_eq!(ki.event.state, ElementState::Pressed);
+    let (_, ki) = s.events.window_keyboard_input().await;
+    assert_eq!(ki.event.physical_key, KeyCode::KeyD);
+    assert_eq!(ki.event.state, ElementState::Released);
+
+    log::info!("Pressing Super+D");
+    {
+        let _super = kb.press(KeyLeftmeta);
+        kb.press(KeyD);
+        instance.wm_shortcuts_handled(1).await;
+    }
+    s.el.barrier().await;
+
+    let mut keys = vec![];
+    for event in s.events.events_until_quiet(200).await {
+        if let Event::WindowEvent(we) = event {
+            match we.event {
+                WindowEvent::KeyboardInput(ki) => {
+                    assert!(!ki.is_synthetic, "Synthetic key event {:?}", ki);
+                    assert_ne!(ki.event.physical_key, KeyCode::KeyD);
+                    keys.push(format!("{:?} {:?}", ki.event.physical_key, ki.event.state));
+                }
+                WindowEvent::ModifiersChanged(m) => keys.push(format!("{:?}", m)),
+                WindowEvent::Focused(f) => log::info!("Focused({})", f),
+                _ => {}
+            }
+        }
+    }
+    let expected = [
+        format!("{:?} {:?}", KeyCode::SuperLeft, ElementState::Pressed),
+        format!("{:?}", ModifiersState::SUPER),
+        format!("{:?} {:?}", KeyCode::SuperLeft, ElementState::Released),
+        format!("{:?}", ModifiersState::empty()),
+    ];
+    assert_eq!(keys, expected);
+    assert!(s.seat.is_focused(&*s.window));
+}
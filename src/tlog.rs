@@ -1,9 +1,18 @@
+use crate::test::ConnectionLost;
 use backtrace::Backtrace;
 use chrono::Local;
 use colored::{ColoredString, Colorize};
 use log::{Level, Log, Metadata, Record};
+use std::cell::Cell;
+use std::fmt::{Debug, Display, Formatter, Write as _};
 use std::fs::File;
 use std::io::{LineWriter, Write};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
 
 const LOG_LEVEL: log::Level = log::Level::Trace;
 
@@ -19,6 +28,190 @@ impl LogState {
     }
 }
 
+/// An X sequence number with which log messages are tagged.
+///
+/// Messages are tagged with the full sequence number followed by its lower 16 bits in
+/// hexadecimal as printed by xtrace.
+#[derive(Copy, Clone, Debug)]
+pub enum Sequence {
+    /// A request sent by the harness.
+    Request(u32),
+    /// An event received by the harness.
+    Event(u32),
+}
+
+impl Display for Sequence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let (kind, seq) = match *self {
+            Sequence::Request(seq) => ("request", seq),
+            Sequence::Event(seq) => ("event", seq),
+        };
+        write!(f, "{} {} ({:04x})", kind, seq, seq & 0xffff)
+    }
+}
+
+thread_local! {
+    static SEQUENCE: Cell<Option<Sequence>> = Cell::new(None);
+}
+
+/// Tags the messages logged by `f` on this thread with `seq`.
+pub fn with_sequence<T>(seq: Sequence, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<Sequence>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            SEQUENCE.with(|s| s.set(self.0));
+        }
+    }
+    let _restore = Restore(SEQUENCE.with(|s| s.replace(Some(seq))));
+    f()
+}
+
+/// The accumulated durations of the spans with the same stack in a test.
+#[derive(Default)]
+pub struct SpanStats {
+    pub count: u32,
+    pub total: Duration,
+    /// The total minus the time spent in child spans.
+    pub self_time: Duration,
+    pub max: Duration,
+}
+
+/// A tracing layer that logs the creation of spans and records their durations in the
+/// data of the current test.
+///
+/// The awaiters of the harness are instrumented with spans so that the summary logged at
+/// the end of each test shows where the test spent its time.
+struct SpanTimings;
+
+struct SpanTiming {
+    start: Instant,
+    children: Duration,
+}
+
+struct FieldFormatter(String);
+
+impl Visit for FieldFormatter {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        let _ = write!(self.0, " {}={:?}", field.name(), value);
+    }
+}
+
+fn log_level(level: tracing::Level) -> Level {
+    match level {
+        tracing::Level::ERROR => Level::Error,
+        tracing::Level::WARN => Level::Warn,
+        tracing::Level::INFO => Level::Info,
+        tracing::Level::DEBUG => Level::Debug,
+        _ => Level::Trace,
+    }
+}
+
+impl<S> Layer<S> for SpanTimings
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let meta = attrs.metadata();
+        let mut fields = FieldFormatter(String::new());
+        attrs.record(&mut fields);
+        log::logger().log(
+            &Record::builder()
+                .level(log_level(*meta.level()))
+                .target(meta.target())
+                .module_path(meta.module_path())
+                .file(meta.file())
+                .line(meta.line())
+                .args(format_args!("Awaiting {}{}", meta.name(), fields.0))
+                .build(),
+        );
+        let start = Instant::now();
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(SpanTiming {
+                start,
+                children: Duration::ZERO,
+            });
+        }
+        if crate::test::has_test_data() {
+            let desc = format!("{}{}", meta.name(), fields.0);
+            crate::test::with_test_data(|td| {
+                td.open_spans
+                    .borrow_mut()
+                    .insert(id.into_u64(), (start, desc))
+            });
+        }
+    }
+
+    fn on_close(&self, id: Id, ctx: Context<'_, S>) {
+        let span = match ctx.span(&id) {
+            Some(s) => s,
+            _ => return,
+        };
+        let (elapsed, children) = match span.extensions().get::<SpanTiming>() {
+            Some(t) => (t.start.elapsed(), t.children),
+            _ => return,
+        };
+        if let Some(parent) = span.parent() {
+            if let Some(t) = parent.extensions_mut().get_mut::<SpanTiming>() {
+                t.children += elapsed;
+            }
+        }
+        log::trace!("{} completed after {:?}", span.name(), elapsed);
+        if !crate::test::has_test_data() {
+            return;
+        }
+        let stack: Vec<_> = span.scope().from_root().map(|s| s.name()).collect();
+        crate::test::with_test_data(|td| {
+            td.open_spans.borrow_mut().remove(&id.into_u64());
+            let mut spans = td.spans.borrow_mut();
+            let stats = spans.entry(stack.join(";")).or_default();
+            stats.count += 1;
+            stats.total += elapsed;
+            stats.self_time += elapsed.saturating_sub(children);
+            stats.max = stats.max.max(elapsed);
+        });
+    }
+}
+
+/// Logs how much time the current test spent in each span stack and writes the self times
+/// in the collapsed stack format of flamegraph tools to `spans.folded` in the test
+/// directory.
+pub fn log_span_summary() {
+    crate::test::with_test_data(|td| {
+        let mut spans: Vec<_> = td.spans.take().into_iter().collect();
+        if spans.is_empty() {
+            return;
+        }
+        spans.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.total));
+        let mut summary = String::new();
+        let mut folded = String::new();
+        for (stack, stats) in &spans {
+            let _ = write!(
+                summary,
+                "\n  - {}: {}x, total {:?}, max {:?}",
+                stack, stats.count, stats.total, stats.max
+            );
+            let _ = writeln!(folded, "{} {}", stack, stats.self_time.as_micros());
+        }
+        log::info!("Time spent in awaiters:{}", summary);
+        if let Err(e) = std::fs::write(td.test_dir.join("spans.folded"), folded) {
+            log::warn!("Could not write the span timings: {}", e);
+        }
+    });
+}
+
+/// Returns the spans of the current test that are still open together with how long
+/// they have been open, oldest first.
+pub fn pending_spans() -> Vec<(Duration, String)> {
+    crate::test::with_test_data(|td| {
+        let mut spans: Vec<_> = td.open_spans.borrow().values().cloned().collect();
+        spans.sort_by_key(|(start, _)| *start);
+        spans
+            .into_iter()
+            .map(|(start, desc)| (start.elapsed(), desc))
+            .collect()
+    })
+}
+
 struct Logger;
 
 fn level_color(level: Level) -> ColoredString {
@@ -37,15 +230,23 @@ impl Log for Logger {
 
     fn log(&self, record: &Record) {
         let now = Local::now().format("%Y-%m-%d %H:%M:%S,%3f");
+        let seq = match SEQUENCE.with(|s| s.get()) {
+            Some(seq) => format!(" [{}]", seq),
+            None => String::new(),
+        };
         if !crate::test::has_test_data() {
-            eprintln!(
-                "{} [{}] [{}] [{}]: {}",
+            if record.level() > Level::Warn && crate::console::active() {
+                return;
+            }
+            crate::console::eprintln(&format!(
+                "{} [{}] [{}] [{}]{}: {}",
                 now,
                 level_color(record.level()),
                 std::thread::current().name().unwrap_or("<unnamed thread>"),
                 record.module_path().unwrap_or(""),
+                seq,
                 record.args()
-            );
+            ));
         } else {
             crate::test::with_test_data(|td| {
                 let mut log = td.log_state.lock();
@@ -53,15 +254,19 @@ impl Log for Logger {
                 if let Some(p) = path.strip_prefix("winit_it::") {
                     if record.metadata().level() == log::Level::Error {
                         td.error.set(true);
+                        td.first_error
+                            .borrow_mut()
+                            .get_or_insert_with(|| record.args().to_string());
                     }
                     path = p;
                 }
                 writeln!(
                     &mut log.file,
-                    "{} [{}] [{}]: {}",
+                    "{} [{}] [{}]{}: {}",
                     now,
                     record.metadata().level(),
                     path,
+                    seq,
                     record.args()
                 )
                 .unwrap();
@@ -75,14 +280,26 @@ impl Log for Logger {
 pub fn init() {
     log::set_logger(&Logger).unwrap();
     log::set_max_level(LOG_LEVEL.to_level_filter());
+    let subscriber = tracing_subscriber::registry().with(SpanTimings);
+    tracing::subscriber::set_global_default(subscriber).unwrap();
     std::panic::set_hook(Box::new(|info| {
         let msg = match info.payload().downcast_ref::<&'static str>() {
             Some(s) => *s,
             None => match info.payload().downcast_ref::<String>() {
                 Some(s) => &**s,
+                None if info.payload().is::<ConnectionLost>() => {
+                    "the connection to the display server has been lost"
+                }
                 None => "Box<Any>",
             },
         };
+        if !info.payload().is::<ConnectionLost>()
+            && crate::test::has_test_data()
+            && crate::test::with_test_data(|td| td.expecting_panic.get())
+        {
+            log::info!("expected panic at '{}'", msg);
+            return;
+        }
         let bt = Backtrace::default();
         match info.location() {
             Some(location) => {
@@ -101,5 +318,8 @@ pub fn init() {
                 bt,
             ),
         }
+        if crate::test::has_test_data() {
+            crate::eventtrace::dump_test();
+        }
     }));
 }
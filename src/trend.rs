@@ -0,0 +1,219 @@
+use crate::report::{escape, Status};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// The fields of a `TestReport` that are aggregated.
+#[derive(Deserialize)]
+struct RecordedReport {
+    backend: String,
+    test: String,
+    status: Status,
+    duration_ms: u64,
+}
+
+#[derive(Serialize)]
+struct Trend {
+    /// The names of the testrun directories from oldest to newest.
+    runs: Vec<String>,
+    tests: Vec<TestTrend>,
+}
+
+#[derive(Serialize)]
+struct TestTrend {
+    backend: String,
+    test: String,
+    /// The number of runs in which the test passed or failed. Runs in which the test was
+    /// not run or required manual verification are not counted.
+    runs: usize,
+    passes: usize,
+    pass_rate: Option<f64>,
+    avg_duration_ms: Option<u64>,
+    /// The status of the test in each run. `None` if the run did not include the test.
+    history: Vec<Option<Status>>,
+}
+
+/// Merges the `summary.json` files of the testrun directories `dirs` into `trend.json` and
+/// `trend.html` in the testruns directory. If `dirs` is empty, all recorded testruns are
+/// merged.
+pub fn run(dirs: &[PathBuf]) {
+    let mut dirs = match dirs.is_empty() {
+        true => recorded_testruns(),
+        false => dirs.to_vec(),
+    };
+    dirs.sort_by_cached_key(|d| start_time(d));
+    let mut runs = vec![];
+    let mut results = BTreeMap::<_, Vec<_>>::new();
+    for dir in &dirs {
+        let reports = match read_summary(dir) {
+            Some(r) => r,
+            _ => continue,
+        };
+        let idx = runs.len();
+        runs.push(
+            dir.file_name()
+                .unwrap_or(dir.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+        );
+        for report in reports {
+            let history = results
+                .entry((report.backend.clone(), report.test.clone()))
+                .or_default();
+            history.resize_with(idx, || None);
+            history.push(Some(report));
+        }
+    }
+    let tests = results
+        .into_iter()
+        .map(|((backend, test), mut history)| {
+            history.resize_with(runs.len(), || None);
+            let counted: Vec<_> = history
+                .iter()
+                .flatten()
+                .filter(|r| !matches!(r.status, Status::NotRun | Status::ManualVerification))
+                .collect();
+            let passes = counted
+                .iter()
+                .filter(|r| matches!(r.status, Status::Passed | Status::FlakyPass))
+                .count();
+            let (pass_rate, avg_duration_ms) = match counted.len() {
+                0 => (None, None),
+                n => (
+                    Some(passes as f64 / n as f64),
+                    Some(counted.iter().map(|r| r.duration_ms).sum::<u64>() / n as u64),
+                ),
+            };
+            TestTrend {
+                backend,
+                test,
+                runs: counted.len(),
+                passes,
+                pass_rate,
+                avg_duration_ms,
+                history: history
+                    .iter()
+                    .map(|r| r.as_ref().map(|r| r.status))
+                    .collect(),
+            }
+        })
+        .collect();
+    let trend = Trend { runs, tests };
+    let dir = crate::testrun::testruns_dir();
+    let json = serde_json::to_string_pretty(&trend).unwrap();
+    if let Err(e) = std::fs::write(dir.join("trend.json"), json) {
+        log::error!("Could not write trend.json: {}", e);
+    }
+    if let Err(e) = std::fs::write(dir.join("trend.html"), html(&trend)) {
+        log::error!("Could not write trend.html: {}", e);
+    }
+    log::info!(
+        "Trend of {} testruns written to {}",
+        trend.runs.len(),
+        dir.join("trend.html").display()
+    );
+}
+
+/// Returns the time at which the testrun in `dir` started.
+///
+/// The time is parsed from the name of the directory. Older directories only contain the
+/// minute. Runs with the same timestamp are ordered by the modification time of their
+/// summary.
+fn start_time(dir: &Path) -> (Option<NaiveDateTime>, Option<SystemTime>) {
+    let name = dir.file_name().unwrap_or_default().to_string_lossy();
+    let timestamp = [crate::testrun::TIMESTAMP_FORMAT, "%Y-%m-%d %H:%M"]
+        .iter()
+        .find_map(|format| {
+            let (time, _) = name.rsplit_once(' ')?;
+            NaiveDateTime::parse_from_str(time, format).ok()
+        });
+    let modified = std::fs::metadata(dir.join("summary.json"))
+        .and_then(|m| m.modified())
+        .ok();
+    (timestamp, modified)
+}
+
+fn recorded_testruns() -> Vec<PathBuf> {
+    let records = crate::testrun::testruns_dir().join("records");
+    match std::fs::read_dir(&records) {
+        Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+        Err(e) => {
+            log::error!("Could not read {}: {}", records.display(), e);
+            vec![]
+        }
+    }
+}
+
+fn read_summary(dir: &Path) -> Option<Vec<RecordedReport>> {
+    let path = dir.join("summary.json");
+    let json = match std::fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) => {
+            log::warn!("Skipping {}: {}", dir.display(), e);
+            return None;
+        }
+    };
+    match serde_json::from_str(&json) {
+        Ok(reports) => Some(reports),
+        Err(e) => {
+            log::warn!("Skipping {}: Invalid summary.json: {}", dir.display(), e);
+            None
+        }
+    }
+}
+
+fn html(trend: &Trend) -> String {
+    let mut s = String::new();
+    s.push_str(concat!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n",
+        "<title>winit-it trend</title>\n<style>\n",
+        "table { border-collapse: collapse; }\n",
+        "td, th { border: 1px solid #888; padding: 4px 8px; text-align: left; }\n",
+        "td.run { padding: 4px; min-width: 8px; }\n",
+        "</style>\n</head>\n<body>\n",
+    ));
+    let _ = writeln!(s, "<p>{} testruns</p>", trend.runs.len());
+    s.push_str(
+        "<table>\n<tr><th>Backend</th><th>Test</th><th>Pass rate</th><th>Average duration</th>",
+    );
+    for run in &trend.runs {
+        let _ = write!(s, "<th title=\"{}\"></th>", escape(run));
+    }
+    s.push_str("</tr>\n");
+    for test in &trend.tests {
+        let _ = write!(
+            s,
+            "<tr><td>{}</td><td>{}</td><td>",
+            escape(&test.backend),
+            escape(&test.test),
+        );
+        if let Some(rate) = test.pass_rate {
+            let _ = write!(s, "{:.0}% ({}/{})", rate * 100.0, test.passes, test.runs);
+        }
+        s.push_str("</td><td>");
+        if let Some(ms) = test.avg_duration_ms {
+            let _ = write!(s, "{} ms", ms);
+        }
+        s.push_str("</td>");
+        for (run, status) in trend.runs.iter().zip(&test.history) {
+            match status {
+                Some(status) => {
+                    let _ = write!(
+                        s,
+                        "<td class=\"run\" style=\"background-color: {}\" title=\"{}: {}\"></td>",
+                        status.color(),
+                        escape(run),
+                        status.name(),
+                    );
+                }
+                _ => s.push_str("<td class=\"run\"></td>"),
+            }
+        }
+        s.push_str("</tr>\n");
+    }
+    s.push_str("</table>\n</body>\n</html>\n");
+    s
+}
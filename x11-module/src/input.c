@@ -4,6 +4,9 @@
 #include <stdint.h>
 #include <exevents.h>
 #include <xserver-properties.h>
+#include <scrnintstr.h>
+#include <inputstr.h>
+#include <windowstr.h>
 #include "winit.h"
 
 #define DRIVER_VERSION 1
@@ -13,6 +16,7 @@ typedef enum {
   TyKeyboard = 1,
   TyMouse,
   TyTouch,
+  TyTablet,
 } Type;
 
 typedef struct Device {
@@ -66,6 +70,21 @@ static void init_mouse(DeviceIntPtr dev, Device *device) {
   xf86InitValuatorAxisStruct(dev, 1, XIGetKnownProperty(AXIS_LABEL_PROP_REL_Y), -1, -1, 0, 0, 0, Relative);
   SetScrollValuator(dev, 2, SCROLL_TYPE_HORIZONTAL, 120, 0);
   SetScrollValuator(dev, 3, SCROLL_TYPE_VERTICAL, 120, 0);
+  /* motion must not be accelerated so that tests can predict the cursor position. the
+     no-op scheme also ignores the pointer control set by clients. */
+  assert(InitPointerAccelerationScheme(dev, PtrAccelNoOp));
+}
+
+static void init_tablet_axes(DeviceIntPtr dev) {
+  /* the server scales the axes to the screen. use the screen size as the range so that
+     device coordinates are screen coordinates. */
+  xf86InitValuatorAxisStruct(dev, 0, XIGetKnownProperty(AXIS_LABEL_PROP_ABS_X), 0, screenInfo.width - 1, 0, 0, 0, Absolute);
+  xf86InitValuatorAxisStruct(dev, 1, XIGetKnownProperty(AXIS_LABEL_PROP_ABS_Y), 0, screenInfo.height - 1, 0, 0, 0, Absolute);
+}
+
+static void init_tablet(DeviceIntPtr dev, Device *device) {
+  init_pointer(dev, device);
+  init_tablet_axes(dev);
   assert(InitPointerAccelerationScheme(dev, PtrAccelNoOp));
 }
 
@@ -85,6 +104,9 @@ static int device_control(DeviceIntPtr dev, int what) {
     case TyTouch:
       init_touch(dev, device);
       break;
+    case TyTablet:
+      init_tablet(dev, device);
+      break;
     }
   case DEVICE_ON:
   case DEVICE_OFF:
@@ -110,6 +132,9 @@ static int pre_init(InputDriverPtr drv, InputInfoPtr pInfo, int flags) {
   case TyTouch:
     pInfo->type_name = XI_TOUCHSCREEN;
     break;
+  case TyTablet:
+    pInfo->type_name = XI_TABLET;
+    break;
   default:
     assert(0 && "Invalid type");
   }
@@ -178,6 +203,11 @@ uint32_t input_new_touch() {
   return input_new("touchscreen");
 }
 
+uint32_t input_new_tablet() {
+  current_type = TyTablet;
+  return input_new("tablet");
+}
+
 #define MIN_KEYCODE 8
 
 static Device *get_device(uint32_t id) {
@@ -210,6 +240,12 @@ static Device *get_touch(uint32_t touch) {
   return device;
 }
 
+static Device *get_tablet(uint32_t tablet) {
+  Device *device = get_device(tablet);
+  assert(device->type == TyTablet);
+  return device;
+}
+
 void input_key_press(uint32_t keyboard, uint8_t key) {
   Device *device = get_keyboard(keyboard);
   xf86PostKeyboardEvent(device->device->dev, key + MIN_KEYCODE, 1);
@@ -275,7 +311,28 @@ void input_touch_move(uint32_t touch, uint32_t touch_id, int32_t x, int32_t y) {
   xf86PostTouchEvent(device->device->dev, touch_id, XI_TouchUpdate, 0, device->mask);
 }
 
+void input_tablet_move(uint32_t tablet, int32_t x, int32_t y) {
+  Device *device = get_tablet(tablet);
+  /* the screen might have been resized since the last motion */
+  init_tablet_axes(device->device->dev);
+  valuator_mask_zero(device->mask);
+  valuator_mask_set(device->mask, 0, x);
+  valuator_mask_set(device->mask, 1, y);
+  xf86PostMotionEventM(device->device->dev, Absolute, device->mask);
+}
+
 void input_remove_device(uint32_t id) {
   Device *device = get_device(id);
   DeleteInputDeviceRequest(device->device->dev);
 }
+
+uint32_t input_get_pointer_grab(uint32_t pointer) {
+  DeviceIntPtr dev;
+  assert(dixLookupDevice(&dev, pointer, serverClient, DixReadAccess) == Success);
+  GrabPtr grab = dev->deviceGrab.grab;
+  /* implicit grabs are created by button presses, not by clients */
+  if (!grab || dev->deviceGrab.implicitGrab) {
+    return 0;
+  }
+  return grab->window->drawable.id;
+}
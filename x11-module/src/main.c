@@ -35,6 +35,13 @@ enum MessageType {
   MT_TOUCH_DOWN_REPLY,
   MT_TOUCH_UP,
   MT_TOUCH_MOVE,
+  MT_SET_PHYSICAL_SIZE,
+  MT_SET_PHYSICAL_SIZE_REPLY,
+  MT_CREATE_TABLET,
+  MT_CREATE_TABLET_REPLY,
+  MT_TABLET_MOVE,
+  MT_GET_POINTER_GRAB,
+  MT_GET_POINTER_GRAB_REPLY,
 };
 
 typedef struct {
@@ -47,6 +54,11 @@ typedef struct {
   uint32_t touch_id;
 } TouchDownReply;
 
+typedef struct {
+  uint32_t type;
+  uint32_t window;
+} GetPointerGrabReply;
+
 typedef struct {
   uint32_t type;
   uint32_t second_crtc;
@@ -95,6 +107,16 @@ typedef union {
     uint32_t type;
     uint32_t enable;
   } enable_second_monitor;
+  struct {
+    uint32_t type;
+    uint32_t output;
+    uint32_t mm_width;
+    uint32_t mm_height;
+  } set_physical_size;
+  struct {
+    uint32_t type;
+    uint32_t pointer;
+  } get_pointer_grab;
 } Message;
 
 static void handle_message(int fd, void *closure) {
@@ -128,6 +150,15 @@ static void handle_message(int fd, void *closure) {
     assert(write(fd, &reply, sizeof(reply)) > 0);
     break;
   }
+  case MT_CREATE_TABLET: {
+    uint32_t id = input_new_tablet();
+    CreateKeyboardReply reply = {
+        .type = MT_CREATE_TABLET_REPLY,
+        .id = id,
+    };
+    assert(write(fd, &reply, sizeof(reply)) > 0);
+    break;
+  }
   case MT_KEY_PRESS:
     input_key_press(message.key_press.id, message.key_press.key);
     break;
@@ -143,6 +174,9 @@ static void handle_message(int fd, void *closure) {
   case MT_MOUSE_MOVE:
     input_mouse_move(message.mouse_move.id, message.mouse_move.dx, message.mouse_move.dy);
     break;
+  case MT_TABLET_MOVE:
+    input_tablet_move(message.mouse_move.id, message.mouse_move.dx, message.mouse_move.dy);
+    break;
   case MT_MOUSE_SCROLL:
     input_mouse_scroll(message.mouse_move.id, message.mouse_move.dx, message.mouse_move.dy);
     break;
@@ -182,6 +216,22 @@ static void handle_message(int fd, void *closure) {
     input_touch_up(message.touch_move.id, message.touch_move.touch_id);
     break;
   }
+  case MT_SET_PHYSICAL_SIZE: {
+    video_set_physical_size(message.set_physical_size.output, message.set_physical_size.mm_width, message.set_physical_size.mm_height);
+    Message reply = {
+        .type = MT_SET_PHYSICAL_SIZE_REPLY,
+    };
+    assert(write(fd, &reply, sizeof(reply)) > 0);
+    break;
+  }
+  case MT_GET_POINTER_GRAB: {
+    GetPointerGrabReply reply = {
+        .type = MT_GET_POINTER_GRAB_REPLY,
+        .window = input_get_pointer_grab(message.get_pointer_grab.pointer),
+    };
+    assert(write(fd, &reply, sizeof(reply)) > 0);
+    break;
+  }
   default:
     LogMessage(X_ERROR, "Invalid message type %u\n", message.type);
     assert(0 && "Invalid message type");
@@ -333,6 +333,15 @@ void video_connect_second_monitor(uint32_t connected) {
   RRGetInfo(driver.screen, TRUE);
 }
 
+void video_set_physical_size(uint32_t output, uint32_t mm_width, uint32_t mm_height) {
+  assert(output < NUM_OUTPUTS);
+  driver.outputs[output].output->mm_width = mm_width;
+  driver.outputs[output].output->mm_height = mm_height;
+  RRSetChanged(driver.screen);
+  xf86RandR12TellChanged(driver.screen);
+  RRGetInfo(driver.screen, TRUE);
+}
+
 void video_get_info(uint32_t *second_crtc, uint32_t *first_output, uint32_t *second_output, uint32_t *small_mode_id, uint32_t *large_mode_id) {
   *second_crtc = driver.outputs[1].crtc->randr_crtc->id;
   *first_output = driver.outputs[0].output->randr_output->id;
@@ -5,6 +5,7 @@
 
 void video_init(pointer module);
 void video_connect_second_monitor(uint32_t connected);
+void video_set_physical_size(uint32_t output, uint32_t mm_width, uint32_t mm_height);
 void video_get_info(uint32_t *second_crtc, uint32_t *first_output, uint32_t *second_output, uint32_t *small_mode_id, uint32_t *large_mode_id);
 
 void input_init(pointer module);
@@ -24,4 +25,9 @@ uint32_t input_touch_down(uint32_t touch, int32_t x, int32_t y);
 void input_touch_up(uint32_t touch, uint32_t touch_id);
 void input_touch_move(uint32_t touch, uint32_t touch_id, int32_t x, int32_t y);
 
+uint32_t input_new_tablet();
+void input_tablet_move(uint32_t tablet, int32_t x, int32_t y);
+
 void input_remove_device(uint32_t id);
+
+uint32_t input_get_pointer_grab(uint32_t pointer);